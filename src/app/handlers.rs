@@ -2,23 +2,67 @@
 
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::path::Path;
 
 use crate::{
-    events::Screen,
+    config::{self, Config},
+    dates,
+    events::{EditUndo, InFlight, Screen},
+    export, format,
     input::{InputBoxState, InputCallbackId},
-    shortcuts,
+    job_store,
+    jobs::{self, JobStatus, ReceiptFields},
+    shortcuts::{self, Shortcuts},
+    submissions, validation,
     wizard::WizardStep,
-    worker::WorkerCmd,
+    worker::{CommitRequest, IdKind, WorkerCmd},
 };
 
 use super::{App, request_refresh};
 
+/// ログ画面でPageUp/PageDownが1回に動かすログの件数。
+const LOG_PAGE_STEP: usize = 10;
+
 /// キー入力を1件処理し、終了すべきならtrueを返す。
+///
+/// ポップアップは上から順に入力を受け取る（描画はrender.rsの`draw`でこの逆順に重ねる）。
 pub async fn handle_key(app: &mut App, k: KeyEvent) -> Result<bool> {
     // 入力ボックスが開いていれば最優先で処理する。
     if app.input_box.is_some() {
         return handle_input_box_key(app, k).await;
     }
+    // 再試行プロンプトの応答待ちなら、その操作だけを受け付ける。
+    if !app.retry_prompts.is_empty() {
+        return handle_retry_prompt_key(app, k).await;
+    }
+    // 確定の確認待ちなら、はい/いいえだけを受け付ける。
+    if app.confirm_commit.is_some() {
+        return handle_confirm_commit_key(app, k).await;
+    }
+    // 設定ファイルの再読み込みの確認中も、はい/いいえだけを受け付ける。
+    if app.config_reload.is_some() {
+        return handle_config_reload_key(app, k).await;
+    }
+    // 既定値へのリセットの確認中は、リセットの仕方の選択だけを受け付ける。
+    if app.confirm_reset {
+        return handle_confirm_reset_key(app, k).await;
+    }
+    // 列の割り当て中は、ピッカーの操作だけを受け付ける。
+    if app.column_picker.is_some() {
+        return handle_column_picker_key(app, k).await;
+    }
+    // 診断結果の表示中はスクロールと閉じる操作だけを受け付ける。
+    if app.diagnostics.is_some() {
+        return Ok(handle_diagnostics_key(app, k));
+    }
+
+    // 全画面共通のショートカットを先に処理する（ウィザード中は除く）。
+    if app.ui.screen != Screen::InitialSetup
+        && shortcuts::matches_shortcut(&k, &app.shortcuts.global.settings)
+    {
+        open_settings(app);
+        return Ok(false);
+    }
 
     // 画面ごとのハンドラへ委譲する。
     match app.ui.screen {
@@ -26,6 +70,8 @@ pub async fn handle_key(app: &mut App, k: KeyEvent) -> Result<bool> {
         Screen::Settings => handle_settings_key(app, k).await,
         Screen::EditJob => handle_edit_job_key(app, k).await,
         Screen::InitialSetup => handle_wizard_key(app, k).await,
+        Screen::Monitor => handle_monitor_key(app, k),
+        Screen::Logs => Ok(handle_logs_key(app, k)),
     }
 }
 
@@ -42,33 +88,735 @@ async fn handle_main_key(app: &mut App, k: KeyEvent) -> Result<bool> {
     if shortcuts::matches_shortcut(&k, &sc.quit) {
         return Ok(true);
     } else if shortcuts::matches_shortcut(&k, &sc.settings) {
-        // 設定画面へ遷移し、編集バッファを更新する。
-        reload_settings_buffers(app);
-        app.ui.screen = Screen::Settings;
-        app.ui.status = "Settings".into();
+        open_settings(app);
     } else if shortcuts::matches_shortcut(&k, &sc.refresh) {
         // ジョブ一覧の再取得を依頼する。
         request_refresh(app).await?;
     } else if shortcuts::matches_shortcut(&k, &sc.down) {
-        // 次の行へ移動する。
-        if app.ui.selected + 1 < app.jobs.len() {
-            app.ui.selected += 1;
+        // 表示中の次の行へ移動する。
+        let visible = app.visible_job_indices();
+        if let Some(&i) = visible.iter().find(|&&i| i > app.ui.selected) {
+            app.select_job(i);
+        } else {
+            // 末尾を越えようとしたら続きのページを読み込む。
+            request_more_jobs(app).await?;
         }
     } else if shortcuts::matches_shortcut(&k, &sc.up) {
-        // 前の行へ移動する。
-        if app.ui.selected > 0 {
-            app.ui.selected -= 1;
+        // 表示中の前の行へ移動する。
+        let visible = app.visible_job_indices();
+        if let Some(&i) = visible.iter().rev().find(|&&i| i < app.ui.selected) {
+            app.select_job(i);
         }
-    } else if shortcuts::matches_shortcut(&k, &sc.enter) && app.jobs.get(app.ui.selected).is_some()
-    {
+    } else if shortcuts::matches_shortcut(&k, &sc.toggle_done) {
+        // 完了したジョブの表示/非表示を切り替え、選択を表示中の行に合わせる。
+        app.hide_done = !app.hide_done;
+        app.keep_selection_visible();
+        app.ui.status = if app.hide_done {
+            "Hiding done jobs".into()
+        } else {
+            "Showing all jobs".into()
+        };
+    } else if shortcuts::matches_shortcut(&k, &sc.status_filter) {
+        // 状態の絞り込みを切り替え、選択を表示中の行に合わせる。
+        app.ui.status_filter = app.ui.status_filter.next();
+        app.keep_selection_visible();
+        app.ui.status = format!("Filter: {}", app.ui.status_filter.label());
+    } else if shortcuts::matches_shortcut(&k, &sc.search) {
+        // 検索語の入力ボックスを開く（入力のたびに一致するジョブへ移動する）。
+        let value = app.ui.search.clone().unwrap_or_default();
+        app.input_box = Some(InputBoxState {
+            prompt: "Search filename:".into(),
+            cursor: value.chars().count(),
+            value,
+            callback_id: InputCallbackId::JobSearch,
+            max_chars: None,
+            suggestions: vec![],
+        });
+    } else if shortcuts::matches_shortcut(&k, &sc.search_next) {
+        jump_to_search_match(app, true);
+    } else if shortcuts::matches_shortcut(&k, &sc.search_prev) {
+        jump_to_search_match(app, false);
+    } else if shortcuts::matches_shortcut(&k, &sc.enter) && app.selected_job().is_some() {
         // 編集画面へ遷移し、編集フィールドを先頭に戻す。
         app.ui.screen = Screen::EditJob;
         app.ui.editing_field_idx = 0;
+        // 氏名が未設定なら確定前に設定するよう促す。
+        if app.cfg.user.has_placeholder_name() {
+            app.ui.status = placeholder_name_warning(app);
+        }
+    } else if shortcuts::matches_shortcut(&k, &sc.pause) {
+        // 確定の送信を一時停止/再開する。
+        if app.paused {
+            resume_commits(app).await?;
+        } else {
+            app.paused = true;
+            app.ui.status = "Paused: new commits will be held".into();
+        }
+    } else if shortcuts::matches_shortcut(&k, &sc.diagnostics)
+        && let Some(j) = app.selected_job()
+    {
+        // 押されたときだけ、選択ジョブに関わるシートの取得を依頼する。
+        app.worker_tx
+            .send(WorkerCmd::Diagnose {
+                job_id: j.id,
+                copied_sheet_id: j.copied_sheet_id.clone(),
+            })
+            .await?;
+        app.ui.status = format!("Fetching diagnostics for {}...", j.filename);
+    } else if shortcuts::matches_shortcut(&k, &sc.commit_all) {
+        commit_all(app, false).await?;
+    } else if shortcuts::matches_shortcut(&k, &sc.commit_by_month) {
+        commit_all(app, true).await?;
+    } else if shortcuts::matches_shortcut(&k, &sc.ocr) {
+        request_ocr(app).await?;
+    } else if shortcuts::matches_shortcut(&k, &sc.pull_from_sheet)
+        && let Some(j) = app.selected_job()
+    {
+        // 書き込み先が分かっている確定済みジョブだけ読み戻せる。
+        match (&j.status, &j.copied_sheet_id, &j.committed_range) {
+            (JobStatus::Done, Some(sheet_id), Some(range)) => {
+                app.worker_tx
+                    .send(WorkerCmd::PullFromSheet {
+                        job_id: j.id,
+                        sheet_id: sheet_id.clone(),
+                        range: range.clone(),
+                    })
+                    .await?;
+                app.ui.status = format!("Pulling {} from sheet...", j.filename);
+            }
+            _ => {
+                app.ui.status =
+                    "Only jobs committed in this session can be pulled from the sheet".into();
+            }
+        }
+    } else if shortcuts::matches_shortcut(&k, &sc.retry_with_month)
+        && let Some(j) = app.selected_job()
+    {
+        // 失敗したジョブだけ、失敗時の対象月を初期値にして月の入力ボックスを開く。
+        if !matches!(j.status, JobStatus::Error(_)) {
+            app.ui.status = "Only failed jobs can be retried with a new month".into();
+            return Ok(false);
+        }
+        let value = j
+            .failed_month
+            .clone()
+            .unwrap_or_else(|| app.edit_target_month.clone());
+        app.input_box = Some(InputBoxState {
+            prompt: format!("Retry {} with month (YYYY-MM):", j.filename),
+            cursor: value.chars().count(),
+            value,
+            callback_id: InputCallbackId::RetryWithMonth,
+            max_chars: None,
+            suggestions: vec![],
+        });
+    } else if shortcuts::matches_shortcut(&k, &sc.monitor) {
+        // 進捗モニター画面へ遷移する。
+        app.ui.screen = Screen::Monitor;
+        app.ui.status = "Monitor".into();
+    } else if shortcuts::matches_shortcut(&k, &sc.reset_month) {
+        reset_target_month(app);
+    } else if shortcuts::matches_shortcut(&k, &sc.logs) {
+        // ログ画面へ遷移し、末尾から表示する。
+        app.ui.screen = Screen::Logs;
+        app.ui.log_scroll = None;
+        app.ui.status = "Logs".into();
+    } else if shortcuts::matches_shortcut(&k, &sc.toggle_mask) {
+        // IDのマスク表示を切り替える（設定ファイルには保存しない）。
+        app.cfg.ui.mask_sensitive = !app.cfg.ui.mask_sensitive;
+        app.ui.status = if app.cfg.ui.mask_sensitive {
+            "Masking IDs".into()
+        } else {
+            "Showing IDs".into()
+        };
+    } else if shortcuts::matches_shortcut(&k, &sc.export_xlsx) {
+        // 書き出し先パスの入力ボックスを開く。
+        let value = String::from("receipts.xlsx");
+        app.input_box = Some(InputBoxState {
+            prompt: "Export XLSX to:".into(),
+            cursor: value.chars().count(),
+            value,
+            callback_id: InputCallbackId::ExportXlsxPath,
+            max_chars: None,
+            suggestions: vec![],
+        });
+    } else if shortcuts::matches_shortcut(&k, &sc.export_csv) {
+        // 対象月入りの既定名で書き出し先パスの入力ボックスを開く。
+        let month = if dates::is_valid_ym(&app.edit_target_month) {
+            app.edit_target_month.clone()
+        } else {
+            dates::current_ym()
+        };
+        let value = format!("receipts_{month}.csv");
+        app.input_box = Some(InputBoxState {
+            prompt: "Export CSV to:".into(),
+            cursor: value.chars().count(),
+            value,
+            callback_id: InputCallbackId::ExportCsvPath,
+            max_chars: None,
+            suggestions: vec![],
+        });
+    }
+
+    Ok(false)
+}
+
+/// 現在のジョブ一覧をCSVへ書き出し、結果をステータスへ表示する。
+fn export_csv(app: &mut App, path: &str) {
+    // 空のパスは何もしない。
+    let path = path.trim();
+    if path.is_empty() {
+        return;
+    }
+    match export::export_jobs_csv(Path::new(path), &app.jobs) {
+        Ok(rows) => app.ui.status = format!("Exported {rows} rows to {path}"),
+        Err(e) => app.ui.error = Some(format!("CSV export failed: {e:#}")),
+    }
+}
+
+/// 検出が有効で、指定の対象月に同じ画像を提出済みか判定する。
+fn is_duplicate_submission(app: &App, month: &str, drive_file_id: &str) -> bool {
+    if !app.cfg.duplicates.detect {
+        return false;
+    }
+    // 確定直前の状態を見るため、台帳は毎回読み直す。
+    let records = submissions::load(Path::new(submissions::SUBMISSIONS_PATH));
+    submissions::is_submitted(&records, month, drive_file_id)
+}
+
+/// 編集待ちのジョブをまとめてWorkerへ送り、順に確定させる。
+///
+/// `one_sheet_per_month`なら、対象月ごとに1枚のシートと1つのPDFへまとめる。
+async fn commit_all(app: &mut App, one_sheet_per_month: bool) -> Result<()> {
+    // 氏名がプレースホルダのままなら確定させない。
+    if app.cfg.user.has_placeholder_name() {
+        app.ui.status = placeholder_name_warning(app);
+        return Ok(());
+    }
+    // 一時停止中は一括確定を受け付けない。
+    if app.paused {
+        app.ui.status = "Paused: resume before committing all jobs".into();
+        return Ok(());
+    }
+    if refresh_blocks_commit(app) {
+        return Ok(());
+    }
+    // 編集待ちのジョブを対象にする（確定中のものは二重に送らず、無効な対象月はWorker側で飛ばす）。
+    let requests: Vec<CommitRequest> = app
+        .jobs
+        .iter()
+        .filter(|j| matches!(j.status, JobStatus::WaitingUserFix))
+        .filter(|j| !app.in_flight.contains(&InFlight::Commit(j.id)))
+        .map(|j| {
+            let month = j
+                .effective_target_month(&app.edit_target_month)
+                .or_else(|| j.target_month.clone())
+                .unwrap_or_else(|| app.edit_target_month.clone());
+            CommitRequest::for_job(j, month)
+        })
+        .collect();
+    if requests.is_empty() {
+        app.ui.status = "No jobs waiting to be committed".into();
+        return Ok(());
+    }
+    let count = requests.len();
+    app.in_flight
+        .extend(requests.iter().map(|r| InFlight::Commit(r.job_id)));
+    app.worker_tx
+        .send(WorkerCmd::CommitAll {
+            requests,
+            one_sheet_per_month,
+        })
+        .await?;
+    app.ui.status = if one_sheet_per_month {
+        format!("Committing {count} jobs, one sheet per month...")
+    } else {
+        format!("Committing {count} jobs...")
+    };
+    Ok(())
+}
+
+/// 失敗したジョブの対象月を直し、すぐに確定を再送する。
+async fn retry_with_month(app: &mut App, value: String) -> Result<()> {
+    // 書式は入力ボックスを閉じる前に検証済み。
+    let month = value.trim().to_string();
+    // 氏名がプレースホルダのままなら確定させない。
+    if app.cfg.user.has_placeholder_name() {
+        app.ui.status = placeholder_name_warning(app);
+        return Ok(());
+    }
+    if refresh_blocks_commit(app) {
+        return Ok(());
+    }
+    let Some(job) = app.jobs.get(app.ui.selected).cloned() else {
+        return Ok(());
+    };
+    // 編集画面からの確定と同じく、必須項目と二重提出を確かめてから再送する。
+    if !fields_ready_to_commit(app, &job.fields) {
+        return Ok(());
+    }
+    if is_duplicate_submission(app, &month, &job.drive_file_id) {
+        app.ui.status =
+            format!("Already submitted for {month}; commit from the edit screen to submit again");
+        return Ok(());
+    }
+    let Some(j) = app.jobs.get_mut(app.ui.selected) else {
+        return Ok(());
+    };
+    // 直した月をジョブ固有の対象月として残し、失敗表示を解除する。
+    j.target_month = Some(month.clone());
+    j.failed_month = None;
+    j.status = JobStatus::Queued;
+    let req = CommitRequest::for_job(j, month.clone());
+    let filename = j.filename.clone();
+    app.save_jobs();
+    if app.paused {
+        // 一時停止中は送信せずに保留する。
+        app.pending_commits.push_back(req);
+        app.ui.status = format!("paused ({} queued)", app.pending_commits.len());
+    } else {
+        send_commit(app, req).await?;
+        app.ui.status = format!("Retrying {filename} for {month}");
+    }
+    Ok(())
+}
+
+/// 一時停止を解除し、保留していた確定を順に送信する。
+async fn resume_commits(app: &mut App) -> Result<()> {
+    // リフレッシュ中は一時停止のまま保留を続ける。
+    if !app.pending_commits.is_empty() && refresh_blocks_commit(app) {
+        return Ok(());
+    }
+    app.paused = false;
+    let count = app.pending_commits.len();
+    // 保留した順にWorkerへ送る。
+    while let Some(req) = app.pending_commits.pop_front() {
+        send_commit(app, req).await?;
+    }
+    app.ui.status = format!("Resumed: sent {} held commits", count);
+    Ok(())
+}
+
+/// 確定をWorkerへ送り、完了まで待っている操作として記録する。
+async fn send_commit(app: &mut App, req: CommitRequest) -> Result<()> {
+    app.in_flight.insert(InFlight::Commit(req.job_id));
+    app.worker_tx.send(WorkerCmd::CommitJobEdits(req)).await?;
+    Ok(())
+}
+
+/// 必須項目と科目ごとのルールを確かめ、満たしていなければ理由を表示する（確定できればtrue）。
+fn fields_ready_to_commit(app: &mut App, fields: &ReceiptFields) -> bool {
+    match fields.validate(&app.cfg.validation.required_fields, &app.cfg.category_rules) {
+        Ok(()) => true,
+        Err(problems) => {
+            app.ui.status = format!("Cannot commit: {}", problems.join("; "));
+            false
+        }
+    }
+}
+
+/// リフレッシュを待っている間は確定を受け付けず、その旨を表示する。
+fn refresh_blocks_commit(app: &mut App) -> bool {
+    if !app.is_refreshing() {
+        return false;
+    }
+    app.ui.status = "Busy: refreshing jobs; commit when it finishes".into();
+    true
+}
+
+/// 現在のジョブ一覧をXLSXへ書き出し、結果をステータスへ表示する。
+fn export_xlsx(app: &mut App, path: &str) {
+    // 空のパスは何もしない。
+    let path = path.trim();
+    if path.is_empty() {
+        return;
+    }
+    match export::export_jobs_xlsx(
+        Path::new(path),
+        &app.jobs,
+        &app.cfg.validation.required_fields,
+        &app.cfg.category_rules,
+    ) {
+        Ok(summary) if summary.invalid.is_empty() => {
+            app.ui.status = format!("Exported {} rows to {}", summary.rows, path);
+        }
+        Ok(summary) => {
+            // 不正な行はログに残し、件数をステータスへ表示する。
+            for line in &summary.invalid {
+                app.ui.log.push(format!("export: {}", line));
+            }
+            app.ui.status = format!(
+                "Exported {} rows to {} ({} invalid, see log)",
+                summary.rows,
+                path,
+                summary.invalid.len()
+            );
+        }
+        Err(e) => app.ui.error = Some(format!("XLSX export failed: {e:#}")),
+    }
+}
+
+/// 氏名が未設定のときに表示する警告文を作る。
+fn placeholder_name_warning(app: &App) -> String {
+    format!(
+        "Full name is not set; open Settings ({}) and set it before committing",
+        app.shortcuts.main.settings.join("/")
+    )
+}
+
+/// 続きのページがあれば、Workerへ追加読み込みを依頼する。
+async fn request_more_jobs(app: &mut App) -> Result<()> {
+    // 読み込み中や続きが無い場合は何もしない。
+    if app.loading_more {
+        return Ok(());
+    }
+    let Some(page_token) = app.next_page_token.clone() else {
+        return Ok(());
+    };
+    app.worker_tx
+        .send(WorkerCmd::LoadMoreJobs { page_token })
+        .await?;
+    app.loading_more = true;
+    app.ui.status = "Loading more jobs...".into();
+    Ok(())
+}
+
+/// 再試行プロンプトのキー処理（再試行/編集/中止）。
+async fn handle_retry_prompt_key(app: &mut App, k: KeyEvent) -> Result<bool> {
+    // 応答待ちでもアプリは終了できるようにする（失敗したジョブは保存済み）。
+    if shortcuts::matches_shortcut(&k, &app.shortcuts.main.quit) {
+        return Ok(true);
+    }
+    let sc = &app.shortcuts.retry_prompt;
+    let retry = shortcuts::matches_shortcut(&k, &sc.retry);
+    let edit = shortcuts::matches_shortcut(&k, &sc.edit);
+    let give_up = shortcuts::matches_shortcut(&k, &sc.give_up);
+    if !(retry || edit || give_up) {
+        return Ok(false);
+    }
+    // 再送はリフレッシュが終わるまで待たせる（プロンプトは残す）。
+    if retry && refresh_blocks_commit(app) {
+        return Ok(false);
+    }
+    let Some(prompt) = app.retry_prompts.pop_front() else {
+        return Ok(false);
+    };
+    // 一覧から対象ジョブを探す（再読み込み等で消えていれば何もしない）。
+    let Some(idx) = app.jobs.iter().position(|j| j.id == prompt.job_id) else {
+        return Ok(false);
+    };
+
+    if retry {
+        // 同じ内容で、失敗したときのジョブ自身の対象月へ確定を再送する。
+        let job = app.jobs[idx].clone();
+        let month = job
+            .failed_month
+            .clone()
+            .or_else(|| job.effective_target_month(&app.edit_target_month))
+            .unwrap_or_else(|| app.edit_target_month.clone());
+        let req = CommitRequest::for_job(&job, month);
+        if app.paused {
+            // 一時停止中は送信せずに保留する。
+            app.pending_commits.push_back(req);
+            app.ui.status = format!("paused ({} queued)", app.pending_commits.len());
+        } else {
+            send_commit(app, req).await?;
+            app.ui.status = format!("Retrying {}", job.filename);
+        }
+    } else if edit {
+        // 対象ジョブの編集画面を開く。
+        app.select_job(idx);
+        app.ui.screen = Screen::EditJob;
+        app.ui.editing_field_idx = 0;
+        app.ui.status = format!("Editing {} after failure", app.jobs[idx].filename);
+    } else {
+        // 失敗のまま残す。
+        app.ui.status = format!("Gave up on {}", app.jobs[idx].filename);
+    }
+    Ok(false)
+}
+
+/// 確定前の確認ポップアップのキー処理（はい/いいえ）。
+async fn handle_confirm_commit_key(app: &mut App, k: KeyEvent) -> Result<bool> {
+    let sc = &app.shortcuts.confirm;
+    if shortcuts::matches_shortcut(&k, &sc.no) {
+        // 送信せずに編集画面へ戻る。
+        app.confirm_commit = None;
+        app.ui.status = "Commit cancelled".into();
+        return Ok(false);
+    }
+    if !shortcuts::matches_shortcut(&k, &sc.yes) {
+        return Ok(false);
+    }
+    let Some(req) = app.confirm_commit.take() else {
+        return Ok(false);
+    };
+    // 画面を戻す。
+    app.ui.screen = Screen::Main;
+    if app.paused {
+        // 一時停止中は送信せずに保留する。
+        app.pending_commits.push_back(req);
+        app.ui.status = format!("paused ({} queued)", app.pending_commits.len());
+    } else {
+        // 編集内容と対象月を送信し、進行状況を表示する。
+        send_commit(app, req).await?;
+        app.ui.status = "Committed (writing sheet/exporting pdf...)".into();
     }
+    Ok(false)
+}
+
+/// 設定ファイルの再読み込み確認のキー処理（はい/いいえ）。
+async fn handle_config_reload_key(app: &mut App, k: KeyEvent) -> Result<bool> {
+    let sc = &app.shortcuts.confirm;
+    if shortcuts::matches_shortcut(&k, &sc.no) {
+        // メモリ上の設定を使い続ける（次の保存でディスクの変更は上書きされる）。
+        app.config_reload = None;
+        app.ui.status = "Kept current config; saving will overwrite the file on disk".into();
+        return Ok(false);
+    }
+    if !shortcuts::matches_shortcut(&k, &sc.yes) {
+        return Ok(false);
+    }
+    let Some(reload) = app.config_reload.take() else {
+        return Ok(false);
+    };
+    if let Some(cfg) = reload.config {
+        // 読み直せたので保存を再開し、設定画面の編集バッファとWorkerにも反映する。
+        app.cfg = cfg;
+        app.cfg_read_only = false;
+        reload_settings_buffers(app);
+        app.worker_tx
+            .send(WorkerCmd::SaveSettings(Box::new(app.cfg.clone())))
+            .await?;
+    }
+    if let Some(shortcuts) = reload.shortcuts {
+        app.shortcuts = shortcuts;
+    }
+    app.ui.status = "Reloaded config from disk".into();
+    Ok(false)
+}
+
+/// 既定値へのリセット確認のキー処理（すべて/IDを残す/いいえ）。
+async fn handle_confirm_reset_key(app: &mut App, k: KeyEvent) -> Result<bool> {
+    let sc = &app.shortcuts.confirm;
+    let keep_ids = if shortcuts::matches_shortcut(&k, &sc.yes) {
+        false
+    } else if shortcuts::matches_shortcut(&k, &sc.keep_ids) {
+        true
+    } else {
+        if shortcuts::matches_shortcut(&k, &sc.no) {
+            // 何も変えずに設定画面へ戻る。
+            app.confirm_reset = false;
+            app.ui.status = "Reset cancelled".into();
+        }
+        return Ok(false);
+    };
+    app.confirm_reset = false;
+    reset_to_defaults(app, keep_ids).await?;
+    Ok(false)
+}
+
+/// 設定とショートカットを既定値へ戻して保存し、Workerへも反映する。
+///
+/// 上書きする前に現在のファイルを`.bak`へ退避する。`keep_ids`なら`[google]`のフォルダ/テンプレートIDは残す。
+async fn reset_to_defaults(app: &mut App, keep_ids: bool) -> Result<()> {
+    // 退避できなければ元に戻せなくなるため、リセットしない。
+    let mut backups = vec![];
+    for path in [app.cfg_path.clone(), app.shortcuts_path.clone()] {
+        match config::backup_file(&path) {
+            Ok(Some(backup)) => backups.push(backup.display().to_string()),
+            Ok(None) => {}
+            Err(e) => {
+                app.ui.error = Some(format!("reset cancelled: {e:#}"));
+                return Ok(());
+            }
+        }
+    }
+
+    // 既定値へ戻す（指定があればIDだけ引き継ぐ）。
+    let google = app.cfg.google.clone();
+    app.cfg = Config::default();
+    if keep_ids {
+        app.cfg.google = google;
+    }
+    app.shortcuts = Shortcuts::default();
+
+    // 既定値で書き直すので、読めなかった設定ファイルも保存を再開する。
+    app.cfg_read_only = false;
+    app.cfg.save(&app.cfg_path)?;
+    app.shortcuts.save(&app.shortcuts_path)?;
+
+    // 設定画面の編集バッファとWorkerにも反映する。
+    reload_settings_buffers(app);
+    app.worker_tx
+        .send(WorkerCmd::SaveSettings(Box::new(app.cfg.clone())))
+        .await?;
+    app.ui.status = if backups.is_empty() {
+        "Reset config and shortcuts to defaults".into()
+    } else {
+        format!(
+            "Reset config and shortcuts to defaults (backup: {})",
+            backups.join(", ")
+        )
+    };
+    Ok(())
+}
+
+/// 列の割り当てピッカーのキー処理（移動・選択・そのまま・中止）。
+async fn handle_column_picker_key(app: &mut App, k: KeyEvent) -> Result<bool> {
+    let sc = &app.shortcuts.column_picker;
+    let (up, down, pick, keep, cancel) = (
+        shortcuts::matches_shortcut(&k, &sc.up),
+        shortcuts::matches_shortcut(&k, &sc.down),
+        shortcuts::matches_shortcut(&k, &sc.pick),
+        shortcuts::matches_shortcut(&k, &sc.keep),
+        shortcuts::matches_shortcut(&k, &sc.cancel),
+    );
+    let Some(picker) = &mut app.column_picker else {
+        return Ok(false);
+    };
+    let finished = if cancel {
+        // 設定は変えずに閉じる。
+        app.column_picker = None;
+        app.ui.status = "Column mapping cancelled".into();
+        return Ok(false);
+    } else if up {
+        picker.up();
+        false
+    } else if down {
+        picker.down();
+        false
+    } else if pick {
+        picker.pick()
+    } else if keep {
+        picker.keep()
+    } else {
+        false
+    };
+    if !finished {
+        return Ok(false);
+    }
+
+    // すべての項目を選び終えたら設定へ書き込み、保存してWorkerにも反映する。
+    let Some(picker) = app.column_picker.take() else {
+        return Ok(false);
+    };
+    picker.apply(&mut app.cfg.general_expense);
+    save_config(app)?;
+    app.worker_tx
+        .send(WorkerCmd::SaveSettings(Box::new(app.cfg.clone())))
+        .await?;
+    let ge = &app.cfg.general_expense;
+    app.ui.status = format!(
+        "Mapped columns: date={} reason={} amount={} category={} note={}",
+        ge.date_col, ge.reason_col, ge.amount_col, ge.category_col, ge.note_col
+    );
+    Ok(false)
+}
+
+/// 診断ポップアップのキー処理（スクロールと閉じる操作）。
+fn handle_diagnostics_key(app: &mut App, k: KeyEvent) -> bool {
+    let sc = &app.shortcuts.diagnostics;
+    if is_ctrl_c(&k) {
+        return true;
+    }
+    if shortcuts::matches_shortcut(&k, &sc.close) {
+        app.diagnostics = None;
+    } else if let Some(view) = &mut app.diagnostics {
+        if shortcuts::matches_shortcut(&k, &sc.up) {
+            view.scroll_up();
+        } else if shortcuts::matches_shortcut(&k, &sc.down) {
+            view.scroll_down();
+        }
+    }
+    false
+}
 
+/// モニター画面のキー処理（読み取り専用のため戻る操作のみ）。
+fn handle_monitor_key(app: &mut App, k: KeyEvent) -> Result<bool> {
+    if shortcuts::matches_shortcut(&k, &app.shortcuts.monitor.back) {
+        // メイン画面へ戻る。
+        app.ui.screen = Screen::Main;
+        app.ui.status.clear();
+    }
     Ok(false)
 }
 
+/// 選択中のジョブと同じシートへまとめて書かれる行数を返す。
+///
+/// 月ごとの一括確定では、同じ対象月の編集待ちジョブが1枚のシートへ並ぶ。
+fn preview_rows(app: &App) -> u32 {
+    let Some(selected) = app.jobs.get(app.ui.selected) else {
+        return 1;
+    };
+    let month = selected.effective_target_month(&app.edit_target_month);
+    let others = app
+        .jobs
+        .iter()
+        .filter(|j| j.id != selected.id && matches!(j.status, JobStatus::WaitingUserFix))
+        .filter(|j| j.effective_target_month(&app.edit_target_month) == month)
+        .count();
+    // 選択中のジョブ自身の1行を加える。
+    (others + 1) as u32
+}
+
+/// 対象月を今月に戻す。
+fn reset_target_month(app: &mut App) {
+    app.edit_target_month = dates::current_ym();
+    app.ui.status = format!("Target month reset to {}", app.edit_target_month);
+}
+
+/// 選択中のジョブの元画像を文字認識し、日付と合計金額の推測を依頼する。
+async fn request_ocr(app: &mut App) -> Result<()> {
+    let Some(j) = app.selected_job() else {
+        return Ok(());
+    };
+    if !jobs::is_image_mime(&j.mime_type) {
+        // 文字認識は画像だけが対象のため、PDFなどは送らない。
+        app.ui.status = format!("OCR not supported for PDF: {}", j.filename);
+        return Ok(());
+    }
+    app.worker_tx
+        .send(WorkerCmd::OcrJob {
+            job_id: j.id,
+            drive_file_id: j.drive_file_id.clone(),
+        })
+        .await?;
+    app.ui.status = format!("Reading {} with OCR...", j.filename);
+    Ok(())
+}
+
+/// ログ画面のキー処理（スクロールと戻る操作）。
+fn handle_logs_key(app: &mut App, k: KeyEvent) -> bool {
+    let sc = &app.shortcuts.logs;
+    if shortcuts::matches_shortcut(&k, &sc.back) {
+        // メイン画面へ戻る。
+        app.ui.screen = Screen::Main;
+        app.ui.status.clear();
+        return false;
+    }
+    // 追従中は、末尾を表示している位置から動かす。
+    let bottom = app.ui.log_bottom_start;
+    let current = app.ui.log_scroll.unwrap_or(bottom);
+    let next = if shortcuts::matches_shortcut(&k, &sc.up) {
+        current.saturating_sub(1)
+    } else if shortcuts::matches_shortcut(&k, &sc.down) {
+        current + 1
+    } else if shortcuts::matches_shortcut(&k, &sc.page_up) {
+        current.saturating_sub(LOG_PAGE_STEP)
+    } else if shortcuts::matches_shortcut(&k, &sc.page_down) {
+        current + LOG_PAGE_STEP
+    } else if shortcuts::matches_shortcut(&k, &sc.top) {
+        0
+    } else if shortcuts::matches_shortcut(&k, &sc.bottom) {
+        bottom
+    } else {
+        return false;
+    };
+    // 末尾まで戻ったら、新しいログへの追従を再開する。
+    app.ui.log_scroll = (next < bottom).then_some(next);
+    false
+}
+
 /// 設定画面のキー処理。
 async fn handle_settings_key(app: &mut App, k: KeyEvent) -> Result<bool> {
     // 設定画面のショートカットを参照する。
@@ -79,21 +827,14 @@ async fn handle_settings_key(app: &mut App, k: KeyEvent) -> Result<bool> {
         reload_settings_buffers(app);
         app.ui.screen = Screen::Main;
     } else if shortcuts::matches_shortcut(&k, &sc.save) {
-        // 編集バッファを設定へ反映する。
-        app.cfg.google.input_folder_id = app.in_folder.clone();
-        app.cfg.google.output_folder_id = app.out_folder.clone();
-        app.cfg.google.template_sheet_id = app.template_id.clone();
-        app.cfg.user.full_name = app.full_name.clone();
-        // 設定ファイルを保存する。
-        app.cfg.save(&app.cfg_path)?;
-
-        // Workerにも設定更新を通知する。
-        app.worker_tx
-            .send(WorkerCmd::SaveSettings(app.cfg.clone()))
-            .await?;
-        // 画面状態を更新してメインへ戻る。
+        // 保存してメインへ戻る。
+        apply_settings(app).await?;
         app.ui.screen = Screen::Main;
         app.ui.status = "Saved settings".into();
+    } else if shortcuts::matches_shortcut(&k, &sc.apply) {
+        // 保存して設定画面に留まる。
+        apply_settings(app).await?;
+        app.ui.status = "Applied settings".into();
     } else if shortcuts::matches_shortcut(&k, &sc.input_folder) {
         // 入力フォルダIDの入力ボックスを開く。
         app.input_box = Some(InputBoxState {
@@ -101,6 +842,8 @@ async fn handle_settings_key(app: &mut App, k: KeyEvent) -> Result<bool> {
             value: app.in_folder.clone(),
             cursor: app.in_folder.chars().count(),
             callback_id: InputCallbackId::SettingsInputFolder,
+            max_chars: None,
+            suggestions: vec![],
         });
     } else if shortcuts::matches_shortcut(&k, &sc.output_folder) {
         // 出力フォルダIDの入力ボックスを開く。
@@ -109,6 +852,8 @@ async fn handle_settings_key(app: &mut App, k: KeyEvent) -> Result<bool> {
             value: app.out_folder.clone(),
             cursor: app.out_folder.chars().count(),
             callback_id: InputCallbackId::SettingsOutputFolder,
+            max_chars: None,
+            suggestions: vec![],
         });
     } else if shortcuts::matches_shortcut(&k, &sc.template) {
         // テンプレートシートIDの入力ボックスを開く。
@@ -117,6 +862,8 @@ async fn handle_settings_key(app: &mut App, k: KeyEvent) -> Result<bool> {
             value: app.template_id.clone(),
             cursor: app.template_id.chars().count(),
             callback_id: InputCallbackId::SettingsTemplateId,
+            max_chars: None,
+            suggestions: vec![],
         });
     } else if shortcuts::matches_shortcut(&k, &sc.name) {
         // 氏名の入力ボックスを開く。
@@ -125,7 +872,27 @@ async fn handle_settings_key(app: &mut App, k: KeyEvent) -> Result<bool> {
             value: app.full_name.clone(),
             cursor: app.full_name.chars().count(),
             callback_id: InputCallbackId::SettingsFullName,
+            max_chars: None,
+            suggestions: vec![],
         });
+    } else if shortcuts::matches_shortcut(&k, &sc.logout) {
+        // 保存済みのトークンの失効と削除を依頼する（完了するとWorkerから通知が来る）。
+        app.worker_tx.send(WorkerCmd::Logout).await?;
+        app.ui.status = "Logging out...".into();
+    } else if shortcuts::matches_shortcut(&k, &sc.reset) {
+        // 既定値へ戻す前に、IDを残すかどうかを確認する。
+        app.confirm_reset = true;
+    } else if shortcuts::matches_shortcut(&k, &sc.map_columns) {
+        // 編集中のテンプレートIDで見出し行を読む（届いたらピッカーを開く）。
+        app.worker_tx
+            .send(WorkerCmd::FetchTemplateHeaders {
+                template_sheet_id: app.template_id.clone(),
+                header_row: app.cfg.general_expense.header_row(),
+            })
+            .await?;
+        app.ui.status = "Reading template headers...".into();
+    } else if shortcuts::matches_shortcut(&k, &sc.profile) {
+        switch_profile(app).await?;
     }
 
     Ok(false)
@@ -141,23 +908,72 @@ async fn handle_edit_job_key(app: &mut App, k: KeyEvent) -> Result<bool> {
         app.ui.screen = Screen::Main;
     } else if shortcuts::matches_shortcut(&k, &sc.next_field) {
         // 次の編集フィールドへ移動する。
-        app.ui.editing_field_idx = (app.ui.editing_field_idx + 1) % 5;
+        app.ui.editing_field_idx = (app.ui.editing_field_idx + 1) % ReceiptFields::FIELD_COUNT;
     } else if shortcuts::matches_shortcut(&k, &sc.commit) {
+        // 氏名がプレースホルダのままなら確定させない。
+        if app.cfg.user.has_placeholder_name() {
+            app.ui.status = placeholder_name_warning(app);
+            return Ok(false);
+        }
+        if refresh_blocks_commit(app) {
+            return Ok(false);
+        }
         // 選択ジョブを確定してWorkerへ送る。
         let Some(job) = app.jobs.get(app.ui.selected).cloned() else {
             return Ok(false);
         };
-        // 編集内容と対象月を送信する。
-        app.worker_tx
-            .send(WorkerCmd::CommitJobEdits {
-                job_id: job.id,
-                fields: job.fields,
-                target_month_ym: app.edit_target_month.clone(),
-            })
-            .await?;
-        // 画面を戻して進行状況を表示する。
-        app.ui.screen = Screen::Main;
-        app.ui.status = "Committed (writing sheet/exporting pdf...)".into();
+        // 科目のルールで備考が必須なのに空なら、備考欄へ移動して確定させない。
+        if job.fields.missing_required_note(&app.cfg.category_rules) {
+            app.ui.editing_field_idx = 4;
+            app.ui.status = format!(
+                "Note is required for category {:?}",
+                job.fields.category.trim()
+            );
+            return Ok(false);
+        }
+        // 一括確定と同じく、必須項目が欠けていれば確定させない。
+        if !fields_ready_to_commit(app, &job.fields) {
+            return Ok(false);
+        }
+        // 対象月が無効なら確定させない。
+        let Some(target_month_ym) = job.effective_target_month(&app.edit_target_month) else {
+            app.ui.status = format!(
+                "Target month is missing or invalid (YYYY-MM); press {} to set it",
+                app.shortcuts.edit_job.target_month.join("/")
+            );
+            return Ok(false);
+        };
+        // 同じ月に提出済みなら、もう一度押されるまで送信しない。
+        if is_duplicate_submission(app, &target_month_ym, &job.drive_file_id)
+            && app.duplicate_confirm != Some(job.id)
+        {
+            app.duplicate_confirm = Some(job.id);
+            app.ui.status = format!(
+                "Already submitted for {}; press {} again to commit anyway",
+                target_month_ym,
+                app.shortcuts.edit_job.commit.join("/")
+            );
+            return Ok(false);
+        }
+        app.duplicate_confirm = None;
+        // 文字数の上限を適用する（切り詰めた場合は一覧側にも反映する）。
+        let mut job = job;
+        match app.cfg.field_limits.enforce(&mut job.fields) {
+            Ok(truncated) => {
+                if !truncated.is_empty() {
+                    app.ui.log.extend(truncated);
+                    if let Some(j) = app.jobs.get_mut(app.ui.selected) {
+                        j.fields = job.fields.clone();
+                    }
+                }
+            }
+            Err(problems) => {
+                app.ui.status = format!("Cannot commit: {}", problems.join("; "));
+                return Ok(false);
+            }
+        }
+        // 取り消せない処理なので、送信前に確認ポップアップを出す。
+        app.confirm_commit = Some(CommitRequest::for_job(&job, target_month_ym));
     } else if shortcuts::matches_shortcut(&k, &sc.target_month) {
         // 対象月の入力ボックスを開く。
         app.input_box = Some(InputBoxState {
@@ -165,31 +981,119 @@ async fn handle_edit_job_key(app: &mut App, k: KeyEvent) -> Result<bool> {
             value: app.edit_target_month.clone(),
             cursor: app.edit_target_month.chars().count(),
             callback_id: InputCallbackId::EditTargetMonth,
+            max_chars: None,
+            suggestions: vec![],
         });
     } else if shortcuts::matches_shortcut(&k, &sc.edit_field)
         && let Some(j) = app.jobs.get(app.ui.selected)
     {
         // 現在の編集対象フィールドに応じて入力ボックスを用意する。
-        let (prompt, value, field_idx) = match app.ui.editing_field_idx {
-            0 => ("Date (YYYY-MM-DD):", j.fields.date_ymd.clone(), 0),
-            1 => ("Reason:", j.fields.reason.clone(), 1),
-            2 => ("Amount (yen):", j.fields.amount_yen.to_string(), 2),
-            3 => ("Category:", j.fields.category.clone(), 3),
-            4 => ("Note:", j.fields.note.clone(), 4),
+        let field_idx = app.ui.editing_field_idx;
+        let prompt = match field_idx {
+            0 => "Date (YYYY-MM-DD):".to_string(),
+            1 => "Reason:".into(),
+            2 => amount_prompt(j.fields.currency_or(app.cfg.user.default_currency())),
+            3 => "Category:".into(),
+            4 => "Note:".into(),
+            5 => format!("Currency (empty for {}):", app.cfg.user.default_currency()),
             _ => return Ok(false),
         };
-        // 入力ボックスを表示する。
+        // 入力ボックスを表示する（上限があれば入力を制限する）。
         app.input_box = Some(InputBoxState {
-            prompt: prompt.into(),
-            value,
+            prompt,
+            value: j.fields.field_value(field_idx),
             cursor: 0,
             callback_id: InputCallbackId::EditJobField(field_idx),
+            max_chars: app.cfg.field_limits.limit_for(field_idx),
+            // 用途の入力時だけ補完候補を渡す。
+            suggestions: if field_idx == 1 {
+                app.cfg.reason_suggestions.clone()
+            } else {
+                vec![]
+            },
         });
+    } else if shortcuts::matches_shortcut(&k, &sc.set_today) {
+        // 日付フィールドを今日の日付にする。
+        let today = dates::today_ymd();
+        if set_selected_field(app, 0, today.clone()) {
+            app.ui.status = format!("Date set to {today}");
+            app.save_jobs();
+        }
+    } else if shortcuts::matches_shortcut(&k, &sc.undo) {
+        undo_field_edit(app);
+    } else if shortcuts::matches_shortcut(&k, &sc.reset_month) {
+        reset_target_month(app);
+    } else if shortcuts::matches_shortcut(&k, &sc.ocr) {
+        request_ocr(app).await?;
+    } else if shortcuts::matches_shortcut(&k, &sc.preview_write) {
+        // テンプレートを読み取り、確定時の書き込み先を見積もってもらう。
+        let rows = preview_rows(app);
+        app.worker_tx.send(WorkerCmd::PreviewWrite { rows }).await?;
+        app.ui.status = "Checking where the commit will write...".into();
+    } else if shortcuts::matches_shortcut(&k, &sc.copy_field)
+        && let Some(j) = app.jobs.get(app.ui.selected)
+    {
+        // 選択中フィールドの値をクリップボードへコピーする。
+        let field_idx = app.ui.editing_field_idx;
+        let value = j.fields.field_value(field_idx);
+        let on_system = app.clipboard.copy(&value);
+        app.ui.status = format!(
+            "Copied {}{}",
+            ReceiptFields::field_name(field_idx),
+            if on_system {
+                ""
+            } else {
+                " (internal clipboard)"
+            }
+        );
+    } else if shortcuts::matches_shortcut(&k, &sc.paste_field) {
+        // クリップボードの内容を選択中フィールドへ貼り付ける。
+        let field_idx = app.ui.editing_field_idx;
+        let Some(value) = app.clipboard.paste() else {
+            app.ui.status = "Clipboard is empty".into();
+            return Ok(false);
+        };
+        // 改行を含む貼り付けは1行目だけを使う。
+        let value = value.lines().next().unwrap_or("").to_string();
+        if set_selected_field(app, field_idx, value) {
+            app.ui.status = format!("Pasted into {}", ReceiptFields::field_name(field_idx));
+            app.save_jobs();
+        }
     }
 
     Ok(false)
 }
 
+/// 選択中ジョブのフィールドを更新し、値が変われば取り消せるよう変更前を残す。
+///
+/// ジョブが選択されていなければ何もせずfalseを返す。
+fn set_selected_field(app: &mut App, field_idx: usize, value: String) -> bool {
+    let Some(j) = app.jobs.get_mut(app.ui.selected) else {
+        return false;
+    };
+    let before = j.fields.clone();
+    j.fields.set_field_value(field_idx, value);
+    if j.fields.field_value(field_idx) != before.field_value(field_idx) {
+        app.edit_undo.push(j.id, before);
+    }
+    true
+}
+
+/// 選択中ジョブの直前のフィールド変更を取り消す。
+fn undo_field_edit(app: &mut App) {
+    let Some(j) = app.jobs.get_mut(app.ui.selected) else {
+        return;
+    };
+    match app.edit_undo.pop(j.id) {
+        Some(before) => {
+            j.fields = before;
+            app.ui.status = "Undid last change".into();
+            app.save_jobs();
+        }
+        None => app.ui.status = "Nothing to undo".into(),
+    }
+}
+
 /// 初期設定ウィザード画面のキー処理。
 async fn handle_wizard_key(app: &mut App, k: KeyEvent) -> Result<bool> {
     // ウィザード画面のショートカットを参照する。
@@ -202,9 +1106,10 @@ async fn handle_wizard_key(app: &mut App, k: KeyEvent) -> Result<bool> {
                 app.wizard_state.next_step();
             }
             WizardStep::CheckAuth => {
-                // credentials.json の存在チェックを行う。
-                if !std::path::Path::new("assets/credentials.json").exists() {
-                    app.ui.error = Some("assets/credentials.json not found. Please add it.".into());
+                // 設定されたcredentials.jsonの存在チェックを行う。
+                let path = app.cfg.google.credentials_path();
+                if !Path::new(path).exists() {
+                    app.ui.error = Some(format!("{path} not found. Please add it."));
                 } else {
                     // エラーを解除して次へ進む。
                     app.ui.error = None;
@@ -218,6 +1123,8 @@ async fn handle_wizard_key(app: &mut App, k: KeyEvent) -> Result<bool> {
                     value: app.in_folder.clone(),
                     cursor: app.in_folder.chars().count(),
                     callback_id: InputCallbackId::WizardInputFolder,
+                    max_chars: None,
+                    suggestions: vec![],
                 });
             }
             WizardStep::OutputFolderId => {
@@ -227,6 +1134,8 @@ async fn handle_wizard_key(app: &mut App, k: KeyEvent) -> Result<bool> {
                     value: app.out_folder.clone(),
                     cursor: app.out_folder.chars().count(),
                     callback_id: InputCallbackId::WizardOutputFolder,
+                    max_chars: None,
+                    suggestions: vec![],
                 });
             }
             WizardStep::TemplateSheetId => {
@@ -236,6 +1145,8 @@ async fn handle_wizard_key(app: &mut App, k: KeyEvent) -> Result<bool> {
                     value: app.template_id.clone(),
                     cursor: app.template_id.chars().count(),
                     callback_id: InputCallbackId::WizardTemplateId,
+                    max_chars: None,
+                    suggestions: vec![],
                 });
             }
             WizardStep::UserName => {
@@ -245,8 +1156,37 @@ async fn handle_wizard_key(app: &mut App, k: KeyEvent) -> Result<bool> {
                     value: app.full_name.clone(),
                     cursor: app.full_name.chars().count(),
                     callback_id: InputCallbackId::WizardFullName,
+                    max_chars: None,
+                    suggestions: vec![],
                 });
             }
+            WizardStep::Verify => {
+                let wizard = &mut app.wizard_state;
+                if wizard.checks_running() {
+                    // 結果を待つ間は何もしない。
+                    app.ui.status = "Verifying setup...".into();
+                } else if wizard.checks_passed() {
+                    // すべて成功していれば完了へ進む。
+                    app.ui.error = None;
+                    wizard.next_step();
+                } else if let Some(check) = wizard.first_failed_check() {
+                    // 失敗した項目を直すステップへ戻る。
+                    app.ui.error = None;
+                    wizard.go_to(check.step());
+                    app.ui.status = format!("Fix the {} and verify again", check.label());
+                } else {
+                    // 入力したIDで接続テストを始める。
+                    wizard.start_checks();
+                    app.ui.error = None;
+                    app.ui.status = "Verifying setup...".into();
+                    app.worker_tx
+                        .send(WorkerCmd::VerifySetup {
+                            input_folder_id: app.in_folder.clone(),
+                            template_sheet_id: app.template_id.clone(),
+                        })
+                        .await?;
+                }
+            }
             WizardStep::Complete => {
                 // 必須項目が揃っているか検証する。
                 if app.in_folder.is_empty()
@@ -263,11 +1203,11 @@ async fn handle_wizard_key(app: &mut App, k: KeyEvent) -> Result<bool> {
                 app.cfg.google.output_folder_id = app.out_folder.clone();
                 app.cfg.google.template_sheet_id = app.template_id.clone();
                 app.cfg.user.full_name = app.full_name.clone();
-                app.cfg.save(&app.cfg_path)?;
+                save_config(app)?;
 
                 // Workerへ設定更新を通知する。
                 app.worker_tx
-                    .send(WorkerCmd::SaveSettings(app.cfg.clone()))
+                    .send(WorkerCmd::SaveSettings(Box::new(app.cfg.clone())))
                     .await?;
 
                 // メイン画面へ移動して一覧を更新する。
@@ -279,6 +1219,10 @@ async fn handle_wizard_key(app: &mut App, k: KeyEvent) -> Result<bool> {
     } else if shortcuts::matches_shortcut(&k, &sc.skip) {
         // 現在のステップをスキップする。
         app.wizard_state.next_step();
+    } else if shortcuts::matches_shortcut(&k, &sc.back) {
+        // 前のステップへ戻る（前のステップのエラーは消す）。
+        app.ui.error = None;
+        app.wizard_state.prev_step();
     }
 
     Ok(false)
@@ -306,13 +1250,28 @@ async fn handle_input_box_key(app: &mut App, k: KeyEvent) -> Result<bool> {
         // 入力ボックスを閉じる前に値とコールバック種別を保存する。
         let value = input_state.value.clone();
         let callback_id = input_state.callback_id.clone();
+        // 日付や月の書式が不正なら、入力ボックスを開いたまま理由を表示する。
+        if callback_id.has_format() {
+            if let Some(err) = input_format_error(&callback_id, &value) {
+                app.ui.error = Some(err);
+                return Ok(false);
+            }
+            app.ui.error = None;
+        }
         app.input_box = None;
 
         // コールバック種別に応じて値を反映する。
         apply_input_callback(app, callback_id, value).await?;
     } else if shortcuts::matches_shortcut(&k, &sc.cancel) {
-        // 入力を破棄して入力ボックスを閉じる。
+        // 入力を破棄して入力ボックスを閉じる（元の値やバッファは変更しない）。
+        let is_wizard = input_state.callback_id.is_wizard();
         app.input_box = None;
+        // 何も保存されていないことを伝える（ウィザードは同じステップに留まる）。
+        app.ui.status = if is_wizard {
+            "Input canceled — press Enter to re-enter".into()
+        } else {
+            "Input canceled; value unchanged".into()
+        };
     } else if shortcuts::matches_shortcut(&k, &sc.backspace) {
         // バックスペースを処理する。
         input_state.backspace();
@@ -322,6 +1281,12 @@ async fn handle_input_box_key(app: &mut App, k: KeyEvent) -> Result<bool> {
     } else if shortcuts::matches_shortcut(&k, &sc.left) {
         // 左移動を処理する。
         input_state.move_left();
+    } else if (shortcuts::matches_shortcut(&k, &sc.accept_suggestion)
+        || (k.code == KeyCode::Right && input_state.cursor_at_end()))
+        && input_state.completion().is_some()
+    {
+        // 補完候補を確定する。
+        input_state.accept_suggestion();
     } else if shortcuts::matches_shortcut(&k, &sc.right) {
         // 右移動を処理する。
         input_state.move_right();
@@ -345,48 +1310,148 @@ async fn handle_input_box_key(app: &mut App, k: KeyEvent) -> Result<bool> {
         }
     }
 
+    // 検索中は入力が変わるたびに最初の一致へ移動する。
+    if let Some(state) = &app.input_box
+        && state.callback_id == InputCallbackId::JobSearch
+    {
+        let query = state.value.clone();
+        if let Some(&first) = search_matches(app, &query).first() {
+            app.select_job(first);
+        }
+        app.ui.status = search_status(app, &query);
+    }
+
     Ok(false)
 }
 
-/// 入力ボックスのコールバックを適用する。
-async fn apply_input_callback(
+/// ファイル名に`query`を含む表示中のジョブの添字（大文字小文字は区別しない）。
+fn search_matches(app: &App, query: &str) -> Vec<usize> {
+    let query = query.to_lowercase();
+    if query.is_empty() {
+        return vec![];
+    }
+    app.visible_job_indices()
+        .into_iter()
+        .filter(|&i| app.jobs[i].filename.to_lowercase().contains(&query))
+        .collect()
+}
+
+/// 検索語の一致件数と、選択中のジョブが何件目かを表す文言（例: `Search "jr": 3/12`）。
+fn search_status(app: &App, query: &str) -> String {
+    if query.is_empty() {
+        return "Search: type part of a filename".into();
+    }
+    let matches = search_matches(app, query);
+    if matches.is_empty() {
+        return format!("Search {query:?}: no match");
+    }
+    match matches.iter().position(|&i| i == app.ui.selected) {
+        Some(pos) => format!("Search {query:?}: {}/{}", pos + 1, matches.len()),
+        None => format!("Search {query:?}: {} matches", matches.len()),
+    }
+}
+
+/// 最後の検索語に一致する次（`forward`でなければ前）のジョブへ移動する（端では折り返す）。
+fn jump_to_search_match(app: &mut App, forward: bool) {
+    let Some(query) = app.ui.search.clone() else {
+        app.ui.status = "No search yet".into();
+        return;
+    };
+    let matches = search_matches(app, &query);
+    let selected = app.ui.selected;
+    let next = if forward {
+        matches.iter().find(|&&i| i > selected).or(matches.first())
+    } else {
+        matches
+            .iter()
+            .rev()
+            .find(|&&i| i < selected)
+            .or(matches.last())
+    };
+    if let Some(&i) = next {
+        app.select_job(i);
+    }
+    app.ui.status = search_status(app, &query);
+}
+
+/// 金額入力のプロンプト（円はこれまで通り"yen"と表示する）。
+fn amount_prompt(currency: &str) -> String {
+    if currency.eq_ignore_ascii_case(config::DEFAULT_CURRENCY) {
+        "Amount (yen):".into()
+    } else {
+        format!("Amount ({}):", currency.to_ascii_uppercase())
+    }
+}
+
+/// 書式の決まった入力を検証し、不正なら理由を返す。
+fn input_format_error(callback_id: &InputCallbackId, value: &str) -> Option<String> {
+    match callback_id {
+        // 日付は空欄（未入力に戻す）も許す。
+        InputCallbackId::EditJobField(0) if !value.is_empty() && !dates::is_valid_ymd(value) => {
+            Some(format!("Invalid date {value:?}: expected YYYY-MM-DD"))
+        }
+        // 金額は空欄（未入力に戻す）か整数のみ許す。
+        InputCallbackId::EditJobField(2)
+            if !value.trim().is_empty() && value.trim().parse::<i64>().is_err() =>
+        {
+            Some(format!("Invalid amount {value:?}: expected a whole number"))
+        }
+        // 通貨コードも空欄（既定の通貨に戻す）を許す。
+        InputCallbackId::EditJobField(5)
+            if !value.trim().is_empty() && !config::is_currency_code(value.trim()) =>
+        {
+            Some(format!(
+                "Invalid currency {value:?}: expected a 3-letter code"
+            ))
+        }
+        InputCallbackId::EditTargetMonth | InputCallbackId::RetryWithMonth
+            if !dates::is_valid_ym(value.trim()) =>
+        {
+            Some(format!("Invalid month {value:?}: expected YYYY-MM"))
+        }
+        _ => None,
+    }
+}
+
+/// 入力ボックスのコールバックを適用する。
+async fn apply_input_callback(
     app: &mut App,
     callback_id: InputCallbackId,
     value: String,
 ) -> Result<()> {
     match callback_id {
-        InputCallbackId::SettingsInputFolder => app.in_folder = value,
-        InputCallbackId::SettingsOutputFolder => app.out_folder = value,
-        InputCallbackId::SettingsTemplateId => app.template_id = value,
+        InputCallbackId::SettingsInputFolder => app.in_folder = clean_id_input(app, value),
+        InputCallbackId::SettingsOutputFolder => app.out_folder = clean_id_input(app, value),
+        InputCallbackId::SettingsTemplateId => app.template_id = clean_id_input(app, value),
         InputCallbackId::SettingsFullName => app.full_name = value,
+        InputCallbackId::ExportXlsxPath => export_xlsx(app, &value),
+        InputCallbackId::ExportCsvPath => export_csv(app, &value),
+        InputCallbackId::RetryWithMonth => retry_with_month(app, value).await?,
+        InputCallbackId::JobSearch => {
+            // 次/前の一致へ移動できるよう検索語を覚える（空なら検索を解除する）。
+            app.ui.status = search_status(app, &value);
+            app.ui.search = Some(value).filter(|q| !q.is_empty());
+        }
         InputCallbackId::EditTargetMonth => app.edit_target_month = value,
         InputCallbackId::EditJobField(field_idx) => {
             // 対象ジョブのフィールドを更新する。
-            if let Some(j) = app.jobs.get_mut(app.ui.selected) {
-                match field_idx {
-                    0 => j.fields.date_ymd = value,
-                    1 => j.fields.reason = value,
-                    2 => j.fields.amount_yen = value.parse().unwrap_or(0),
-                    3 => j.fields.category = value,
-                    4 => j.fields.note = value,
-                    _ => {}
-                }
-            }
+            set_selected_field(app, field_idx, value);
+            app.save_jobs();
         }
         InputCallbackId::WizardInputFolder => {
-            // ウィザードの入力フォルダIDを更新し次へ進む。
-            app.in_folder = value;
-            app.wizard_state.next_step();
+            // ウィザードの入力フォルダIDを更新し、確認が済んだら次へ進む。
+            app.in_folder = clean_id_input(app, value);
+            check_wizard_id(app, IdKind::InputFolder, app.in_folder.clone()).await?;
         }
         InputCallbackId::WizardOutputFolder => {
-            // ウィザードの出力フォルダIDを更新し次へ進む。
-            app.out_folder = value;
-            app.wizard_state.next_step();
+            // ウィザードの出力フォルダIDを更新し、確認が済んだら次へ進む。
+            app.out_folder = clean_id_input(app, value);
+            check_wizard_id(app, IdKind::OutputFolder, app.out_folder.clone()).await?;
         }
         InputCallbackId::WizardTemplateId => {
-            // ウィザードのテンプレートIDを更新し次へ進む。
-            app.template_id = value;
-            app.wizard_state.next_step();
+            // ウィザードのテンプレートIDを更新し、確認が済んだら次へ進む。
+            app.template_id = clean_id_input(app, value);
+            check_wizard_id(app, IdKind::TemplateSheet, app.template_id.clone()).await?;
         }
         InputCallbackId::WizardFullName => {
             // ウィザードの氏名を更新し次へ進む。
@@ -397,6 +1462,137 @@ async fn apply_input_callback(
     Ok(())
 }
 
+/// ウィザードで入力したIDの確認をWorkerへ依頼する（結果は`IdChecked`で届く）。
+///
+/// 空欄は確認せずに次へ進み、完了時の必須項目チェックに任せる。
+async fn check_wizard_id(app: &mut App, kind: IdKind, id: String) -> Result<()> {
+    if id.is_empty() {
+        app.wizard_state.next_step();
+        return Ok(());
+    }
+    app.ui.status = format!("Checking {}...", kind.label());
+    app.worker_tx.send(WorkerCmd::CheckId { kind, id }).await?;
+    Ok(())
+}
+
+/// 貼り付けたIDを正規化し、変化があればユーザーへ知らせる。
+fn clean_id_input(app: &mut App, value: String) -> String {
+    // 空白や不可視文字を取り除き、URLならIDの部分を取り出す。
+    let cleaned = validation::extract_drive_id(&value);
+    if cleaned != value {
+        // ログファイルにはIDそのものを残さず、長さの変化と伏せた値だけを書く。
+        tracing::info!(
+            "sanitized id input: {} chars -> {} chars ({})",
+            value.chars().count(),
+            cleaned.chars().count(),
+            format::display_id(&cleaned, true)
+        );
+        // 何が除去されたか分かるよう、元の値をエスケープ表示で残す。
+        let mask = app.cfg.ui.mask_sensitive;
+        app.ui.log.push(format!(
+            "ID cleaned: {:?} -> {}",
+            format::display_id(&value, mask),
+            format::display_id(&cleaned, mask)
+        ));
+        app.ui.status = format!("Cleaned ID: {}", format::display_id(&cleaned, mask));
+    }
+    cleaned
+}
+
+/// 設定画面の編集バッファを設定へ反映し、保存してWorkerへ通知する。
+async fn apply_settings(app: &mut App) -> Result<()> {
+    // 編集バッファを設定へ反映する。
+    app.cfg.google.input_folder_id = app.in_folder.clone();
+    app.cfg.google.output_folder_id = app.out_folder.clone();
+    app.cfg.google.template_sheet_id = app.template_id.clone();
+    app.cfg.user.full_name = app.full_name.clone();
+    // 設定ファイルを保存する。
+    save_config(app)?;
+
+    // Workerにも設定更新を通知する。
+    app.worker_tx
+        .send(WorkerCmd::SaveSettings(Box::new(app.cfg.clone())))
+        .await?;
+    Ok(())
+}
+
+/// 次のプロファイルへ切り替え、Workerへ反映して一覧を読み直す。
+///
+/// 設定画面で編集中の値は、切り替え前のプロファイルに保存する。
+/// 確定のリクエストは氏名やテンプレートを持たないため、確定が残っている間は切り替えない。
+async fn switch_profile(app: &mut App) -> Result<()> {
+    if app.cfg.profiles.len() < 2 {
+        app.ui.status = "No other profile (add [[profiles]] to config.toml)".into();
+        return Ok(());
+    }
+    if app.commits_in_flight() > 0
+        || !app.pending_commits.is_empty()
+        || !app.retry_prompts.is_empty()
+    {
+        app.ui.status = "Busy: finish pending commits before switching profile".into();
+        tracing::info!("profile switch skipped: commits pending");
+        return Ok(());
+    }
+    app.cfg.google.input_folder_id = app.in_folder.clone();
+    app.cfg.google.output_folder_id = app.out_folder.clone();
+    app.cfg.google.template_sheet_id = app.template_id.clone();
+    app.cfg.user.full_name = app.full_name.clone();
+    // 切り替え前のジョブはそのプロファイルの保存ファイルへ残す。
+    app.save_jobs();
+    app.cfg.cycle_profile();
+    reload_settings_buffers(app);
+    save_config(app)?;
+
+    // 切り替え先の保存ファイルから復元し、Driveの一覧が届いたら突き合わせる。
+    app.jobs.clear();
+    app.restored_jobs.clear();
+    app.next_page_token = None;
+    app.ui.selected = 0;
+    app.edit_undo = EditUndo::default();
+    if app.jobs_path.is_some() {
+        let jobs_path = job_store::path_for(&app.cfg_path, app.cfg.active_profile_name());
+        app.restored_jobs = job_store::load(&jobs_path);
+        app.jobs_path = Some(jobs_path);
+    }
+
+    // Workerへ新しいIDを渡してから一覧を読み直す。
+    app.worker_tx
+        .send(WorkerCmd::SaveSettings(Box::new(app.cfg.clone())))
+        .await?;
+    request_refresh(app).await?;
+    // 読み直しの状況に切り替え先の名前を添える。
+    let name = app.cfg.active_profile_name().unwrap_or_default();
+    app.ui.log.push(format!("Switched to profile {name}"));
+    app.ui.status = format!("Profile {name}: {}", app.ui.status);
+    Ok(())
+}
+
+/// 設定をファイルへ保存する（読めなかったファイルは上書きしない）。
+fn save_config(app: &mut App) -> Result<()> {
+    // 設定ファイルの変更検知で自分の保存を変更と誤らないよう、メモリ上のプロファイルも揃える。
+    app.cfg.store_active_profile();
+    if app.cfg_read_only {
+        // 読めなかったファイルを既定値ベースの内容で上書きしないよう、保存を見送る。
+        tracing::warn!(
+            "skip saving {}: file was unreadable",
+            app.cfg_path.display()
+        );
+        app.ui.log.push(format!(
+            "{} is unreadable; settings apply to this session only",
+            app.cfg_path.display()
+        ));
+        return Ok(());
+    }
+    app.cfg.save(&app.cfg_path)
+}
+
+/// 設定画面へ遷移し、編集バッファを設定値で更新する。
+fn open_settings(app: &mut App) {
+    reload_settings_buffers(app);
+    app.ui.screen = Screen::Settings;
+    app.ui.status = "Settings".into();
+}
+
 /// 設定画面用の編集バッファを設定値から再読み込みする。
 fn reload_settings_buffers(app: &mut App) {
     // 設定の現在値を編集用バッファへ反映する。
@@ -405,3 +1601,1286 @@ fn reload_settings_buffers(app: &mut App) {
     app.template_id = app.cfg.google.template_sheet_id.clone();
     app.full_name = app.cfg.user.full_name.clone();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::tests::test_app;
+    use crate::config::CategoryRule;
+    use crate::diagnostics::DiagnosticsView;
+    use crate::events::{RetryPrompt, StatusFilter};
+    use crate::jobs::Job;
+    use crate::worker::WorkerEvent;
+
+    /// 修飾キー無しのキーイベントを作る。
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::empty())
+    }
+
+    /// 既定の必須項目（日付・用途・金額）を入力済みのジョブを作る。
+    fn filled_job(drive_file_id: &str, filename: &str) -> Job {
+        let mut job = Job::new(drive_file_id.into(), filename.into());
+        job.fields.date_ymd = "2025-12-01".into();
+        job.fields.reason = "タクシー".into();
+        job.fields.amount_yen = 1200;
+        job
+    }
+
+    #[tokio::test]
+    async fn test_retry_prompt_resends_commit() {
+        // 再試行プロンプトで再試行を選ぶと確定が再送されることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        let mut job = Job::new("f1".into(), "a.jpg".into());
+        job.failed_month = Some("2025-11".into());
+        app.retry_prompts.push_back(RetryPrompt {
+            job_id: job.id,
+            reason: "boom".into(),
+        });
+        app.jobs.push(job);
+
+        // プロンプト中は他の操作を受け付けないが、終了はできる。
+        handle_key(&mut app, key(KeyCode::Char('c'))).await.unwrap();
+        assert_eq!(app.retry_prompts.len(), 1);
+        assert!(rx_cmd.try_recv().is_err());
+        assert!(handle_key(&mut app, key(KeyCode::Char('q'))).await.unwrap());
+        assert_eq!(app.retry_prompts.len(), 1);
+
+        // 再送は全体の対象月ではなく、失敗したときの対象月で行う。
+        handle_key(&mut app, key(KeyCode::Char('r'))).await.unwrap();
+        assert!(app.retry_prompts.is_empty());
+        assert!(matches!(
+            rx_cmd.try_recv(),
+            Ok(WorkerCmd::CommitJobEdits(req))
+                if req.drive_file_id == "f1" && req.target_month_ym == "2025-11"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retry_prompt_queues_while_paused() {
+        // 一時停止中に再試行を選ぶと、送信せずに保留へ積まれることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        let mut job = Job::new("f1".into(), "a.jpg".into());
+        job.failed_month = Some("2025-11".into());
+        app.retry_prompts.push_back(RetryPrompt {
+            job_id: job.id,
+            reason: "boom".into(),
+        });
+        app.jobs.push(job);
+        app.paused = true;
+
+        handle_key(&mut app, key(KeyCode::Char('r'))).await.unwrap();
+        assert!(app.retry_prompts.is_empty());
+        assert!(rx_cmd.try_recv().is_err());
+        assert_eq!(app.pending_commits.len(), 1);
+        assert_eq!(app.pending_commits[0].target_month_ym, "2025-11");
+        assert_eq!(app.ui.status, "paused (1 queued)");
+    }
+
+    #[tokio::test]
+    async fn test_global_settings_key_across_screens() {
+        // 全画面共通キーでどの画面からでも設定画面へ移れることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        app.jobs.push(Job::new("f1".into(), "a.jpg".into()));
+        let ctrl_t = KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL);
+
+        for screen in [
+            Screen::Main,
+            Screen::EditJob,
+            Screen::Monitor,
+            Screen::Settings,
+        ] {
+            app.ui.screen = screen;
+            app.full_name = "stale".into();
+            handle_key(&mut app, ctrl_t).await.unwrap();
+            assert_eq!(app.ui.screen, Screen::Settings);
+            // 編集バッファが設定値から読み直される。
+            assert_eq!(app.full_name, app.cfg.user.full_name);
+        }
+
+        // 入力ボックスが開いている間は発火しない。
+        app.ui.screen = Screen::EditJob;
+        handle_key(&mut app, key(KeyCode::Char('e'))).await.unwrap();
+        assert!(app.input_box.is_some());
+        handle_key(&mut app, ctrl_t).await.unwrap();
+        assert_eq!(app.ui.screen, Screen::EditJob);
+    }
+
+    #[tokio::test]
+    async fn test_apply_settings_stays_on_settings() {
+        // 適用キーは保存してWorkerへ通知し、設定画面に留まることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.cfg_path =
+            std::env::temp_dir().join(format!("receipt_tui_{}.toml", uuid::Uuid::new_v4()));
+        app.ui.screen = Screen::Settings;
+        app.full_name = "山田 太郎".into();
+
+        let ctrl_s = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        handle_key(&mut app, ctrl_s).await.unwrap();
+        assert_eq!(app.ui.screen, Screen::Settings);
+        assert_eq!(app.ui.status, "Applied settings");
+        assert_eq!(app.cfg.user.full_name, "山田 太郎");
+        assert!(matches!(rx_cmd.try_recv(), Ok(WorkerCmd::SaveSettings(_))));
+        assert!(app.cfg_path.exists());
+        std::fs::remove_file(&app.cfg_path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_commit_blocked_with_default_name() {
+        // 氏名が既定値のままだと確定が送信されないことを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.jobs.push(filled_job("f1", "a.jpg"));
+        app.ui.screen = Screen::EditJob;
+
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(rx_cmd.try_recv().is_err());
+        assert_eq!(app.ui.screen, Screen::EditJob);
+        assert!(app.ui.status.contains("Full name is not set"));
+
+        // 氏名を設定すれば確定できる。
+        app.cfg.user.full_name = "山田 太郎".into();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        handle_key(&mut app, key(KeyCode::Char('y'))).await.unwrap();
+        assert!(matches!(
+            rx_cmd.try_recv(),
+            Ok(WorkerCmd::CommitJobEdits(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_commit_waits_for_confirmation() {
+        // 確定キーでは確認ポップアップが開くだけで、はいで送信し、いいえで取り消せることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.cfg.user.full_name = "山田 太郎".into();
+        app.jobs.push(filled_job("f1", "a.jpg"));
+        app.ui.screen = Screen::EditJob;
+
+        // いいえで閉じると送信されず、編集画面に残る。
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(app.confirm_commit.is_some());
+        assert!(rx_cmd.try_recv().is_err());
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(rx_cmd.try_recv().is_err());
+        handle_key(&mut app, key(KeyCode::Char('n'))).await.unwrap();
+        assert!(app.confirm_commit.is_none());
+        assert!(rx_cmd.try_recv().is_err());
+        assert_eq!(app.ui.screen, Screen::EditJob);
+
+        // はいで送信され、メイン画面へ戻る。
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        handle_key(&mut app, key(KeyCode::Char('y'))).await.unwrap();
+        assert!(app.confirm_commit.is_none());
+        assert!(matches!(
+            rx_cmd.try_recv(),
+            Ok(WorkerCmd::CommitJobEdits(_))
+        ));
+        assert_eq!(app.ui.screen, Screen::Main);
+    }
+
+    #[tokio::test]
+    async fn test_commit_blocked_while_refreshing() {
+        // リフレッシュ中は確定を、確定中はリフレッシュを受け付けず、完了後に再び受け付けることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.cfg.user.full_name = "山田 太郎".into();
+        app.cfg.google.input_folder_id = "in".into();
+        app.cfg.google.output_folder_id = "out".into();
+        app.cfg.google.template_sheet_id = "tpl".into();
+        app.jobs.push(filled_job("f1", "a.jpg"));
+
+        handle_key(&mut app, key(KeyCode::Char('r'))).await.unwrap();
+        assert!(matches!(rx_cmd.try_recv(), Ok(WorkerCmd::RefreshJobs)));
+        assert!(app.is_refreshing());
+
+        // 他の操作のエラーが先に届いても、リフレッシュ中のまま。
+        crate::app::handle_worker_event(
+            &mut app,
+            WorkerEvent::Error(crate::google::error::WorkerError::Other(
+                "OCR failed".into(),
+            )),
+        )
+        .unwrap();
+        assert!(app.is_refreshing());
+
+        // リフレッシュ中の確定は確認ポップアップも開かない。
+        app.ui.screen = Screen::EditJob;
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(app.confirm_commit.is_none());
+        assert!(rx_cmd.try_recv().is_err());
+        assert_eq!(
+            app.ui.status,
+            "Busy: refreshing jobs; commit when it finishes"
+        );
+
+        // 一覧が届いたら確定できる。
+        let jobs = vec![Job::new("f1".into(), "a.jpg".into())];
+        crate::app::handle_worker_event(
+            &mut app,
+            WorkerEvent::JobsLoaded {
+                jobs,
+                next_page_token: None,
+            },
+        )
+        .unwrap();
+        assert!(!app.is_refreshing());
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        handle_key(&mut app, key(KeyCode::Char('y'))).await.unwrap();
+        assert!(matches!(
+            rx_cmd.try_recv(),
+            Ok(WorkerCmd::CommitJobEdits(_))
+        ));
+        assert_eq!(app.commits_in_flight(), 1);
+
+        // 確定中のリフレッシュは送らない。
+        handle_key(&mut app, key(KeyCode::Char('r'))).await.unwrap();
+        assert!(rx_cmd.try_recv().is_err());
+        assert_eq!(app.ui.status, "Busy: committing; refresh when it finishes");
+
+        // 確定が終わればリフレッシュできる。
+        let job_id = app.jobs[0].id;
+        crate::app::handle_worker_event(
+            &mut app,
+            WorkerEvent::JobUpdated {
+                job_id,
+                status: JobStatus::Done,
+            },
+        )
+        .unwrap();
+        assert_eq!(app.commits_in_flight(), 0);
+        handle_key(&mut app, key(KeyCode::Char('r'))).await.unwrap();
+        assert!(matches!(rx_cmd.try_recv(), Ok(WorkerCmd::RefreshJobs)));
+    }
+
+    #[tokio::test]
+    async fn test_logout_returns_to_auth_check() {
+        // 設定画面からログアウトを依頼し、完了通知で認証の確認へ戻ることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.ui.screen = Screen::Settings;
+        handle_key(&mut app, key(KeyCode::Char('l'))).await.unwrap();
+        assert!(matches!(rx_cmd.try_recv(), Ok(WorkerCmd::Logout)));
+        assert_eq!(app.ui.status, "Logging out...");
+
+        crate::app::handle_worker_event(&mut app, WorkerEvent::LoggedOut).unwrap();
+        assert_eq!(app.ui.screen, Screen::InitialSetup);
+        assert_eq!(app.wizard_state.current_step, WizardStep::CheckAuth);
+    }
+
+    #[tokio::test]
+    async fn test_ocr_refused_for_pdf() {
+        // PDFのジョブでは文字認識を依頼せず、未対応と表示することを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        let mut job = Job::new("f1".into(), "a.pdf".into());
+        job.mime_type = "application/pdf".into();
+        app.jobs.push(job);
+
+        handle_key(&mut app, key(KeyCode::Char('o'))).await.unwrap();
+        assert!(rx_cmd.try_recv().is_err());
+        assert_eq!(app.ui.status, "OCR not supported for PDF: a.pdf");
+    }
+
+    #[tokio::test]
+    async fn test_ocr_fills_only_empty_fields() {
+        // 選択ジョブの文字認識を依頼し、推測は空欄の項目にだけ入ることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.jobs.push(Job::new("f1".into(), "a.jpg".into()));
+        app.jobs[0].fields.date_ymd = "2025-12-01".into();
+        let job_id = app.jobs[0].id;
+
+        handle_key(&mut app, key(KeyCode::Char('o'))).await.unwrap();
+        match rx_cmd.try_recv() {
+            Ok(WorkerCmd::OcrJob {
+                job_id: id,
+                drive_file_id,
+            }) => {
+                assert_eq!(id, job_id);
+                assert_eq!(drive_file_id, "f1");
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+
+        let guess = crate::ocr::OcrGuess {
+            date_ymd: Some("2025-12-19".into()),
+            amount_yen: Some(770),
+        };
+        crate::app::handle_worker_event(&mut app, WorkerEvent::OcrGuessed { job_id, guess })
+            .unwrap();
+        assert_eq!(app.jobs[0].fields.date_ymd, "2025-12-01");
+        assert_eq!(app.jobs[0].fields.amount_yen, 770);
+        assert_eq!(app.ui.status, "OCR filled amount for a.jpg");
+    }
+
+    #[tokio::test]
+    async fn test_reload_config_changed_on_disk() {
+        // 外部で変更された設定は確認後に読み直され、Workerへも送られることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        let path =
+            std::env::temp_dir().join(format!("receipt_tui_reload_{}.toml", uuid::Uuid::new_v4()));
+        app.cfg_path = path.clone();
+        app.cfg.save(&path).unwrap();
+
+        // 自分で保存しただけなら尋ねない。
+        app.on_config_files_changed(std::slice::from_ref(&path));
+        assert!(app.config_reload.is_none());
+
+        // 外部で書き換えられると確認が出て、はいで読み直す。
+        let mut edited = app.cfg.clone();
+        edited.user.full_name = "山田 太郎".into();
+        edited.save(&path).unwrap();
+        app.on_config_files_changed(std::slice::from_ref(&path));
+        assert!(app.config_reload.is_some());
+        handle_key(&mut app, key(KeyCode::Char('y'))).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(app.config_reload.is_none());
+        assert_eq!(app.cfg.user.full_name, "山田 太郎");
+        assert_eq!(app.full_name, "山田 太郎");
+        match rx_cmd.try_recv() {
+            Ok(WorkerCmd::SaveSettings(cfg)) => assert_eq!(cfg.user.full_name, "山田 太郎"),
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_switch_profile_updates_worker_and_refreshes() {
+        // 設定画面でプロファイルを切り替えると、IDが入れ替わってWorkerへ送られ一覧を読み直すことを検証する。
+        use crate::config::Profile;
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.cfg_path =
+            std::env::temp_dir().join(format!("receipt_tui_profile_{}.toml", uuid::Uuid::new_v4()));
+        app.ui.screen = Screen::Settings;
+
+        // プロファイルが無ければ何もしない。
+        handle_key(&mut app, key(KeyCode::Char('P'))).await.unwrap();
+        assert!(rx_cmd.try_recv().is_err());
+
+        app.cfg.profiles = vec![
+            Profile {
+                name: "me".into(),
+                ..Profile::default()
+            },
+            Profile {
+                name: "spouse".into(),
+                full_name: "Hanako".into(),
+                input_folder_id: "in2".into(),
+                output_folder_id: "out2".into(),
+                template_sheet_id: "tpl2".into(),
+                required_fields: None,
+            },
+        ];
+        app.in_folder = "in1".into();
+        handle_key(&mut app, key(KeyCode::Char('P'))).await.unwrap();
+        assert_eq!(app.ui.status, "Profile spouse: Refreshing jobs...");
+        assert_eq!(app.in_folder, "in2");
+        assert_eq!(app.cfg.profiles[0].input_folder_id, "in1");
+        assert!(matches!(
+            rx_cmd.try_recv(),
+            Ok(WorkerCmd::SaveSettings(cfg)) if cfg.google.input_folder_id == "in2"
+        ));
+        assert!(matches!(rx_cmd.try_recv(), Ok(WorkerCmd::RefreshJobs)));
+        let saved = Config::load_or_default(&app.cfg_path).unwrap().value;
+        std::fs::remove_file(&app.cfg_path).ok();
+        assert_eq!(saved.active_profile, 1);
+        assert_eq!(saved.google.template_sheet_id, "tpl2");
+    }
+
+    #[tokio::test]
+    async fn test_switch_profile_waits_for_commits_and_keeps_jobs_apart() {
+        // 確定が残っている間は切り替えず、ジョブはプロファイルごとのファイルへ分けて保存されることを検証する。
+        use crate::config::Profile;
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        let dir =
+            std::env::temp_dir().join(format!("receipt_tui_profile_jobs_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        app.cfg_path = dir.join("config.toml");
+        app.ui.screen = Screen::Settings;
+        app.cfg.profiles = vec![
+            Profile {
+                name: "me".into(),
+                ..Profile::default()
+            },
+            Profile {
+                name: "spouse".into(),
+                ..Profile::default()
+            },
+        ];
+        app.jobs_path = Some(job_store::path_for(&app.cfg_path, Some("me")));
+        let mut job = Job::new("a".into(), "a.jpg".into());
+        job.fields.reason = "タクシー".into();
+        app.jobs.push(job);
+
+        // 保留中の確定があれば切り替えない。
+        app.pending_commits
+            .push_back(CommitRequest::for_job(&app.jobs[0], "2025-12".into()));
+        handle_key(&mut app, key(KeyCode::Char('P'))).await.unwrap();
+        assert_eq!(app.cfg.active_profile, 0);
+        assert!(rx_cmd.try_recv().is_err());
+        assert!(app.ui.status.starts_with("Busy"));
+        app.pending_commits.clear();
+
+        // 切り替えると元のジョブは元のプロファイルのファイルに残り、一覧は空から読み直す。
+        handle_key(&mut app, key(KeyCode::Char('P'))).await.unwrap();
+        assert_eq!(app.cfg.active_profile, 1);
+        assert!(app.jobs.is_empty());
+        assert_eq!(app.jobs_path, Some(dir.join("jobs_spouse.json")));
+        let me = job_store::load(&dir.join("jobs_me.json"));
+        assert_eq!(me.len(), 1);
+        assert_eq!(me[0].fields.reason, "タクシー");
+
+        // 戻れば、そのプロファイルで編集していたジョブが復元される。
+        handle_key(&mut app, key(KeyCode::Char('P'))).await.unwrap();
+        assert_eq!(app.restored_jobs.len(), 1);
+        assert_eq!(app.restored_jobs[0].fields.reason, "タクシー");
+        while rx_cmd.try_recv().is_ok() {}
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reset_to_defaults_backs_up_and_writes_defaults() {
+        // リセットで元のファイルが`.bak`へ退避され、既定値が保存されてWorkerへ送られることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        let dir = std::env::temp_dir().join(format!("receipt_tui_reset_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        app.cfg_path = dir.join("config.toml");
+        app.shortcuts_path = dir.join("shortcut.toml");
+        app.cfg.google.input_folder_id = "in-folder".into();
+        app.cfg.user.full_name = "山田 太郎".into();
+        app.cfg.save(&app.cfg_path).unwrap();
+        app.shortcuts.main.quit = vec!["z".into()];
+        app.shortcuts.save(&app.shortcuts_path).unwrap();
+        app.ui.screen = Screen::Settings;
+
+        // 確認でいいえを選ぶと何も変わらない。
+        handle_key(&mut app, key(KeyCode::Char('R'))).await.unwrap();
+        assert!(app.confirm_reset);
+        handle_key(&mut app, key(KeyCode::Esc)).await.unwrap();
+        assert!(!app.confirm_reset);
+        assert_eq!(app.shortcuts.main.quit, vec!["z".to_string()]);
+
+        // IDを残してリセットする。
+        handle_key(&mut app, key(KeyCode::Char('R'))).await.unwrap();
+        handle_key(&mut app, key(KeyCode::Char('k'))).await.unwrap();
+        let backup = std::fs::read_to_string(dir.join("config.toml.bak")).unwrap();
+        assert!(backup.contains("山田 太郎"));
+        let backup = std::fs::read_to_string(dir.join("shortcut.toml.bak")).unwrap();
+        assert!(backup.contains(r#"quit = ["z"]"#));
+
+        let saved = Config::load_or_default(&app.cfg_path).unwrap().value;
+        assert_eq!(saved.google.input_folder_id, "in-folder");
+        assert_eq!(saved.user.full_name, Config::default().user.full_name);
+        let saved = Shortcuts::load_or_default(&app.shortcuts_path)
+            .unwrap()
+            .value;
+        assert_eq!(saved.main.quit, Shortcuts::default().main.quit);
+        assert_eq!(app.shortcuts.main.quit, Shortcuts::default().main.quit);
+        assert_eq!(app.full_name, Config::default().user.full_name);
+        match rx_cmd.try_recv() {
+            Ok(WorkerCmd::SaveSettings(cfg)) => {
+                assert_eq!(cfg.google.input_folder_id, "in-folder")
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+
+        // すべてリセットするとIDも既定値に戻る。
+        handle_key(&mut app, key(KeyCode::Char('R'))).await.unwrap();
+        handle_key(&mut app, key(KeyCode::Char('y'))).await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            app.cfg.google.input_folder_id,
+            Config::default().google.input_folder_id
+        );
+    }
+
+    #[tokio::test]
+    async fn test_column_mapping_from_template_headers() {
+        // 見出しを要求し、届いた見出しから選んだ列が設定に保存されWorkerへ送られることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        let path =
+            std::env::temp_dir().join(format!("receipt_tui_columns_{}.toml", uuid::Uuid::new_v4()));
+        app.cfg_path = path.clone();
+        app.template_id = "template-id".into();
+        app.ui.screen = Screen::Settings;
+        handle_key(&mut app, key(KeyCode::Char('c'))).await.unwrap();
+        match rx_cmd.try_recv() {
+            Ok(WorkerCmd::FetchTemplateHeaders {
+                template_sheet_id,
+                header_row,
+            }) => {
+                assert_eq!(template_sheet_id, "template-id");
+                assert_eq!(header_row, app.cfg.general_expense.header_row());
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+
+        let columns = ["B", "C", "D", "E", "H"]
+            .iter()
+            .map(|c| (c.to_string(), format!("見出し{c}")))
+            .collect();
+        crate::app::handle_worker_event(
+            &mut app,
+            WorkerEvent::TemplateHeaders {
+                header_row: 6,
+                columns,
+            },
+        )
+        .unwrap();
+        assert!(app.column_picker.is_some());
+        // 日付〜勘定科目は現在の列のまま、備考だけ最後の列を選ぶ（現在のF列は見出しに無いので先頭から動かす）。
+        for code in [KeyCode::Enter, KeyCode::Enter, KeyCode::Tab, KeyCode::Enter] {
+            handle_key(&mut app, key(code)).await.unwrap();
+        }
+        for _ in 0..4 {
+            handle_key(&mut app, key(KeyCode::Down)).await.unwrap();
+        }
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(app.column_picker.is_none());
+        assert_eq!(app.cfg.general_expense.note_col, "H");
+        let saved = Config::load_or_default(&path).unwrap().value;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved.general_expense.note_col, "H");
+        assert_eq!(saved.general_expense.date_col, "B");
+        match rx_cmd.try_recv() {
+            Ok(WorkerCmd::SaveSettings(cfg)) => assert_eq!(cfg.general_expense.note_col, "H"),
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_log_screen_scrolls_and_resumes_following() {
+        // ログ画面で遡ると新しいログが来ても位置を保ち、末尾へ戻ると追従を再開することを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        app.ui.log = (0..50).map(|i| format!("line {i}")).collect();
+        handle_key(&mut app, key(KeyCode::Char('l'))).await.unwrap();
+        assert_eq!(app.ui.screen, Screen::Logs);
+        // 描画で末尾の位置が決まる（ここでは40件目から末尾までが収まるとする）。
+        app.ui.log_bottom_start = 40;
+
+        handle_key(&mut app, key(KeyCode::PageUp)).await.unwrap();
+        assert_eq!(app.ui.log_scroll, Some(30));
+        handle_key(&mut app, key(KeyCode::Up)).await.unwrap();
+        assert_eq!(app.ui.log_scroll, Some(29));
+        app.ui.log.push("new line".into());
+        assert_eq!(app.ui.log_scroll, Some(29));
+
+        handle_key(&mut app, key(KeyCode::PageDown)).await.unwrap();
+        handle_key(&mut app, key(KeyCode::PageDown)).await.unwrap();
+        assert_eq!(app.ui.log_scroll, None);
+        handle_key(&mut app, key(KeyCode::Home)).await.unwrap();
+        assert_eq!(app.ui.log_scroll, Some(0));
+        handle_key(&mut app, key(KeyCode::End)).await.unwrap();
+        assert_eq!(app.ui.log_scroll, None);
+
+        handle_key(&mut app, key(KeyCode::Esc)).await.unwrap();
+        assert_eq!(app.ui.screen, Screen::Main);
+    }
+
+    #[tokio::test]
+    async fn test_reset_month_returns_to_current_month() {
+        // メイン画面と編集画面のどちらでも、対象月が今月に戻ることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        let current = chrono::Local::now().format("%Y-%m").to_string();
+        app.edit_target_month = "2019-01".into();
+        handle_key(&mut app, key(KeyCode::Char('0'))).await.unwrap();
+        assert_eq!(app.edit_target_month, current);
+
+        app.jobs.push(Job::new("f1".into(), "a.jpg".into()));
+        app.ui.screen = Screen::EditJob;
+        app.edit_target_month = "2030-12".into();
+        handle_key(&mut app, key(KeyCode::Char('0'))).await.unwrap();
+        assert_eq!(app.edit_target_month, current);
+        assert_eq!(app.ui.status, format!("Target month reset to {current}"));
+    }
+
+    #[tokio::test]
+    async fn test_diagnostics_requested_only_on_demand() {
+        // 診断はキー操作時にだけ依頼され、結果のポップアップを閉じられることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        let mut job = Job::new("f1".into(), "a.jpg".into());
+        job.copied_sheet_id = Some("copy1".into());
+        let job_id = job.id;
+        app.jobs.push(job);
+        assert!(rx_cmd.try_recv().is_err());
+
+        handle_key(&mut app, key(KeyCode::Char('i'))).await.unwrap();
+        match rx_cmd.try_recv() {
+            Ok(WorkerCmd::Diagnose {
+                job_id: id,
+                copied_sheet_id,
+            }) => {
+                assert_eq!(id, job_id);
+                assert_eq!(copied_sheet_id.as_deref(), Some("copy1"));
+            }
+            other => panic!("unexpected: {other:?}"),
+        }
+
+        app.diagnostics = Some(DiagnosticsView::new("diag".into(), "a\nb"));
+        handle_key(&mut app, key(KeyCode::Down)).await.unwrap();
+        assert_eq!(app.diagnostics.as_ref().unwrap().scroll, 1);
+        // 表示中はメイン画面のキーが効かない。
+        handle_key(&mut app, key(KeyCode::Char('w'))).await.unwrap();
+        assert_eq!(app.ui.screen, Screen::Main);
+        handle_key(&mut app, key(KeyCode::Esc)).await.unwrap();
+        assert!(app.diagnostics.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_check_auth_uses_configured_credentials_path() {
+        // 認証確認のステップが設定されたパスの存在を確かめることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        app.ui.screen = Screen::InitialSetup;
+        app.wizard_state.current_step = WizardStep::CheckAuth;
+        let path =
+            std::env::temp_dir().join(format!("receipt_tui_creds_{}.json", uuid::Uuid::new_v4()));
+        app.cfg.google.credentials_path = Some(path.display().to_string());
+
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert_eq!(
+            app.ui.error,
+            Some(format!("{} not found. Please add it.", path.display()))
+        );
+        assert_eq!(app.wizard_state.current_step, WizardStep::CheckAuth);
+
+        std::fs::write(&path, "{}").unwrap();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(app.ui.error.is_none());
+        assert_ne!(app.wizard_state.current_step, WizardStep::CheckAuth);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wizard_checks_pasted_folder_url() {
+        // 貼り付けたURLからIDを取り出して確認を依頼し、失敗なら留まり成功で進むことを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.ui.screen = Screen::InitialSetup;
+        app.wizard_state.current_step = WizardStep::InputFolderId;
+
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        app.input_box.as_mut().unwrap().value =
+            "https://drive.google.com/drive/folders/1AbC-d_E?usp=sharing".into();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert_eq!(app.in_folder, "1AbC-d_E");
+        assert!(matches!(
+            rx_cmd.try_recv(),
+            Ok(WorkerCmd::CheckId { kind: IdKind::InputFolder, ref id }) if id == "1AbC-d_E"
+        ));
+        assert_eq!(app.wizard_state.current_step, WizardStep::InputFolderId);
+
+        let checked = |error: Option<&str>| WorkerEvent::IdChecked {
+            kind: IdKind::InputFolder,
+            id: "1AbC-d_E".into(),
+            error: error.map(str::to_string),
+        };
+        crate::app::handle_worker_event(&mut app, checked(Some("not a Drive folder"))).unwrap();
+        assert!(
+            app.ui
+                .error
+                .as_ref()
+                .unwrap()
+                .contains("not a Drive folder")
+        );
+        assert_eq!(app.wizard_state.current_step, WizardStep::InputFolderId);
+
+        crate::app::handle_worker_event(&mut app, checked(None)).unwrap();
+        assert!(app.ui.error.is_none());
+        assert_eq!(app.wizard_state.current_step, WizardStep::OutputFolderId);
+
+        // 先へ進んだ後に届いた古い結果は無視する。
+        crate::app::handle_worker_event(&mut app, checked(Some("late"))).unwrap();
+        assert!(app.ui.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wizard_verify_jumps_back_to_failed_step() {
+        // 接続テストを依頼し、失敗した項目のステップへ戻り、全項目成功で完了へ進むことを検証する。
+        use crate::wizard::SetupCheck;
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.ui.screen = Screen::InitialSetup;
+        app.wizard_state.current_step = WizardStep::Verify;
+        app.in_folder = "in1".into();
+        app.template_id = "tpl1".into();
+
+        let run = |app: &mut App, failed: Option<SetupCheck>| {
+            for check in SetupCheck::ALL {
+                let error = (Some(check) == failed).then(|| "404".to_string());
+                crate::app::handle_worker_event(app, WorkerEvent::SetupChecked { check, error })
+                    .unwrap();
+            }
+        };
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(matches!(
+            rx_cmd.try_recv(),
+            Ok(WorkerCmd::VerifySetup { ref input_folder_id, ref template_sheet_id })
+                if input_folder_id == "in1" && template_sheet_id == "tpl1"
+        ));
+        assert!(app.wizard_state.checks_running());
+        run(&mut app, Some(SetupCheck::Template));
+        assert_eq!(
+            app.ui.error.as_deref(),
+            Some("Verification failed: Template sheet")
+        );
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert_eq!(app.wizard_state.current_step, WizardStep::TemplateSheetId);
+
+        // もう一度接続テストのステップで全項目が成功すれば完了へ進む。
+        app.wizard_state.go_to(WizardStep::Verify);
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        run(&mut app, None);
+        assert!(app.wizard_state.checks_passed());
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert_eq!(app.wizard_state.current_step, WizardStep::Complete);
+    }
+
+    #[tokio::test]
+    async fn test_undo_restores_previous_field_value() {
+        // 入力ボックスでの変更を順に取り消せ、別のジョブを選ぶと履歴が消えることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        let mut job = Job::new("f1".into(), "a.jpg".into());
+        job.fields.reason = "タクシー".into();
+        app.jobs.push(job);
+        app.jobs.push(Job::new("f2".into(), "b.jpg".into()));
+        app.ui.screen = Screen::EditJob;
+        app.ui.editing_field_idx = 1;
+
+        for value in ["", "電車"] {
+            handle_key(&mut app, key(KeyCode::Char('e'))).await.unwrap();
+            app.input_box.as_mut().unwrap().value = value.into();
+            handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        }
+        assert_eq!(app.jobs[0].fields.reason, "電車");
+        handle_key(&mut app, key(KeyCode::Char('u'))).await.unwrap();
+        assert_eq!(app.jobs[0].fields.reason, "");
+        handle_key(&mut app, key(KeyCode::Char('u'))).await.unwrap();
+        assert_eq!(app.jobs[0].fields.reason, "タクシー");
+        handle_key(&mut app, key(KeyCode::Char('u'))).await.unwrap();
+        assert_eq!(app.ui.status, "Nothing to undo");
+
+        // 別のジョブを選ぶと、元のジョブの履歴は使えない。
+        handle_key(&mut app, key(KeyCode::Char('d'))).await.unwrap();
+        app.ui.selected = 1;
+        handle_key(&mut app, key(KeyCode::Char('u'))).await.unwrap();
+        assert_eq!(app.ui.status, "Nothing to undo");
+        app.ui.selected = 0;
+        handle_key(&mut app, key(KeyCode::Char('u'))).await.unwrap();
+        assert_eq!(app.ui.status, "Nothing to undo");
+    }
+
+    #[tokio::test]
+    async fn test_moving_selection_clears_undo() {
+        // 一覧で選択を動かすと、戻ってきても前の取り消し履歴が残らないことを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        app.jobs.push(Job::new("f1".into(), "a.jpg".into()));
+        app.jobs.push(Job::new("f2".into(), "b.jpg".into()));
+        app.ui.screen = Screen::EditJob;
+        app.ui.editing_field_idx = 1;
+        handle_key(&mut app, key(KeyCode::Char('e'))).await.unwrap();
+        app.input_box.as_mut().unwrap().value = "電車".into();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+
+        app.ui.screen = Screen::Main;
+        handle_key(&mut app, key(KeyCode::Down)).await.unwrap();
+        assert_eq!(app.ui.selected, 1);
+        handle_key(&mut app, key(KeyCode::Up)).await.unwrap();
+        assert_eq!(app.ui.selected, 0);
+
+        app.ui.screen = Screen::EditJob;
+        handle_key(&mut app, key(KeyCode::Char('u'))).await.unwrap();
+        assert_eq!(app.ui.status, "Nothing to undo");
+        assert_eq!(app.jobs[0].fields.reason, "電車");
+    }
+
+    #[tokio::test]
+    async fn test_input_cancel_preserves_value() {
+        // 入力をキャンセルしても元の値が残り、ウィザードが進まないことを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        app.ui.screen = Screen::InitialSetup;
+        app.wizard_state.current_step = WizardStep::InputFolderId;
+        app.in_folder = "folder123".into();
+
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        handle_key(&mut app, key(KeyCode::Char('x'))).await.unwrap();
+        handle_key(&mut app, key(KeyCode::Esc)).await.unwrap();
+        assert!(app.input_box.is_none());
+        assert_eq!(app.in_folder, "folder123");
+        assert_eq!(app.wizard_state.current_step, WizardStep::InputFolderId);
+        assert_eq!(app.ui.status, "Input canceled — press Enter to re-enter");
+
+        // 設定画面でもバッファは変わらない。
+        app.ui.screen = Screen::Settings;
+        handle_key(&mut app, key(KeyCode::Char('i'))).await.unwrap();
+        handle_key(&mut app, key(KeyCode::Char('y'))).await.unwrap();
+        handle_key(&mut app, key(KeyCode::Esc)).await.unwrap();
+        assert!(app.input_box.is_none());
+        assert_eq!(app.in_folder, "folder123");
+        assert_eq!(app.ui.screen, Screen::Settings);
+        assert_eq!(app.ui.status, "Input canceled; value unchanged");
+    }
+
+    #[tokio::test]
+    async fn test_commit_all_sends_waiting_jobs() {
+        // 一括確定で編集待ちのジョブだけがまとめて送られることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.cfg.user.full_name = "山田 太郎".into();
+        let mut waiting = Job::new("f1".into(), "a.jpg".into());
+        waiting.status = JobStatus::WaitingUserFix;
+        let mut done = Job::new("f2".into(), "b.jpg".into());
+        done.status = JobStatus::Done;
+        let mut waiting2 = Job::new("f3".into(), "c.jpg".into());
+        waiting2.status = JobStatus::WaitingUserFix;
+        app.jobs = vec![waiting, done, waiting2];
+
+        handle_key(&mut app, key(KeyCode::Char('c'))).await.unwrap();
+        match rx_cmd.try_recv() {
+            Ok(WorkerCmd::CommitAll {
+                requests,
+                one_sheet_per_month: false,
+            }) => {
+                let names: Vec<&str> = requests.iter().map(|r| r.filename.as_str()).collect();
+                assert_eq!(names, vec!["a.jpg", "c.jpg"]);
+                assert!(
+                    requests
+                        .iter()
+                        .all(|r| r.target_month_ym == app.edit_target_month)
+                );
+            }
+            other => panic!("unexpected: {other:?}"),
+        }
+        assert_eq!(app.ui.status, "Committing 2 jobs...");
+    }
+
+    #[tokio::test]
+    async fn test_commit_all_skips_jobs_in_flight() {
+        // 確定中のジョブは一括確定で二重に送られないことを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.cfg.user.full_name = "山田 太郎".into();
+        let mut busy = Job::new("f1".into(), "a.jpg".into());
+        busy.status = JobStatus::WaitingUserFix;
+        let mut waiting = Job::new("f2".into(), "b.jpg".into());
+        waiting.status = JobStatus::WaitingUserFix;
+        app.in_flight.insert(InFlight::Commit(busy.id));
+        app.jobs = vec![busy, waiting];
+
+        handle_key(&mut app, key(KeyCode::Char('c'))).await.unwrap();
+        match rx_cmd.try_recv() {
+            Ok(WorkerCmd::CommitAll { requests, .. }) => {
+                let names: Vec<&str> = requests.iter().map(|r| r.filename.as_str()).collect();
+                assert_eq!(names, vec!["b.jpg"]);
+            }
+            other => panic!("unexpected: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_preview_write_requested_from_edit_job() {
+        // 編集画面でプレビューキーを押すと、書き込みを伴わない見積もりが依頼されることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.jobs.push(Job::new("f1".into(), "a.jpg".into()));
+        app.ui.screen = Screen::EditJob;
+
+        handle_key(&mut app, key(KeyCode::Char('w'))).await.unwrap();
+        assert!(matches!(
+            rx_cmd.try_recv(),
+            Ok(WorkerCmd::PreviewWrite { rows: 1 })
+        ));
+        assert_eq!(app.ui.screen, Screen::EditJob);
+
+        // 同じ対象月の編集待ちジョブは同じシートへ並ぶため、その分も行数に数える。
+        for (id, month) in [("f2", None), ("f3", Some("2025-01"))] {
+            let mut job = Job::new(id.into(), format!("{id}.jpg"));
+            job.status = JobStatus::WaitingUserFix;
+            job.target_month = month.map(String::from);
+            app.jobs.push(job);
+        }
+        handle_key(&mut app, key(KeyCode::Char('w'))).await.unwrap();
+        assert!(matches!(
+            rx_cmd.try_recv(),
+            Ok(WorkerCmd::PreviewWrite { rows: 2 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_date_keeps_input_box_open() {
+        // 不正な日付では入力ボックスが開いたまま理由が表示され、正しい日付で反映されることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        app.jobs.push(Job::new("f1".into(), "a.jpg".into()));
+        app.ui.screen = Screen::EditJob;
+
+        handle_key(&mut app, key(KeyCode::Char('e'))).await.unwrap();
+        app.input_box.as_mut().unwrap().value = "2025-13-40".into();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(app.input_box.is_some());
+        assert!(app.ui.error.as_deref().unwrap().contains("YYYY-MM-DD"));
+        assert_eq!(app.jobs[0].fields.date_ymd, "");
+
+        app.input_box.as_mut().unwrap().value = "2025-12-19".into();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(app.input_box.is_none());
+        assert_eq!(app.ui.error, None);
+        assert_eq!(app.jobs[0].fields.date_ymd, "2025-12-19");
+
+        // 対象月も同じように検証される。
+        handle_key(&mut app, key(KeyCode::Char('m'))).await.unwrap();
+        app.input_box.as_mut().unwrap().value = "2025-1".into();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(app.ui.error.as_deref().unwrap().contains("YYYY-MM"));
+        assert!(app.input_box.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_currency_field_is_editable() {
+        // 編集画面で通貨を入力でき、不正な通貨コードは受け付けないことを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        app.jobs.push(Job::new("f1".into(), "a.jpg".into()));
+        app.ui.screen = Screen::EditJob;
+        app.ui.editing_field_idx = 5;
+
+        handle_key(&mut app, key(KeyCode::Char('e'))).await.unwrap();
+        assert_eq!(
+            app.input_box.as_ref().unwrap().prompt,
+            "Currency (empty for JPY):"
+        );
+        app.input_box.as_mut().unwrap().value = "US$".into();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(app.input_box.is_some());
+        assert!(app.ui.error.as_deref().unwrap().contains("3-letter"));
+
+        app.input_box.as_mut().unwrap().value = "usd".into();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(app.input_box.is_none());
+        assert_eq!(app.jobs[0].fields.currency, "USD");
+    }
+
+    #[tokio::test]
+    async fn test_amount_field_rejects_non_integer() {
+        // 整数でない金額は0として保存せず、入力ボックスを開いたままにすることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        let mut job = Job::new("f1".into(), "a.jpg".into());
+        job.fields.amount_yen = 500;
+        app.jobs.push(job);
+        app.ui.screen = Screen::EditJob;
+        app.ui.editing_field_idx = 2;
+
+        handle_key(&mut app, key(KeyCode::Char('e'))).await.unwrap();
+        app.input_box.as_mut().unwrap().value = "1200.5".into();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(app.input_box.is_some());
+        assert!(app.ui.error.as_deref().unwrap().contains("whole number"));
+        assert_eq!(app.jobs[0].fields.amount_yen, 500);
+
+        app.input_box.as_mut().unwrap().value = " 1200 ".into();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(app.input_box.is_none());
+        assert_eq!(app.jobs[0].fields.amount_yen, 1200);
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_corrected_month() {
+        // 失敗したジョブの月入力が失敗時の月で開き、直した月ですぐに再送されることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.cfg.user.full_name = "山田 太郎".into();
+        let mut job = filled_job("f1", "a.jpg");
+        job.status = JobStatus::Error("invalid month".into());
+        job.failed_month = Some("2025-13".into());
+        app.jobs.push(job);
+
+        // 不正な月のまま確定しても再送せず、入力ボックスは開いたまま。
+        handle_key(&mut app, key(KeyCode::Char('m'))).await.unwrap();
+        assert_eq!(app.input_box.as_ref().unwrap().value, "2025-13");
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(rx_cmd.try_recv().is_err());
+        assert!(app.input_box.is_some());
+
+        // 直した月で確定すると、その月で再送される。
+        app.input_box.as_mut().unwrap().value = "2025-12".into();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        match rx_cmd.try_recv() {
+            Ok(WorkerCmd::CommitJobEdits(req)) => assert_eq!(req.target_month_ym, "2025-12"),
+            other => panic!("unexpected command: {other:?}"),
+        }
+        assert_eq!(app.jobs[0].target_month.as_deref(), Some("2025-12"));
+        assert_eq!(app.jobs[0].failed_month, None);
+        assert!(matches!(app.jobs[0].status, JobStatus::Queued));
+
+        // 失敗していないジョブでは開かない。
+        handle_key(&mut app, key(KeyCode::Char('m'))).await.unwrap();
+        assert!(app.input_box.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_month_requires_fields() {
+        // 月を直して再送する場合も、必須項目が欠けていれば送らないことを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.cfg.user.full_name = "山田 太郎".into();
+        let mut job = Job::new("f1".into(), "a.jpg".into());
+        job.status = JobStatus::Error("invalid month".into());
+        job.failed_month = Some("2025-13".into());
+        app.jobs.push(job);
+
+        handle_key(&mut app, key(KeyCode::Char('m'))).await.unwrap();
+        app.input_box.as_mut().unwrap().value = "2025-12".into();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(rx_cmd.try_recv().is_err());
+        assert!(app.ui.status.starts_with("Cannot commit: "));
+        assert!(matches!(app.jobs[0].status, JobStatus::Error(_)));
+    }
+
+    #[tokio::test]
+    async fn test_commit_blocked_when_category_requires_note() {
+        // 備考必須の科目で備考が空だと、備考欄へ移動して確定しないことを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.cfg.user.full_name = "山田 太郎".into();
+        app.cfg.category_rules = vec![CategoryRule {
+            category: "その他".into(),
+            require_note: true,
+        }];
+        let mut job = filled_job("f1", "a.jpg");
+        job.fields.category = "その他".into();
+        app.jobs.push(job);
+        app.ui.screen = Screen::EditJob;
+
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(rx_cmd.try_recv().is_err());
+        assert_eq!(app.ui.editing_field_idx, 4);
+        assert!(app.ui.status.contains("Note is required"));
+
+        // 備考を埋めれば確定できる。
+        app.jobs[0].fields.note = "詳細".into();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        handle_key(&mut app, key(KeyCode::Char('y'))).await.unwrap();
+        assert!(matches!(
+            rx_cmd.try_recv(),
+            Ok(WorkerCmd::CommitJobEdits(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_commit_blocked_when_required_fields_empty() {
+        // 編集画面からの確定でも、必須項目が欠けていれば送信も確認もしないことを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.cfg.user.full_name = "山田 太郎".into();
+        let mut job = filled_job("f1", "a.jpg");
+        job.fields.reason.clear();
+        app.jobs.push(job);
+        app.ui.screen = Screen::EditJob;
+
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(app.confirm_commit.is_none());
+        assert!(rx_cmd.try_recv().is_err());
+        assert_eq!(app.ui.status, "Cannot commit: reason is empty");
+
+        // 必須でなくせば確定できる。
+        app.cfg
+            .validation
+            .required_fields
+            .retain(|f| *f != config::RequiredField::Reason);
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(app.confirm_commit.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_commit_blocked_without_target_month() {
+        // 対象月が空だと確定が送信されないことを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.cfg.user.full_name = "山田 太郎".into();
+        app.jobs.push(filled_job("f1", "a.jpg"));
+        app.ui.screen = Screen::EditJob;
+        app.edit_target_month = String::new();
+
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(rx_cmd.try_recv().is_err());
+        assert!(app.ui.status.contains("Target month is missing"));
+
+        // 対象月を設定すればその月で確定される。
+        app.edit_target_month = "2025-12".into();
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        handle_key(&mut app, key(KeyCode::Char('y'))).await.unwrap();
+        match rx_cmd.try_recv() {
+            Ok(WorkerCmd::CommitJobEdits(req)) => assert_eq!(req.target_month_ym, "2025-12"),
+            other => panic!("unexpected: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commits_held_while_paused_then_flushed() {
+        // 一時停止中の確定は保留され、再開時に順番通り送信されることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.cfg.user.full_name = "山田 太郎".into();
+        app.jobs.push(filled_job("f1", "a.jpg"));
+        app.jobs.push(filled_job("f2", "b.jpg"));
+
+        handle_key(&mut app, key(KeyCode::Char('p'))).await.unwrap();
+        assert!(app.paused);
+        for selected in [0, 1] {
+            app.ui.selected = selected;
+            app.ui.screen = Screen::EditJob;
+            handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+            handle_key(&mut app, key(KeyCode::Char('y'))).await.unwrap();
+        }
+        assert!(rx_cmd.try_recv().is_err());
+        assert_eq!(app.pending_commits.len(), 2);
+        assert_eq!(app.ui.status, "paused (2 queued)");
+
+        handle_key(&mut app, key(KeyCode::Char('p'))).await.unwrap();
+        assert!(!app.paused);
+        for expected in ["f1", "f2"] {
+            match rx_cmd.try_recv() {
+                Ok(WorkerCmd::CommitJobEdits(req)) => assert_eq!(req.drive_file_id, expected),
+                other => panic!("unexpected command: {other:?}"),
+            }
+        }
+        assert!(app.pending_commits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_toggle_mask_keeps_real_ids() {
+        // マスク切替は表示だけに作用し、実際のIDは保持されることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        app.cfg.google.input_folder_id = "1AbCdEfGhIjK".into();
+
+        handle_key(&mut app, key(KeyCode::Char('v'))).await.unwrap();
+        assert!(app.cfg.ui.mask_sensitive);
+        assert_eq!(app.cfg.google.input_folder_id, "1AbCdEfGhIjK");
+
+        handle_key(&mut app, key(KeyCode::Char('v'))).await.unwrap();
+        assert!(!app.cfg.ui.mask_sensitive);
+    }
+
+    #[tokio::test]
+    async fn test_toggle_done_hides_and_reveals_rows() {
+        // 完了ジョブの非表示切替で表示行が変わり、選択と移動が表示中の行に限られることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        for (i, done) in [false, true, true, false].into_iter().enumerate() {
+            let mut job = Job::new(format!("f{i}"), format!("{i}.jpg"));
+            if done {
+                job.status = JobStatus::Done;
+            }
+            app.jobs.push(job);
+        }
+        app.ui.selected = 1;
+
+        // 隠すと完了ジョブが消え、選択は後ろの表示中の行へ移る。
+        handle_key(&mut app, key(KeyCode::Char('h'))).await.unwrap();
+        assert!(app.hide_done);
+        assert_eq!(app.visible_job_indices(), vec![0, 3]);
+        assert_eq!(app.ui.selected, 3);
+        assert_eq!(app.jobs.len(), 4);
+
+        // 上下移動は隠れた行を飛ばす。
+        handle_key(&mut app, key(KeyCode::Up)).await.unwrap();
+        assert_eq!(app.ui.selected, 0);
+        handle_key(&mut app, key(KeyCode::Down)).await.unwrap();
+        assert_eq!(app.ui.selected, 3);
+
+        // 戻すと全件が表示される。
+        handle_key(&mut app, key(KeyCode::Char('h'))).await.unwrap();
+        assert!(!app.hide_done);
+        assert_eq!(app.visible_job_indices(), vec![0, 1, 2, 3]);
+        assert_eq!(app.ui.selected, 3);
+    }
+
+    #[tokio::test]
+    async fn test_job_search_jumps_while_typing_and_cycles() {
+        // 入力のたびに最初の一致へ移動し、確定後は次/前の一致へ折り返しながら移動することを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        for name in ["lunch.jpg", "Receipt_A.jpg", "taxi.png", "receipt_b.jpg"] {
+            app.jobs.push(Job::new(format!("id-{name}"), name.into()));
+        }
+
+        handle_key(&mut app, key(KeyCode::Char('/'))).await.unwrap();
+        for c in "REC".chars() {
+            handle_key(&mut app, key(KeyCode::Char(c))).await.unwrap();
+        }
+        assert_eq!(app.ui.selected, 1);
+        assert_eq!(app.ui.status, "Search \"REC\": 1/2");
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert!(app.input_box.is_none());
+        assert_eq!(app.ui.search.as_deref(), Some("REC"));
+
+        handle_key(&mut app, key(KeyCode::Char('n'))).await.unwrap();
+        assert_eq!(app.ui.selected, 3);
+        assert_eq!(app.ui.status, "Search \"REC\": 2/2");
+        handle_key(&mut app, key(KeyCode::Char('n'))).await.unwrap();
+        assert_eq!(app.ui.selected, 1);
+        let shift_n = KeyEvent::new(KeyCode::Char('N'), crossterm::event::KeyModifiers::SHIFT);
+        handle_key(&mut app, shift_n).await.unwrap();
+        assert_eq!(app.ui.selected, 3);
+    }
+
+    #[tokio::test]
+    async fn test_status_filter_cycles_and_keeps_selection() {
+        // 状態の絞り込みが順に切り替わり、選択が絞り込み後の行に合わせられることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        let statuses = [
+            JobStatus::WaitingUserFix,
+            JobStatus::Done,
+            JobStatus::Error("boom".into()),
+            JobStatus::WaitingUserFix,
+        ];
+        for (i, status) in statuses.into_iter().enumerate() {
+            let mut job = Job::new(format!("f{i}"), format!("{i}.jpg"));
+            job.status = status;
+            app.jobs.push(job);
+        }
+        app.ui.selected = 1;
+
+        // 編集待ちだけを表示すると、選択は後ろの編集待ちの行へ移る。
+        handle_key(&mut app, key(KeyCode::Char('f'))).await.unwrap();
+        assert_eq!(app.ui.status_filter, StatusFilter::WaitingUserFix);
+        assert_eq!(app.visible_job_indices(), vec![0, 3]);
+        assert_eq!(app.ui.selected, 3);
+        assert_eq!(app.ui.status, "Filter: waiting");
+
+        handle_key(&mut app, key(KeyCode::Char('f'))).await.unwrap();
+        assert_eq!(app.visible_job_indices(), vec![1]);
+        assert_eq!(app.ui.selected, 1);
+
+        handle_key(&mut app, key(KeyCode::Char('f'))).await.unwrap();
+        assert_eq!(app.ui.status_filter, StatusFilter::Error);
+        assert_eq!(
+            app.selected_job().map(|j| j.filename.as_str()),
+            Some("2.jpg")
+        );
+
+        // 該当するジョブが無ければ、隠れたジョブは選択中として扱わない。
+        app.jobs[2].status = JobStatus::Done;
+        app.keep_selection_visible();
+        assert!(app.selected_job().is_none());
+        handle_key(&mut app, key(KeyCode::Enter)).await.unwrap();
+        assert_eq!(app.ui.screen, Screen::Main);
+
+        handle_key(&mut app, key(KeyCode::Char('f'))).await.unwrap();
+        assert_eq!(app.ui.status_filter, StatusFilter::All);
+        assert_eq!(app.visible_job_indices(), vec![0, 1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_copy_and_paste_field_between_jobs() {
+        // フィールド値を別ジョブへコピー&ペーストできることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        let mut first = Job::new("f1".into(), "a.jpg".into());
+        first.fields.reason = "顧客訪問".into();
+        app.jobs.push(first);
+        app.jobs.push(Job::new("f2".into(), "b.jpg".into()));
+        app.ui.screen = Screen::EditJob;
+        app.ui.editing_field_idx = 1;
+
+        handle_key(&mut app, key(KeyCode::Char('y'))).await.unwrap();
+        app.ui.selected = 1;
+        handle_key(&mut app, key(KeyCode::Char('p'))).await.unwrap();
+        assert_eq!(app.jobs[1].fields.reason, "顧客訪問");
+    }
+
+    #[tokio::test]
+    async fn test_down_at_last_row_requests_next_page() {
+        // 最終行でさらに下へ移動すると続きのページを要求することを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.jobs.push(Job::new("f1".into(), "a.jpg".into()));
+        app.next_page_token = Some("page-2".into());
+
+        handle_key(&mut app, key(KeyCode::Down)).await.unwrap();
+        assert!(matches!(
+            rx_cmd.try_recv(),
+            Ok(WorkerCmd::LoadMoreJobs { page_token }) if page_token == "page-2"
+        ));
+        assert!(app.loading_more);
+
+        // 読み込み中は重複して要求しない。
+        handle_key(&mut app, key(KeyCode::Down)).await.unwrap();
+        assert!(rx_cmd.try_recv().is_err());
+    }
+}
@@ -4,35 +4,102 @@ mod handlers;
 mod render;
 
 use anyhow::Result;
-use chrono::Datelike;
+use chrono::{DateTime, Local};
 use crossterm::event::{self, Event};
-use std::{path::PathBuf, time::Duration};
+use std::{
+    collections::{HashSet, VecDeque},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 use tokio::sync::mpsc;
+use uuid::Uuid;
 
 use crate::{
-    config::Config,
-    events::{Screen, UiState},
+    bell::Bell,
+    breaker::{BreakerState, BreakerStatus},
+    clipboard::ClipboardBridge,
+    column_map::ColumnPicker,
+    config::{self, Config, StartupScreen},
+    config_watch::{self, ConfigReload, ConfigWatcher},
+    dates,
+    diagnostics::DiagnosticsView,
+    events::{EditUndo, InFlight, RetryPrompt, Screen, StatusFilter, UiState},
+    format,
+    google::error::WorkerError,
     input::InputBoxState,
-    jobs::Job,
-    shortcuts::Shortcuts,
+    job_store,
+    jobs::{Job, JobStats, JobStatus, ReceiptFields},
+    ocr::OcrGuess,
+    shortcuts::{self, Shortcuts},
+    submissions,
+    theme::{self, Theme},
     ui::Tui,
     wizard,
-    worker::{self, WorkerCmd, WorkerEvent},
+    worker::{self, CommitRequest, IdKind, WorkerCmd, WorkerEvent},
 };
 
 use handlers::{handle_key, is_ctrl_c};
 use render::draw;
 
+/// 終了時に、Workerが残りの処理（アーカイブ待ちの移動など）を終えるのを待つ上限。
+const WORKER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// 入力処理と描画で共有するアプリ状態。
 pub struct App {
     /// 永続化された設定ファイルのパス。
     pub cfg_path: PathBuf,
+    /// 設定ファイルを読めず、保存を控えているか。
+    pub cfg_read_only: bool,
+    /// ショートカット設定ファイルのパス。
+    pub shortcuts_path: PathBuf,
+    /// 設定ファイルの外部での変更の監視（Noneなら監視しない）。
+    pub config_watch: Option<ConfigWatcher>,
+    /// ディスク上で変わっていた設定（再読み込みの確認中はSome）。
+    pub config_reload: Option<ConfigReload>,
+    /// セッションの開始時刻。
+    pub started_at: DateTime<Local>,
     /// メモリ上の現在設定。
     pub cfg: Config,
     /// 選択位置やステータスなどUI固有の状態。
     pub ui: UiState,
     /// Driveから読み込んだジョブ（画像1件につき1ジョブ）。
     pub jobs: Vec<Job>,
+    /// ジョブの保存先（Noneなら保存しない）。
+    pub jobs_path: Option<PathBuf>,
+    /// 保存から復元したが、まだDriveの一覧と突き合わせていないジョブ。
+    pub restored_jobs: Vec<Job>,
+    /// 続きのページがある場合のDriveページトークン。
+    pub next_page_token: Option<String>,
+    /// 続きのページを読み込み中か。
+    pub loading_more: bool,
+    /// 新しい確定の送信を一時停止しているか。
+    pub paused: bool,
+    /// Drive/Sheetsへ書き込まずに確定の流れだけを試しているか（`--dry-run`）。
+    pub dry_run: bool,
+    /// 完了したジョブを一覧から隠しているか（ジョブ自体は残す）。
+    pub hide_done: bool,
+    /// 完了を待っている確定とリフレッシュ。
+    pub in_flight: HashSet<InFlight>,
+    /// 一時停止中に保留した確定（再開時に順に送信する）。
+    pub pending_commits: VecDeque<CommitRequest>,
+    /// Workerから通知されたサーキットブレーカーの状態。
+    pub breaker: Option<BreakerStatus>,
+    /// 提出済みの警告を表示済みで、次の確定で送信するジョブ。
+    pub duplicate_confirm: Option<Uuid>,
+    /// 応答待ちの再試行プロンプト（先頭を表示する）。
+    pub retry_prompts: VecDeque<RetryPrompt>,
+    /// 送信前の確認を待っている確定（確認ポップアップの表示中はSome）。
+    pub confirm_commit: Option<CommitRequest>,
+    /// 設定とショートカットを既定値へ戻す確認の表示中か。
+    pub confirm_reset: bool,
+    /// 編集画面で入力項目の変更を取り消すための履歴。
+    pub edit_undo: EditUndo,
+    /// このセッションで最後に確定した入力項目（新しいジョブの初期値に使う）。
+    pub last_committed: Option<ReceiptFields>,
+    /// テンプレートの見出しから列を割り当てるピッカー（開いている間はSome）。
+    pub column_picker: Option<ColumnPicker>,
+    /// 表示中の診断結果（開いている間はSome）。
+    pub diagnostics: Option<DiagnosticsView>,
     /// Workerへのコマンド送信チャネル。
     pub worker_tx: mpsc::Sender<WorkerCmd>,
     /// Workerからのイベント受信チャネル。
@@ -58,75 +125,55 @@ pub struct App {
 
     /// ショートカットキー設定。
     pub shortcuts: Shortcuts,
+    /// 画面の配色（`theme.toml`）。
+    pub theme: Theme,
+
+    /// フィールド値のコピー/貼り付けに使うクリップボード。
+    pub clipboard: ClipboardBridge,
+    /// 完了/失敗を知らせる端末ベル。
+    pub bell: Bell,
 }
 
 /// ユーザーが終了するまでメインTUIループを回す。
-pub async fn run_app(terminal: &mut Tui) -> Result<()> {
-    // 設定ファイルを読み込む（初回はデフォルトを生成）。
-    let cfg_path = PathBuf::from("config.toml");
-    let cfg = Config::load_or_default(&cfg_path)?;
-
-    // ショートカット設定を読み込む（無ければデフォルト）。
-    let shortcuts_path = PathBuf::from("shortcut.toml");
-    let shortcuts = Shortcuts::load_or_default(&shortcuts_path)?;
-
+pub async fn run_app(
+    terminal: &mut Tui,
+    cfg_path: PathBuf,
+    shortcuts_path: &Path,
+    dry_run: bool,
+) -> Result<()> {
     // Worker通信用のコマンド/イベントチャネルを作る。
     let (tx_cmd, rx_cmd) = mpsc::channel::<WorkerCmd>(64);
     let (tx_ev, rx_ev) = mpsc::channel::<WorkerEvent>(256);
 
-    // 初期設定スナップショットでWorkerを起動する。
-    tokio::spawn(worker::run(rx_cmd, tx_ev, cfg.clone()));
-
-    // 設定の充足度に応じて初期画面を決める。
-    let initial_screen = if needs_initial_setup(&cfg) {
-        Screen::InitialSetup
-    } else {
-        Screen::Main
-    };
-
-    // 現在日時から編集対象月を自動生成する。
-    let now = chrono::Local::now();
-    let edit_target_month = format!("{}-{:02}", now.year(), now.month());
+    // 設定を読み込んでアプリ状態を初期化する。
+    let mut app = load_app(cfg_path, shortcuts_path, tx_cmd, rx_ev)?;
+    app.dry_run = dry_run;
 
-    // アプリ状態を初期化する。
-    let mut app = App {
-        cfg_path,
-        cfg: cfg.clone(),
-        ui: UiState {
-            screen: initial_screen.clone(),
-            selected: 0,
-            log: vec![],
-            status: "Ready".into(),
-            editing_field_idx: 0,
-            error: None,
-        },
-        jobs: vec![],
-        worker_tx: tx_cmd,
-        worker_rx: rx_ev,
-        in_folder: cfg.google.input_folder_id.clone(),
-        out_folder: cfg.google.output_folder_id.clone(),
-        template_id: cfg.google.template_sheet_id.clone(),
-        full_name: cfg.user.full_name.clone(),
-        edit_target_month,
-        input_box: None,
-        wizard_state: wizard::WizardState::new(),
-        shortcuts,
-    };
+    // 初期設定スナップショットでWorkerを起動する。
+    let worker = tokio::spawn(worker::run(rx_cmd, tx_ev, app.cfg.clone(), dry_run));
 
-    // ウィザード以外なら起動時に一覧を更新する。
-    if initial_screen == Screen::Main {
+    // ウィザード以外なら起動時に一覧を更新する（設定画面から始めても裏で読み込んでおく）。
+    if app.ui.screen != Screen::InitialSetup {
         request_refresh(&mut app).await?;
     }
 
     loop {
         // 現在の状態を描画する。
-        terminal.draw(|f| draw(f, &app))?;
+        terminal.draw(|f| draw(f, &mut app))?;
 
         // 入力処理の前にWorkerイベントを消化する。
         while let Ok(ev) = app.worker_rx.try_recv() {
             handle_worker_event(&mut app, ev)?;
         }
 
+        // 外部で変更された設定ファイルがあれば再読み込みを尋ねる。
+        if let Some(watch) = &mut app.config_watch {
+            let changed = watch.poll(Instant::now());
+            if !changed.is_empty() {
+                app.on_config_files_changed(&changed);
+            }
+        }
+
         // UIの応答性確保のため短いタイムアウトで入力をポーリングする。
         if event::poll(Duration::from_millis(50))?
             && let Event::Key(k) = event::read()?
@@ -140,59 +187,1080 @@ pub async fn run_app(terminal: &mut Tui) -> Result<()> {
             }
         }
     }
+
+    // コマンドの送信側を閉じてWorkerのループを終わらせ、終了時の後処理を待つ。
+    let (closed_tx, _) = mpsc::channel::<WorkerCmd>(1);
+    drop(std::mem::replace(&mut app.worker_tx, closed_tx));
+    if tokio::time::timeout(WORKER_SHUTDOWN_TIMEOUT, worker)
+        .await
+        .is_err()
+    {
+        tracing::warn!("worker did not finish within {WORKER_SHUTDOWN_TIMEOUT:?}");
+    }
+
+    // 終了前に、キューに残ったWorkerイベント（完了直後の結果など）を取りこぼさず反映する。
+    for ev in drain_pending_events(&mut app.worker_rx) {
+        tracing::info!("worker event drained on shutdown: {ev:?}");
+        handle_worker_event(&mut app, ev)?;
+    }
+    app.on_exit();
     Ok(())
 }
 
+/// 設定ファイルとショートカット設定を読み込み、アプリ状態を組み立てる。
+fn load_app(
+    cfg_path: PathBuf,
+    shortcuts_path: &Path,
+    worker_tx: mpsc::Sender<WorkerCmd>,
+    worker_rx: mpsc::Receiver<WorkerEvent>,
+) -> Result<App> {
+    // 設定ファイルを読み込む（初回はデフォルトを生成）。
+    let cfg_loaded = Config::load_or_default(&cfg_path)?;
+    let cfg = cfg_loaded.value;
+
+    // ショートカット設定を読み込む（無ければデフォルト）。
+    let shortcuts_loaded = Shortcuts::load_or_default(shortcuts_path)?;
+    let shortcuts = shortcuts_loaded.value;
+    // 解釈できないキーや衝突しているキーは、起動時の警告として知らせる。
+    let shortcut_problems = shortcuts
+        .validate()
+        .err()
+        .map(|problems| format!("{}: {}", shortcuts_path.display(), problems.join("; ")));
+
+    // 配色設定を読み込む（無ければ既定の配色）。
+    let theme_loaded = Theme::load_or_default(theme::DEFAULT_THEME_PATH)?;
+
+    // 設定の充足度と`ui.startup_screen`に応じて初期画面を決める。
+    let initial_screen = initial_screen(&cfg);
+
+    // アプリ状態を初期化する。
+    let mut app = App::new(
+        cfg_path,
+        cfg,
+        shortcuts,
+        worker_tx,
+        worker_rx,
+        initial_screen,
+    );
+
+    // 外部での設定ファイルの変更を監視する（失敗しても起動は続ける）。
+    app.shortcuts_path = shortcuts_path.to_path_buf();
+    app.theme = theme_loaded.value;
+    match ConfigWatcher::new(&[app.cfg_path.as_path(), shortcuts_path]) {
+        Ok(watch) => app.config_watch = Some(watch),
+        Err(e) => tracing::warn!("cannot watch config files: {e}"),
+    }
+
+    // 前回の編集内容を復元し、Driveの一覧が届いたら突き合わせる。
+    let jobs_path = job_store::path_for(&app.cfg_path, app.cfg.active_profile_name());
+    app.restored_jobs = job_store::load(&jobs_path);
+    app.jobs_path = Some(jobs_path);
+
+    // 設定ファイルを読めなかった場合は保存を止め、警告を表示する。
+    app.cfg_read_only = cfg_loaded.warning.is_some();
+    let warnings: Vec<String> = [
+        cfg_loaded.warning,
+        shortcuts_loaded.warning,
+        shortcut_problems,
+        theme_loaded.warning,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !warnings.is_empty() {
+        app.ui.log.extend(warnings.iter().cloned());
+        app.ui.error = Some(warnings.join(" / "));
+    }
+    Ok(app)
+}
+
+impl App {
+    /// 既定以外の設定ファイルを使っている場合、そのファイル名を返す。
+    pub fn config_label(&self) -> Option<String> {
+        if self.cfg_path == Path::new(config::DEFAULT_CONFIG_PATH) {
+            return None;
+        }
+        // ディレクトリ部分は省き、ファイル名だけを表示する。
+        let name = self
+            .cfg_path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.cfg_path.display().to_string());
+        Some(name)
+    }
+
+    /// 設定とチャネルからアプリ状態を組み立てる。
+    pub fn new(
+        cfg_path: PathBuf,
+        cfg: Config,
+        shortcuts: Shortcuts,
+        worker_tx: mpsc::Sender<WorkerCmd>,
+        worker_rx: mpsc::Receiver<WorkerEvent>,
+        screen: Screen,
+    ) -> Self {
+        // 現在日時から編集対象月を自動生成する。
+        let now = Local::now();
+        let edit_target_month = dates::current_ym();
+
+        Self {
+            cfg_path,
+            cfg_read_only: false,
+            shortcuts_path: PathBuf::from(shortcuts::DEFAULT_SHORTCUTS_PATH),
+            config_watch: None,
+            config_reload: None,
+            started_at: now,
+            ui: UiState {
+                screen,
+                selected: 0,
+                table_offset: 0,
+                log_scroll: None,
+                log_bottom_start: 0,
+                log: vec![],
+                status: "Ready".into(),
+                editing_field_idx: 0,
+                error: None,
+                frame: 0,
+                status_filter: StatusFilter::All,
+                search: None,
+            },
+            jobs: vec![],
+            jobs_path: None,
+            restored_jobs: vec![],
+            next_page_token: None,
+            loading_more: false,
+            paused: false,
+            dry_run: false,
+            hide_done: false,
+            in_flight: HashSet::new(),
+            pending_commits: VecDeque::new(),
+            breaker: None,
+            duplicate_confirm: None,
+            retry_prompts: VecDeque::new(),
+            confirm_commit: None,
+            confirm_reset: false,
+            edit_undo: EditUndo::default(),
+            last_committed: None,
+            column_picker: None,
+            diagnostics: None,
+            worker_tx,
+            worker_rx,
+            in_folder: cfg.google.input_folder_id.clone(),
+            out_folder: cfg.google.output_folder_id.clone(),
+            template_id: cfg.google.template_sheet_id.clone(),
+            full_name: cfg.user.full_name.clone(),
+            cfg,
+            edit_target_month,
+            input_box: None,
+            wizard_state: wizard::WizardState::new(),
+            shortcuts,
+            theme: Theme::default(),
+            clipboard: ClipboardBridge::new(),
+            bell: Bell::default(),
+        }
+    }
+
+    /// ジョブ一覧の再取得を待っているか。
+    pub fn is_refreshing(&self) -> bool {
+        self.in_flight.contains(&InFlight::Refresh)
+    }
+
+    /// 完了を待っている確定の件数。
+    pub fn commits_in_flight(&self) -> usize {
+        self.in_flight
+            .iter()
+            .filter(|op| matches!(op, InFlight::Commit(_)))
+            .count()
+    }
+
+    /// 変更を通知された設定ファイルを読み直し、内容が変わっていれば再読み込みを尋ねる。
+    pub fn on_config_files_changed(&mut self, paths: &[PathBuf]) {
+        let mut reload = self.config_reload.take().unwrap_or(ConfigReload {
+            config: None,
+            shortcuts: None,
+        });
+        for path in paths {
+            let result = if *path == self.cfg_path {
+                config_watch::changed_on_disk(path, &self.cfg).map(|c| {
+                    reload.config = c.or(reload.config.take());
+                })
+            } else if *path == self.shortcuts_path {
+                config_watch::changed_on_disk(path, &self.shortcuts).map(|s| {
+                    reload.shortcuts = s.or(reload.shortcuts.take());
+                })
+            } else {
+                Ok(())
+            };
+            // 解釈できない途中の保存などは、ログに残して次の変更を待つ。
+            if let Err(e) = result {
+                tracing::warn!("{e}");
+                self.ui.log.push(e);
+            }
+        }
+        if reload.config.is_some() || reload.shortcuts.is_some() {
+            self.config_reload = Some(reload);
+        }
+    }
+
+    /// 一覧に表示するジョブか（完了を隠している場合や、状態で絞り込んでいる場合はそれを除く）。
+    pub fn is_job_visible(&self, job: &Job) -> bool {
+        !(self.hide_done && matches!(job.status, JobStatus::Done))
+            && self.ui.status_filter.matches(&job.status)
+    }
+
+    /// 一覧で選択中のジョブ（表示中のジョブが無く、隠れたジョブを指していれば`None`）。
+    pub fn selected_job(&self) -> Option<&Job> {
+        self.jobs
+            .get(self.ui.selected)
+            .filter(|j| self.is_job_visible(j))
+    }
+
+    /// 選択を指定の行へ移し、別の行になれば編集の取り消し履歴を捨てる。
+    pub fn select_job(&mut self, idx: usize) {
+        if self.ui.selected != idx {
+            self.edit_undo = EditUndo::default();
+        }
+        self.ui.selected = idx;
+    }
+
+    /// 一覧に表示するジョブの添字を返す。
+    pub fn visible_job_indices(&self) -> Vec<usize> {
+        (0..self.jobs.len())
+            .filter(|&i| self.is_job_visible(&self.jobs[i]))
+            .collect()
+    }
+
+    /// 選択が隠れたジョブを指していれば、後ろ（無ければ前）の表示中のジョブへ移す。
+    pub fn keep_selection_visible(&mut self) {
+        let visible = self.visible_job_indices();
+        if visible.contains(&self.ui.selected) {
+            return;
+        }
+        let next = visible.iter().find(|&&i| i > self.ui.selected);
+        if let Some(&i) = next.or(visible.last()) {
+            self.select_job(i);
+        }
+    }
+
+    /// ジョブ一覧を保存する（未突き合わせの復元分も含める）。
+    ///
+    /// dry-runでは完了にしたジョブが次回の起動で確定済みとして復元されないよう、保存しない。
+    pub fn save_jobs(&mut self) {
+        if self.dry_run {
+            return;
+        }
+        let Some(path) = &self.jobs_path else {
+            return;
+        };
+        let jobs: Vec<Job> = self
+            .jobs
+            .iter()
+            .chain(&self.restored_jobs)
+            .cloned()
+            .collect();
+        if let Err(e) = job_store::save(path, &jobs) {
+            tracing::warn!("failed to save jobs: {e}");
+            self.ui
+                .log
+                .push(format!("warning: failed to save jobs: {e}"));
+        }
+    }
+
+    /// 終了時の後処理として、セッションの集計をログへ残す。
+    pub fn on_exit(&self) {
+        tracing::info!("{}", self.session_summary(Local::now()));
+    }
+
+    /// セッションの集計を1行の文字列にする（ログのgrep用）。
+    fn session_summary(&self, ended_at: DateTime<Local>) -> String {
+        let stats = JobStats::from_jobs(&self.jobs);
+        format!(
+            "session summary: start={} end={} loaded={} committed={} failed={} amounts={}",
+            self.started_at.to_rfc3339(),
+            ended_at.to_rfc3339(),
+            stats.total,
+            stats.done,
+            stats.error,
+            stats.done_amounts_label(self.cfg.user.default_currency())
+        )
+    }
+}
+
+/// 受信キューに溜まっているWorkerイベントをすべて取り出す。
+fn drain_pending_events(rx: &mut mpsc::Receiver<WorkerEvent>) -> Vec<WorkerEvent> {
+    let mut pending = vec![];
+    // ブロックせずに取り出せる分だけ回収する。
+    while let Ok(ev) = rx.try_recv() {
+        pending.push(ev);
+    }
+    pending
+}
+
+/// 検出が有効なら、対象月に提出済みのジョブへ印を付ける。
+fn mark_submitted_jobs(app: &mut App) {
+    if !app.cfg.duplicates.detect {
+        return;
+    }
+    let records = submissions::load(Path::new(submissions::SUBMISSIONS_PATH));
+    submissions::mark_already_submitted(&mut app.jobs, &records, &app.edit_target_month);
+}
+
+/// 設定が有効なら、直前の確定内容を今回初めて読み込んだ編集待ちのジョブへ引き継ぐ。
+///
+/// `known`は読み込み前から一覧や保存データにあったジョブのID（確定済みや意図して空欄にしたものは変えない）。
+fn inherit_last_committed(app: &mut App, known: &HashSet<Uuid>) {
+    if !app.cfg.defaults.inherit_from_last {
+        return;
+    }
+    // まだ確定していなければ既定の初期値のままにする。
+    let Some(last) = &app.last_committed else {
+        return;
+    };
+    for job in app
+        .jobs
+        .iter_mut()
+        .filter(|j| !known.contains(&j.id) && matches!(j.status, JobStatus::WaitingUserFix))
+    {
+        job.fields.inherit_from(last);
+    }
+}
+
 /// WorkerイベントをUI状態へ反映する。
 fn handle_worker_event(app: &mut App, ev: WorkerEvent) -> Result<()> {
     match ev {
-        WorkerEvent::JobsLoaded(jobs) => {
-            // ジョブ一覧を更新し選択を先頭に戻す。
+        WorkerEvent::JobsLoaded {
+            jobs,
+            next_page_token,
+        } => {
+            // 編集中や復元したジョブの内容を引き継いで一覧を更新し、選択を先頭に戻す。
+            let mut saved = std::mem::take(&mut app.jobs);
+            saved.append(&mut app.restored_jobs);
+            let known: HashSet<Uuid> = saved.iter().map(|j| j.id).collect();
+            let (jobs, leftover) = job_store::reconcile(saved, jobs);
             app.jobs = jobs;
+            // 続きのページがあれば、まだ現れていないだけなので残しておく。
+            if next_page_token.is_some() {
+                app.restored_jobs = leftover;
+            }
+            inherit_last_committed(app, &known);
+            mark_submitted_jobs(app);
+            app.save_jobs();
+            app.next_page_token = next_page_token;
+            app.loading_more = false;
+            app.in_flight.remove(&InFlight::Refresh);
+            // 一覧を読み直したので、前の一覧での取り消し履歴も捨てる。
             app.ui.selected = 0;
+            app.edit_undo = EditUndo::default();
+            app.ui.status = format!("Loaded {} jobs", app.jobs.len());
+        }
+        WorkerEvent::JobsAppended {
+            jobs,
+            next_page_token,
+        } => {
+            // 既存の一覧の末尾へ追加する（選択位置はそのまま維持する）。
+            let saved = std::mem::take(&mut app.restored_jobs);
+            let known: HashSet<Uuid> = app.jobs.iter().chain(&saved).map(|j| j.id).collect();
+            let (jobs, leftover) = job_store::reconcile(saved, jobs);
+            app.jobs.extend(jobs);
+            if next_page_token.is_some() {
+                app.restored_jobs = leftover;
+            }
+            inherit_last_committed(app, &known);
+            mark_submitted_jobs(app);
+            app.save_jobs();
+            app.next_page_token = next_page_token;
+            app.loading_more = false;
             app.ui.status = format!("Loaded {} jobs", app.jobs.len());
         }
         WorkerEvent::JobUpdated { job_id, status } => {
+            // 完了/失敗への遷移なら設定に応じてベルを鳴らす。
+            let ring = match status {
+                JobStatus::Done => app.cfg.ui.bell_on_complete,
+                JobStatus::Error(_) => app.cfg.ui.bell_on_error,
+                _ => false,
+            };
+            if ring {
+                app.bell.ring();
+            }
             // 対象ジョブの状態を更新する。
             if let Some(j) = app.jobs.iter_mut().find(|j| j.id == job_id) {
+                // 確定できた内容は次に読み込むジョブの初期値として覚えておく（dry-runは確定していない）。
+                if matches!(status, JobStatus::Done) && !app.dry_run {
+                    app.last_committed = Some(j.fields.clone());
+                }
+                let finished = matches!(status, JobStatus::Done | JobStatus::Error(_));
                 j.status = status;
+                // 確定が終わったら結果を保存し、リフレッシュを受け付ける。
+                if finished {
+                    app.save_jobs();
+                    app.in_flight.remove(&InFlight::Commit(job_id));
+                }
+            }
+        }
+        WorkerEvent::CommitFailed {
+            job_id,
+            target_month_ym,
+            error,
+        } => {
+            if app.cfg.ui.bell_on_error {
+                app.bell.ring();
+            }
+            // 一覧では失敗として表示し、月を直して再送できるよう対象月を覚えておく。
+            let reason = error.to_string();
+            if let Some(j) = app.jobs.iter_mut().find(|j| j.id == job_id) {
+                j.status = JobStatus::Error(reason.clone());
+                j.failed_month = Some(target_month_ym);
+            }
+            app.save_jobs();
+            app.in_flight.remove(&InFlight::Commit(job_id));
+            app.ui.status = format!("Commit failed: {reason}");
+            // 対話的な再試行が有効なら確認する（認証エラーは再試行しても直らないため、再認証の案内だけにする）。
+            if app.cfg.ui.interactive_retry && !matches!(error, WorkerError::Auth(_)) {
+                app.retry_prompts.push_back(RetryPrompt { job_id, reason });
             }
         }
+        WorkerEvent::SheetCopied {
+            job_id,
+            copied_sheet_id,
+        } => {
+            // 診断で参照できるよう作成したシートを記録する。
+            if let Some(j) = app.jobs.iter_mut().find(|j| j.id == job_id) {
+                j.copied_sheet_id = Some(copied_sheet_id);
+            }
+        }
+        WorkerEvent::BatchFinished {
+            job_ids,
+            committed,
+            total,
+            skipped,
+        } => {
+            // 飛ばしたジョブも含め、この一括確定の分はすべて終わっている（単体の確定は残す）。
+            for job_id in job_ids {
+                app.in_flight.remove(&InFlight::Commit(job_id));
+            }
+            // 一括確定の内訳をステータスへ表示する。
+            let failed = total - committed - skipped;
+            let mut status = format!("Committed {committed} of {total} ({skipped} skipped");
+            if failed > 0 {
+                status.push_str(&format!(", {failed} failed"));
+            }
+            status.push(')');
+            app.ui.log.push(status.clone());
+            app.ui.status = status;
+        }
+        WorkerEvent::RowWritten { job_id, range } => {
+            // シートから読み戻せるよう書き込んだレンジを記録する。
+            if let Some(j) = app.jobs.iter_mut().find(|j| j.id == job_id) {
+                j.committed_range = Some(range);
+            }
+        }
+        WorkerEvent::SheetPulled { job_id, fields } => {
+            apply_pulled_fields(app, job_id, fields);
+            app.save_jobs();
+        }
+        WorkerEvent::OcrGuessed { job_id, guess } => {
+            apply_ocr_guess(app, job_id, guess);
+            app.save_jobs();
+        }
+        WorkerEvent::IdChecked { kind, id, error } => apply_id_check(app, kind, id, error),
+        WorkerEvent::SetupChecked { check, error } => apply_setup_check(app, check, error),
+        WorkerEvent::TemplateHeaders {
+            header_row,
+            columns,
+        } => {
+            // 見出しがあれば列の割り当てピッカーを開く。
+            if columns.is_empty() {
+                app.ui.status = format!(
+                    "No headers in row {header_row} of the template (set general_expense.header_row)"
+                );
+            } else {
+                app.ui.status = format!("Map columns from row {header_row}");
+                app.column_picker = Some(ColumnPicker::new(
+                    header_row,
+                    columns,
+                    &app.cfg.general_expense,
+                ));
+            }
+        }
+        WorkerEvent::Diagnostics { job_id, text } => {
+            // 対象ジョブのファイル名をタイトルにしてポップアップを開く。
+            let filename = app
+                .jobs
+                .iter()
+                .find(|j| j.id == job_id)
+                .map(|j| j.filename.clone())
+                .unwrap_or_default();
+            app.diagnostics = Some(DiagnosticsView::new(
+                format!("Diagnostics: {filename}"),
+                &text,
+            ));
+            app.ui.status = "Diagnostics loaded".into();
+        }
+        WorkerEvent::WritePlanned(plan) => {
+            // 書き込み予定の行をステータスとログに表示する。
+            app.ui.status = plan.describe();
+            app.ui.log.push(plan.describe());
+        }
+        WorkerEvent::Breaker(status) => {
+            // 開閉の切り替わりをログに残し、状態はステータスバーで表示する。
+            match status.state {
+                BreakerState::Open { .. } => app
+                    .ui
+                    .log
+                    .push("commits paused: Google API keeps failing".into()),
+                BreakerState::Closed
+                    if app.breaker.is_some_and(|b| b.state != BreakerState::Closed) =>
+                {
+                    app.ui
+                        .log
+                        .push("commits resumed: Google API recovered".into());
+                }
+                _ => {}
+            }
+            app.breaker = Some(status);
+        }
+        WorkerEvent::LoggedOut => {
+            // 認証の確認からやり直す（フォルダIDなどは入力済みの値が初期値になる）。
+            app.ui.screen = Screen::InitialSetup;
+            app.wizard_state.current_step = wizard::WizardStep::CheckAuth;
+            app.ui.log.push("logged out".into());
+            app.ui.status = "Logged out; sign in again to continue".into();
+        }
         WorkerEvent::Log(s) => {
             // ログを追加する。
             app.ui.log.push(s);
         }
-        WorkerEvent::Error(s) => {
-            // ステータスにエラーを表示する。
-            app.ui.status = format!("Error: {s}");
+        WorkerEvent::ListFailed(e) => {
+            // 追加読み込みやリフレッシュを再試行できるよう解除してから、エラーとして表示する。
+            app.loading_more = false;
+            app.in_flight.remove(&InFlight::Refresh);
+            show_worker_error(app, &e);
+        }
+        WorkerEvent::Error(e) => {
+            // 他の操作のエラーでは、まだ届いていない一覧の読み込み待ちを解除しない。
+            show_worker_error(app, &e);
         }
     }
+    // 完了して隠れたジョブを選択したままにしない。
+    app.keep_selection_visible();
     Ok(())
 }
 
+/// Workerのエラーをステータスに表示する（認証エラーは再起動が必要なので強調する）。
+fn show_worker_error(app: &mut App, e: &WorkerError) {
+    app.ui.status = format!("Error: {e}");
+    if matches!(e, WorkerError::Auth(_)) {
+        app.ui.error = Some(e.to_string());
+    }
+}
+
 /// 必須設定が揃っていればWorkerへリフレッシュ要求する。
 pub async fn request_refresh(app: &mut App) -> Result<()> {
     // 必須IDが未設定なら案内メッセージを出す。
     if app.cfg.google.input_folder_id.is_empty()
-        || app.cfg.google.output_folder_id.is_empty()
+        || (app.cfg.pdf.enabled && app.cfg.google.output_folder_id.is_empty())
         || app.cfg.google.template_sheet_id.is_empty()
     {
         app.ui.status = "Settings required (press t)".into();
         tracing::warn!("refresh skipped: settings required");
+    } else if app.commits_in_flight() > 0 {
+        // 確定の途中で一覧が入れ替わらないよう、終わるまで待たせる。
+        app.ui.status = "Busy: committing; refresh when it finishes".into();
+        tracing::info!("refresh skipped: commit in flight");
     } else {
         // Workerへリフレッシュを依頼する。
         tracing::info!("refresh requested");
         app.worker_tx.send(WorkerCmd::RefreshJobs).await?;
+        app.in_flight.insert(InFlight::Refresh);
         app.ui.status = "Refreshing jobs...".into();
     }
     Ok(())
 }
 
+/// ウィザードのIDの確認結果を反映する。
+///
+/// 問題が無ければ次のステップへ進み、失敗なら理由を表示して同じステップに留まる。
+/// 確認中にステップや値が変わっていれば、古い結果として無視する。
+fn apply_id_check(app: &mut App, kind: IdKind, id: String, error: Option<String>) {
+    let (step, current) = match kind {
+        IdKind::InputFolder => (wizard::WizardStep::InputFolderId, &app.in_folder),
+        IdKind::OutputFolder => (wizard::WizardStep::OutputFolderId, &app.out_folder),
+        IdKind::TemplateSheet => (wizard::WizardStep::TemplateSheetId, &app.template_id),
+    };
+    if app.ui.screen != Screen::InitialSetup
+        || app.wizard_state.current_step != step
+        || *current != id
+    {
+        return;
+    }
+    match error {
+        None => {
+            app.ui.error = None;
+            app.ui.status = format!("{} OK", kind.label());
+            app.wizard_state.next_step();
+        }
+        Some(e) => {
+            app.ui.status = format!("{} check failed", kind.label());
+            app.ui.error = Some(format!(
+                "{} {}: {e}",
+                kind.label(),
+                format::display_id(&id, app.cfg.ui.mask_sensitive)
+            ));
+        }
+    }
+}
+
+/// 接続テストの1項目の結果を記録し、全項目が揃ったら結果を知らせる。
+fn apply_setup_check(app: &mut App, check: wizard::SetupCheck, error: Option<String>) {
+    // 接続テストのステップを離れていれば古い結果として無視する。
+    if app.ui.screen != Screen::InitialSetup
+        || app.wizard_state.current_step != wizard::WizardStep::Verify
+    {
+        return;
+    }
+    let wizard = &mut app.wizard_state;
+    wizard.record_check(check, error);
+    if wizard.checks_running() {
+        return;
+    }
+    match wizard.first_failed_check() {
+        None => app.ui.status = "Setup verified — press Enter to finish".into(),
+        Some(failed) => {
+            app.ui.status = format!("Press Enter to fix the {}", failed.label());
+            app.ui.error = Some(format!("Verification failed: {}", failed.label()));
+        }
+    }
+}
+
+/// 文字認識で推測した項目のうち、まだ空欄のものだけをジョブへ入れる。
+///
+/// 入力済みの値は推測より確かなので上書きせず、推測値はログにだけ残す。
+fn apply_ocr_guess(app: &mut App, job_id: Uuid, guess: OcrGuess) {
+    let Some(j) = app.jobs.iter_mut().find(|j| j.id == job_id) else {
+        return;
+    };
+    if guess.is_empty() {
+        app.ui.status = format!("OCR could not read a date or total from {}", j.filename);
+        return;
+    }
+    let mut filled = vec![];
+    if let Some(date) = guess.date_ymd {
+        if j.fields.date_ymd.trim().is_empty() {
+            j.fields.date_ymd = date;
+            filled.push("date");
+        } else {
+            app.ui
+                .log
+                .push(format!("{}: OCR date {date} (kept yours)", j.filename));
+        }
+    }
+    if let Some(amount) = guess.amount_yen {
+        if j.fields.amount_yen == 0 {
+            j.fields.amount_yen = amount;
+            filled.push("amount");
+        } else {
+            app.ui
+                .log
+                .push(format!("{}: OCR amount {amount} (kept yours)", j.filename));
+        }
+    }
+    app.ui.status = if filled.is_empty() {
+        format!("OCR filled nothing for {}", j.filename)
+    } else {
+        format!("OCR filled {} for {}", filled.join(", "), j.filename)
+    };
+    app.ui.log.push(app.ui.status.clone());
+}
+
+/// シートから読み戻した内容をジョブへ反映し、差分を知らせる。
+fn apply_pulled_fields(app: &mut App, job_id: Uuid, fields: ReceiptFields) {
+    let Some(j) = app.jobs.iter_mut().find(|j| j.id == job_id) else {
+        return;
+    };
+    // 経費行に通貨の列は無いため、通貨はジョブの値を残す。
+    let fields = ReceiptFields {
+        currency: j.fields.currency.clone(),
+        ..fields
+    };
+    // 変わった項目ごとに旧値と新値をログへ残す。
+    let changed = j.fields.differing_fields(&fields);
+    for &idx in &changed {
+        app.ui.log.push(format!(
+            "{}: {} {:?} -> {:?}",
+            j.filename,
+            ReceiptFields::field_name(idx),
+            j.fields.field_value(idx),
+            fields.field_value(idx)
+        ));
+    }
+    j.fields = fields;
+    app.ui.status = if changed.is_empty() {
+        format!("Pulled {} from sheet: no differences", j.filename)
+    } else {
+        let names: Vec<&str> = changed
+            .iter()
+            .map(|&i| ReceiptFields::field_name(i))
+            .collect();
+        format!(
+            "Pulled {} from sheet: {} changed",
+            j.filename,
+            names.join(", ")
+        )
+    };
+}
+
+/// 起動時の画面を決める（ウィザードが必要なら設定より優先する）。
+fn initial_screen(cfg: &Config) -> Screen {
+    if needs_initial_setup(cfg) {
+        return Screen::InitialSetup;
+    }
+    match cfg.ui.startup_screen {
+        StartupScreen::Main => Screen::Main,
+        StartupScreen::Settings => Screen::Settings,
+    }
+}
+
 /// 初期設定ウィザードが必要か判定する。
 fn needs_initial_setup(cfg: &Config) -> bool {
-    // いずれかの必須項目が未入力ならウィザード対象。
+    // いずれかの必須項目が未入力ならウィザード対象（出力フォルダはPDFを出す場合だけ必須）。
     cfg.google.input_folder_id.is_empty()
-        || cfg.google.output_folder_id.is_empty()
+        || (cfg.pdf.enabled && cfg.google.output_folder_id.is_empty())
         || cfg.google.template_sheet_id.is_empty()
-        || cfg.user.full_name == "Your Name"
+        || cfg.user.has_placeholder_name()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// テスト用のアプリ状態と、Worker側のチャネル端を作る。
+    pub(crate) fn test_app() -> (App, mpsc::Receiver<WorkerCmd>, mpsc::Sender<WorkerEvent>) {
+        let (tx_cmd, rx_cmd) = mpsc::channel::<WorkerCmd>(64);
+        let (tx_ev, rx_ev) = mpsc::channel::<WorkerEvent>(256);
+        let app = App::new(
+            PathBuf::from(config::DEFAULT_CONFIG_PATH),
+            Config::default(),
+            Shortcuts::default(),
+            tx_cmd,
+            rx_ev,
+            Screen::Main,
+        );
+        let app = App {
+            clipboard: ClipboardBridge::headless(),
+            ..app
+        };
+        (app, rx_cmd, tx_ev)
+    }
+
+    #[test]
+    fn test_batch_finished_summary() {
+        // 一括確定の内訳がステータスに表示されることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        let finished = |committed, skipped| WorkerEvent::BatchFinished {
+            job_ids: vec![],
+            committed,
+            total: 9,
+            skipped,
+        };
+        handle_worker_event(&mut app, finished(7, 2)).unwrap();
+        assert_eq!(app.ui.status, "Committed 7 of 9 (2 skipped)");
+        handle_worker_event(&mut app, finished(6, 2)).unwrap();
+        assert_eq!(app.ui.status, "Committed 6 of 9 (2 skipped, 1 failed)");
+    }
+
+    #[test]
+    fn test_batch_finished_keeps_unrelated_commits() {
+        // 一括確定の完了では、その一括分だけを処理中から外し、単体の確定は残すことを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        let batch = Uuid::new_v4();
+        let single = Uuid::new_v4();
+        app.in_flight.insert(InFlight::Commit(batch));
+        app.in_flight.insert(InFlight::Commit(single));
+        let finished = WorkerEvent::BatchFinished {
+            job_ids: vec![batch],
+            committed: 1,
+            total: 1,
+            skipped: 0,
+        };
+        handle_worker_event(&mut app, finished).unwrap();
+        assert!(!app.in_flight.contains(&InFlight::Commit(batch)));
+        assert!(app.in_flight.contains(&InFlight::Commit(single)));
+    }
+
+    #[test]
+    fn test_custom_config_path_is_loaded_and_labeled() {
+        // 指定したパスの設定が読み込まれ、既定以外ならファイル名が表示用に返ることを検証する。
+        let dir = std::env::temp_dir().join(format!("receipt_tui_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cfg_path = dir.join("work.toml");
+        let mut cfg = Config::default();
+        cfg.google.input_folder_id = "work-inbox".into();
+        cfg.save(&cfg_path).unwrap();
+
+        let (tx_cmd, _rx_cmd) = mpsc::channel::<WorkerCmd>(64);
+        let (_tx_ev, rx_ev) = mpsc::channel::<WorkerEvent>(256);
+        let app = load_app(cfg_path, &dir.join("keys.toml"), tx_cmd, rx_ev).unwrap();
+        assert_eq!(app.cfg.google.input_folder_id, "work-inbox");
+        assert_eq!(app.config_label().as_deref(), Some("work.toml"));
+
+        // 既定の設定ファイルでは表示しない。
+        let (app, _rx_cmd, _tx_ev) = test_app();
+        assert_eq!(app.config_label(), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_startup_screen_honored_when_setup_not_needed() {
+        // 必須項目がそろっていれば設定した起動画面で始まり、欠けていればウィザードが優先されることを検証する。
+        let dir = std::env::temp_dir().join(format!("receipt_tui_{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cfg_path = dir.join("config.toml");
+        let mut cfg = Config::default();
+        cfg.google.input_folder_id = "inbox".into();
+        cfg.google.output_folder_id = "outbox".into();
+        cfg.google.template_sheet_id = "template".into();
+        cfg.user.full_name = "山田 太郎".into();
+        cfg.ui.startup_screen = StartupScreen::Settings;
+        cfg.save(&cfg_path).unwrap();
+
+        let load = || {
+            let (tx_cmd, _rx_cmd) = mpsc::channel::<WorkerCmd>(64);
+            let (_tx_ev, rx_ev) = mpsc::channel::<WorkerEvent>(256);
+            load_app(cfg_path.clone(), &dir.join("keys.toml"), tx_cmd, rx_ev).unwrap()
+        };
+        assert_eq!(load().ui.screen, Screen::Settings);
+
+        // PDFを出さないなら、出力フォルダが空でもウィザードへ戻さない。
+        cfg.google.output_folder_id.clear();
+        cfg.pdf.enabled = false;
+        cfg.save(&cfg_path).unwrap();
+        assert_eq!(load().ui.screen, Screen::Settings);
+
+        cfg.google.template_sheet_id.clear();
+        cfg.save(&cfg_path).unwrap();
+        assert_eq!(load().ui.screen, Screen::InitialSetup);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_refresh_without_output_folder_when_pdf_disabled() {
+        // PDFを出さない設定では、出力フォルダが空でも一覧を読み込めることを検証する。
+        let (mut app, mut rx_cmd, _tx_ev) = test_app();
+        app.cfg.google.input_folder_id = "in".into();
+        app.cfg.google.template_sheet_id = "tpl".into();
+        request_refresh(&mut app).await.unwrap();
+        assert_eq!(app.ui.status, "Settings required (press t)");
+        assert!(rx_cmd.try_recv().is_err());
+
+        app.cfg.pdf.enabled = false;
+        request_refresh(&mut app).await.unwrap();
+        assert!(matches!(rx_cmd.try_recv(), Ok(WorkerCmd::RefreshJobs)));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_commit_leaves_jobs_committable() {
+        // dry-runで完了になったジョブが保存されず、読み直すと確定待ちのままであることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        let path =
+            std::env::temp_dir().join(format!("receipt_tui_dry_run_{}.json", uuid::Uuid::new_v4()));
+        app.jobs_path = Some(path.clone());
+        let mut job = Job::new("f1".into(), "f1.jpg".into());
+        job.status = JobStatus::WaitingUserFix;
+        app.jobs.push(job);
+        app.save_jobs();
+
+        app.dry_run = true;
+        let job_id = app.jobs[0].id;
+        handle_worker_event(
+            &mut app,
+            WorkerEvent::JobUpdated {
+                job_id,
+                status: JobStatus::Done,
+            },
+        )
+        .unwrap();
+        assert!(matches!(app.jobs[0].status, JobStatus::Done));
+        assert!(app.last_committed.is_none());
+
+        let reloaded = job_store::load(&path);
+        assert_eq!(reloaded.len(), 1);
+        assert!(matches!(reloaded[0].status, JobStatus::WaitingUserFix));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_new_jobs_inherit_last_committed_fields() {
+        // 確定済みの勘定科目と備考が、後から読み込んだジョブへ引き継がれることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        app.cfg.defaults.inherit_from_last = true;
+        let waiting = |id: &str| {
+            let mut job = Job::new(id.into(), format!("{id}.jpg"));
+            job.status = JobStatus::WaitingUserFix;
+            job
+        };
+        let loaded = |id: &str| WorkerEvent::JobsLoaded {
+            jobs: vec![waiting(id)],
+            next_page_token: None,
+        };
+
+        // 確定前は既定の初期値のまま。
+        handle_worker_event(&mut app, loaded("f1")).unwrap();
+        assert_eq!(app.jobs[0].fields.category, "");
+
+        app.jobs[0].fields.category = "旅費交通費".into();
+        app.jobs[0].fields.note = "客先訪問".into();
+        let job_id = app.jobs[0].id;
+        handle_worker_event(
+            &mut app,
+            WorkerEvent::JobUpdated {
+                job_id,
+                status: JobStatus::Done,
+            },
+        )
+        .unwrap();
+
+        handle_worker_event(
+            &mut app,
+            WorkerEvent::JobsAppended {
+                jobs: vec![waiting("f2")],
+                next_page_token: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(app.jobs[1].fields.category, "旅費交通費");
+        assert_eq!(app.jobs[1].fields.note, "客先訪問");
+        // 日付や金額は引き継がない。
+        assert_eq!(app.jobs[1].fields.amount_yen, 0);
+
+        // 無効なら引き継がない。
+        app.cfg.defaults.inherit_from_last = false;
+        handle_worker_event(&mut app, loaded("f3")).unwrap();
+        assert_eq!(app.jobs[0].fields.category, "");
+    }
+
+    #[tokio::test]
+    async fn test_refresh_keeps_existing_jobs_unchanged() {
+        // 再読み込みでは、既にある確定済みのジョブへ直前の確定内容を引き継がないことを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        app.cfg.defaults.inherit_from_last = true;
+        app.last_committed = Some(ReceiptFields {
+            category: "旅費交通費".into(),
+            note: "客先訪問".into(),
+            ..ReceiptFields::default()
+        });
+        let mut done = Job::new("f1".into(), "f1.jpg".into());
+        done.status = JobStatus::Done;
+        done.fields.category = "会議費".into();
+        app.jobs.push(done);
+
+        let mut fresh = Job::new("f1".into(), "f1.jpg".into());
+        fresh.status = JobStatus::WaitingUserFix;
+        handle_worker_event(
+            &mut app,
+            WorkerEvent::JobsLoaded {
+                jobs: vec![fresh],
+                next_page_token: None,
+            },
+        )
+        .unwrap();
+        assert!(matches!(app.jobs[0].status, JobStatus::Done));
+        assert_eq!(app.jobs[0].fields.category, "会議費");
+        assert_eq!(app.jobs[0].fields.note, "");
+    }
+
+    #[tokio::test]
+    async fn test_commit_failed_event_opens_retry_prompt() {
+        // 確定失敗イベントが再試行プロンプトとジョブの失敗表示に変換されることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        app.cfg.ui.interactive_retry = true;
+        let job = Job::new("file-1".into(), "a.jpg".into());
+        let job_id = job.id;
+        app.jobs.push(job);
+
+        handle_worker_event(
+            &mut app,
+            WorkerEvent::CommitFailed {
+                job_id,
+                target_month_ym: "2025-12".into(),
+                error: WorkerError::Other("HTTP status 400".into()),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            app.retry_prompts.front(),
+            Some(&RetryPrompt {
+                job_id,
+                reason: "HTTP status 400".into(),
+            })
+        );
+        assert!(matches!(&app.jobs[0].status, JobStatus::Error(r) if r == "HTTP status 400"));
+        assert_eq!(app.jobs[0].failed_month.as_deref(), Some("2025-12"));
+
+        // 認証エラーは再試行を尋ねず、再認証を案内する。
+        app.retry_prompts.clear();
+        handle_worker_event(
+            &mut app,
+            WorkerEvent::CommitFailed {
+                job_id,
+                target_month_ym: "2025-12".into(),
+                error: WorkerError::Auth("token failed".into()),
+            },
+        )
+        .unwrap();
+        assert!(app.retry_prompts.is_empty());
+        assert!(app.ui.status.contains("sign in again"));
+    }
+
+    #[tokio::test]
+    async fn test_session_summary_counts_outcomes() {
+        // ジョブ状態からセッション集計が組み立てられることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = test_app();
+        for (status, amount, currency) in [
+            (JobStatus::Done, 1200, ""),
+            (JobStatus::Done, 800, "JPY"),
+            (JobStatus::Done, 15, "USD"),
+            (JobStatus::Error("quota".into()), 500, ""),
+            (JobStatus::Queued, 300, ""),
+        ] {
+            let mut job = Job::new("f".into(), "a.jpg".into());
+            job.status = status;
+            job.fields.amount_yen = amount;
+            job.fields.currency = currency.into();
+            app.jobs.push(job);
+        }
+        let ended_at = app.started_at + chrono::Duration::minutes(5);
+
+        let summary = app.session_summary(ended_at);
+        assert!(summary.starts_with("session summary: "));
+        assert!(summary.contains(&format!("start={}", app.started_at.to_rfc3339())));
+        assert!(summary.contains(&format!("end={}", ended_at.to_rfc3339())));
+        assert!(summary.ends_with("loaded=5 committed=3 failed=1 amounts=JPY:2000,USD:15"));
+    }
+
+    #[tokio::test]
+    async fn test_pending_events_drained_on_shutdown() {
+        // 終了時に未処理のイベントが回収され、状態へ反映されることを検証する。
+        let (mut app, _rx_cmd, tx_ev) = test_app();
+        let job = Job::new("file-1".into(), "a.jpg".into());
+        let job_id = job.id;
+        app.jobs.push(job);
+
+        tx_ev
+            .send(WorkerEvent::JobUpdated {
+                job_id,
+                status: JobStatus::Done,
+            })
+            .await
+            .unwrap();
+        tx_ev.send(WorkerEvent::Log("final".into())).await.unwrap();
+        drop(tx_ev);
+
+        let pending = drain_pending_events(&mut app.worker_rx);
+        assert_eq!(pending.len(), 2);
+        for ev in pending {
+            handle_worker_event(&mut app, ev).unwrap();
+        }
+        assert!(matches!(app.jobs[0].status, JobStatus::Done));
+        assert_eq!(app.ui.log, vec!["final".to_string()]);
+        assert!(drain_pending_events(&mut app.worker_rx).is_empty());
+    }
 }
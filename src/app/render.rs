@@ -3,71 +3,559 @@
 use ratatui::{
     Frame,
     prelude::*,
-    widgets::{Block, Borders, Paragraph, Row, Table, Wrap},
+    widgets::{
+        Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Row, Table, Wrap,
+    },
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::{events::Screen, input, jobs::JobStatus, layout, shortcuts::Shortcuts};
+use crate::{
+    column_map::{ColumnPicker, MAPPED_FIELDS},
+    config,
+    config_watch::ConfigReload,
+    diagnostics::DiagnosticsView,
+    events::{RetryPrompt, Screen, StatusFilter},
+    format, input,
+    jobs::{Job, JobStats, JobStatus},
+    layout,
+    shortcuts::Shortcuts,
+    validation,
+    wizard::CheckState,
+    worker::{self, CommitRequest},
+};
 
 use super::App;
 
+/// ジョブ一覧の状態列の表示幅。
+const STATUS_COL_WIDTH: usize = 12;
+/// ジョブ一覧の金額列の表示幅。
+const AMOUNT_COL_WIDTH: usize = 10;
+/// ジョブ一覧の日付列の表示幅。
+const DATE_COL_WIDTH: usize = 12;
+
+/// ジョブ一覧の勘定科目列の表示幅。
+const CATEGORY_COL_WIDTH: usize = 14;
+
+/// 処理中のジョブに添えるスピナーのコマ（描画ごとに1コマ進める）。
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// ジョブ一覧に表示できる列。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum JobColumn {
+    Index,
+    File,
+    Status,
+    Amount,
+    Date,
+    Category,
+}
+
+impl JobColumn {
+    /// 設定の列名から列を求める（未知の名前はNone）。
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "#" => Some(Self::Index),
+            "file" => Some(Self::File),
+            "status" => Some(Self::Status),
+            "amount" => Some(Self::Amount),
+            "date" => Some(Self::Date),
+            "category" => Some(Self::Category),
+            _ => None,
+        }
+    }
+
+    /// 見出しの文字列。
+    fn header(self) -> String {
+        match self {
+            Self::Index => "#".into(),
+            Self::File => "file".into(),
+            Self::Status => "status".into(),
+            // 金額の見出しは値に合わせて右寄せにする。
+            Self::Amount => format::align_right("amount", AMOUNT_COL_WIDTH),
+            Self::Date => "date".into(),
+            Self::Category => "category".into(),
+        }
+    }
+
+    /// 列幅の制約。
+    fn constraint(self) -> Constraint {
+        match self {
+            Self::Index => Constraint::Length(3),
+            Self::File => Constraint::Min(10),
+            Self::Status => Constraint::Length(STATUS_COL_WIDTH as u16),
+            Self::Amount => Constraint::Length(AMOUNT_COL_WIDTH as u16),
+            Self::Date => Constraint::Length(DATE_COL_WIDTH as u16),
+            Self::Category => Constraint::Length(CATEGORY_COL_WIDTH as u16),
+        }
+    }
+
+    /// ジョブ1件分のセルの文字列。
+    fn cell(self, app: &App, index: usize, j: &Job) -> String {
+        match self {
+            Self::Index => format!("{}", index + 1),
+            // 対象月が無効なジョブと提出済みの画像には印を付ける。
+            Self::File => format!(
+                "{}{}{}",
+                if j.effective_target_month(&app.edit_target_month).is_none() {
+                    "✗ "
+                } else {
+                    ""
+                },
+                if j.already_submitted { "⚠ " } else { "" },
+                format::display_filename(
+                    &j.filename,
+                    app.cfg.ui.strip_extension,
+                    app.cfg.ui.filename_max_chars,
+                )
+            ),
+            // 固定幅の列は表示幅で切り詰める。
+            // Workerが処理中なら、固まって見えないようスピナーを添える。
+            Self::Status if j.status.is_active() => format::truncate_to_width(
+                &format!("{} {}", spinner(app.ui.frame), status_str(&j.status)),
+                STATUS_COL_WIDTH,
+            ),
+            Self::Status => format::truncate_to_width(&status_str(&j.status), STATUS_COL_WIDTH),
+            Self::Amount => {
+                format_amount_cell(j.fields.amount_yen, app.cfg.ui.amount_thousands_separator)
+            }
+            Self::Date => format::truncate_to_width(&j.fields.date_ymd, DATE_COL_WIDTH),
+            Self::Category => format::truncate_to_width(&j.fields.category, CATEGORY_COL_WIDTH),
+        }
+    }
+}
+
+/// 設定の列名を表示する列へ変換する（未知の名前は飛ばし、空なら既定の列にする）。
+fn job_columns(names: &[String]) -> Vec<JobColumn> {
+    let columns: Vec<JobColumn> = names
+        .iter()
+        .filter_map(|n| JobColumn::from_name(n))
+        .collect();
+    if columns.is_empty() {
+        return config::DEFAULT_TABLE_COLUMNS
+            .iter()
+            .filter_map(|n| JobColumn::from_name(n))
+            .collect();
+    }
+    columns
+}
+
+/// モニター画面に表示する直近のエラー件数。
+const MONITOR_RECENT_ERRORS: usize = 5;
+
+/// 選択行が表示範囲に収まるよう、前回の位置からテーブルのスクロール位置を決める。
+///
+/// `height`は表示できる行数、`len`は全行数。端末が縮んだ場合も末尾に余白が出ないよう詰める。
+fn table_offset(prev: usize, selected: Option<usize>, height: usize, len: usize) -> usize {
+    if height == 0 || len == 0 {
+        return 0;
+    }
+    let mut offset = prev.min(len.saturating_sub(height));
+    if let Some(sel) = selected {
+        if sel < offset {
+            offset = sel;
+        } else if sel >= offset + height {
+            offset = sel + 1 - height;
+        }
+    }
+    offset
+}
+
 /// 画面全体のレイアウトを描画する。
-pub fn draw(f: &mut Frame, app: &App) {
+pub fn draw(f: &mut Frame, app: &mut App) {
+    app.ui.frame = app.ui.frame.wrapping_add(1);
     // ウィザード画面は専用描画で処理する。
     if app.ui.screen == Screen::InitialSetup {
         draw_wizard_screen(f, app);
         // 入力ボックスが開いていれば重ねて描画する。
         if let Some(input_state) = &app.input_box {
-            input::render_input_box(f, input_state);
+            input::render_input_box(f, input_state, &app.theme);
         }
         return;
     }
 
     // メインレイアウト（Body + HELP + STATUS）を作る。
     let main_layout = layout::create_main_layout(f.area());
-    let body_layout = layout::create_body_layout(main_layout.body);
 
-    // ジョブ一覧からテーブル行を組み立てる。
-    let rows = app.jobs.iter().enumerate().map(|(i, j)| {
-        Row::new(vec![
-            format!("{}", i + 1),
-            j.filename.clone(),
-            status_str(&j.status),
-            j.fields.amount_yen.to_string(),
-            j.fields.date_ymd.clone(),
-        ])
-    });
+    // モニター画面は本文だけを差し替える。
+    if app.ui.screen == Screen::Monitor {
+        draw_monitor_body(f, app, main_layout.body);
+    } else if app.ui.screen == Screen::Logs {
+        draw_logs_body(f, app, main_layout.body);
+    } else {
+        draw_jobs_body(f, app, main_layout.body);
+    }
 
-    // ジョブテーブルのウィジェットを構築する。
-    let table = Table::new(
-        rows,
-        [
-            Constraint::Length(3),
-            Constraint::Min(10),
-            Constraint::Length(12),
-            Constraint::Length(10),
-            Constraint::Length(12),
-        ],
-    )
-    .block(Block::default().borders(Borders::ALL).title("JOBS"))
-    .header(Row::new(vec!["#", "file", "status", "amount", "date"]).bold())
-    .row_highlight_style(
-        Style::default()
-            .bg(Color::Rgb(255, 140, 0)) // オレンジ色の背景
-            .fg(Color::Black) // 黒文字
-            .add_modifier(Modifier::BOLD),
+    // HELPバー（画面ごとのショートカット）を描画する。
+    let help_text = get_help_text(&app.ui.screen, &app.shortcuts);
+    let help_bar = Paragraph::new(help_text)
+        .block(Block::default().borders(Borders::ALL).title("HELP"))
+        .wrap(Wrap { trim: true });
+    f.render_widget(help_bar, main_layout.help_bar);
+
+    // STATUSバー（画面名・ジョブ情報・エラー）を描画する。
+    let status_bar = build_status_bar(app, main_layout.status_bar.width.saturating_sub(2));
+    f.render_widget(status_bar, main_layout.status_bar);
+
+    // ポップアップはキー入力を受け取る順（handle_key）の逆に重ね、操作できるものを一番上に出す。
+    // 診断結果があれば重ねて描画する。
+    if let Some(view) = &app.diagnostics {
+        draw_diagnostics(f, app, view);
+    }
+
+    // 列の割り当て中ならピッカーを重ねて描画する。
+    if let Some(picker) = &app.column_picker {
+        draw_column_picker(f, app, picker);
+    }
+
+    // 既定値へのリセットの確認中なら確認ポップアップを重ねて描画する。
+    if app.confirm_reset {
+        draw_confirm_reset(f, app);
+    }
+
+    // 設定ファイルが外部で変わっていれば再読み込みを尋ねる。
+    if let Some(reload) = &app.config_reload {
+        draw_config_reload(f, app, reload);
+    }
+
+    // 確定の確認待ちなら確認ポップアップを重ねて描画する。
+    if let Some(req) = &app.confirm_commit {
+        draw_commit_confirm(f, app, req);
+    }
+
+    // 再試行プロンプトがあれば重ねて描画する。
+    if let Some(prompt) = app.retry_prompts.front() {
+        draw_retry_prompt(f, app, prompt);
+    }
+
+    // 入力ボックスが開いていれば重ねて描画する。
+    if let Some(input_state) = &app.input_box {
+        input::render_input_box(f, input_state, &app.theme);
+    }
+}
+
+/// 診断で取得した生レスポンスをスクロール可能なポップアップで描画する。
+fn draw_diagnostics(f: &mut Frame, app: &App, view: &DiagnosticsView) {
+    let area = input::centered_popup(f.area(), 90, f.area().height.saturating_sub(4));
+    f.render_widget(Clear, area);
+
+    // 現在の位置と操作キーをタイトルに含める。
+    let sc = &app.shortcuts.diagnostics;
+    let title = format!(
+        "{} [{}/{}] {}/{}: scroll | {}: close",
+        view.title,
+        (view.scroll + 1).min(view.lines.len()),
+        view.lines.len(),
+        format_keys(&sc.up),
+        format_keys(&sc.down),
+        format_keys(&sc.close)
+    );
+    let text: Vec<Line> = view
+        .lines
+        .iter()
+        .skip(view.scroll)
+        .map(|l| Line::raw(l.as_str()))
+        .collect();
+    let popup = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(Style::default().bg(app.theme.popup_bg)),
+    );
+    f.render_widget(popup, area);
+}
+
+/// 確定失敗時の再試行プロンプトを描画する。
+fn draw_retry_prompt(f: &mut Frame, app: &App, prompt: &RetryPrompt) {
+    // 対象ジョブのファイル名と理由、選択肢を並べる。
+    let filename = app
+        .jobs
+        .iter()
+        .find(|j| j.id == prompt.job_id)
+        .map(|j| j.filename.as_str())
+        .unwrap_or("-");
+    let sc = &app.shortcuts.retry_prompt;
+    let text = format!(
+        "{}\nCommit failed: {}\n\nRetry? ({}) / Edit ({}) / Give up ({})",
+        filename,
+        prompt.reason,
+        format_keys(&sc.retry),
+        format_keys(&sc.edit),
+        format_keys(&sc.give_up)
+    );
+    // APIエラーの詳細で理由が長くなっても読めるよう、折り返した行数に合わせて高さを決める。
+    let inner_width = (f.area().width * 70 / 100).saturating_sub(2) as usize;
+    let height = (wrapped_rows(&text, inner_width) as u16 + 2).clamp(7, f.area().height);
+    let area = input::centered_popup(f.area(), 70, height);
+    f.render_widget(Clear, area);
+
+    let title = match app.retry_prompts.len() {
+        1 => "Commit failed".to_string(),
+        n => format!("Commit failed (1/{n})"),
+    };
+    let popup = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .style(Style::default().bg(app.theme.popup_bg)),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(popup, area);
+}
+
+/// 確定前に対象月・金額・日付を示す確認ポップアップを描画する。
+fn draw_commit_confirm(f: &mut Frame, app: &App, req: &CommitRequest) {
+    // ヘッダーに書き込むセルと値を、確定時と同じ組み立てで並べる。
+    let header: Vec<String> = worker::header_cells(&app.cfg, &req.target_month_ym, &req.fields)
+        .into_iter()
+        .map(|(cell, value)| {
+            // セル番地として解釈できなければ、確定前に気付けるよう印を付ける。
+            let note = if validation::is_valid_cell(&cell) {
+                ""
+            } else {
+                " (invalid cell)"
+            };
+            format!("{cell} <- {value}{note}")
+        })
+        .collect();
+    let area = input::centered_popup(f.area(), 60, 9 + header.len() as u16);
+    f.render_widget(Clear, area);
+
+    let sc = &app.shortcuts.confirm;
+    let currency = req.fields.currency_or(app.cfg.user.default_currency());
+    let text = format!(
+        "{}\nTarget month: {}\nAmount: {} {}\nDate: {}\nHeader cells:\n{}\n\nCommit? Yes ({}) / No ({})",
+        req.filename,
+        req.target_month_ym,
+        format::group_thousands(req.fields.amount_yen),
+        currency,
+        req.fields.date_ymd,
+        header.join("\n"),
+        format_keys(&sc.yes),
+        format_keys(&sc.no)
+    );
+    let popup = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Confirm commit")
+                .style(Style::default().bg(app.theme.popup_bg)),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(popup, area);
+}
+
+/// 外部で変更された設定ファイルの再読み込みを尋ねるポップアップを描画する。
+fn draw_config_reload(f: &mut Frame, app: &App, reload: &ConfigReload) {
+    let area = input::centered_popup(f.area(), 60, 6);
+    f.render_widget(Clear, area);
+
+    // 変わっていたファイル名を並べる。
+    let files: Vec<String> = [
+        (reload.config.is_some(), &app.cfg_path),
+        (reload.shortcuts.is_some(), &app.shortcuts_path),
+    ]
+    .into_iter()
+    .filter(|(changed, _)| *changed)
+    .map(|(_, path)| path.display().to_string())
+    .collect();
+    let sc = &app.shortcuts.confirm;
+    let text = format!(
+        "{} changed on disk\n\nReload? Yes ({}) / No ({})",
+        files.join(", "),
+        format_keys(&sc.yes),
+        format_keys(&sc.no)
+    );
+    let popup = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Config changed")
+                .style(Style::default().bg(app.theme.popup_bg)),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(popup, area);
+}
+
+/// テンプレートの見出しから経費行の列を選ぶピッカーを描画する。
+fn draw_column_picker(f: &mut Frame, app: &App, picker: &ColumnPicker) {
+    let height = (picker.columns.len() as u16 + 4).clamp(8, f.area().height.saturating_sub(4));
+    let area = input::centered_popup(f.area(), 60, height);
+    f.render_widget(Clear, area);
+
+    // 見出しのある列を並べ、現在割り当てられている列に印を付ける。
+    let items: Vec<ListItem> = picker
+        .columns
+        .iter()
+        .map(|(col, text)| {
+            let mark = if col == picker.current_col() {
+                "*"
+            } else {
+                " "
+            };
+            ListItem::new(format!("{mark} {col:<3} {text}"))
+        })
+        .collect();
+    let sc = &app.shortcuts.column_picker;
+    let title = format!(
+        "Map columns ({}/{}): {} [row {}]",
+        picker.step + 1,
+        MAPPED_FIELDS.len(),
+        picker.field_label(),
+        picker.header_row
+    );
+    let keys = format!(
+        "{}: pick | {}: keep {} | {}: cancel",
+        format_keys(&sc.pick),
+        format_keys(&sc.keep),
+        picker.current_col(),
+        format_keys(&sc.cancel)
+    );
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_bottom(keys)
+                .style(Style::default().bg(app.theme.popup_bg)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(app.theme.selection_bg)
+                .fg(app.theme.selection_fg),
+        );
+    // 選択行が見える位置までスクロールする。
+    let mut state = ListState::default().with_selected(Some(picker.selected));
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// 設定とショートカットを既定値へ戻すか尋ねるポップアップを描画する。
+fn draw_confirm_reset(f: &mut Frame, app: &App) {
+    let area = input::centered_popup(f.area(), 70, 7);
+    f.render_widget(Clear, area);
+
+    let sc = &app.shortcuts.confirm;
+    let text = format!(
+        "Reset {} and {} to defaults?\nThe current files are backed up to .bak\n\nReset all ({}) / Keep folder & template IDs ({}) / Cancel ({})",
+        app.cfg_path.display(),
+        app.shortcuts_path.display(),
+        format_keys(&sc.yes),
+        format_keys(&sc.keep_ids),
+        format_keys(&sc.no)
+    );
+    let popup = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Reset to defaults")
+                .style(Style::default().bg(app.theme.popup_bg)),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(popup, area);
+}
+
+/// ログ全体を折り返して描画する（スクロールしていなければ末尾に追従する）。
+fn draw_logs_body(f: &mut Frame, app: &mut App, area: Rect) {
+    // 枠線の内側に収まる末尾の位置を求め、キー操作の基準として残す。
+    let width = area.width.saturating_sub(2) as usize;
+    let height = area.height.saturating_sub(2) as usize;
+    let bottom = log_bottom_start(&app.ui.log, width, height);
+    app.ui.log_bottom_start = bottom;
+    let start = app.ui.log_scroll.map_or(bottom, |s| s.min(bottom));
+
+    let follow = if app.ui.log_scroll.is_some() {
+        format!(
+            "scrolled, {}: follow",
+            format_keys(&app.shortcuts.logs.bottom)
+        )
+    } else {
+        "following".into()
+    };
+    let title = format!(
+        "LOG [{}/{}] {}",
+        (start + 1).min(app.ui.log.len()),
+        app.ui.log.len(),
+        follow
     );
+    let text = app.ui.log[start..].join("\n");
+    let body = Paragraph::new(text)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .wrap(Wrap { trim: false });
+    f.render_widget(body, area);
+}
 
-    // 選択中の行をハイライトする。
-    let mut table_state = ratatui::widgets::TableState::default();
-    if !app.jobs.is_empty() {
-        table_state.select(Some(app.ui.selected));
+/// 末尾のログまで`height`行に収まる先頭の位置を返す（折り返しは表示幅から見積もる）。
+fn log_bottom_start(log: &[String], width: usize, height: usize) -> usize {
+    let mut used = 0;
+    for (i, entry) in log.iter().enumerate().rev() {
+        let rows = wrapped_rows(entry, width);
+        // 最後の1件は収まらなくても表示する。
+        if used > 0 && used + rows > height {
+            return i + 1;
+        }
+        used += rows;
     }
+    0
+}
+
+/// 文字列を`width`桁で折り返したときの行数を見積もる。
+fn wrapped_rows(text: &str, width: usize) -> usize {
+    text.split('\n')
+        .map(|l| l.width().div_ceil(width.max(1)).max(1))
+        .sum()
+}
+
+/// ジョブ一覧とINFOパネルを描画する。
+fn draw_jobs_body(f: &mut Frame, app: &mut App, area: Rect) {
+    let body_layout = layout::create_body_layout(area);
+
+    // 設定された列の順にテーブル行を組み立てる。
+    let columns = job_columns(&app.cfg.ui.table_columns);
+    // 完了を隠している場合は表示中のジョブだけを並べる（番号は元の位置のまま）。
+    let visible = app.visible_job_indices();
+    let mut rows: Vec<Row> = visible
+        .iter()
+        .map(|&i| Row::new(columns.iter().map(|c| c.cell(app, i, &app.jobs[i]))))
+        .collect();
+    // 続きのページがある場合は末尾に案内行を置く（ファイル名列が無ければ先頭列）。
+    if app.next_page_token.is_some() {
+        let at = columns
+            .iter()
+            .position(|c| *c == JobColumn::File)
+            .unwrap_or(0);
+        let cells = (0..columns.len()).map(|i| if i == at { "loading more…" } else { "" });
+        rows.push(Row::new(cells).dim());
+    }
+
+    // ジョブテーブルのウィジェットを構築する。
+    let row_count = rows.len();
+    let table = Table::new(rows, columns.iter().map(|c| c.constraint()))
+        .block(Block::default().borders(Borders::ALL).title("JOBS"))
+        .header(Row::new(columns.iter().map(|c| c.header())).bold())
+        .row_highlight_style(
+            Style::default()
+                .bg(app.theme.selection_bg)
+                .fg(app.theme.selection_fg)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    // 選択中の行をハイライトし、選択行が見える位置までスクロールする。
+    let selected = visible.iter().position(|&i| i == app.ui.selected);
+    // 枠線2行と見出し1行を除いた行数が表示できる。
+    let height = body_layout.jobs_table.height.saturating_sub(3) as usize;
+    app.ui.table_offset = table_offset(app.ui.table_offset, selected, height, row_count);
+    let mut table_state = ratatui::widgets::TableState::default()
+        .with_offset(app.ui.table_offset)
+        .with_selected(selected);
     // テーブルを描画する。
     f.render_stateful_widget(table, body_layout.jobs_table, &mut table_state);
 
     // 選択中のファイル情報（またはプレースホルダ）を用意する。
-    let (sel_name, sel_id) = if let Some(j) = app.jobs.get(app.ui.selected) {
-        (j.filename.clone(), j.drive_file_id.clone())
+    let (sel_name, sel_id) = if let Some(j) = app.selected_job() {
+        (
+            j.filename.clone(),
+            format::display_id(&j.drive_file_id, app.cfg.ui.mask_sensitive),
+        )
     } else {
         ("-".into(), "-".into())
     };
@@ -84,34 +572,119 @@ pub fn draw(f: &mut Frame, app: &App) {
         .block(Block::default().borders(Borders::ALL).title("INFO"))
         .wrap(Wrap { trim: true });
     f.render_widget(info_panel, body_layout.info_panel);
+}
 
-    // HELPバー（画面ごとのショートカット）を描画する。
-    let help_text = get_help_text(&app.ui.screen, &app.shortcuts);
-    let help_bar = Paragraph::new(help_text)
-        .block(Block::default().borders(Borders::ALL).title("HELP"))
-        .wrap(Wrap { trim: true });
-    f.render_widget(help_bar, main_layout.help_bar);
+/// モニター画面の本文（進捗バー・状態別件数・処理中・直近のエラー）を描画する。
+fn draw_monitor_body(f: &mut Frame, app: &App, area: Rect) {
+    let stats = JobStats::from_jobs(&app.jobs);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // 進捗バー
+            Constraint::Length(9), // 状態別件数
+            Constraint::Min(3),    // 処理中のジョブ
+            Constraint::Min(3),    // 直近のエラー
+        ])
+        .split(area);
 
-    // STATUSバー（画面名・ジョブ情報・エラー）を描画する。
-    let status_bar = build_status_bar(app);
-    f.render_widget(status_bar, main_layout.status_bar);
+    // 完了数/全件数の進捗バーを描画する。
+    let gauge = Gauge::default()
+        .block(Block::default().borders(Borders::ALL).title("PROGRESS"))
+        .gauge_style(Style::default().fg(app.theme.gauge_fg))
+        .label(format!("{}/{} done", stats.done, stats.total))
+        .ratio(stats.done_ratio());
+    f.render_widget(gauge, rows[0]);
 
-    // 入力ボックスが開いていれば重ねて描画する。
-    if let Some(input_state) = &app.input_box {
-        input::render_input_box(f, input_state);
-    }
+    // 状態別の件数を描画する。
+    let counts = format!(
+        "Queued: {}\nEdit: {}\nWriteSheet: {}\nExportPdf: {}\nUploadPdf: {}\nDone: {}\nError: {}",
+        stats.queued,
+        stats.waiting_user_fix,
+        stats.writing_sheet,
+        stats.exporting_pdf,
+        stats.uploading_pdf,
+        stats.done,
+        stats.error
+    );
+    let counts = Paragraph::new(counts).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("STATUS COUNTS"),
+    );
+    f.render_widget(counts, rows[1]);
+
+    // Workerが処理中のジョブ名を並べる。
+    let active: Vec<String> = app
+        .jobs
+        .iter()
+        .filter(|j| j.status.is_active())
+        .map(|j| format!("{} ({})", j.filename, status_str(&j.status)))
+        .collect();
+    let active = Paragraph::new(if active.is_empty() {
+        "-".to_string()
+    } else {
+        active.join("\n")
+    })
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("ACTIVE ({})", stats.active())),
+    );
+    f.render_widget(active, rows[2]);
+
+    // 失敗したジョブのうち末尾のものを表示する。
+    let errors: Vec<String> = app
+        .jobs
+        .iter()
+        .filter_map(|j| match &j.status {
+            JobStatus::Error(e) => Some(format!("{}: {}", j.filename, e)),
+            _ => None,
+        })
+        .collect();
+    let recent = errors
+        .iter()
+        .skip(errors.len().saturating_sub(MONITOR_RECENT_ERRORS))
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("\n");
+    let errors = Paragraph::new(if recent.is_empty() {
+        "-".to_string()
+    } else {
+        recent
+    })
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("RECENT ERRORS"),
+    )
+    .style(Style::default().fg(app.theme.error_fg))
+    .wrap(Wrap { trim: true });
+    f.render_widget(errors, rows[3]);
 }
 
 /// 編集画面用の情報テキストを構築する。
 fn build_edit_info_text(app: &App) -> String {
     if let Some(job) = app.jobs.get(app.ui.selected) {
+        // 金額は表示用に整形する（編集するのは元の値）。
+        let amount = if app.cfg.ui.amount_thousands_separator {
+            format::group_thousands(job.fields.amount_yen)
+        } else {
+            job.fields.amount_yen.to_string()
+        };
+        // 通貨は未設定なら既定の通貨を示す。
+        let currency = if job.fields.currency.is_empty() {
+            format!("({})", app.cfg.user.default_currency())
+        } else {
+            job.fields.currency.clone()
+        };
         // 編集対象フィールド一覧を作成する。
         let fields = [
             ("Date", &job.fields.date_ymd),
             ("Reason", &job.fields.reason),
-            ("Amount", &job.fields.amount_yen.to_string()),
+            ("Amount", &amount),
             ("Category", &job.fields.category),
             ("Note", &job.fields.note),
+            ("Currency", &currency),
         ];
         let mut lines = vec![
             format!("Editing: {}", job.filename),
@@ -125,7 +698,16 @@ fn build_edit_info_text(app: &App) -> String {
             } else {
                 " "
             };
-            lines.push(format!("{} [{}] {}: {}", marker, i, name, value));
+            // 科目のルールで必須の備考が空なら目立たせる。
+            let required = if i == 4 && job.fields.missing_required_note(&app.cfg.category_rules) {
+                "  ← required for this category"
+            } else {
+                ""
+            };
+            lines.push(format!(
+                "{} [{}] {}: {}{}",
+                marker, i, name, value, required
+            ));
         }
         // 対象月の情報も追加する。
         lines.push(String::new());
@@ -138,13 +720,22 @@ fn build_edit_info_text(app: &App) -> String {
 
 /// メイン画面用の情報テキストを構築する。
 fn build_main_info_text(app: &App, sel_name: &str, sel_id: &str) -> String {
+    // マスク設定が有効ならIDを伏せて表示する。
+    let mask = app.cfg.ui.mask_sensitive;
+    // プロファイルを使っていれば名前を添える。
+    let profile = app
+        .cfg
+        .active_profile_name()
+        .map(|name| format!("Profile: {name}\n"))
+        .unwrap_or_default();
     format!(
-        "Selected: {}\nSelected ID: {}\n\nIn: {}\nOut: {}\nTpl: {}\nName: {}\nMonth: {}\n\nLog:\n{}",
+        "Selected: {}\nSelected ID: {}\n\n{}In: {}\nOut: {}\nTpl: {}\nName: {}\nMonth: {}\n\nLog:\n{}",
         sel_name,
         sel_id,
-        app.cfg.google.input_folder_id,
-        app.cfg.google.output_folder_id,
-        app.cfg.google.template_sheet_id,
+        profile,
+        format::display_id(&app.cfg.google.input_folder_id, mask),
+        format::display_id(&app.cfg.google.output_folder_id, mask),
+        format::display_id(&app.cfg.google.template_sheet_id, mask),
         app.cfg.user.full_name,
         app.edit_target_month,
         app.ui
@@ -160,23 +751,67 @@ fn build_main_info_text(app: &App, sel_name: &str, sel_id: &str) -> String {
 }
 
 /// ステータスバーを構築する。
-fn build_status_bar(app: &App) -> Paragraph<'static> {
+///
+/// `width`は枠を除いた表示幅。収まらない文言は、設定が有効でポップアップが無ければ横に流す。
+fn build_status_bar(app: &App, width: u16) -> Paragraph<'static> {
     let screen_name = match app.ui.screen {
         Screen::Main => "Main",
         Screen::Settings => "Settings",
         Screen::EditJob => "EditJob",
         Screen::InitialSetup => "Setup",
+        Screen::Monitor => "Monitor",
+        Screen::Logs => "Logs",
     };
 
     // ジョブ件数と完了数を集計する。
-    let job_info = format!(
-        "Jobs: {} total, {} done",
-        app.jobs.len(),
-        app.jobs
-            .iter()
-            .filter(|j| matches!(j.status, JobStatus::Done))
-            .count()
-    );
+    let stats = JobStats::from_jobs(&app.jobs);
+    let mut job_info = format!("Jobs: {} total, {} done", stats.total, stats.done);
+    // 一時停止中は保留件数を併記する。
+    if app.paused {
+        job_info.push_str(&format!(" | paused ({} queued)", app.pending_commits.len()));
+    }
+    // 完了を待っている操作があれば併記する。
+    if app.is_refreshing() {
+        job_info.push_str(" | busy: refreshing");
+    }
+    let committing = app.commits_in_flight();
+    if committing > 0 {
+        job_info.push_str(&format!(" | busy: committing {committing}"));
+        // 処理中のジョブがあれば、今どの段階かをスピナー付きで示す。
+        if let Some(job) = app.jobs.iter().find(|j| j.status.is_active()) {
+            job_info.push_str(&format!(
+                " {} {}",
+                spinner(app.ui.frame),
+                status_str(&job.status)
+            ));
+        }
+    }
+    // 完了を隠していれば件数を併記する。
+    if app.hide_done {
+        job_info.push_str(&format!(" | hiding {} done", stats.done));
+    }
+    // 状態で絞り込んでいれば条件と表示件数を併記する。
+    if app.ui.status_filter != StatusFilter::All {
+        job_info.push_str(&format!(
+            " | filter: {} ({} shown)",
+            app.ui.status_filter.label(),
+            app.visible_job_indices().len()
+        ));
+    }
+
+    // ブレーカーが平常でなければ状態を併記する。
+    if let Some(label) = app.breaker.and_then(|b| b.label(std::time::Instant::now())) {
+        job_info.push_str(&format!(" | {label}"));
+    }
+
+    // 既定以外の設定ファイルを使っている場合はファイル名を併記する。
+    if let Some(label) = app.config_label() {
+        job_info = format!("config: {label} | {job_info}");
+    }
+    // 書き込まないモードであることを先頭で示す。
+    if app.dry_run {
+        job_info = format!("[DRY RUN] {job_info}");
+    }
 
     // エラーの有無でステータス文字列を切り替える。
     let status_text = if let Some(err) = &app.ui.error {
@@ -186,18 +821,69 @@ fn build_status_bar(app: &App) -> Paragraph<'static> {
     };
 
     // ステータスバーのウィジェットを生成する。
-    let mut status_bar = Paragraph::new(status_text)
-        .block(Block::default().borders(Borders::ALL).title("STATUS"))
-        .wrap(Wrap { trim: true });
+    let block = Block::default().borders(Borders::ALL).title("STATUS");
+    let width = width as usize;
+    let mut status_bar =
+        if app.cfg.ui.status_marquee && !has_popup(app) && status_text.width() > width {
+            // 1フレームに1文字ずつ流し、1行に収まる分だけ表示する。
+            let offset = marquee_offset(status_text.chars().count(), app.ui.frame);
+            Paragraph::new(marquee_window(&status_text, offset, width)).block(block)
+        } else {
+            Paragraph::new(status_text)
+                .block(block)
+                .wrap(Wrap { trim: true })
+        };
 
-    // エラー時は赤色で強調表示する。
+    // エラー時はエラー色で強調表示する。
     if app.ui.error.is_some() {
-        status_bar = status_bar.style(Style::default().fg(Color::Red));
+        status_bar = status_bar.style(Style::default().fg(app.theme.error_fg));
     }
 
     status_bar
 }
 
+/// 流れる文言の末尾と次の先頭の間に入れる空白。
+const MARQUEE_GAP: &str = "   ";
+
+/// 入力ボックスや確認などのポップアップを表示中か。
+fn has_popup(app: &App) -> bool {
+    app.input_box.is_some()
+        || app.diagnostics.is_some()
+        || !app.retry_prompts.is_empty()
+        || app.confirm_commit.is_some()
+        || app.config_reload.is_some()
+        || app.confirm_reset
+        || app.column_picker.is_some()
+}
+
+/// `frame`回目の描画で、流れる文言の何文字目から表示するかを返す（`chars`は文言の文字数）。
+fn marquee_offset(chars: usize, frame: u64) -> usize {
+    // 文言と区切りの空白を1周として繰り返す。
+    let cycle = (chars + MARQUEE_GAP.chars().count()) as u64;
+    (frame % cycle) as usize
+}
+
+/// 文言を`offset`文字目から表示幅`cols`に収まる分だけ切り出す（末尾の後ろには先頭が続く）。
+fn marquee_window(text: &str, offset: usize, cols: usize) -> String {
+    let mut shown = String::new();
+    let mut used = 0;
+    for c in text
+        .chars()
+        .chain(MARQUEE_GAP.chars())
+        .chain(text.chars())
+        .skip(offset)
+    {
+        // 省略記号は付けず、全角文字が途中で切れる場合はそこで止める。
+        let w = c.width().unwrap_or(0);
+        if used + w > cols {
+            break;
+        }
+        shown.push(c);
+        used += w;
+    }
+    shown
+}
+
 /// ウィザード画面を描画する。
 fn draw_wizard_screen(f: &mut Frame, app: &App) {
     // 余白込みで縦方向に3分割する。
@@ -213,12 +899,30 @@ fn draw_wizard_screen(f: &mut Frame, app: &App) {
     // ステップ番号と総数、プロンプトを取得する。
     let step_num = app.wizard_state.get_step_number();
     let total_steps = app.wizard_state.total_steps;
-    let prompt = app.wizard_state.get_prompt();
+    let mut prompt = app.wizard_state.get_prompt();
+    // 接続テストの結果をチェックリストとして添える。
+    if !app.wizard_state.checks.is_empty() {
+        prompt.push('\n');
+    }
+    for (check, state) in &app.wizard_state.checks {
+        let line = match state {
+            CheckState::Pending => format!("\n[..] {}", check.label()),
+            CheckState::Passed => format!("\n[OK] {}", check.label()),
+            CheckState::Failed(e) => format!("\n[NG] {}: {e}", check.label()),
+        };
+        prompt.push_str(&line);
+    }
 
     // 表示するテキストを組み立てる。
+    let sc = &app.shortcuts.wizard;
     let content_text = format!(
-        "=== Initial Setup Wizard ===\n\nStep {}/{}\n\n{}\n\nPress Enter to proceed, ESC to skip step.",
-        step_num, total_steps, prompt
+        "=== Initial Setup Wizard ===\n\nStep {}/{}\n\n{}\n\n{}: proceed | {}: skip step | {}: previous step",
+        step_num,
+        total_steps,
+        prompt,
+        format_keys(&sc.proceed),
+        format_keys(&sc.skip),
+        format_keys(&sc.back)
     );
 
     // メインの本文を描画する。
@@ -240,7 +944,7 @@ fn draw_wizard_screen(f: &mut Frame, app: &App) {
         // エラー用のパネルを構成する。
         let error_text = Paragraph::new(format!("ERROR: {}", err))
             .block(Block::default().borders(Borders::ALL).title("Error"))
-            .style(Style::default().fg(Color::Red))
+            .style(Style::default().fg(app.theme.error_fg))
             .wrap(Wrap { trim: true });
 
         // エラー表示を描画する。
@@ -252,35 +956,82 @@ fn draw_wizard_screen(f: &mut Frame, app: &App) {
 fn get_help_text(screen: &Screen, shortcuts: &Shortcuts) -> String {
     match screen {
         Screen::Main => format!(
-            "{}: quit | {}: refresh | {}: settings | {}: edit | {}/{}↑: navigate",
+            "{}: quit | {}: refresh | {}: settings | {}: edit | {}: commit all | {}: commit by month | {}: monitor | {}: logs | {}: pause/resume | {}: export xlsx | {}: export csv | {}: mask ids | {}: hide done | {}: filter status | {}: search | {}/{}: next/prev match | {}: diagnostics | {}: pull from sheet | {}: retry with month | {}: this month | {}: ocr | {}/{}↑: navigate",
             format_keys(&shortcuts.main.quit),
             format_keys(&shortcuts.main.refresh),
             format_keys(&shortcuts.main.settings),
             format_keys(&shortcuts.main.enter),
+            format_keys(&shortcuts.main.commit_all),
+            format_keys(&shortcuts.main.commit_by_month),
+            format_keys(&shortcuts.main.monitor),
+            format_keys(&shortcuts.main.logs),
+            format_keys(&shortcuts.main.pause),
+            format_keys(&shortcuts.main.export_xlsx),
+            format_keys(&shortcuts.main.export_csv),
+            format_keys(&shortcuts.main.toggle_mask),
+            format_keys(&shortcuts.main.toggle_done),
+            format_keys(&shortcuts.main.status_filter),
+            format_keys(&shortcuts.main.search),
+            format_keys(&shortcuts.main.search_next),
+            format_keys(&shortcuts.main.search_prev),
+            format_keys(&shortcuts.main.diagnostics),
+            format_keys(&shortcuts.main.pull_from_sheet),
+            format_keys(&shortcuts.main.retry_with_month),
+            format_keys(&shortcuts.main.reset_month),
+            format_keys(&shortcuts.main.ocr),
             format_keys(&shortcuts.main.up),
             format_keys(&shortcuts.main.down)
         ),
         Screen::Settings => format!(
-            "{}: input folder | {}: output folder | {}: template | {}: name | {}: save | {}: cancel",
+            "{}: input folder | {}: output folder | {}: template | {}: name | {}: map columns | {}: switch profile | {}: logout | {}: reset to defaults | {}: save | {}: apply | {}: cancel",
             format_keys(&shortcuts.settings.input_folder),
             format_keys(&shortcuts.settings.output_folder),
             format_keys(&shortcuts.settings.template),
             format_keys(&shortcuts.settings.name),
+            format_keys(&shortcuts.settings.map_columns),
+            format_keys(&shortcuts.settings.profile),
+            format_keys(&shortcuts.settings.logout),
+            format_keys(&shortcuts.settings.reset),
             format_keys(&shortcuts.settings.save),
+            format_keys(&shortcuts.settings.apply),
             format_keys(&shortcuts.settings.cancel)
         ),
         Screen::EditJob => format!(
-            "{}: edit field | {}: next field | {}: month | {}: commit | {}: cancel",
+            "{}: edit field | {}: next field | {}/{}: copy/paste | {}: undo | {}: date today | {}: month | {}: this month | {}: preview rows | {}: ocr | {}: commit | {}: cancel | {}: settings",
             format_keys(&shortcuts.edit_job.edit_field),
             format_keys(&shortcuts.edit_job.next_field),
+            format_keys(&shortcuts.edit_job.copy_field),
+            format_keys(&shortcuts.edit_job.paste_field),
+            format_keys(&shortcuts.edit_job.undo),
+            format_keys(&shortcuts.edit_job.set_today),
             format_keys(&shortcuts.edit_job.target_month),
+            format_keys(&shortcuts.edit_job.reset_month),
+            format_keys(&shortcuts.edit_job.preview_write),
+            format_keys(&shortcuts.edit_job.ocr),
             format_keys(&shortcuts.edit_job.commit),
-            format_keys(&shortcuts.edit_job.cancel)
+            format_keys(&shortcuts.edit_job.cancel),
+            format_keys(&shortcuts.global.settings)
         ),
         Screen::InitialSetup => format!(
-            "Follow wizard steps | {}: proceed | {}: skip step",
+            "Follow wizard steps | {}: proceed | {}: skip step | {}: previous step",
             format_keys(&shortcuts.wizard.proceed),
-            format_keys(&shortcuts.wizard.skip)
+            format_keys(&shortcuts.wizard.skip),
+            format_keys(&shortcuts.wizard.back)
+        ),
+        Screen::Monitor => format!(
+            "Read-only progress monitor | {}: back | {}: settings",
+            format_keys(&shortcuts.monitor.back),
+            format_keys(&shortcuts.global.settings)
+        ),
+        Screen::Logs => format!(
+            "{}/{}: scroll | {}/{}: page | {}/{}: top/bottom (follow) | {}: back",
+            format_keys(&shortcuts.logs.up),
+            format_keys(&shortcuts.logs.down),
+            format_keys(&shortcuts.logs.page_up),
+            format_keys(&shortcuts.logs.page_down),
+            format_keys(&shortcuts.logs.top),
+            format_keys(&shortcuts.logs.bottom),
+            format_keys(&shortcuts.logs.back)
         ),
     }
 }
@@ -290,6 +1041,21 @@ fn format_keys(keys: &[String]) -> String {
     keys.join("/")
 }
 
+/// 金額セルを列幅に合わせて右寄せで整形する。
+fn format_amount_cell(amount_yen: i64, thousands_separator: bool) -> String {
+    let amount = if thousands_separator {
+        format::group_thousands(amount_yen)
+    } else {
+        amount_yen.to_string()
+    };
+    format::align_right(&amount, AMOUNT_COL_WIDTH)
+}
+
+/// `frame`回目の描画で表示するスピナーのコマを返す。
+fn spinner(frame: u64) -> &'static str {
+    SPINNER_FRAMES[(frame % SPINNER_FRAMES.len() as u64) as usize]
+}
+
 /// ジョブ状態を一覧表示用の短いラベルへ変換する。
 fn status_str(s: &JobStatus) -> String {
     match s {
@@ -302,3 +1068,121 @@ fn status_str(s: &JobStatus) -> String {
         JobStatus::Error(e) => format!("Error: {e}"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_table_columns_headers() {
+        // 設定した列と順序で見出しが組み立てられることを検証する。
+        let names: Vec<String> = ["date", "category", "amount", "file"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let headers: Vec<String> = job_columns(&names).iter().map(|c| c.header()).collect();
+        assert_eq!(headers, vec!["date", "category", "    amount", "file"]);
+
+        // 既定値は従来の列構成になる。
+        let defaults = crate::config::UiCfg::default().table_columns;
+        let headers: Vec<String> = job_columns(&defaults).iter().map(|c| c.header()).collect();
+        assert_eq!(headers, vec!["#", "file", "status", "    amount", "date"]);
+    }
+
+    #[test]
+    fn test_table_offset_keeps_selection_visible() {
+        // 選択行が範囲外に出るとスクロールし、縮小時は詰め直し、空なら0になることを検証する。
+        // 下へ移動して範囲を越えると、選択行が最下行になる位置まで進む。
+        assert_eq!(table_offset(0, Some(19), 20, 50), 0);
+        assert_eq!(table_offset(0, Some(20), 20, 50), 1);
+        // 範囲内の移動ではスクロールしない。
+        assert_eq!(table_offset(10, Some(15), 20, 50), 10);
+        // 上へ戻って範囲を越えると、選択行が最上行になる。
+        assert_eq!(table_offset(10, Some(3), 20, 50), 3);
+        // 端末が縮んでも選択行が見えるよう詰め直す。
+        assert_eq!(table_offset(30, Some(49), 5, 50), 45);
+        assert_eq!(table_offset(40, Some(45), 20, 50), 30);
+        // 行が無い・表示できない場合は先頭。
+        assert_eq!(table_offset(7, None, 20, 0), 0);
+        assert_eq!(table_offset(7, Some(3), 0, 50), 0);
+    }
+
+    #[test]
+    fn test_edit_info_groups_amount_for_display() {
+        // 編集画面の金額は3桁区切りで表示し、編集用の値は元のままであることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = crate::app::tests::test_app();
+        let mut job = Job::new("f1".into(), "a.jpg".into());
+        job.fields.amount_yen = -1234567;
+        app.jobs.push(job);
+        assert!(build_edit_info_text(&app).contains("Amount: -1,234,567"));
+        assert_eq!(app.jobs[0].fields.field_value(2), "-1234567");
+
+        app.cfg.ui.amount_thousands_separator = false;
+        assert!(build_edit_info_text(&app).contains("Amount: -1234567"));
+    }
+
+    #[test]
+    fn test_active_job_status_shows_spinner() {
+        // 処理中のジョブだけ状態列にスピナーが付き、描画ごとにコマが進むことを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = crate::app::tests::test_app();
+        let mut job = Job::new("f1".into(), "a.jpg".into());
+        job.status = JobStatus::ExportingPdf;
+        app.jobs.push(job);
+        app.jobs.push(Job::new("f2".into(), "b.jpg".into()));
+
+        app.ui.frame = 0;
+        assert_eq!(JobColumn::Status.cell(&app, 0, &app.jobs[0]), "⠋ ExportPdf");
+        app.ui.frame = 1;
+        assert_eq!(JobColumn::Status.cell(&app, 0, &app.jobs[0]), "⠙ ExportPdf");
+        app.ui.frame = 10;
+        assert_eq!(JobColumn::Status.cell(&app, 0, &app.jobs[0]), "⠋ ExportPdf");
+        assert_eq!(JobColumn::Status.cell(&app, 1, &app.jobs[1]), "Queued");
+    }
+
+    #[test]
+    fn test_marquee_offset_wraps_after_gap() {
+        // 1フレームに1文字ずつ進み、文言と区切りを流し終えると先頭へ戻ることを検証する。
+        assert_eq!(marquee_offset(10, 0), 0);
+        assert_eq!(marquee_offset(10, 4), 4);
+        assert_eq!(marquee_offset(10, 12), 12);
+        assert_eq!(marquee_offset(10, 13), 0);
+        assert_eq!(marquee_offset(10, 27), 1);
+
+        // 末尾を越えると区切りの後に先頭が続き、全角文字も幅に収まる。
+        assert_eq!(marquee_window("abcdefgh", 6, 6), "gh   a");
+        assert_eq!(marquee_window("交通費の精算", 2, 7), "費の精");
+    }
+
+    #[test]
+    fn test_log_bottom_start_accounts_for_wrapping() {
+        // 折り返しを含めて末尾が収まる位置を返し、長すぎる最後の1件も表示することを検証する。
+        let log: Vec<String> = vec!["a".into(), "b".repeat(25), "c".into(), "d".into()];
+        assert_eq!(log_bottom_start(&log, 10, 10), 0);
+        assert_eq!(log_bottom_start(&log, 10, 5), 1);
+        assert_eq!(log_bottom_start(&log, 10, 4), 2);
+        assert_eq!(log_bottom_start(&log[..2], 10, 1), 1);
+        assert_eq!(log_bottom_start(&[], 10, 5), 0);
+    }
+
+    #[test]
+    fn test_draw_scrolls_jobs_and_renders_empty_list() {
+        // 空の一覧でも描画でき、画面より多いジョブでは選択行までスクロールすることを検証する。
+        let (mut app, _rx_cmd, _tx_ev) = crate::app::tests::test_app();
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|f| draw(f, &mut app)).unwrap();
+        assert_eq!(app.ui.table_offset, 0);
+
+        for i in 0..50 {
+            app.jobs.push(Job::new(format!("f{i}"), format!("{i}.jpg")));
+        }
+        app.ui.selected = 49;
+        terminal.draw(|f| draw(f, &mut app)).unwrap();
+        let offset = app.ui.table_offset;
+        assert!(offset > 0);
+
+        // 端末を縮めても選択行が見える位置に詰め直す。
+        terminal.backend_mut().resize(80, 16);
+        terminal.draw(|f| draw(f, &mut app)).unwrap();
+        assert!(app.ui.table_offset > offset);
+    }
+}
@@ -0,0 +1,56 @@
+//! ジョブの完了/失敗を知らせる端末ベル。
+
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+/// 連続したベルをまとめる間隔（大量のジョブで鳴り続けないようにする）。
+const BELL_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// 間引き付きで端末ベルを鳴らす。
+#[derive(Debug, Default)]
+pub struct Bell {
+    /// 最後にベルを鳴らした時刻。
+    last_rung: Option<Instant>,
+}
+
+impl Bell {
+    /// 前回から間引き間隔が過ぎていればBELを出力する。
+    pub fn ring(&mut self) {
+        if self.should_ring(Instant::now()) {
+            // BEL文字は画面に何も描画しないので、描画中の端末へ直接書いてよい。
+            let mut out = std::io::stdout();
+            let _ = out.write_all(b"\x07");
+            let _ = out.flush();
+        }
+    }
+
+    /// 指定時刻に鳴らすべきか判定し、鳴らす場合は時刻を記録する。
+    fn should_ring(&mut self, now: Instant) -> bool {
+        if self
+            .last_rung
+            .is_some_and(|last| now.duration_since(last) < BELL_DEBOUNCE)
+        {
+            return false;
+        }
+        self.last_rung = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_ring_debounces() {
+        // 間引き間隔内の連続した要求が1回にまとめられることを検証する。
+        let mut bell = Bell::default();
+        let start = Instant::now();
+        assert!(bell.should_ring(start));
+        assert!(!bell.should_ring(start + Duration::from_millis(500)));
+        assert!(!bell.should_ring(start + Duration::from_millis(1999)));
+        assert!(bell.should_ring(start + BELL_DEBOUNCE));
+    }
+}
@@ -0,0 +1,221 @@
+//! Google APIの障害中に確定を送り続けないためのサーキットブレーカー。
+//!
+//! 同じ種類の一時的な失敗が短時間に続いたら開き、待ち時間が過ぎたら1件だけ試す。
+
+use std::{
+    mem::Discriminant,
+    time::{Duration, Instant},
+};
+
+use crate::{config::BreakerCfg, google::error::WorkerError};
+
+/// ブレーカーが開いている間に確定を失敗させる際の説明文。
+pub const OPEN_MESSAGE: &str = "service unavailable, paused retries";
+
+/// ブレーカーの状態。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakerState {
+    /// 通常通り確定を送る。
+    Closed,
+    /// 障害中とみなし、指定時刻まで確定を即座に失敗させる。
+    Open { until: Instant },
+    /// 待ち時間が過ぎ、次の確定で回復したかを試す。
+    HalfOpen,
+}
+
+/// UIへ伝えるブレーカーの状態と残りの再試行枠。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BreakerStatus {
+    /// 現在の状態。
+    pub state: BreakerState,
+    /// 窓内で続いている同種の失敗回数。
+    pub failures: u32,
+    /// 開くまでの失敗回数（0なら無効）。
+    pub threshold: u32,
+}
+
+impl BreakerStatus {
+    /// ステータスバー用の表示（平常時はNone）。
+    pub fn label(&self, now: Instant) -> Option<String> {
+        match self.state {
+            BreakerState::Open { until } => {
+                let secs = until.saturating_duration_since(now).as_secs();
+                Some(format!("breaker open, retry in {secs}s"))
+            }
+            BreakerState::HalfOpen => Some("breaker half-open, testing".into()),
+            BreakerState::Closed if self.failures > 0 && self.threshold > 0 => Some(format!(
+                "retry budget {}/{}",
+                self.threshold.saturating_sub(self.failures),
+                self.threshold
+            )),
+            BreakerState::Closed => None,
+        }
+    }
+}
+
+/// 連続失敗を数え、開閉を判断するサーキットブレーカー。
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    state: BreakerState,
+    /// 数えている失敗の種別。
+    kind: Option<Discriminant<WorkerError>>,
+    /// 窓内の失敗時刻（古い順）。
+    failures: Vec<Instant>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            state: BreakerState::Closed,
+            kind: None,
+            failures: vec![],
+        }
+    }
+}
+
+impl CircuitBreaker {
+    /// 現在の状態と再試行枠を返す。
+    pub fn status(&self, cfg: &BreakerCfg) -> BreakerStatus {
+        BreakerStatus {
+            state: self.state,
+            failures: self.failures.len() as u32,
+            threshold: cfg.failures,
+        }
+    }
+
+    /// 確定を送ってよいか判定する（待ち時間が過ぎていれば半開にする）。
+    pub fn allow(&mut self, now: Instant) -> bool {
+        match self.state {
+            BreakerState::Open { until } if now < until => false,
+            BreakerState::Open { .. } => {
+                self.state = BreakerState::HalfOpen;
+                true
+            }
+            BreakerState::Closed | BreakerState::HalfOpen => true,
+        }
+    }
+
+    /// 確定の結果を記録する（`error`がNoneなら成功）。
+    pub fn record(&mut self, cfg: &BreakerCfg, error: Option<&WorkerError>, now: Instant) {
+        // 障害を示す失敗以外は、APIが応答しているので平常に戻す。
+        let Some(error) = error.filter(|e| is_outage(e)) else {
+            *self = Self::default();
+            return;
+        };
+        if cfg.failures == 0 {
+            return;
+        }
+        let open = BreakerState::Open {
+            until: now + Duration::from_secs(cfg.cooldown_secs),
+        };
+        // 半開での試行が失敗したら、もう一度待つ。
+        if self.state == BreakerState::HalfOpen {
+            self.state = open;
+            return;
+        }
+        // 種別が変わったら数え直し、窓から外れた失敗は捨てる。
+        let kind = std::mem::discriminant(error);
+        if self.kind != Some(kind) {
+            self.kind = Some(kind);
+            self.failures.clear();
+        }
+        let window = Duration::from_secs(cfg.window_secs);
+        self.failures
+            .retain(|&t| now.saturating_duration_since(t) < window);
+        self.failures.push(now);
+        if self.failures.len() as u32 >= cfg.failures {
+            self.state = open;
+        }
+    }
+}
+
+/// サービス側の障害とみなす失敗か（レート制限と通信エラー）。
+fn is_outage(error: &WorkerError) -> bool {
+    matches!(error, WorkerError::RateLimited(_) | WorkerError::Network(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 3回で開き、10秒待つ設定。
+    fn cfg() -> BreakerCfg {
+        BreakerCfg {
+            failures: 3,
+            window_secs: 60,
+            cooldown_secs: 10,
+        }
+    }
+
+    #[test]
+    fn test_breaker_transitions_closed_open_half_open_closed() {
+        // 連続失敗で開き、待ち時間後に半開となり、成功で閉じることを検証する。
+        let cfg = cfg();
+        let t0 = Instant::now();
+        let outage = WorkerError::Network("503".into());
+        let mut b = CircuitBreaker::default();
+
+        // 閾値未満では閉じたまま、再試行枠が減っていく。
+        b.record(&cfg, Some(&outage), t0);
+        b.record(&cfg, Some(&outage), t0 + Duration::from_secs(1));
+        assert!(b.allow(t0 + Duration::from_secs(2)));
+        assert_eq!(
+            b.status(&cfg).label(t0).as_deref(),
+            Some("retry budget 1/3")
+        );
+
+        // 3回目で開き、待ち時間中は確定を通さない。
+        let t3 = t0 + Duration::from_secs(2);
+        b.record(&cfg, Some(&outage), t3);
+        let until = t3 + Duration::from_secs(10);
+        assert_eq!(b.status(&cfg).state, BreakerState::Open { until });
+        assert!(!b.allow(t3 + Duration::from_secs(5)));
+        assert_eq!(
+            b.status(&cfg).label(t3 + Duration::from_secs(5)).as_deref(),
+            Some("breaker open, retry in 5s")
+        );
+
+        // 待ち時間が過ぎると半開になり、試行が失敗すればまた開く。
+        assert!(b.allow(until));
+        assert_eq!(b.status(&cfg).state, BreakerState::HalfOpen);
+        b.record(&cfg, Some(&outage), until);
+        assert!(!b.allow(until + Duration::from_secs(1)));
+
+        // 再び半開となり、試行が成功すれば閉じる。
+        let later = until + Duration::from_secs(10);
+        assert!(b.allow(later));
+        b.record(&cfg, None, later);
+        assert_eq!(b.status(&cfg).state, BreakerState::Closed);
+        assert_eq!(b.status(&cfg).label(later), None);
+    }
+
+    #[test]
+    fn test_breaker_counts_only_recent_failures_of_one_kind() {
+        // 種別の違う失敗や窓外の失敗、障害以外の失敗では開かないことを検証する。
+        let cfg = cfg();
+        let t0 = Instant::now();
+        let network = WorkerError::Network("503".into());
+        let limited = WorkerError::RateLimited("429".into());
+        let mut b = CircuitBreaker::default();
+
+        b.record(&cfg, Some(&network), t0);
+        b.record(&cfg, Some(&network), t0);
+        b.record(&cfg, Some(&limited), t0);
+        assert_eq!(b.status(&cfg).state, BreakerState::Closed);
+        assert_eq!(b.status(&cfg).failures, 1);
+
+        b.record(&cfg, Some(&limited), t0);
+        b.record(&cfg, Some(&limited), t0 + Duration::from_secs(61));
+        assert_eq!(b.status(&cfg).failures, 1);
+
+        b.record(&cfg, Some(&WorkerError::NotFound("404".into())), t0);
+        assert_eq!(b.status(&cfg).failures, 0);
+
+        // 閾値0なら無効になる。
+        let off = BreakerCfg { failures: 0, ..cfg };
+        for _ in 0..5 {
+            b.record(&off, Some(&network), t0);
+        }
+        assert!(b.allow(t0));
+    }
+}
@@ -0,0 +1,123 @@
+//! `receipt_tui check` サブコマンド：設定ファイルの自己診断。
+
+use std::{fs, path::Path};
+
+use crate::{config::Config, shortcuts::Shortcuts};
+
+/// 1項目分の診断結果。
+pub struct CheckResult {
+    /// 診断項目名。
+    pub label: String,
+    /// 見つかった問題（空なら合格）。
+    pub problems: Vec<String>,
+}
+
+impl CheckResult {
+    /// 問題が無ければ合格とみなす。
+    pub fn passed(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// 設定ファイルとショートカット設定を読み込み、すべての検証を実行する。
+///
+/// TUIの起動やネットワークアクセスは行わず、ファイルも生成しない。
+pub fn run_checks(config_path: &Path, shortcuts_path: &Path) -> Vec<CheckResult> {
+    let mut results = vec![];
+
+    // config.toml の読み込みと検証を行う。
+    let label = format!("config ({})", config_path.display());
+    match read_toml::<Config>(config_path) {
+        Ok(Some(cfg)) => results.push(CheckResult {
+            label,
+            problems: cfg.validate().err().unwrap_or_default(),
+        }),
+        Ok(None) => results.push(CheckResult {
+            label,
+            problems: vec!["file not found (run the app once to generate it)".into()],
+        }),
+        Err(e) => results.push(CheckResult {
+            label,
+            problems: vec![e],
+        }),
+    }
+
+    // shortcut.toml の読み込みと検証を行う（無ければ既定値を検証する）。
+    let label = format!("shortcuts ({})", shortcuts_path.display());
+    match read_toml::<Shortcuts>(shortcuts_path) {
+        Ok(sc) => results.push(CheckResult {
+            label,
+            problems: sc.unwrap_or_default().validate().err().unwrap_or_default(),
+        }),
+        Err(e) => results.push(CheckResult {
+            label,
+            problems: vec![e],
+        }),
+    }
+
+    results
+}
+
+/// 診断結果を標準出力へ表示し、すべて合格ならtrueを返す。
+pub fn print_report(results: &[CheckResult]) -> bool {
+    for r in results {
+        if r.passed() {
+            println!("[PASS] {}", r.label);
+        } else {
+            println!("[FAIL] {}", r.label);
+            // 問題点を字下げして列挙する。
+            for p in &r.problems {
+                println!("       - {p}");
+            }
+        }
+    }
+    results.iter().all(CheckResult::passed)
+}
+
+/// TOMLファイルを読み込む（存在しなければNone）。
+fn read_toml<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Option<T>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    // 読み込みとパースの失敗を区別して報告する。
+    let s = fs::read_to_string(path).map_err(|e| format!("cannot read file: {e}"))?;
+    toml::from_str(&s)
+        .map(Some)
+        .map_err(|e| format!("parse error: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// テスト用の一時ファイルパスを作る。
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("receipt_tui_{}_{}", uuid::Uuid::new_v4(), name))
+    }
+
+    #[test]
+    fn test_run_checks_pass_and_fail() {
+        // 正常な設定は合格し、不正な列指定は不合格になることを検証する。
+        let cfg_path = temp_path("config.toml");
+        let sc_path = temp_path("shortcut.toml");
+        Config::default().save(&cfg_path).unwrap();
+        let results = run_checks(&cfg_path, &sc_path);
+        assert!(results.iter().all(CheckResult::passed));
+
+        let mut cfg = Config::default();
+        cfg.general_expense.date_col = "1".into();
+        cfg.save(&cfg_path).unwrap();
+        let results = run_checks(&cfg_path, &sc_path);
+        assert!(!results[0].passed());
+        assert!(results[1].passed());
+
+        let _ = fs::remove_file(&cfg_path);
+    }
+
+    #[test]
+    fn test_run_checks_missing_config_fails() {
+        // config.toml が無い場合は不合格になることを検証する。
+        let results = run_checks(&temp_path("missing.toml"), &temp_path("missing_sc.toml"));
+        assert!(!results[0].passed());
+    }
+}
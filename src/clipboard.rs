@@ -0,0 +1,78 @@
+//! システムクリップボードとの橋渡し（使えない環境では内部バッファで代替）。
+
+/// フィールド値のコピー/貼り付けに使うクリップボード。
+pub struct ClipboardBridge {
+    /// システムクリップボード（ヘッドレス環境ではNone）。
+    system: Option<arboard::Clipboard>,
+    /// システムクリップボードが使えない場合の1件だけの内部バッファ。
+    fallback: Option<String>,
+}
+
+impl ClipboardBridge {
+    /// システムクリップボードへの接続を試みて作成する。
+    pub fn new() -> Self {
+        // 接続できなければ内部バッファのみで動作する。
+        let system = match arboard::Clipboard::new() {
+            Ok(c) => Some(c),
+            Err(e) => {
+                tracing::warn!("system clipboard unavailable, using internal buffer: {e}");
+                None
+            }
+        };
+        Self {
+            system,
+            fallback: None,
+        }
+    }
+
+    /// システムクリップボードを使わずに作成する。
+    #[cfg(test)]
+    pub fn headless() -> Self {
+        Self {
+            system: None,
+            fallback: None,
+        }
+    }
+
+    /// テキストをコピーし、システムクリップボードへ書けたらtrueを返す。
+    pub fn copy(&mut self, text: &str) -> bool {
+        // 内部バッファには常に保持しておく。
+        self.fallback = Some(text.to_string());
+        match self.system.as_mut().map(|c| c.set_text(text.to_string())) {
+            Some(Ok(())) => true,
+            Some(Err(e)) => {
+                tracing::warn!("clipboard copy failed: {e}");
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// システムクリップボード（無ければ内部バッファ）からテキストを取り出す。
+    pub fn paste(&mut self) -> Option<String> {
+        if let Some(Ok(text)) = self.system.as_mut().map(|c| c.get_text()) {
+            return Some(text);
+        }
+        self.fallback.clone()
+    }
+}
+
+impl Default for ClipboardBridge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headless_fallback_roundtrip() {
+        // システムクリップボードが無くても内部バッファで往復できることを検証する。
+        let mut cb = ClipboardBridge::headless();
+        assert_eq!(cb.paste(), None);
+        assert!(!cb.copy("交通費"));
+        assert_eq!(cb.paste(), Some("交通費".to_string()));
+    }
+}
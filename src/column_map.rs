@@ -0,0 +1,164 @@
+//! テンプレートの見出し行から経費行の列を選ぶピッカーの状態。
+
+use crate::config::GeneralExpenseCfg;
+
+/// 列を割り当てる項目の表示名（日付・用途・金額・勘定科目・備考の順）。
+pub const MAPPED_FIELDS: [&str; 5] = ["Date", "Reason", "Amount", "Category", "Note"];
+
+/// 見出しを見ながら経費行の列を1項目ずつ選ぶピッカーの状態。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnPicker {
+    /// 見出しを読んだ行。
+    pub header_row: u32,
+    /// 見出しのある列（列名, 見出し）。
+    pub columns: Vec<(String, String)>,
+    /// 選んでいる項目の位置（`MAPPED_FIELDS`の添字）。
+    pub step: usize,
+    /// カーソルのある列の位置（`columns`の添字）。
+    pub selected: usize,
+    /// 割り当て前の列（項目ごと）。
+    pub current: [String; 5],
+    /// 選び終えた列（項目の順）。
+    pub picked: Vec<String>,
+}
+
+impl ColumnPicker {
+    /// 読み取った見出しと現在の設定からピッカーを作る。
+    pub fn new(header_row: u32, columns: Vec<(String, String)>, ge: &GeneralExpenseCfg) -> Self {
+        let mut picker = Self {
+            header_row,
+            columns,
+            step: 0,
+            selected: 0,
+            current: [
+                ge.date_col.clone(),
+                ge.reason_col.clone(),
+                ge.amount_col.clone(),
+                ge.category_col.clone(),
+                ge.note_col.clone(),
+            ],
+            picked: vec![],
+        };
+        picker.select_current();
+        picker
+    }
+
+    /// 選んでいる項目の表示名。
+    pub fn field_label(&self) -> &'static str {
+        MAPPED_FIELDS[self.step.min(MAPPED_FIELDS.len() - 1)]
+    }
+
+    /// 選んでいる項目に現在割り当てられている列。
+    pub fn current_col(&self) -> &str {
+        &self.current[self.step.min(MAPPED_FIELDS.len() - 1)]
+    }
+
+    /// カーソルを1つ上へ動かす。
+    pub fn up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// カーソルを1つ下へ動かす（最後の列より先へは進まない）。
+    pub fn down(&mut self) {
+        if self.selected + 1 < self.columns.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// カーソルのある列を選んで次の項目へ進み、すべて選び終えたらtrueを返す。
+    pub fn pick(&mut self) -> bool {
+        let col = match self.columns.get(self.selected) {
+            Some((col, _)) => col.clone(),
+            None => self.current_col().to_string(),
+        };
+        self.advance(col)
+    }
+
+    /// 現在の列のまま次の項目へ進み、すべて選び終えたらtrueを返す。
+    pub fn keep(&mut self) -> bool {
+        let col = self.current_col().to_string();
+        self.advance(col)
+    }
+
+    /// 選び終えた列を設定へ書き込む。
+    pub fn apply(&self, ge: &mut GeneralExpenseCfg) {
+        let targets = [
+            &mut ge.date_col,
+            &mut ge.reason_col,
+            &mut ge.amount_col,
+            &mut ge.category_col,
+            &mut ge.note_col,
+        ];
+        for (target, col) in targets.into_iter().zip(&self.picked) {
+            *target = col.clone();
+        }
+    }
+
+    /// 選んだ列を記録して次の項目へ進む。
+    fn advance(&mut self, col: String) -> bool {
+        self.picked.push(col);
+        self.step += 1;
+        if self.step >= MAPPED_FIELDS.len() {
+            return true;
+        }
+        self.select_current();
+        false
+    }
+
+    /// 現在割り当てられている列にカーソルを合わせる（見出しに無ければ先頭）。
+    fn select_current(&mut self) {
+        let current = self.current_col().to_string();
+        self.selected = self
+            .columns
+            .iter()
+            .position(|(col, _)| *col == current)
+            .unwrap_or(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_picker_walks_fields_and_applies() {
+        // 現在の列にカーソルが合い、選ぶ・そのままにするを混ぜて全項目を割り当てられることを検証する。
+        let mut ge = Config::default().general_expense;
+        let columns: Vec<(String, String)> =
+            [("B", "日付"), ("C", "用途"), ("D", "金額"), ("G", "科目")]
+                .iter()
+                .map(|(c, t)| (c.to_string(), t.to_string()))
+                .collect();
+        let mut picker = ColumnPicker::new(6, columns, &ge);
+        assert_eq!(picker.field_label(), "Date");
+        assert_eq!(picker.selected, 0);
+
+        assert!(!picker.pick());
+        assert!(!picker.keep());
+        assert_eq!(picker.field_label(), "Amount");
+        assert_eq!(picker.selected, 2);
+        picker.down();
+        assert!(!picker.pick());
+        // 現在の列（E）が見出しに無ければ先頭にカーソルが来る。
+        assert_eq!(picker.selected, 0);
+        picker.down();
+        picker.down();
+        picker.down();
+        picker.down();
+        assert!(!picker.pick());
+        assert!(picker.keep());
+
+        picker.apply(&mut ge);
+        assert_eq!(
+            [
+                ge.date_col.as_str(),
+                &ge.reason_col,
+                &ge.amount_col,
+                &ge.category_col,
+                &ge.note_col
+            ],
+            ["B", "C", "G", "G", "F"]
+        );
+    }
+}
@@ -1,8 +1,54 @@
 //! 設定モデルと永続化ヘルパー。
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::{fs, path::Path};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{format, jobs::ReceiptFields, validation};
+
+/// 設定ファイルの読み込み結果。
+#[derive(Debug)]
+pub struct Loaded<T> {
+    /// 読み込んだ値（読めなかった場合は既定値）。
+    pub value: T,
+    /// ファイルを読めず既定値で代用した場合の警告文。
+    pub warning: Option<String>,
+}
+
+/// 設定ファイルを文字列として読み込む。
+///
+/// 存在しなければ`Ok(None)`を返し、それ以外の読み込み失敗はエラー文言にする。
+pub fn read_optional(path: &Path) -> std::result::Result<Option<String>, String> {
+    match fs::read_to_string(path) {
+        Ok(s) => Ok(Some(s)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        // 権限不足などは原因が分かる文言にする。
+        Err(e) => Err(format!(
+            "cannot read {}: {}; using defaults, changes won't persist",
+            path.display(),
+            e.kind()
+        )),
+    }
+}
+
+/// 設定ファイルを同じディレクトリの`<ファイル名>.bak`へ複製し、複製先を返す。
+///
+/// ファイルが無ければ何もせず`Ok(None)`を返す。
+pub fn backup_file(path: &Path) -> Result<Option<PathBuf>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    // 元のファイル名の末尾に`.bak`を付ける（例: config.toml.bak）。
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    let backup = path.with_file_name(name);
+    fs::copy(path, &backup)
+        .with_context(|| format!("cannot back up {} to {}", path.display(), backup.display()))?;
+    Ok(Some(backup))
+}
 
 /// `config.toml` に保存するトップレベル設定。
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +61,53 @@ pub struct Config {
     pub template: TemplateCfg,
     /// 経費行の列レイアウト。
     pub general_expense: GeneralExpenseCfg,
+    /// PDF出力に関する設定。
+    #[serde(default)]
+    pub pdf: PdfCfg,
+    /// PDFエクスポート時のページ設定。
+    #[serde(default)]
+    pub pdf_export: PdfExportCfg,
+    /// 画面表示に関する設定。
+    #[serde(default)]
+    pub ui: UiCfg,
+    /// 二重提出の検出に関する設定。
+    #[serde(default)]
+    pub duplicates: DuplicateCfg,
+    /// 確定済み画像のアーカイブに関する設定。
+    #[serde(default)]
+    pub archive: ArchiveCfg,
+    /// API障害時に確定を止めるサーキットブレーカーの設定。
+    #[serde(default)]
+    pub breaker: BreakerCfg,
+    /// 元画像の文字認識（OCR）に関する設定。
+    #[serde(default)]
+    pub ocr: OcrCfg,
+    /// 作成したシートの共有に関する設定。
+    #[serde(default)]
+    pub sheets: SheetsCfg,
+    /// 入力項目ごとの最大文字数。
+    #[serde(default)]
+    pub field_limits: FieldLimitsCfg,
+    /// 用途入力時に補完候補として出す語句。
+    #[serde(default)]
+    pub reason_suggestions: Vec<String>,
+    /// 勘定科目ごとの入力ルール。
+    #[serde(default)]
+    pub category_rules: Vec<CategoryRule>,
+    /// 新しいジョブの入力項目の初期値に関する設定。
+    #[serde(default)]
+    pub defaults: DefaultsCfg,
+    /// 入力項目の検証に関する設定（テンプレートごとの必須項目）。
+    #[serde(default)]
+    pub validation: ValidationCfg,
+    /// 切り替えて使うユーザーごとの氏名とID（空なら`[user]`/`[google]`だけの1人分）。
+    ///
+    /// 使用中のプロファイルの値は`[user]`/`[google]`に入り、保存時にこちらへも書き戻す。
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+    /// 使用中のプロファイルの番号（`profiles`の添字）。
+    #[serde(default)]
+    pub active_profile: usize,
 }
 
 /// Google API関連のID群。
@@ -26,6 +119,47 @@ pub struct GoogleCfg {
     pub output_folder_id: String,
     /// テンプレートスプレッドシートID（ショートカット可）。
     pub template_sheet_id: String,
+    /// OAuthクライアントシークレットのパス（未設定なら`DEFAULT_CREDENTIALS_PATH`）。
+    #[serde(default)]
+    pub credentials_path: Option<String>,
+    /// 確定した元画像を移動するDriveフォルダID（未設定なら移動しない。`[archive]`より優先）。
+    #[serde(default)]
+    pub processed_folder_id: Option<String>,
+    /// 入力フォルダから一覧に出すファイルのMIMEタイプ（前方一致ではなく部分一致。空なら`DEFAULT_INPUT_MIME_TYPES`）。
+    #[serde(default)]
+    pub input_mime_types: Vec<String>,
+}
+
+/// 入力フォルダから一覧に出す既定のMIMEタイプ（HEICなどの写真は`image/`に含まれる）。
+pub const DEFAULT_INPUT_MIME_TYPES: [&str; 2] = ["image/", "application/pdf"];
+
+/// OAuthクライアントシークレットの既定のパス。
+pub const DEFAULT_CREDENTIALS_PATH: &str = "assets/credentials.json";
+
+impl GoogleCfg {
+    /// OAuthクライアントシークレットのパスを返す。
+    pub fn credentials_path(&self) -> &str {
+        self.credentials_path
+            .as_deref()
+            .unwrap_or(DEFAULT_CREDENTIALS_PATH)
+    }
+
+    /// 入力フォルダから一覧に出すMIMEタイプを返す。
+    pub fn input_mime_types(&self) -> Vec<&str> {
+        if self.input_mime_types.is_empty() {
+            DEFAULT_INPUT_MIME_TYPES.to_vec()
+        } else {
+            self.input_mime_types.iter().map(String::as_str).collect()
+        }
+    }
+
+    /// 処理済みの画像の移動先を返す（空文字は未設定として扱う）。
+    pub fn processed_folder_id(&self) -> Option<&str> {
+        self.processed_folder_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+    }
 }
 
 /// テンプレートに挿入するユーザー情報。
@@ -33,6 +167,54 @@ pub struct GoogleCfg {
 pub struct UserCfg {
     /// テンプレートに記載する氏名。
     pub full_name: String,
+    /// 金額の既定の通貨コード（未設定なら`DEFAULT_CURRENCY`）。
+    #[serde(default)]
+    pub default_currency: Option<String>,
+}
+
+/// 通貨が未設定の場合に使う通貨コード。
+pub const DEFAULT_CURRENCY: &str = "JPY";
+
+/// 通貨コードとして使える形か（英字3文字）。
+pub fn is_currency_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// 氏名が未設定であることを示すプレースホルダ。
+pub const DEFAULT_FULL_NAME: &str = "Your Name";
+
+/// 既定の設定ファイルのパス。
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+impl UserCfg {
+    /// 氏名が空またはプレースホルダのままか判定する。
+    pub fn has_placeholder_name(&self) -> bool {
+        let name = self.full_name.trim();
+        name.is_empty() || name == DEFAULT_FULL_NAME
+    }
+
+    /// 既定の通貨コードを返す。
+    pub fn default_currency(&self) -> &str {
+        self.default_currency.as_deref().unwrap_or(DEFAULT_CURRENCY)
+    }
+}
+
+/// 1人分の氏名とDrive/SheetsのID。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    /// 画面に表示するプロファイル名。
+    pub name: String,
+    /// テンプレートに記載する氏名。
+    pub full_name: String,
+    /// 入力画像が置かれるDriveフォルダID。
+    pub input_folder_id: String,
+    /// PDFをアップロードするDriveフォルダID。
+    pub output_folder_id: String,
+    /// テンプレートスプレッドシートID。
+    pub template_sheet_id: String,
+    /// このプロファイルで必須にする入力項目（未設定なら切り替えても`[validation]`の値を変えない）。
+    pub required_fields: Option<Vec<RequiredField>>,
 }
 
 /// テンプレートシート内のセル位置。
@@ -42,6 +224,22 @@ pub struct TemplateCfg {
     pub name_cell: String,
     /// 対象月を入れるセル。
     pub target_month_cell: String,
+    /// 通貨コードを入れるセル（未設定なら書き込まない）。
+    #[serde(default)]
+    pub currency_cell: Option<String>,
+    /// 書き込み先のシート（タブ）名（未設定なら最初のシート）。
+    #[serde(default)]
+    pub sheet_name: Option<String>,
+}
+
+impl TemplateCfg {
+    /// 書き込み先のシート名を返す（空文字は未設定として扱う）。
+    pub fn sheet_name(&self) -> Option<&str> {
+        self.sheet_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+    }
 }
 
 /// 経費行のレイアウト情報。
@@ -59,27 +257,768 @@ pub struct GeneralExpenseCfg {
     pub category_col: String,
     /// 備考列。
     pub note_col: String,
+    /// 経費行の書き込み位置の決め方。
+    #[serde(default)]
+    pub target_mode: TargetMode,
+    /// 見出し行を探すためのラベル（日付列を上から検索する）。
+    #[serde(default)]
+    pub header_label: Option<String>,
+    /// 経費データ領域を示す名前付き範囲（設定時は先頭行が`start_row`より優先される）。
+    #[serde(default)]
+    pub named_range: Option<String>,
+    /// 通し番号を書き込む列（未設定なら書き込まない）。
+    #[serde(default)]
+    pub seq_col: Option<String>,
+    /// 確定日時を書き込む列（未設定なら書き込まない）。
+    #[serde(default)]
+    pub submitted_at_col: Option<String>,
+    /// 確定日時の書式（chronoのstrftime形式、未設定なら`DEFAULT_SUBMITTED_AT_FORMAT`）。
+    #[serde(default)]
+    pub submitted_at_format: Option<String>,
+    /// 提出者（`user.full_name`）を書き込む列（未設定なら書き込まない）。
+    #[serde(default)]
+    pub submitter_col: Option<String>,
+    /// 既存行を数えるとき、日付列だけでなく経費列のどれかが埋まっている行も使用済みとみなすか。
+    ///
+    /// 複数行にまたがる記入では続き行の日付列が空のことがあり、日付列だけで数えると続き行を上書きしてしまう。
+    /// そうしたテンプレート向け。
+    #[serde(default)]
+    pub count_any_column: bool,
+    /// 書き込み後にセルを読み戻して送った値と比べるか、食い違いをどう扱うか。
+    #[serde(default)]
+    pub verify_write: VerifyWrite,
+    /// 設定画面の列の割り当てで見出しを読む行（未設定なら開始行の1行上）。
+    #[serde(default)]
+    pub header_row: Option<u32>,
+}
+
+/// 書き込み後の読み戻し確認の扱い。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifyWrite {
+    /// 読み戻さない。
+    Off,
+    /// 食い違いをログへ警告し、確定は続ける。
+    #[default]
+    Warn,
+    /// 食い違えばPDFの前に確定を失敗させる。
+    Fail,
+}
+
+impl<'de> Deserialize<'de> for VerifyWrite {
+    /// 以前の真偽値の書き方も受け付ける（`true`は`warn`、`false`は`off`）。
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Flag(bool),
+            Mode(String),
+        }
+        match Repr::deserialize(d)? {
+            Repr::Flag(true) => Ok(Self::Warn),
+            Repr::Flag(false) => Ok(Self::Off),
+            Repr::Mode(mode) => match mode.as_str() {
+                "off" => Ok(Self::Off),
+                "warn" => Ok(Self::Warn),
+                "fail" => Ok(Self::Fail),
+                other => Err(serde::de::Error::unknown_variant(
+                    other,
+                    &["off", "warn", "fail"],
+                )),
+            },
+        }
+    }
+}
+
+/// 確定日時の既定の書式。
+pub const DEFAULT_SUBMITTED_AT_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+impl GeneralExpenseCfg {
+    /// 経費行の本体以外に、行番号を知る必要のある列を書き込むか。
+    pub fn writes_row_extras(&self) -> bool {
+        self.seq_col.is_some() || self.submitted_at_col.is_some() || self.submitter_col.is_some()
+    }
+
+    /// 確定日時の書式を返す。
+    pub fn submitted_at_format(&self) -> &str {
+        self.submitted_at_format
+            .as_deref()
+            .unwrap_or(DEFAULT_SUBMITTED_AT_FORMAT)
+    }
+
+    /// 列の割り当てで見出しを読む行を返す。
+    pub fn header_row(&self) -> u32 {
+        self.header_row
+            .unwrap_or(self.start_row.saturating_sub(1))
+            .max(1)
+    }
+}
+
+/// 経費行を書き込む位置の決定方式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetMode {
+    /// `start_row` から既存行を数えて次の空行に書き込む。
+    #[default]
+    Count,
+    /// 見出し行を起点にSheetsのappendで最終行の後ろへ追加する。
+    AppendAnchored,
+}
+
+/// PDF出力に関する設定。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfCfg {
+    /// 確定時にPDFをエクスポートしてアップロードするか（falseならシートへの書き込みで完了）。
+    pub enabled: bool,
+    /// エクスポート前に領収書画像をシートへ埋め込むか。
+    pub embed_image: bool,
+    /// 画像を埋め込む起点セル。
+    pub image_anchor_cell: String,
+    /// PDFのファイル名の書式（未設定なら`DEFAULT_PDF_NAME_FORMAT`）。
+    ///
+    /// `{month}`（対象月）、`{name}`（空白を除いた氏名）、`{receipt_date}`（支払日）、
+    /// `{today}`（確定した日）を置き換える。
+    pub name_format: Option<String>,
+}
+
+/// PDFのファイル名の既定の書式。
+pub const DEFAULT_PDF_NAME_FORMAT: &str = "{month}_立替経費精算書_{name}.pdf";
+
+impl Default for PdfCfg {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            embed_image: false,
+            image_anchor_cell: "H7".into(),
+            name_format: None,
+        }
+    }
+}
+
+impl PdfCfg {
+    /// PDFのファイル名の書式を返す。
+    pub fn name_format(&self) -> &str {
+        self.name_format
+            .as_deref()
+            .unwrap_or(DEFAULT_PDF_NAME_FORMAT)
+    }
+}
+
+/// PDFエクスポート時のページ設定（スプレッドシートのエクスポートURLのパラメータ）。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfExportCfg {
+    /// 用紙サイズ（`A4`・`A3`・`letter`など）。
+    pub size: String,
+    /// 縦向きにするか（falseなら横向き）。
+    pub portrait: bool,
+    /// 幅をページに合わせるか。
+    pub fit_width: bool,
+    /// 拡大縮小の方法（1: 標準、2: 幅に合わせる、3: 高さに合わせる、4: ページに合わせる。未設定なら送らない）。
+    pub scale: Option<u8>,
+    /// 枠線を印刷するか。
+    pub gridlines: bool,
+}
+
+impl Default for PdfExportCfg {
+    /// A4・幅に合わせる・枠線なしを既定にする。
+    fn default() -> Self {
+        Self {
+            size: "A4".into(),
+            portrait: true,
+            fit_width: true,
+            scale: None,
+            gridlines: false,
+        }
+    }
+}
+
+impl PdfExportCfg {
+    /// エクスポートURLに付けるクエリパラメータを返す。
+    pub fn query_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![
+            ("size", self.size.clone()),
+            ("portrait", self.portrait.to_string()),
+            ("fitw", self.fit_width.to_string()),
+            ("gridlines", self.gridlines.to_string()),
+        ];
+        if let Some(scale) = self.scale {
+            params.push(("scale", scale.to_string()));
+        }
+        params
+    }
+}
+
+/// 画面表示に関する設定。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiCfg {
+    /// 1ページで読み込むジョブ数（0なら全件を一度に読み込む）。
+    pub max_displayed_jobs: u32,
+    /// ジョブ一覧でファイル名の拡張子を隠すか。
+    pub strip_extension: bool,
+    /// ジョブ一覧のファイル名の最大表示文字数（0なら省略しない）。
+    pub filename_max_chars: usize,
+    /// INFOパネルやログのフォルダ/シートIDを末尾4文字以外伏せるか。
+    pub mask_sensitive: bool,
+    /// ジョブ一覧と編集画面の金額を3桁区切りで表示するか（表示のみで、編集・書き込みは元の値）。
+    pub amount_thousands_separator: bool,
+    /// 確定に失敗したとき、再試行/編集/中止を尋ねるか。
+    pub interactive_retry: bool,
+    /// ジョブが完了したときに端末ベルを鳴らすか。
+    pub bell_on_complete: bool,
+    /// ジョブが失敗したときに端末ベルを鳴らすか。
+    pub bell_on_error: bool,
+    /// ジョブ一覧に表示する列と順序（`TABLE_COLUMN_NAMES`の名前で指定）。
+    pub table_columns: Vec<String>,
+    /// ステータスバーに収まらない文言を横に流して表示するか。
+    pub status_marquee: bool,
+    /// 起動時に開く画面（必須項目が未設定なら常にウィザード）。
+    pub startup_screen: StartupScreen,
+}
+
+/// 起動時に開く画面。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartupScreen {
+    /// ジョブ一覧。
+    #[default]
+    Main,
+    /// 設定画面。
+    Settings,
+}
+
+/// ジョブ一覧に指定できる列名。
+pub const TABLE_COLUMN_NAMES: &[&str] = &["#", "file", "status", "amount", "date", "category"];
+
+/// 既定で表示するジョブ一覧の列。
+pub const DEFAULT_TABLE_COLUMNS: &[&str] = &["#", "file", "status", "amount", "date"];
+
+impl Default for UiCfg {
+    fn default() -> Self {
+        Self {
+            max_displayed_jobs: 100,
+            strip_extension: false,
+            filename_max_chars: 0,
+            mask_sensitive: false,
+            amount_thousands_separator: true,
+            interactive_retry: false,
+            bell_on_complete: false,
+            bell_on_error: false,
+            table_columns: DEFAULT_TABLE_COLUMNS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            status_marquee: false,
+            startup_screen: StartupScreen::Main,
+        }
+    }
+}
+
+/// 二重提出の検出に関する設定。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DuplicateCfg {
+    /// 提出済みの画像をローカル台帳に記録し、同じ月の再提出を警告するか。
+    pub detect: bool,
+}
+
+/// 確定済み画像のアーカイブに関する設定。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveCfg {
+    /// 確定済みの画像を移動するDriveフォルダID（空ならアーカイブしない）。
+    pub folder_id: String,
+    /// 何件の確定が完了するごとにまとめて移動するか。
+    pub after_commits: u32,
+}
+
+impl Default for ArchiveCfg {
+    /// アーカイブは無効、有効時は確定ごとに移動する。
+    fn default() -> Self {
+        Self {
+            folder_id: String::new(),
+            after_commits: 1,
+        }
+    }
+}
+
+impl ArchiveCfg {
+    /// アーカイブ先が設定されているか。
+    pub fn enabled(&self) -> bool {
+        !self.folder_id.trim().is_empty()
+    }
+
+    /// 保留中の件数がまとめて移動する件数に達したか（0は1として扱う）。
+    pub fn is_due(&self, pending: usize) -> bool {
+        pending >= self.after_commits.max(1) as usize
+    }
+}
+
+/// 元画像の文字認識（OCR）に関する設定。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OcrCfg {
+    /// 画像を送るOCRエンドポイント（空ならGoogle Cloud Vision APIを使う）。
+    ///
+    /// 画像のバイト列をPOSTし、`{"text": "..."}`形式のJSONを受け取る。
+    pub endpoint: String,
+    /// Vision APIの課金先プロジェクトID（空なら送らない）。
+    pub quota_project: String,
+}
+
+impl OcrCfg {
+    /// 独自のOCRエンドポイント（未設定なら`None`）。
+    pub fn endpoint(&self) -> Option<&str> {
+        Some(self.endpoint.trim()).filter(|s| !s.is_empty())
+    }
+
+    /// Vision APIの課金先プロジェクトID（未設定なら`None`）。
+    pub fn quota_project(&self) -> Option<&str> {
+        Some(self.quota_project.trim()).filter(|s| !s.is_empty())
+    }
+}
+
+/// 作成したシートの共有に関する設定。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SheetsCfg {
+    /// シートの作成後に共有するメールアドレス（空なら共有しない）。
+    pub share_with: Vec<String>,
+    /// 共有するときの権限。
+    pub share_role: ShareRole,
+}
+
+/// シートを共有するときの権限。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShareRole {
+    /// 閲覧のみ。
+    #[default]
+    Reader,
+    /// 編集も可能。
+    Writer,
+}
+
+impl ShareRole {
+    /// Drive APIの`role`に渡す名前。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ShareRole::Reader => "reader",
+            ShareRole::Writer => "writer",
+        }
+    }
+}
+
+/// API障害時に確定を止めるサーキットブレーカーの設定。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BreakerCfg {
+    /// 同じ種類の一時的な失敗が何回続いたら止めるか（0なら止めない）。
+    pub failures: u32,
+    /// 失敗を数える時間幅（秒）。
+    pub window_secs: u64,
+    /// 止めてから試しに1件送るまでの待ち時間（秒）。
+    pub cooldown_secs: u64,
+}
+
+impl Default for BreakerCfg {
+    /// 1分以内に5回続いたら30秒止める。
+    fn default() -> Self {
+        Self {
+            failures: 5,
+            window_secs: 60,
+            cooldown_secs: 30,
+        }
+    }
+}
+
+/// 新しいジョブの入力項目の初期値に関する設定。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DefaultsCfg {
+    /// 直前に確定した領収書の勘定科目と備考を新しいジョブへ引き継ぐか。
+    pub inherit_from_last: bool,
+}
+
+/// 空欄を許さない入力項目。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RequiredField {
+    Date,
+    Reason,
+    Amount,
+    Category,
+    Note,
+}
+
+/// 入力項目の検証に関する設定。
+///
+/// テンプレートごとに必須項目が異なるため、使うテンプレートの設定ファイルで指定する。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidationCfg {
+    /// 空欄を許さない入力項目（含まれない項目は空欄でもよい）。
+    pub required_fields: Vec<RequiredField>,
+}
+
+impl Default for ValidationCfg {
+    /// 日付・用途・金額を必須とする。
+    fn default() -> Self {
+        Self {
+            required_fields: vec![
+                RequiredField::Date,
+                RequiredField::Reason,
+                RequiredField::Amount,
+            ],
+        }
+    }
+}
+
+/// 勘定科目ごとの入力ルール（`[[category_rules]]`）。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryRule {
+    /// 対象の勘定科目（前後の空白は無視して比較する）。
+    pub category: String,
+    /// この科目を選んだとき備考を必須にするか。
+    #[serde(default)]
+    pub require_note: bool,
+}
+
+/// Sheetsの1セルに入る最大文字数。
+pub const SHEETS_CELL_MAX_CHARS: usize = 50_000;
+
+/// 入力項目ごとの最大文字数（0ならSheetsの上限のみ）。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldLimitsCfg {
+    /// 用途の最大文字数。
+    pub reason: usize,
+    /// 勘定科目の最大文字数。
+    pub category: usize,
+    /// 備考の最大文字数。
+    pub note: usize,
+    /// 確定時に上限を超えていた場合の扱い。
+    pub on_overflow: OverflowMode,
+    /// シートへ書き込む用途の1行の表示幅（全角は2、0なら整形しない）。
+    ///
+    /// PDFに出力したときにセルからはみ出さないよう、列幅に合わせて指定する。
+    pub reason_cell_width: usize,
+    /// シートへ書き込む備考の1行の表示幅（全角は2、0なら整形しない）。
+    pub note_cell_width: usize,
+    /// セル幅を超えた用途/備考の整形方法。
+    pub cell_fit: CellFitMode,
+}
+
+/// セル幅を超えた値をシートへ書き込むときの整形方法。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellFitMode {
+    /// 末尾を「…」で省略する。
+    #[default]
+    Truncate,
+    /// 改行を入れて折り返す。
+    Wrap,
+}
+
+/// 上限を超えた入力の確定時の扱い。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowMode {
+    /// エラーとして確定を止める。
+    #[default]
+    Error,
+    /// 上限で切り詰めて確定する。
+    Truncate,
+}
+
+impl Default for FieldLimitsCfg {
+    fn default() -> Self {
+        Self {
+            reason: 200,
+            category: 50,
+            note: 500,
+            on_overflow: OverflowMode::Error,
+            reason_cell_width: 0,
+            note_cell_width: 0,
+            cell_fit: CellFitMode::Truncate,
+        }
+    }
+}
+
+impl FieldLimitsCfg {
+    /// 編集画面のフィールド番号に対応する最大文字数を返す（日付と金額は対象外）。
+    pub fn limit_for(&self, idx: usize) -> Option<usize> {
+        let limit = match idx {
+            1 => self.reason,
+            3 => self.category,
+            4 => self.note,
+            _ => return None,
+        };
+        // 0や過大な値はSheetsの上限に揃える。
+        Some(if limit == 0 {
+            SHEETS_CELL_MAX_CHARS
+        } else {
+            limit.min(SHEETS_CELL_MAX_CHARS)
+        })
+    }
+
+    /// 確定前に上限を適用する。
+    ///
+    /// 切り詰めた場合はその内容を`Ok`で、エラー扱いの場合は問題点を`Err`で返す。
+    pub fn enforce(
+        &self,
+        fields: &mut ReceiptFields,
+    ) -> std::result::Result<Vec<String>, Vec<String>> {
+        let mut truncated = vec![];
+        let mut problems = vec![];
+        for idx in 0..ReceiptFields::FIELD_COUNT {
+            let Some(limit) = self.limit_for(idx) else {
+                continue;
+            };
+            let value = fields.field_value(idx);
+            let len = value.chars().count();
+            if len <= limit {
+                continue;
+            }
+            let name = ReceiptFields::field_name(idx);
+            match self.on_overflow {
+                OverflowMode::Error => {
+                    problems.push(format!("{name} is {len} chars (max {limit})"));
+                }
+                OverflowMode::Truncate => {
+                    fields.set_field_value(idx, value.chars().take(limit).collect());
+                    truncated.push(format!("{name} truncated to {limit} chars"));
+                }
+            }
+        }
+        if problems.is_empty() {
+            Ok(truncated)
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// シートへ書き込む用途/備考をセル幅に収めた値と、切り詰めた項目の説明を返す。
+    ///
+    /// 入力値そのものは変えず、書き込む値だけを整形する。
+    pub fn fit_for_sheet(&self, fields: &ReceiptFields) -> (ReceiptFields, Vec<String>) {
+        let mut fitted = fields.clone();
+        let mut truncated = vec![];
+        for (idx, width) in [(1, self.reason_cell_width), (4, self.note_cell_width)] {
+            if width == 0 {
+                continue;
+            }
+            let value = fields.field_value(idx);
+            let new_value = match self.cell_fit {
+                CellFitMode::Truncate => value
+                    .split('\n')
+                    .map(|line| format::truncate_to_width(line, width))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                CellFitMode::Wrap => format::wrap_to_width(&value, width),
+            };
+            if new_value == value {
+                continue;
+            }
+            if self.cell_fit == CellFitMode::Truncate {
+                truncated.push(format!(
+                    "{} truncated to {width} columns for the sheet",
+                    ReceiptFields::field_name(idx)
+                ));
+            }
+            fitted.set_field_value(idx, new_value);
+        }
+        (fitted, truncated)
+    }
 }
 
 impl Config {
     /// ディスクから読み込み、無ければデフォルトを生成する。
-    pub fn load_or_default(path: &Path) -> Result<Self> {
-        if path.exists() {
-            // 既存ファイルを読み込んでTOMLとしてパースする。
-            let s = fs::read_to_string(path)?;
-            Ok(toml::from_str(&s)?)
+    ///
+    /// 権限不足などで読めない場合は、ファイルに触れず既定値と警告を返す。
+    pub fn load_or_default(path: &Path) -> Result<Loaded<Self>> {
+        match read_optional(path) {
+            // 既存ファイルをTOMLとしてパースする。
+            Ok(Some(s)) => Ok(Loaded {
+                value: toml::from_str(&s)?,
+                warning: None,
+            }),
+            Ok(None) => {
+                // デフォルト設定を生成し、ファイルとして保存する。
+                let cfg = Self::default();
+                cfg.save(path)?;
+                Ok(Loaded {
+                    value: cfg,
+                    warning: None,
+                })
+            }
+            Err(warning) => {
+                tracing::warn!("{warning}");
+                Ok(Loaded {
+                    value: Self::default(),
+                    warning: Some(warning),
+                })
+            }
+        }
+    }
+
+    /// 列指定やセル番地などの書式を検証し、問題点を列挙する。
+    pub fn validate(&self) -> std::result::Result<(), Vec<String>> {
+        let mut problems = vec![];
+        let ge = &self.general_expense;
+
+        // 経費行の列指定を検証する。
+        for (name, col) in [
+            ("general_expense.date_col", &ge.date_col),
+            ("general_expense.reason_col", &ge.reason_col),
+            ("general_expense.amount_col", &ge.amount_col),
+            ("general_expense.category_col", &ge.category_col),
+            ("general_expense.note_col", &ge.note_col),
+        ] {
+            if !validation::is_valid_column(col) {
+                problems.push(format!("{name}: invalid column {col:?}"));
+            }
+        }
+        // 任意の列は設定されている場合のみ検証する。
+        for (name, col) in [
+            ("general_expense.seq_col", &ge.seq_col),
+            ("general_expense.submitted_at_col", &ge.submitted_at_col),
+            ("general_expense.submitter_col", &ge.submitter_col),
+        ] {
+            if let Some(col) = col
+                && !validation::is_valid_column(col)
+            {
+                problems.push(format!("{name}: invalid column {col:?}"));
+            }
+        }
+        // 確定日時の書式はchronoで解釈できること。
+        if chrono::format::StrftimeItems::new(ge.submitted_at_format())
+            .any(|item| matches!(item, chrono::format::Item::Error))
+        {
+            problems.push(format!(
+                "general_expense.submitted_at_format: invalid format {:?}",
+                ge.submitted_at_format()
+            ));
+        }
+        // 開始行は1以上であること。
+        if ge.start_row == 0 {
+            problems.push("general_expense.start_row: must be 1 or greater".into());
+        }
+
+        // テンプレート上のセル番地を検証する。
+        for (name, cell) in [
+            ("template.name_cell", &self.template.name_cell),
+            (
+                "template.target_month_cell",
+                &self.template.target_month_cell,
+            ),
+            ("pdf.image_anchor_cell", &self.pdf.image_anchor_cell),
+        ] {
+            if !validation::is_valid_cell(cell) {
+                problems.push(format!("{name}: invalid cell address {cell:?}"));
+            }
+        }
+        if let Some(cell) = &self.template.currency_cell
+            && !validation::is_valid_cell(cell)
+        {
+            problems.push(format!(
+                "template.currency_cell: invalid cell address {cell:?}"
+            ));
+        }
+        // 通貨コードは英字3文字であること。
+        if let Some(code) = &self.user.default_currency
+            && !is_currency_code(code)
+        {
+            problems.push(format!(
+                "user.default_currency: invalid currency code {code:?}"
+            ));
+        }
+
+        // ジョブ一覧の列名は既知のもので、1列以上あること。
+        for col in &self.ui.table_columns {
+            if !TABLE_COLUMN_NAMES.contains(&col.as_str()) {
+                problems.push(format!(
+                    "ui.table_columns: unknown column {col:?} (expected one of {})",
+                    TABLE_COLUMN_NAMES.join(", ")
+                ));
+            }
+        }
+        if self.ui.table_columns.is_empty() {
+            problems.push("ui.table_columns: at least one column is required".into());
+        }
+
+        // 共有先はメールアドレスの形であること。
+        for email in &self.sheets.share_with {
+            if !validation::is_valid_email(email) {
+                problems.push(format!("sheets.share_with: invalid email {email:?}"));
+            }
+        }
+
+        // 使用中のプロファイルの番号は一覧の範囲内であること。
+        if !self.profiles.is_empty() && self.active_profile >= self.profiles.len() {
+            problems.push(format!(
+                "active_profile: {} is out of range ({} profiles)",
+                self.active_profile,
+                self.profiles.len()
+            ));
+        }
+
+        if problems.is_empty() {
+            Ok(())
         } else {
-            // デフォルト設定を生成し、ファイルとして保存する。
-            let cfg = Self::default();
-            cfg.save(path)?;
-            Ok(cfg)
+            Err(problems)
         }
     }
 
+    /// 使用中のプロファイル名（プロファイルが無ければ`None`）。
+    pub fn active_profile_name(&self) -> Option<&str> {
+        self.profiles
+            .get(self.active_profile)
+            .map(|p| p.name.as_str())
+    }
+
+    /// 現在の`[user]`/`[google]`の値を使用中のプロファイルへ書き戻す。
+    pub fn store_active_profile(&mut self) {
+        let Some(p) = self.profiles.get_mut(self.active_profile) else {
+            return;
+        };
+        p.full_name = self.user.full_name.clone();
+        p.input_folder_id = self.google.input_folder_id.clone();
+        p.output_folder_id = self.google.output_folder_id.clone();
+        p.template_sheet_id = self.google.template_sheet_id.clone();
+        if p.required_fields.is_some() {
+            p.required_fields = Some(self.validation.required_fields.clone());
+        }
+    }
+
+    /// 次のプロファイルへ切り替え、その値を`[user]`/`[google]`/`[validation]`へ反映する。
+    ///
+    /// 切り替え前の値は元のプロファイルへ書き戻す。プロファイルが2つ未満なら何もせず`false`を返す。
+    pub fn cycle_profile(&mut self) -> bool {
+        if self.profiles.len() < 2 {
+            return false;
+        }
+        self.store_active_profile();
+        self.active_profile = (self.active_profile + 1) % self.profiles.len();
+        let p = &self.profiles[self.active_profile];
+        self.user.full_name = p.full_name.clone();
+        self.google.input_folder_id = p.input_folder_id.clone();
+        self.google.output_folder_id = p.output_folder_id.clone();
+        self.google.template_sheet_id = p.template_sheet_id.clone();
+        if let Some(fields) = &p.required_fields {
+            self.validation.required_fields = fields.clone();
+        }
+        true
+    }
+
     /// 設定を整形済みTOMLで保存する。
     pub fn save(&self, path: &Path) -> Result<()> {
-        // TOML文字列に変換する。
-        let s = toml::to_string_pretty(self)?;
+        // 使用中のプロファイルへ現在の値を書き戻してから、TOML文字列に変換する。
+        let mut cfg = self.clone();
+        cfg.store_active_profile();
+        let s = toml::to_string_pretty(&cfg)?;
         // 指定パスへ書き込む。
         fs::write(path, s)?;
         Ok(())
@@ -95,15 +1034,21 @@ impl Default for Config {
                 input_folder_id: "".into(),
                 output_folder_id: "".into(),
                 template_sheet_id: "".into(),
+                credentials_path: None,
+                processed_folder_id: None,
+                input_mime_types: DEFAULT_INPUT_MIME_TYPES.map(String::from).to_vec(),
             },
             // ユーザー情報の既定値を設定する。
             user: UserCfg {
-                full_name: "Your Name".into(),
+                full_name: DEFAULT_FULL_NAME.into(),
+                default_currency: None,
             },
             // テンプレート内のセル位置の既定値を設定する。
             template: TemplateCfg {
                 name_cell: "F3".into(),
                 target_month_cell: "B3".into(),
+                currency_cell: None,
+                sheet_name: None,
             },
             // 経費行のレイアウト既定値を設定する。
             general_expense: GeneralExpenseCfg {
@@ -113,7 +1058,456 @@ impl Default for Config {
                 amount_col: "D".into(),
                 category_col: "E".into(),
                 note_col: "F".into(),
+                target_mode: TargetMode::Count,
+                header_label: None,
+                named_range: None,
+                seq_col: None,
+                submitted_at_col: None,
+                submitted_at_format: None,
+                submitter_col: None,
+                count_any_column: false,
+                verify_write: VerifyWrite::Warn,
+                header_row: None,
             },
+            // PDF出力の既定値を設定する。
+            pdf: PdfCfg::default(),
+            pdf_export: PdfExportCfg::default(),
+            // 画面表示の既定値を設定する。
+            ui: UiCfg::default(),
+            // 二重提出の検出は既定で無効。
+            duplicates: DuplicateCfg::default(),
+            archive: ArchiveCfg::default(),
+            breaker: BreakerCfg::default(),
+            // 文字認識は既定でVision APIを使う。
+            ocr: OcrCfg::default(),
+            // 作成したシートの共有は既定で無し。
+            sheets: SheetsCfg::default(),
+            // 入力項目の最大文字数の既定値を設定する。
+            field_limits: FieldLimitsCfg::default(),
+            // 用途の補完候補は既定では無し。
+            reason_suggestions: vec![],
+            // 勘定科目ごとのルールは既定では無し。
+            category_rules: vec![],
+            // 直前の確定内容の引き継ぎは既定で無効。
+            defaults: DefaultsCfg::default(),
+            validation: ValidationCfg::default(),
+            // プロファイルは既定では無し（`[user]`/`[google]`の1人分）。
+            profiles: vec![],
+            active_profile: 0,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// テスト用の一時パスを作る。
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("receipt_tui_{}_{}", uuid::Uuid::new_v4(), name))
+    }
+
+    #[test]
+    fn test_load_or_default_creates_missing_file() {
+        // ファイルが無ければ既定値で作成され、警告は出ないことを検証する。
+        let path = temp_path("config.toml");
+        let loaded = Config::load_or_default(&path).unwrap();
+        assert!(loaded.warning.is_none());
+        assert!(path.exists());
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_load_or_default_unreadable_falls_back() {
+        // 読めないパス（ここではディレクトリ）は既定値と警告になり、上書きしないことを検証する。
+        let path = temp_path("config_dir");
+        fs::create_dir(&path).unwrap();
+        let loaded = Config::load_or_default(&path).unwrap();
+        let warning = loaded.warning.unwrap();
+        assert!(warning.starts_with("cannot read"));
+        assert!(warning.contains("won't persist"));
+        assert_eq!(loaded.value.user.full_name, DEFAULT_FULL_NAME);
+        assert!(path.is_dir());
+        fs::remove_dir(&path).ok();
+    }
+
+    #[test]
+    fn test_field_limits_enforce_at_boundary() {
+        // 上限ちょうどは通り、1文字超えるとエラーになることを検証する。
+        let limits = FieldLimitsCfg {
+            reason: 5,
+            ..FieldLimitsCfg::default()
+        };
+        let mut fields = ReceiptFields {
+            reason: "交通費精算".into(),
+            ..ReceiptFields::default()
+        };
+        assert_eq!(limits.enforce(&mut fields), Ok(vec![]));
+
+        fields.reason.push('分');
+        let problems = limits.enforce(&mut fields).unwrap_err();
+        assert_eq!(problems, vec!["Reason is 6 chars (max 5)".to_string()]);
+        assert_eq!(fields.reason, "交通費精算分");
+    }
+
+    #[test]
+    fn test_field_limits_truncate_mode() {
+        // 切り詰めモードでは上限まで切り詰めて確定できることを検証する。
+        let limits = FieldLimitsCfg {
+            note: 3,
+            on_overflow: OverflowMode::Truncate,
+            ..FieldLimitsCfg::default()
+        };
+        let mut fields = ReceiptFields {
+            note: "abcdef".into(),
+            ..ReceiptFields::default()
+        };
+        let notes = limits.enforce(&mut fields).unwrap();
+        assert_eq!(fields.note, "abc");
+        assert_eq!(notes, vec!["Note truncated to 3 chars".to_string()]);
+        // 日付と金額には上限が無く、0はSheetsの上限になる。
+        assert_eq!(limits.limit_for(0), None);
+        let unlimited = FieldLimitsCfg {
+            reason: 0,
+            ..FieldLimitsCfg::default()
+        };
+        assert_eq!(unlimited.limit_for(1), Some(SHEETS_CELL_MAX_CHARS));
+    }
+
+    #[test]
+    fn test_fit_for_sheet_truncates_with_ellipsis() {
+        // 切り詰めモードではセル幅を超えた行を「…」で省略し、その旨を返すことを検証する。
+        let limits = FieldLimitsCfg {
+            note_cell_width: 8,
+            ..FieldLimitsCfg::default()
+        };
+        let fields = ReceiptFields {
+            reason: "とても長い用途の説明".into(),
+            note: "会議用の飲み物代\nok".into(),
+            ..ReceiptFields::default()
+        };
+        let (fitted, truncated) = limits.fit_for_sheet(&fields);
+        // 幅の指定が無い用途はそのまま。
+        assert_eq!(fitted.reason, fields.reason);
+        assert_eq!(fitted.note, "会議用…\nok");
+        assert_eq!(
+            truncated,
+            vec!["Note truncated to 8 columns for the sheet".to_string()]
+        );
+        // 入力値そのものは変えない。
+        assert_eq!(fields.note, "会議用の飲み物代\nok");
+    }
+
+    #[test]
+    fn test_fit_for_sheet_wraps_with_newlines() {
+        // 折り返しモードでは幅ごとに改行を入れ、切り詰めとしては報告しないことを検証する。
+        let limits = FieldLimitsCfg {
+            reason_cell_width: 6,
+            note_cell_width: 6,
+            cell_fit: CellFitMode::Wrap,
+            ..FieldLimitsCfg::default()
+        };
+        let fields = ReceiptFields {
+            reason: "タクシー代".into(),
+            note: "abc".into(),
+            ..ReceiptFields::default()
+        };
+        let (fitted, truncated) = limits.fit_for_sheet(&fields);
+        assert_eq!(fitted.reason, "タクシ\nー代");
+        assert_eq!(fitted.note, "abc");
+        assert!(truncated.is_empty());
+    }
+
+    #[test]
+    fn test_archive_is_due_after_n_commits() {
+        // 設定した件数に達したときだけまとめて移動し、0は1件ごととして扱うことを検証する。
+        let mut archive = ArchiveCfg::default();
+        assert!(!archive.enabled());
+        archive.folder_id = "archive".into();
+        archive.after_commits = 3;
+        assert!(archive.enabled());
+        assert!(!archive.is_due(2));
+        assert!(archive.is_due(3));
+        archive.after_commits = 0;
+        assert!(archive.is_due(1));
+        assert!(!archive.is_due(0));
+    }
+
+    #[test]
+    fn test_required_fields_parse() {
+        // 必須項目が小文字の名前で指定でき、未指定なら既定値になることを検証する。
+        let cfg: ValidationCfg =
+            toml::from_str(r#"required_fields = ["date", "category"]"#).unwrap();
+        assert_eq!(
+            cfg.required_fields,
+            vec![RequiredField::Date, RequiredField::Category]
+        );
+        let cfg: ValidationCfg = toml::from_str("").unwrap();
+        assert_eq!(
+            cfg.required_fields,
+            ValidationCfg::default().required_fields
+        );
+    }
+
+    #[test]
+    fn test_pdf_enabled_defaults_to_true() {
+        // PDFの出力は未指定なら有効で、falseを指定すると無効になることを検証する。
+        let cfg: PdfCfg = toml::from_str("").unwrap();
+        assert!(cfg.enabled);
+        let cfg: PdfCfg = toml::from_str("enabled = false").unwrap();
+        assert!(!cfg.enabled);
+        assert_eq!(cfg.image_anchor_cell, PdfCfg::default().image_anchor_cell);
+    }
+
+    #[test]
+    fn test_verify_write_defaults_to_warn() {
+        // 既存の設定ファイルに項目が無くても、書き込みの読み戻しが警告として有効になることを検証する。
+        let saved = toml::to_string(&Config::default()).unwrap();
+        let without: String = saved
+            .lines()
+            .filter(|l| !l.starts_with("verify_write"))
+            .map(|l| format!("{l}\n"))
+            .collect();
+        assert_ne!(saved, without);
+        let cfg: Config = toml::from_str(&without).unwrap();
+        assert_eq!(cfg.general_expense.verify_write, VerifyWrite::Warn);
+
+        // 名前での指定に加え、以前の真偽値の書き方も読める。
+        let parse = |value: &str| {
+            let text = without.replace(
+                "[general_expense]\n",
+                &format!("[general_expense]\nverify_write = {value}\n"),
+            );
+            toml::from_str::<Config>(&text).map(|c| c.general_expense.verify_write)
+        };
+        assert_eq!(parse("\"fail\"").unwrap(), VerifyWrite::Fail);
+        assert_eq!(parse("\"off\"").unwrap(), VerifyWrite::Off);
+        assert_eq!(parse("true").unwrap(), VerifyWrite::Warn);
+        assert_eq!(parse("false").unwrap(), VerifyWrite::Off);
+        assert!(parse("\"strict\"").is_err());
+    }
+
+    #[test]
+    fn test_pdf_export_query_params() {
+        // 未指定ならA4・幅合わせ・枠線なしで、指定した項目と倍率だけが変わることを検証する。
+        let params = |cfg: &PdfExportCfg| {
+            cfg.query_params()
+                .into_iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect::<Vec<_>>()
+                .join("&")
+        };
+        let cfg: PdfExportCfg = toml::from_str("").unwrap();
+        assert_eq!(
+            params(&cfg),
+            "size=A4&portrait=true&fitw=true&gridlines=false"
+        );
+        let cfg: PdfExportCfg = toml::from_str("portrait = false\nscale = 4").unwrap();
+        assert_eq!(
+            params(&cfg),
+            "size=A4&portrait=false&fitw=true&gridlines=false&scale=4"
+        );
+    }
+
+    #[test]
+    fn test_sheets_share_parse_and_validate() {
+        // 共有先と権限が読み込め、メールアドレスでない共有先は検証で報告されることを検証する。
+        let cfg: SheetsCfg = toml::from_str("").unwrap();
+        assert!(cfg.share_with.is_empty());
+        assert_eq!(cfg.share_role, ShareRole::Reader);
+        let cfg: SheetsCfg = toml::from_str(
+            r#"
+            share_with = ["boss@example.com", "accounting"]
+            share_role = "writer"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cfg.share_role.as_str(), "writer");
+
+        let config = Config {
+            sheets: cfg,
+            ..Config::default()
+        };
+        assert_eq!(
+            config.validate().unwrap_err(),
+            vec!["sheets.share_with: invalid email \"accounting\"".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_profiles_cycle_and_stay_backward_compatible() {
+        // プロファイルが無い設定はそのまま読め、切り替えで値が入れ替わり保存で書き戻されることを検証する。
+        let mut config = Config::default();
+        assert!(!config.cycle_profile());
+        assert_eq!(config.active_profile_name(), None);
+
+        config.profiles = vec![
+            Profile {
+                name: "me".into(),
+                ..Profile::default()
+            },
+            Profile {
+                name: "spouse".into(),
+                full_name: "Hanako".into(),
+                input_folder_id: "in2".into(),
+                output_folder_id: "out2".into(),
+                template_sheet_id: "tpl2".into(),
+                required_fields: None,
+            },
+        ];
+        config.user.full_name = "Taro".into();
+        config.google.input_folder_id = "in1".into();
+        assert!(config.cycle_profile());
+        assert_eq!(config.active_profile_name(), Some("spouse"));
+        assert_eq!(config.user.full_name, "Hanako");
+        assert_eq!(config.google.template_sheet_id, "tpl2");
+        assert_eq!(config.profiles[0].full_name, "Taro");
+        assert_eq!(config.profiles[0].input_folder_id, "in1");
+
+        // 保存した内容を読み直すと、編集した値が使用中のプロファイルにも入っている。
+        config.google.output_folder_id = "out2b".into();
+        let path =
+            std::env::temp_dir().join(format!("receipt_tui_cfg_{}.toml", uuid::Uuid::new_v4()));
+        config.save(&path).unwrap();
+        let loaded = Config::load_or_default(&path).unwrap().value;
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded.active_profile, 1);
+        assert_eq!(loaded.profiles[1].output_folder_id, "out2b");
+        assert!(loaded.validate().is_ok());
+
+        config.active_profile = 5;
+        assert!(config.validate().unwrap_err()[0].starts_with("active_profile: 5"));
+    }
+
+    #[test]
+    fn test_profiles_switch_required_fields() {
+        // 同じ入力でも、プロファイルごとの必須項目に従って検証結果が変わることを検証する。
+        let profiles: Vec<Profile> = toml::from_str::<toml::Table>(
+            r#"
+            [[profiles]]
+            name = "me"
+            required_fields = ["date", "reason", "amount"]
+
+            [[profiles]]
+            name = "work"
+            required_fields = ["date", "reason", "amount", "category"]
+            "#,
+        )
+        .unwrap()["profiles"]
+            .clone()
+            .try_into()
+            .unwrap();
+        let mut config = Config {
+            profiles,
+            ..Config::default()
+        };
+        let fields = crate::jobs::ReceiptFields {
+            date_ymd: "2025-12-19".into(),
+            reason: "taxi".into(),
+            amount_yen: 1200,
+            ..Default::default()
+        };
+        assert!(
+            fields
+                .validate(&config.validation.required_fields, &config.category_rules)
+                .is_ok()
+        );
+
+        assert!(config.cycle_profile());
+        assert_eq!(config.active_profile_name(), Some("work"));
+        assert!(
+            fields
+                .validate(&config.validation.required_fields, &config.category_rules)
+                .is_err()
+        );
+
+        // 元のプロファイルへ戻すと、その必須項目が再び使われる。
+        assert!(config.cycle_profile());
+        assert!(
+            fields
+                .validate(&config.validation.required_fields, &config.category_rules)
+                .is_ok()
+        );
+        assert_eq!(
+            config.profiles[1]
+                .required_fields
+                .as_deref()
+                .map(<[_]>::len),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn test_startup_screen_parse() {
+        // 起動画面は小文字の名前で指定でき、未知の名前は読み込みエラーになることを検証する。
+        let cfg: UiCfg = toml::from_str("").unwrap();
+        assert_eq!(cfg.startup_screen, StartupScreen::Main);
+        let cfg: UiCfg = toml::from_str(r#"startup_screen = "settings""#).unwrap();
+        assert_eq!(cfg.startup_screen, StartupScreen::Settings);
+        let err = toml::from_str::<UiCfg>(r#"startup_screen = "monitor""#).unwrap_err();
+        assert!(err.to_string().contains("unknown variant"));
+    }
+
+    #[test]
+    fn test_validate_default_config() {
+        // 既定の設定は検証を通ることを検証する。
+        assert!(Config::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_reports_bad_columns_and_cells() {
+        // 不正な列指定とセル番地が報告されることを検証する。
+        let mut cfg = Config::default();
+        cfg.general_expense.amount_col = "d".into();
+        cfg.template.name_cell = "F".into();
+        let problems = cfg.validate().unwrap_err();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("general_expense.amount_col"));
+        assert!(problems[1].contains("template.name_cell"));
+    }
+
+    #[test]
+    fn test_validate_reports_unknown_table_columns() {
+        // ジョブ一覧の未知の列名と空の指定が報告されることを検証する。
+        let mut cfg = Config::default();
+        cfg.ui.table_columns = vec!["file".into(), "memo".into()];
+        let problems = cfg.validate().unwrap_err();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("ui.table_columns: unknown column \"memo\""));
+
+        cfg.ui.table_columns.clear();
+        assert_eq!(
+            cfg.validate().unwrap_err(),
+            vec!["ui.table_columns: at least one column is required".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_bad_optional_columns_and_format() {
+        // 任意列の不正な指定と解釈できない日時書式が報告されることを検証する。
+        let mut cfg = Config::default();
+        cfg.general_expense.submitter_col = Some("h".into());
+        cfg.general_expense.submitted_at_format = Some("%Y-%Q".into());
+        let problems = cfg.validate().unwrap_err();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("general_expense.submitter_col"));
+        assert!(problems[1].contains("general_expense.submitted_at_format"));
+    }
+
+    #[test]
+    fn test_validate_reports_bad_currency_settings() {
+        // 不正な通貨セルと通貨コードが報告され、未設定なら円になることを検証する。
+        let mut cfg = Config::default();
+        assert_eq!(cfg.user.default_currency(), "JPY");
+        cfg.template.currency_cell = Some("H".into());
+        cfg.user.default_currency = Some("US$".into());
+        let problems = cfg.validate().unwrap_err();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("template.currency_cell"));
+        assert!(problems[1].contains("user.default_currency"));
+
+        cfg.template.currency_cell = Some("H3".into());
+        cfg.user.default_currency = Some("USD".into());
+        assert!(cfg.validate().is_ok());
+    }
+}
@@ -0,0 +1,206 @@
+//! 設定ファイルの外部での変更を検知する。
+//!
+//! 置き換えで保存するエディタもあるため、ファイルではなく親ディレクトリを監視してファイル名で絞り込む。
+//! 連続する通知は`DEBOUNCE`の間まとめ、落ち着いてから1度だけ報告する。
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Serialize, de::DeserializeOwned};
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::{self, Config},
+    shortcuts::Shortcuts,
+};
+
+/// 連続した変更通知をまとめる待ち時間。
+pub const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// ディスク上で変わっていた設定（再読み込みの確認待ち）。
+#[derive(Debug, Clone)]
+pub struct ConfigReload {
+    /// 変わっていた設定ファイルの内容。
+    pub config: Option<Config>,
+    /// 変わっていたショートカット設定の内容。
+    pub shortcuts: Option<Shortcuts>,
+}
+
+/// 監視対象のファイル。
+struct Target {
+    /// 呼び出し側が指定したパス（報告に使う）。
+    path: PathBuf,
+    /// 正規化した親ディレクトリ。
+    dir: PathBuf,
+    /// ファイル名。
+    name: OsString,
+}
+
+/// 設定ファイルの変更を監視する。
+pub struct ConfigWatcher {
+    /// 監視を続けるために保持する（破棄すると止まる）。
+    _watcher: RecommendedWatcher,
+    rx: mpsc::Receiver<notify::Result<notify::Event>>,
+    targets: Vec<Target>,
+    debouncer: Debouncer,
+}
+
+impl ConfigWatcher {
+    /// 指定したファイルの監視を始める。
+    pub fn new(paths: &[&Path]) -> notify::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        let mut targets: Vec<Target> = vec![];
+        for path in paths {
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+            let dir = watch_dir(path);
+            // 同じディレクトリは1度だけ監視する。
+            if !targets.iter().any(|t| t.dir == dir) {
+                watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+            }
+            targets.push(Target {
+                path: path.to_path_buf(),
+                dir,
+                name: name.to_os_string(),
+            });
+        }
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+            targets,
+            debouncer: Debouncer::default(),
+        })
+    }
+
+    /// 届いた通知を取り込み、変更が落ち着いたファイルを返す。
+    pub fn poll(&mut self, now: Instant) -> Vec<PathBuf> {
+        while let Ok(res) = self.rx.try_recv() {
+            match res {
+                // 読み取りだけの通知は変更ではないので無視する。
+                Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                    for p in &event.paths {
+                        if let Some(t) = self.target_for(p) {
+                            self.debouncer.touch(t.path.clone(), now);
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("config watch error: {e}"),
+            }
+        }
+        self.debouncer.settled(now)
+    }
+
+    /// 通知されたパスに対応する監視対象を探す。
+    fn target_for(&self, p: &Path) -> Option<&Target> {
+        let name = p.file_name()?;
+        let dir = watch_dir(p);
+        self.targets.iter().find(|t| t.name == name && t.dir == dir)
+    }
+}
+
+/// ファイルの親ディレクトリを正規化して返す（相対パスはカレントディレクトリ）。
+fn watch_dir(path: &Path) -> PathBuf {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
+
+/// ファイルごとに最後の通知時刻を覚え、待ち時間が過ぎたものを返す。
+#[derive(Debug, Default)]
+struct Debouncer {
+    last: HashMap<PathBuf, Instant>,
+}
+
+impl Debouncer {
+    /// 通知を記録する（待ち時間は最後の通知から数え直す）。
+    fn touch(&mut self, path: PathBuf, now: Instant) {
+        self.last.insert(path, now);
+    }
+
+    /// 待ち時間が過ぎたファイルを取り出す。
+    fn settled(&mut self, now: Instant) -> Vec<PathBuf> {
+        let mut done: Vec<PathBuf> = self
+            .last
+            .iter()
+            .filter(|(_, t)| now.saturating_duration_since(**t) >= DEBOUNCE)
+            .map(|(p, _)| p.clone())
+            .collect();
+        for p in &done {
+            self.last.remove(p);
+        }
+        done.sort();
+        done
+    }
+}
+
+/// ディスク上の内容が`current`と異なれば、読み込んだ値を返す。
+///
+/// 自分で保存した直後の通知は内容が同じなので無視される。読めない・解釈できない場合は理由を返す。
+pub fn changed_on_disk<T: Serialize + DeserializeOwned>(
+    path: &Path,
+    current: &T,
+) -> Result<Option<T>, String> {
+    let Some(content) = config::read_optional(path)? else {
+        // 削除された場合は、次の保存で作り直されるので何もしない。
+        return Ok(None);
+    };
+    let value: T = toml::from_str(&content)
+        .map_err(|e| format!("{} changed but cannot be parsed: {e}", path.display()))?;
+    // 書式の違いは無視し、解釈した内容で比べる。
+    let same = toml::to_string(&value).ok() == toml::to_string(current).ok();
+    Ok((!same).then_some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debouncer_waits_for_quiet_period() {
+        // 連続した通知は最後の通知から待ち時間が過ぎるまで報告されないことを検証する。
+        let t0 = Instant::now();
+        let path = PathBuf::from("config.toml");
+        let mut d = Debouncer::default();
+
+        d.touch(path.clone(), t0);
+        d.touch(path.clone(), t0 + Duration::from_millis(300));
+        assert!(d.settled(t0 + Duration::from_millis(600)).is_empty());
+        assert_eq!(d.settled(t0 + Duration::from_millis(800)), vec![path]);
+        assert!(d.settled(t0 + Duration::from_secs(5)).is_empty());
+    }
+
+    #[test]
+    fn test_changed_on_disk_compares_parsed_content() {
+        // 内容が同じなら変更なし、異なれば新しい値、壊れていれば理由を返すことを検証する。
+        let path =
+            std::env::temp_dir().join(format!("receipt_tui_watch_{}.toml", uuid::Uuid::new_v4()));
+        let cfg = Config::default();
+        cfg.save(&path).unwrap();
+        assert!(changed_on_disk(&path, &cfg).unwrap().is_none());
+
+        let mut edited = cfg.clone();
+        edited.user.full_name = "山田 太郎".into();
+        edited.save(&path).unwrap();
+        let reloaded = changed_on_disk(&path, &cfg).unwrap().unwrap();
+        assert_eq!(reloaded.user.full_name, "山田 太郎");
+
+        fs::write(&path, "[user\n").unwrap();
+        assert!(
+            changed_on_disk(&path, &cfg)
+                .unwrap_err()
+                .contains("cannot be parsed")
+        );
+        fs::remove_file(&path).unwrap();
+        assert!(changed_on_disk(&path, &cfg).unwrap().is_none());
+    }
+}
@@ -0,0 +1,71 @@
+//! 日付の整形ヘルパー。
+
+use chrono::NaiveDate;
+
+/// 日付を入力項目の形式（YYYY-MM-DD）へ整形する。
+pub fn format_ymd(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// 今日の日付（ローカル時刻）をYYYY-MM-DD形式で返す。
+pub fn today_ymd() -> String {
+    format_ymd(chrono::Local::now().date_naive())
+}
+
+/// 今月（ローカル時刻）をYYYY-MM形式で返す。
+pub fn current_ym() -> String {
+    chrono::Local::now().format("%Y-%m").to_string()
+}
+
+/// 日付（YYYY-MM-DD）として有効か判定する。
+pub fn is_valid_ymd(ymd: &str) -> bool {
+    // 桁を省いた表記は受け付けず、実在する日付かで判定する。
+    ymd.len() == 10 && NaiveDate::parse_from_str(ymd, "%Y-%m-%d").is_ok()
+}
+
+/// 対象月（YYYY-MM）として有効か判定する。
+pub fn is_valid_ym(ym: &str) -> bool {
+    // 月初の日付として解釈できるかで判定する。
+    ym.len() == 7 && NaiveDate::parse_from_str(&format!("{ym}-01"), "%Y-%m-%d").is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_ymd_zero_pads() {
+        // 月日が2桁にゼロ埋めされることを検証する。
+        let date = NaiveDate::from_ymd_opt(2025, 1, 5).unwrap();
+        assert_eq!(format_ymd(date), "2025-01-05");
+    }
+
+    #[test]
+    fn test_today_ymd_is_valid_field_value() {
+        // 今日の日付が入力項目の検証を通る形式であることを検証する。
+        let today = today_ymd();
+        assert_eq!(today.len(), 10);
+        assert!(NaiveDate::parse_from_str(&today, "%Y-%m-%d").is_ok());
+    }
+
+    #[test]
+    fn test_is_valid_ym() {
+        // YYYY-MM形式の実在する月だけを受け付けることを検証する。
+        assert!(is_valid_ym("2025-12"));
+        assert!(!is_valid_ym(""));
+        assert!(!is_valid_ym("2025-13"));
+        assert!(!is_valid_ym("2025-1"));
+        assert!(!is_valid_ym("202512"));
+    }
+
+    #[test]
+    fn test_is_valid_ymd() {
+        // YYYY-MM-DD形式の実在する日付だけを受け付けることを検証する。
+        assert!(is_valid_ymd("2025-12-19"));
+        assert!(is_valid_ymd("2024-02-29"));
+        assert!(!is_valid_ymd("2025-13-40"));
+        assert!(!is_valid_ymd("2025-02-29"));
+        assert!(!is_valid_ymd("2025-1-5"));
+        assert!(!is_valid_ymd(""));
+    }
+}
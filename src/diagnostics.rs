@@ -0,0 +1,136 @@
+//! 選択ジョブの診断で取得した生レスポンスの表示状態。
+
+/// 値を伏せるJSONキー（トークンや秘密情報）。
+const SECRET_KEYS: &[&str] = &["access_token", "refresh_token", "id_token", "client_secret"];
+
+/// Googleのアクセストークンの接頭辞。
+const ACCESS_TOKEN_PREFIX: &str = "ya29.";
+
+/// 伏せた値の代わりに表示する文字列。
+const REDACTED: &str = "***";
+
+/// 診断結果をスクロール表示するポップアップの状態。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiagnosticsView {
+    /// ポップアップのタイトル。
+    pub title: String,
+    /// 表示する行（トークンは伏せ済み）。
+    pub lines: Vec<String>,
+    /// 先頭に表示している行。
+    pub scroll: usize,
+}
+
+impl DiagnosticsView {
+    /// 生レスポンスのテキストから表示状態を作る（トークンは伏せる）。
+    pub fn new(title: String, text: &str) -> Self {
+        Self {
+            title,
+            lines: redact(text).lines().map(str::to_string).collect(),
+            scroll: 0,
+        }
+    }
+
+    /// 1行上へスクロールする。
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    /// 1行下へスクロールする（最終行より先へは進まない）。
+    pub fn scroll_down(&mut self) {
+        if self.scroll + 1 < self.lines.len() {
+            self.scroll += 1;
+        }
+    }
+}
+
+/// テキスト中のトークンや秘密情報を伏せる。
+pub fn redact(text: &str) -> String {
+    let mut out = text.to_string();
+    // 秘密情報のキーに続く文字列値を伏せる。
+    for key in SECRET_KEYS {
+        out = redact_json_value(&out, key);
+    }
+    // 本文に紛れたアクセストークンを伏せる。
+    redact_prefixed_tokens(&out, ACCESS_TOKEN_PREFIX)
+}
+
+/// `"key": "value"` の値部分を伏せる。
+fn redact_json_value(text: &str, key: &str) -> String {
+    let needle = format!("\"{key}\"");
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(&needle) {
+        let (head, tail) = rest.split_at(pos + needle.len());
+        out.push_str(head);
+        // コロンと空白を読み飛ばし、文字列値の開始を探す。
+        let after_colon = tail.trim_start().strip_prefix(':').map(str::trim_start);
+        let Some(value) = after_colon.and_then(|t| t.strip_prefix('"')) else {
+            rest = tail;
+            continue;
+        };
+        // 閉じ引用符までを伏せる。
+        let end = value.find('"').unwrap_or(value.len());
+        out.push_str(&tail[..tail.len() - value.len()]);
+        out.push_str(REDACTED);
+        rest = &value[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 接頭辞で始まるトークンを伏せる。
+fn redact_prefixed_tokens(text: &str, prefix: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(prefix) {
+        out.push_str(&rest[..pos]);
+        out.push_str(REDACTED);
+        // トークンに使われる文字が続く限り読み飛ばす。
+        let token = &rest[pos..];
+        let end = token
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')))
+            .unwrap_or(token.len());
+        rest = &token[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_hides_tokens() {
+        // JSONの秘密情報とアクセストークンが伏せられることを検証する。
+        let text = r#"{"access_token": "ya29.a0AfB_xyz-123", "title": "Sheet1"}
+Authorization: Bearer ya29.other_token
+{"refresh_token":"1//abc","expires_in": 3599}"#;
+        let redacted = redact(text);
+        assert_eq!(
+            redacted,
+            r#"{"access_token": "***", "title": "Sheet1"}
+Authorization: Bearer ***
+{"refresh_token":"***","expires_in": 3599}"#
+        );
+    }
+
+    #[test]
+    fn test_redact_leaves_plain_text() {
+        // 秘密情報を含まないテキストは変わらないことを検証する。
+        let text = r#"{"values": [["2025-01-01", "交通費", "1200"]]}"#;
+        assert_eq!(redact(text), text);
+    }
+
+    #[test]
+    fn test_view_scroll_is_clamped() {
+        // スクロールが先頭と最終行で止まることを検証する。
+        let mut view = DiagnosticsView::new("diag".into(), "a\nb\nc");
+        view.scroll_up();
+        assert_eq!(view.scroll, 0);
+        for _ in 0..5 {
+            view.scroll_down();
+        }
+        assert_eq!(view.scroll, 2);
+    }
+}
@@ -1,5 +1,10 @@
 //! 画面遷移用のUI状態と画面種別。
 
+use crate::jobs::{JobStatus, ReceiptFields};
+
+/// 編集の取り消し履歴に残す最大件数。
+const EDIT_UNDO_LIMIT: usize = 20;
+
 /// TUIで現在表示中の画面。
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Screen {
@@ -11,6 +16,10 @@ pub enum Screen {
     EditJob,
     /// 初期設定ウィザード画面。
     InitialSetup,
+    /// 進捗だけを表示する読み取り専用のモニター画面。
+    Monitor,
+    /// ログ全体をスクロールして読むログ画面。
+    Logs,
 }
 
 /// 描画側と共有するUI状態。
@@ -20,6 +29,12 @@ pub struct UiState {
     pub screen: Screen,
     /// ジョブ一覧の選択行。
     pub selected: usize,
+    /// ジョブ一覧の先頭に表示している行（表示中の行の中での位置）。
+    pub table_offset: usize,
+    /// ログ画面の先頭に表示しているログの位置（Noneなら末尾に追従する）。
+    pub log_scroll: Option<usize>,
+    /// 末尾のログまで画面に収まる先頭の位置（描画時に更新する）。
+    pub log_bottom_start: usize,
     /// 右側パネルに表示するログ。
     pub log: Vec<String>,
     /// 画面下部のステータス文言。
@@ -28,4 +43,107 @@ pub struct UiState {
     pub editing_field_idx: usize, // 0..4 の範囲
     /// エラーメッセージ（強調表示用）。
     pub error: Option<String>,
+    /// 描画した回数（ステータスの横流しの位置に使う）。
+    pub frame: u64,
+    /// ジョブ一覧を状態で絞り込む条件。
+    pub status_filter: StatusFilter,
+    /// 最後に確定したファイル名の検索語（次/前の一致へ移動するときに使う）。
+    pub search: Option<String>,
+}
+
+/// ジョブ一覧を状態で絞り込む条件。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StatusFilter {
+    /// すべてのジョブ。
+    #[default]
+    All,
+    /// 編集待ちのジョブ。
+    WaitingUserFix,
+    /// 完了したジョブ。
+    Done,
+    /// 失敗したジョブ。
+    Error,
+}
+
+impl StatusFilter {
+    /// 切り替えで次に選ぶ条件（All → WaitingUserFix → Done → Error → All）。
+    pub fn next(self) -> Self {
+        match self {
+            Self::All => Self::WaitingUserFix,
+            Self::WaitingUserFix => Self::Done,
+            Self::Done => Self::Error,
+            Self::Error => Self::All,
+        }
+    }
+
+    /// ステータスバーに表示する名前。
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::WaitingUserFix => "waiting",
+            Self::Done => "done",
+            Self::Error => "error",
+        }
+    }
+
+    /// ジョブの状態が条件に合うか。
+    pub fn matches(self, status: &JobStatus) -> bool {
+        match self {
+            Self::All => true,
+            Self::WaitingUserFix => matches!(status, JobStatus::WaitingUserFix),
+            Self::Done => matches!(status, JobStatus::Done),
+            Self::Error => matches!(status, JobStatus::Error(_)),
+        }
+    }
+}
+
+/// 完了を待っているWorkerへの操作（確定とリフレッシュを同時に走らせないために使う）。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InFlight {
+    /// ジョブ一覧の再取得。
+    Refresh,
+    /// 指定したジョブの確定。
+    Commit(uuid::Uuid),
+}
+
+/// 編集画面で入力項目の変更を取り消すための履歴（1ジョブ分）。
+#[derive(Clone, Debug, Default)]
+pub struct EditUndo {
+    /// 履歴の対象ジョブ。
+    job_id: Option<uuid::Uuid>,
+    /// 変更前の入力項目（新しいものが末尾）。
+    snapshots: Vec<ReceiptFields>,
+}
+
+impl EditUndo {
+    /// 変更前の入力項目を積む（別のジョブなら、それまでの履歴を捨ててから積む）。
+    pub fn push(&mut self, job_id: uuid::Uuid, before: ReceiptFields) {
+        if self.job_id != Some(job_id) {
+            self.job_id = Some(job_id);
+            self.snapshots.clear();
+        }
+        // 上限を超えたら古いものから捨てる。
+        if self.snapshots.len() == EDIT_UNDO_LIMIT {
+            self.snapshots.remove(0);
+        }
+        self.snapshots.push(before);
+    }
+
+    /// 対象ジョブの直前の入力項目を取り出す（別のジョブの履歴は捨てる）。
+    pub fn pop(&mut self, job_id: uuid::Uuid) -> Option<ReceiptFields> {
+        if self.job_id != Some(job_id) {
+            self.snapshots.clear();
+            return None;
+        }
+        self.snapshots.pop()
+    }
+}
+
+/// 確定失敗時に再試行/編集/中止を尋ねるプロンプト。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPrompt {
+    /// 失敗したジョブのID。
+    pub job_id: uuid::Uuid,
+    /// 失敗理由。
+    pub reason: String,
 }
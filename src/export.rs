@@ -0,0 +1,227 @@
+//! Googleを介さないローカルへの書き出し。
+
+use anyhow::Result;
+use rust_xlsxwriter::{Format, Workbook};
+use std::path::Path;
+
+use crate::{
+    config::{CategoryRule, RequiredField},
+    google::sheets,
+    jobs::{Job, JobStatus, ReceiptFields},
+};
+
+/// XLSX書き出しの結果。
+#[derive(Debug, Default)]
+pub struct ExportSummary {
+    /// 書き出した行数。
+    pub rows: usize,
+    /// 検証に失敗した行（ファイル名と問題点）。
+    pub invalid: Vec<String>,
+}
+
+/// ジョブ一覧の入力項目をXLSXとして保存する。
+///
+/// 検証に失敗した行も書き出し、末尾の列に問題点を記載する。
+pub fn export_jobs_xlsx(
+    path: &Path,
+    jobs: &[Job],
+    required: &[RequiredField],
+    rules: &[CategoryRule],
+) -> Result<ExportSummary> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    let bold = Format::new().set_bold();
+
+    // 見出し行を書き込む。
+    let mut headers = vec!["File"];
+    headers.extend((0..ReceiptFields::FIELD_COUNT).map(ReceiptFields::field_name));
+    headers.push("Problems");
+    for (col, header) in headers.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *header, &bold)?;
+    }
+
+    let mut summary = ExportSummary::default();
+    for (i, job) in jobs.iter().enumerate() {
+        let row = (i + 1) as u32;
+        let f = &job.fields;
+        // 入力項目を列順に書き込む。金額は数値セルにする。
+        sheet.write_string(row, 0, &job.filename)?;
+        sheet.write_string(row, 1, &f.date_ymd)?;
+        sheet.write_string(row, 2, &f.reason)?;
+        sheet.write_number(row, 3, f.amount_yen as f64)?;
+        sheet.write_string(row, 4, &f.category)?;
+        sheet.write_string(row, 5, &f.note)?;
+        sheet.write_string(row, 6, &f.currency)?;
+        // 検証に失敗した行は問題点を記録する。
+        if let Err(problems) = f.validate(required, rules) {
+            let joined = problems.join("; ");
+            sheet.write_string(row, 7, &joined)?;
+            summary
+                .invalid
+                .push(format!("{}: {}", job.filename, joined));
+        }
+        summary.rows += 1;
+    }
+
+    // 保存する。
+    workbook.save(path)?;
+    Ok(summary)
+}
+
+/// ジョブ一覧をUTF-8のCSVとして保存し、書き出した行数を返す。
+///
+/// 1ジョブ1行で、見出し行の後にファイル名・入力項目・状態を並べる。金額は桁区切りの無い整数で書く。
+pub fn export_jobs_csv(path: &Path, jobs: &[Job]) -> Result<usize> {
+    // 見出し行を組み立てる。
+    let mut headers = vec!["File"];
+    headers.extend((0..ReceiptFields::FIELD_COUNT).map(ReceiptFields::field_name));
+    headers.push("Status");
+    let mut out = csv_line(headers);
+
+    for job in jobs {
+        let f = &job.fields;
+        let amount = f.amount_yen.to_string();
+        let status = status_label(&job.status);
+        out.push_str(&csv_line([
+            job.filename.as_str(),
+            &f.date_ymd,
+            &f.reason,
+            &amount,
+            &f.category,
+            &f.note,
+            &f.currency,
+            &status,
+        ]));
+    }
+
+    // 保存する。
+    std::fs::write(path, out)?;
+    Ok(jobs.len())
+}
+
+/// CSVの1行を組み立てる（改行はCRLF）。
+fn csv_line<'a>(fields: impl IntoIterator<Item = &'a str>) -> String {
+    let mut line = fields
+        .into_iter()
+        .map(csv_field)
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// カンマ・引用符・改行を含む値を引用符で囲み、引用符は2つ重ねる。
+///
+/// 表計算ソフトで開いたときに数式として実行されないよう、数式の先頭文字で始まる値には先に`'`を付ける
+/// （負の金額などの数値はそのまま）。
+fn csv_field(value: &str) -> String {
+    let value = if value.parse::<f64>().is_ok() {
+        value.to_string()
+    } else {
+        sheets::escape_formula(value)
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// 台帳に残すジョブ状態の名前。
+fn status_label(status: &JobStatus) -> String {
+    match status {
+        JobStatus::Queued => "Queued".into(),
+        JobStatus::WaitingUserFix => "WaitingUserFix".into(),
+        JobStatus::WritingSheet => "WritingSheet".into(),
+        JobStatus::ExportingPdf => "ExportingPdf".into(),
+        JobStatus::UploadingPdf => "UploadingPdf".into(),
+        JobStatus::Done => "Done".into(),
+        JobStatus::Error(e) => format!("Error: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ValidationCfg;
+    use std::io::Read;
+
+    /// XLSX内の指定パートを文字列として読み出す。
+    fn read_part(path: &Path, name: &str) -> String {
+        let file = std::fs::File::open(path).unwrap();
+        let mut archive = zip::ZipArchive::new(file).unwrap();
+        let mut part = archive.by_name(name).unwrap();
+        let mut xml = String::new();
+        part.read_to_string(&mut xml).unwrap();
+        xml
+    }
+
+    #[test]
+    fn test_export_jobs_xlsx_contains_rows() {
+        // 書き出したXLSXが開け、各行の内容が含まれることを検証する。
+        let mut ok = Job::new("f1".into(), "taxi.jpg".into());
+        ok.fields.date_ymd = "2025-12-19".into();
+        ok.fields.reason = "タクシー".into();
+        ok.fields.amount_yen = 2480;
+        let bad = Job::new("f2".into(), "blank.jpg".into());
+
+        let path = std::env::temp_dir().join(format!("receipt_tui_{}.xlsx", uuid::Uuid::new_v4()));
+        let summary = export_jobs_xlsx(
+            &path,
+            &[ok, bad],
+            &ValidationCfg::default().required_fields,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(summary.rows, 2);
+        assert_eq!(summary.invalid.len(), 1);
+        assert!(summary.invalid[0].starts_with("blank.jpg"));
+
+        let strings = read_part(&path, "xl/sharedStrings.xml");
+        let sheet = read_part(&path, "xl/worksheets/sheet1.xml");
+        std::fs::remove_file(&path).ok();
+        assert!(strings.contains("taxi.jpg"));
+        assert!(strings.contains("タクシー"));
+        assert!(strings.contains("2025-12-19"));
+        assert!(sheet.contains("<v>2480</v>"));
+        assert!(sheet.contains(r#"<row r="3""#));
+    }
+
+    #[test]
+    fn test_export_jobs_csv_escapes_fields() {
+        // 見出し付きで1ジョブ1行を書き、カンマや引用符を含む値を正しく囲むことを検証する。
+        let mut job = Job::new("f1".into(), "taxi.jpg".into());
+        job.fields.date_ymd = "2025-12-19".into();
+        job.fields.reason = "タクシー, 深夜".into();
+        job.fields.amount_yen = 12480;
+        job.fields.note = "say \"hi\"".into();
+        job.status = JobStatus::Done;
+        let other = Job::new("f2".into(), "blank.jpg".into());
+
+        let path = std::env::temp_dir().join(format!("receipt_tui_{}.csv", uuid::Uuid::new_v4()));
+        assert_eq!(export_jobs_csv(&path, &[job, other]).unwrap(), 2);
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+        assert_eq!(
+            lines[0],
+            "File,Date,Reason,Amount,Category,Note,Currency,Status"
+        );
+        assert_eq!(
+            lines[1],
+            r#"taxi.jpg,2025-12-19,"タクシー, 深夜",12480,,"say ""hi""",,Done"#
+        );
+        assert!(lines[2].starts_with("blank.jpg,,,0,"));
+        assert_eq!(lines[3], "");
+    }
+
+    #[test]
+    fn test_csv_field_escapes_formulas() {
+        // 数式の先頭文字で始まる値には`'`を付けてから囲み、数値はそのまま残すことを検証する。
+        assert_eq!(csv_field("=HYPERLINK(\"x\")"), r#""'=HYPERLINK(""x"")""#);
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("+81, Tokyo"), r#""'+81, Tokyo""#);
+        assert_eq!(csv_field("-1200"), "-1200");
+        assert_eq!(csv_field("タクシー"), "タクシー");
+    }
+}
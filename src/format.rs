@@ -0,0 +1,212 @@
+//! 画面表示用の文字列整形ヘルパー。
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// ジョブ一覧に表示するファイル名を整形する。
+///
+/// `strip_extension` で拡張子を隠し、`max_chars` が1以上なら中央を省略して収める。
+pub fn display_filename(name: &str, strip_extension: bool, max_chars: usize) -> String {
+    // 拡張子を取り除く（".env" のような先頭ドットのみの名前はそのまま）。
+    let base = match name.rsplit_once('.') {
+        Some((stem, _)) if strip_extension && !stem.is_empty() => stem,
+        _ => name,
+    };
+    // 上限が無いか収まっていればそのまま返す。
+    let chars: Vec<char> = base.chars().collect();
+    if max_chars == 0 || chars.len() <= max_chars {
+        return base.to_string();
+    }
+    // 省略記号の分を除いて、先頭側を少し多めに残す。
+    let keep = max_chars.saturating_sub(1);
+    let head = keep - keep / 2;
+    let tail = keep / 2;
+    let mut out: String = chars[..head].iter().collect();
+    out.push('…');
+    out.extend(&chars[chars.len() - tail..]);
+    out
+}
+
+/// 表示幅`cols`に収まるよう末尾を省略する。
+///
+/// 全角文字の途中では切らず、省略時は末尾に「…」（幅1）を付ける。
+pub fn truncate_to_width(s: &str, cols: usize) -> String {
+    // 収まっていればそのまま返す。
+    if s.width() <= cols {
+        return s.to_string();
+    }
+    if cols == 0 {
+        return String::new();
+    }
+    // 省略記号の1桁を残して、はみ出さない所まで文字を詰める。
+    let budget = cols - 1;
+    let mut used = 0;
+    let mut out = String::new();
+    for c in s.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > budget {
+            break;
+        }
+        used += w;
+        out.push(c);
+    }
+    out.push('…');
+    out
+}
+
+/// 表示幅`cols`ごとに改行を入れて折り返す（元の改行はそのまま残す）。
+///
+/// 全角文字の途中では切らない。`cols`より広い1文字はその文字だけで1行にする。
+pub fn wrap_to_width(s: &str, cols: usize) -> String {
+    if cols == 0 {
+        return s.to_string();
+    }
+    let mut out = String::new();
+    for (i, line) in s.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        // 行頭からの幅を数え、次の文字がはみ出すなら改行する。
+        let mut used = 0;
+        for c in line.chars() {
+            let w = c.width().unwrap_or(0);
+            if used > 0 && used + w > cols {
+                out.push('\n');
+                used = 0;
+            }
+            used += w;
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// 表示幅`cols`の列内で右寄せする（はみ出す場合は切り詰める）。
+pub fn align_right(s: &str, cols: usize) -> String {
+    let s = truncate_to_width(s, cols);
+    // 不足する表示幅の分だけ左に空白を詰める。
+    format!("{}{}", " ".repeat(cols - s.width()), s)
+}
+
+/// 金額を3桁区切り（カンマ）で整形する。
+pub fn group_thousands(n: i64) -> String {
+    let digits = n.unsigned_abs().to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        // 末尾から数えて3桁ごとに区切りを入れる。
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    if n < 0 { format!("-{}", out) } else { out }
+}
+
+/// 画面共有時に備えて、IDを末尾4文字以外伏せて表示する。
+pub fn mask_id(id: &str) -> String {
+    // 空のIDは未設定と分かるようそのまま返す。
+    if id.is_empty() {
+        return String::new();
+    }
+    // 末尾4文字だけを残す（4文字以下なら全て伏せる）。
+    let chars: Vec<char> = id.chars().collect();
+    let tail: String = if chars.len() > 4 {
+        chars[chars.len() - 4..].iter().collect()
+    } else {
+        String::new()
+    };
+    format!("****{}", tail)
+}
+
+/// マスク設定に応じてIDを表示用に整形する。
+pub fn display_id(id: &str, mask: bool) -> String {
+    if mask { mask_id(id) } else { id.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_to_width_fits_unchanged() {
+        // 幅に収まる文字列はそのまま返ることを検証する。
+        assert_eq!(truncate_to_width("Done", 12), "Done");
+        assert_eq!(truncate_to_width("交通費", 6), "交通費");
+    }
+
+    #[test]
+    fn test_truncate_to_width_never_splits_wide_glyph() {
+        // 全角文字の途中で切らず、省略記号込みで幅に収まることを検証する。
+        let shown = truncate_to_width("交通費精算", 6);
+        assert_eq!(shown, "交通…");
+        assert!(shown.width() <= 6);
+        // 奇数幅では全角1文字分の隙間が残っても半分に割らない。
+        let shown = truncate_to_width("交通費精算", 7);
+        assert_eq!(shown, "交通費…");
+        assert_eq!(shown.width(), 7);
+    }
+
+    #[test]
+    fn test_truncate_to_width_mixed_width() {
+        // 半角と全角が混ざった文字列を表示幅で切り詰めることを検証する。
+        let shown = truncate_to_width("Taxi代 2480円", 8);
+        assert_eq!(shown, "Taxi代 …");
+        assert!(shown.width() <= 8);
+        assert_eq!(truncate_to_width("abc", 0), "");
+    }
+
+    #[test]
+    fn test_align_right_pads_to_width() {
+        // 表示幅に合わせて左側が空白で埋められることを検証する。
+        assert_eq!(align_right("1200", 8), "    1200");
+        assert_eq!(align_right("1,234,567", 10), " 1,234,567");
+        assert_eq!(align_right("円", 4), "  円");
+        assert_eq!(align_right("", 3), "   ");
+        // はみ出す値は切り詰めて幅を保つ。
+        assert_eq!(align_right("123456789", 5), "1234…");
+    }
+
+    #[test]
+    fn test_group_thousands() {
+        // 3桁ごとにカンマが入ることを検証する。
+        assert_eq!(group_thousands(0), "0");
+        assert_eq!(group_thousands(980), "980");
+        assert_eq!(group_thousands(1000), "1,000");
+        assert_eq!(group_thousands(1234567), "1,234,567");
+        assert_eq!(group_thousands(-25000), "-25,000");
+    }
+
+    #[test]
+    fn test_mask_id_keeps_last_four() {
+        // 末尾4文字以外が伏せられることを検証する。
+        assert_eq!(mask_id("1AbCdEfGhIjK"), "****hIjK");
+        assert_eq!(mask_id("abcd"), "****");
+        assert_eq!(mask_id(""), "");
+        assert_eq!(display_id("1AbCdEfGhIjK", false), "1AbCdEfGhIjK");
+    }
+
+    #[test]
+    fn test_display_filename_strip_extension() {
+        // 拡張子が隠されることを検証する。
+        assert_eq!(display_filename("IMG_0001.jpg", true, 0), "IMG_0001");
+        assert_eq!(display_filename("scan.v2.jpeg", true, 0), "scan.v2");
+        assert_eq!(display_filename("IMG_0001.jpg", false, 0), "IMG_0001.jpg");
+    }
+
+    #[test]
+    fn test_display_filename_keeps_dotfiles_and_plain_names() {
+        // 拡張子の無い名前や先頭ドットの名前は変わらないことを検証する。
+        assert_eq!(display_filename("receipt", true, 0), "receipt");
+        assert_eq!(display_filename(".hidden", true, 0), ".hidden");
+    }
+
+    #[test]
+    fn test_display_filename_middle_ellipsis() {
+        // 長い名前が中央省略されることを検証する。
+        let name = "IMG_20250103_receipt_final_v2.jpg";
+        let shown = display_filename(name, true, 12);
+        assert_eq!(shown, "IMG_20…al_v2");
+        assert_eq!(shown.chars().count(), 12);
+        // 上限内なら省略しない。
+        assert_eq!(display_filename("short.jpg", true, 12), "short");
+    }
+}
@@ -1,9 +1,11 @@
 //! Google API向けOAuth設定とスコープ管理。
 
-use anyhow::Result;
-use std::{future::Future, pin::Pin, result::Result as StdResult};
+use anyhow::{Context, Result, bail};
+use reqwest::Client;
+use std::{future::Future, path::Path, pin::Pin, result::Result as StdResult};
 use yup_oauth2::authenticator::Authenticator;
 use yup_oauth2::authenticator_delegate::{DefaultInstalledFlowDelegate, InstalledFlowDelegate};
+use yup_oauth2::storage::TokenInfo;
 use yup_oauth2::{
     DefaultHyperClientBuilder, HyperClientBuilder, InstalledFlowAuthenticator,
     InstalledFlowReturnMethod,
@@ -11,6 +13,12 @@ use yup_oauth2::{
 
 use super::token_store::FileTokenStorage;
 
+/// OAuthトークンの保存先。
+pub const TOKEN_PATH: &str = "token.json";
+
+/// トークン失効APIのURL。
+const REVOKE_URL: &str = "https://oauth2.googleapis.com/revoke";
+
 /// アプリ全体で使うAuthenticator型。
 pub type InstalledAuth =
     Authenticator<<DefaultHyperClientBuilder as HyperClientBuilder>::Connector>;
@@ -40,14 +48,21 @@ impl InstalledFlowDelegate for InstalledFlowBrowserDelegate {
 }
 
 /// ファイル保存型トークンストレージでAuthenticatorを構築する。
-pub async fn authenticator() -> Result<InstalledAuth> {
-    // OAuthクライアントシークレットを埋め込みで読み込む。
-    const CREDS: &str = include_str!("../../assets/credentials.json");
-    // クライアント情報をパースする。
-    let secret = yup_oauth2::parse_application_secret(CREDS.as_bytes())?;
+pub async fn authenticator(credentials_path: &Path) -> Result<InstalledAuth> {
+    // 見つからない場合は、設定すべき項目が分かるエラーにする。
+    if !credentials_path.exists() {
+        bail!(
+            "{} not found; download the OAuth client secret or set google.credentials_path",
+            credentials_path.display()
+        );
+    }
+    // OAuthクライアントシークレットを実行時に読み込む。
+    let secret = yup_oauth2::read_application_secret(credentials_path)
+        .await
+        .with_context(|| format!("cannot read {}", credentials_path.display()))?;
 
     // トークン保存先を準備する。
-    let storage = FileTokenStorage::new("token.json");
+    let storage = FileTokenStorage::new(TOKEN_PATH);
 
     // Installed Flow用のAuthenticatorを構築する。
     let auth = InstalledFlowAuthenticator::builder(secret, InstalledFlowReturnMethod::HTTPRedirect)
@@ -59,6 +74,25 @@ pub async fn authenticator() -> Result<InstalledAuth> {
     Ok(auth)
 }
 
+/// 保存済みのトークンをGoogle側で失効させる。失効させるトークンが無ければfalseを返す。
+///
+/// アクセストークンは期限切れだと失効できないため、リフレッシュトークンがあればそちらを使う（対応するアクセストークンも無効になる）。
+pub async fn revoke(http: &Client, token: &TokenInfo) -> Result<bool> {
+    let Some(t) = token
+        .refresh_token
+        .as_deref()
+        .or(token.access_token.as_deref())
+    else {
+        return Ok(false);
+    };
+    http.post(REVOKE_URL)
+        .form(&[("token", t)])
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(true)
+}
+
 /// Drive/Sheets操作に必要なOAuthスコープ。
 pub fn scopes() -> Vec<&'static str> {
     vec![
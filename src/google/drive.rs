@@ -4,10 +4,14 @@ use anyhow::{Result, anyhow};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
+use super::error::{TRANSIENT_RETRY_ATTEMPTS, ensure_success, retry_request};
+
 /// Driveファイル一覧のレスポンス。
 #[derive(Debug, Deserialize)]
 pub struct FileListResp {
     pub files: Vec<DriveFile>,
+    #[serde(rename = "nextPageToken", default)]
+    pub next_page_token: Option<String>,
 }
 
 /// アプリが必要とする最小限のDriveファイル情報。
@@ -15,6 +19,8 @@ pub struct FileListResp {
 pub struct DriveFile {
     pub id: String,
     pub name: String,
+    #[serde(rename = "mimeType", default)]
+    pub mime_type: String,
 }
 
 /// ショートカット解決に使うメタデータ。
@@ -35,34 +41,101 @@ struct ShortcutDetails {
     target_mime_type: String,
 }
 
-/// 指定フォルダ内の画像ファイルを一覧取得する。
+/// 1回の一覧取得で要求するファイル数（Drive APIの上限）。
+const LIST_PAGE_SIZE: u32 = 1000;
+
+/// 指定フォルダ内の対象ファイルを、全ページ分まとめて一覧取得する。
 pub async fn list_images_in_folder(
     http: &Client,
     token: &str,
     folder_id: &str,
+    mime_types: &[&str],
 ) -> Result<Vec<DriveFile>> {
-    // 対象フォルダ配下の画像（ゴミ箱除外）を検索する。
-    let q = format!(
-        "'{}' in parents and trashed=false and mimeType contains 'image/'",
-        folder_id
-    );
-    // Drive APIのクエリURLを組み立てる。
-    let url = format!(
-        "https://www.googleapis.com/drive/v3/files?q={}&fields=files(id,name)",
-        urlencoding::encode(&q)
+    let mut files = vec![];
+    let mut page_token: Option<String> = None;
+    loop {
+        // 次ページのトークンを引き継いで1ページずつ取得する（一時的な失敗は再試行する）。
+        let (page, next) = retry_request(TRANSIENT_RETRY_ATTEMPTS, || {
+            list_images_page(
+                http,
+                token,
+                folder_id,
+                mime_types,
+                LIST_PAGE_SIZE,
+                page_token.as_deref(),
+            )
+        })
+        .await?;
+        files.extend(page);
+        // トークンが無くなれば最終ページなので終了する。
+        match next {
+            Some(t) if !t.is_empty() => page_token = Some(t),
+            _ => break,
+        }
+    }
+    Ok(files)
+}
+
+/// フォルダ配下の、いずれかのMIMEタイプを含むファイル（ゴミ箱除外）を探す検索式を組み立てる。
+pub fn folder_query(folder_id: &str, mime_types: &[&str]) -> String {
+    let mimes = mime_types
+        .iter()
+        .map(|m| format!("mimeType contains '{}'", m.replace('\'', "\\'")))
+        .collect::<Vec<_>>()
+        .join(" or ");
+    format!("'{folder_id}' in parents and trashed=false and ({mimes})")
+}
+
+/// 指定フォルダ内の対象ファイルを1ページ分取得し、次ページのトークンも返す。
+pub async fn list_images_page(
+    http: &Client,
+    token: &str,
+    folder_id: &str,
+    mime_types: &[&str],
+    page_size: u32,
+    page_token: Option<&str>,
+) -> Result<(Vec<DriveFile>, Option<String>)> {
+    // 対象フォルダ配下の画像・PDFなど（ゴミ箱除外）を検索する。
+    let q = folder_query(folder_id, mime_types);
+    // Drive APIのページ上限（1000）に収める。
+    let page_size = page_size.clamp(1, 1000);
+    // ページ指定付きのクエリURLを組み立てる。
+    let mut url = format!(
+        "https://www.googleapis.com/drive/v3/files?q={}&pageSize={}&fields=nextPageToken,files(id,name,mimeType)",
+        urlencoding::encode(&q),
+        page_size
     );
+    if let Some(t) = page_token {
+        url.push_str(&format!("&pageToken={}", urlencoding::encode(t)));
+    }
 
     // HTTPリクエストを送信し、レスポンスを解析する。
-    let resp = http
-        .get(url)
-        .bearer_auth(token)
-        .send()
-        .await?
-        .error_for_status()?
-        .json::<FileListResp>()
-        .await?;
+    let resp = http.get(url).bearer_auth(token).send().await?;
+    let resp = ensure_success(resp).await?;
+    let resp = resp.json::<FileListResp>().await?;
 
-    Ok(resp.files)
+    Ok((resp.files, resp.next_page_token))
+}
+
+/// DriveフォルダのMIMEタイプ。
+const FOLDER_MIME: &str = "application/vnd.google-apps.folder";
+
+/// IDが存在するDriveフォルダを指しているか確認する。
+pub async fn ensure_folder(http: &Client, token: &str, folder_id: &str) -> Result<()> {
+    // IDと種別だけを取得するURLを組み立てる。
+    let url = format!(
+        "https://www.googleapis.com/drive/v3/files/{}?fields=id,mimeType",
+        urlencoding::encode(folder_id)
+    );
+    // メタデータを取得してJSONへパースする。
+    let resp = ensure_success(http.get(url).bearer_auth(token).send().await?).await?;
+    let meta = resp.json::<FileMeta>().await?;
+    // フォルダ以外（画像やシートのID）は入力・出力先に使えない。
+    if meta.mime_type == FOLDER_MIME {
+        Ok(())
+    } else {
+        Err(anyhow!("not a Drive folder (got {})", meta.mime_type))
+    }
 }
 
 /// テンプレートIDがショートカットの場合、実体のシートIDへ解決する。
@@ -110,6 +183,45 @@ pub async fn resolve_sheet_id(http: &Client, token: &str, file_id: &str) -> Resu
     }
 }
 
+/// `=IMAGE()` から参照できるDrive画像のURLを返す。
+pub fn image_view_url(file_id: &str) -> String {
+    format!("https://drive.google.com/uc?export=view&id={}", file_id)
+}
+
+/// 共有設定の確認に使う権限一覧。
+#[derive(Debug, Deserialize)]
+struct PermissionList {
+    #[serde(default)]
+    permissions: Vec<Permission>,
+}
+
+/// 権限1件（種別のみ）。
+#[derive(Debug, Deserialize)]
+struct Permission {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// ファイルが「リンクを知っている全員」に公開されているか確認する。
+pub async fn is_shared_publicly(http: &Client, token: &str, file_id: &str) -> Result<bool> {
+    // 権限の種別だけを取得するURLを組み立てる。
+    let url = format!(
+        "https://www.googleapis.com/drive/v3/files/{}?fields=permissions(type)",
+        file_id
+    );
+    // 権限一覧を取得する。
+    let list = http
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<PermissionList>()
+        .await?;
+    // anyone権限があれば公開とみなす。
+    Ok(list.permissions.iter().any(|p| p.kind == "anyone"))
+}
+
 /// DriveコピーAPIのリクエストボディ。
 #[derive(Debug, Serialize)]
 struct CopyReq<'a> {
@@ -152,13 +264,70 @@ pub async fn copy_file(
         .to_string())
 }
 
-/// スプレッドシートをPDFとしてエクスポートする。
-pub async fn export_pdf(http: &Client, token: &str, sheet_file_id: &str) -> Result<Vec<u8>> {
-    // エクスポート用URLを作る。
-    let url = format!(
-        "https://www.googleapis.com/drive/v3/files/{}/export?mimeType=application/pdf",
-        sheet_file_id
+/// ユーザーへ権限を付与するリクエストの本文を組み立てる。
+pub fn permission_body(email: &str, role: &str) -> serde_json::Value {
+    serde_json::json!({
+        "type": "user",
+        "role": role,
+        "emailAddress": email,
+    })
+}
+
+/// 権限作成APIのURLを組み立てる（確定のたびに共有通知メールが届かないよう通知は止める）。
+pub fn permissions_url(file_id: &str) -> String {
+    format!(
+        "https://www.googleapis.com/drive/v3/files/{}/permissions?fields=id&sendNotificationEmail=false",
+        urlencoding::encode(file_id)
+    )
+}
+
+/// ファイルを指定のユーザーへ共有する（`role`は`reader`/`writer`）。
+pub async fn share_file(
+    http: &Client,
+    token: &str,
+    file_id: &str,
+    email: &str,
+    role: &str,
+) -> Result<()> {
+    // 権限作成APIのURLを組み立てる。
+    let url = permissions_url(file_id);
+    // 同じユーザーへの権限作成は繰り返しても増えないため、一時的な失敗は再試行する。
+    retry_request(TRANSIENT_RETRY_ATTEMPTS, || async {
+        let resp = http
+            .post(&url)
+            .bearer_auth(token)
+            .json(&permission_body(email, role))
+            .send()
+            .await?;
+        ensure_success(resp).await?;
+        Ok(())
+    })
+    .await
+}
+
+/// ページ設定付きでスプレッドシートをPDFにするエクスポートURLを組み立てる。
+///
+/// Drive APIのエクスポートは用紙や向きを指定できないため、スプレッドシート側のエクスポートURLを使う。
+pub fn pdf_export_url(sheet_file_id: &str, params: &[(&str, String)]) -> String {
+    let mut url = format!(
+        "https://docs.google.com/spreadsheets/d/{}/export?format=pdf",
+        urlencoding::encode(sheet_file_id)
     );
+    for (key, value) in params {
+        url.push_str(&format!("&{key}={}", urlencoding::encode(value)));
+    }
+    url
+}
+
+/// スプレッドシートをページ設定付きでPDFとしてエクスポートする。
+pub async fn export_pdf(
+    http: &Client,
+    token: &str,
+    sheet_file_id: &str,
+    params: &[(&str, String)],
+) -> Result<Vec<u8>> {
+    // エクスポート用URLを作る。
+    let url = pdf_export_url(sheet_file_id, params);
 
     // PDFのバイナリを取得する。
     let bytes = http
@@ -172,6 +341,51 @@ pub async fn export_pdf(http: &Client, token: &str, sheet_file_id: &str) -> Resu
     Ok(bytes.to_vec())
 }
 
+/// ファイルの中身（元画像など）をダウンロードする。
+pub async fn download_file(http: &Client, token: &str, file_id: &str) -> Result<Vec<u8>> {
+    // 中身を取得するURLを作る（`alt=media`でメタデータではなく本体を返す）。
+    let url = format!(
+        "https://www.googleapis.com/drive/v3/files/{}?alt=media",
+        urlencoding::encode(file_id)
+    );
+
+    // 読み取りだけなので、一時的な失敗は再試行する。
+    let bytes = retry_request(TRANSIENT_RETRY_ATTEMPTS, || async {
+        let resp = http.get(&url).bearer_auth(token).send().await?;
+        let resp = ensure_success(resp).await?;
+        Ok(resp.bytes().await?)
+    })
+    .await?;
+    Ok(bytes.to_vec())
+}
+
+/// PDFファイル先頭のマジックバイト。
+const PDF_MAGIC: &[u8] = b"%PDF";
+
+/// エラー表示に含める本文の最大文字数。
+const BODY_SNIPPET_CHARS: usize = 120;
+
+/// エクスポート結果がPDFか確認する。
+///
+/// トークン不正やリダイレクト時に、200でHTMLのエラーページが返ることがあるため。
+pub fn ensure_pdf(bytes: &[u8]) -> Result<()> {
+    if bytes.starts_with(PDF_MAGIC) {
+        return Ok(());
+    }
+    // 原因を追えるよう、本文の先頭を1行に詰めて添える。
+    let snippet: String = String::from_utf8_lossy(bytes)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(BODY_SNIPPET_CHARS)
+        .collect();
+    Err(anyhow!(
+        "export did not return a PDF ({} bytes): {snippet}",
+        bytes.len()
+    ))
+}
+
 /// PDFをDriveへアップロードし、ファイルIDを返す。
 pub async fn upload_pdf(
     http: &Client,
@@ -217,3 +431,269 @@ pub async fn upload_pdf(
         .ok_or_else(|| anyhow!("no id"))?
         .to_string())
 }
+
+/// 親フォルダを付け替えて1件のファイルを移動するURLを組み立てる。
+pub fn move_file_url(file_id: &str, from_parent: &str, to_parent: &str) -> String {
+    format!(
+        "https://www.googleapis.com/drive/v3/files/{}?addParents={}&removeParents={}&fields=id",
+        urlencoding::encode(file_id),
+        urlencoding::encode(to_parent),
+        urlencoding::encode(from_parent),
+    )
+}
+
+/// ファイルを別フォルダへ移動する（`addParents`/`removeParents`で親を付け替える）。
+pub async fn move_file(
+    http: &Client,
+    token: &str,
+    file_id: &str,
+    from_parent: &str,
+    to_parent: &str,
+) -> Result<()> {
+    http.patch(move_file_url(file_id, from_parent, to_parent))
+        .bearer_auth(token)
+        .json(&serde_json::json!({}))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// DriveのバッチAPIのエンドポイント。
+const BATCH_URL: &str = "https://www.googleapis.com/batch/drive/v3";
+
+/// 1回のバッチリクエストに含められる最大件数。
+const BATCH_MAX_REQUESTS: usize = 100;
+
+/// 親フォルダの付け替えでファイルを移動するバッチリクエストの本文を組み立てる。
+pub fn move_batch_body(
+    boundary: &str,
+    file_ids: &[String],
+    from_folder_id: &str,
+    to_folder_id: &str,
+) -> String {
+    let mut body = String::new();
+    for (i, id) in file_ids.iter().enumerate() {
+        // 各ファイルをaddParents/removeParents付きのPATCHとして1パートにする。
+        body.push_str(&format!(
+            "--{boundary}\r\n\
+             Content-Type: application/http\r\n\
+             Content-ID: <move-{i}>\r\n\r\n\
+             PATCH /drive/v3/files/{id}?addParents={to}&removeParents={from}&fields=id HTTP/1.1\r\n\
+             Content-Type: application/json; charset=UTF-8\r\n\r\n\
+             {{}}\r\n",
+            to = urlencoding::encode(to_folder_id),
+            from = urlencoding::encode(from_folder_id),
+        ));
+    }
+    body.push_str(&format!("--{boundary}--\r\n"));
+    body
+}
+
+/// バッチレスポンスから、失敗したパートの番号とステータス行を取り出す。
+///
+/// 番号は`Content-ID: <response-move-N>`から読み、無ければレスポンス内の順番を使う。
+fn batch_failures(response: &str) -> Vec<(usize, String)> {
+    let mut failures = vec![];
+    let mut content_id = None;
+    let mut ordinal = 0;
+    for line in response.lines().map(str::trim) {
+        if let Some(id) = line
+            .strip_prefix("Content-ID: <response-move-")
+            .and_then(|rest| rest.strip_suffix('>'))
+        {
+            content_id = id.parse::<usize>().ok();
+        } else if line.starts_with("HTTP/") {
+            let index = content_id.take().unwrap_or(ordinal);
+            ordinal += 1;
+            let ok = line
+                .split_whitespace()
+                .nth(1)
+                .is_some_and(|s| s.starts_with('2'));
+            if !ok {
+                failures.push((index, line.to_string()));
+            }
+        }
+    }
+    failures
+}
+
+/// 複数のファイルを別フォルダへまとめて移動する（100件ごとに1リクエスト）。
+///
+/// 移動できなかったファイルのIDと理由を返す（すべて移動できれば空）。
+/// 一部のパートだけが失敗しても、移動済みのファイルは含めない。
+pub async fn move_files_batch(
+    http: &Client,
+    token: &str,
+    file_ids: &[String],
+    from_folder_id: &str,
+    to_folder_id: &str,
+) -> Vec<(String, String)> {
+    let mut failed = vec![];
+    for (n, chunk) in file_ids.chunks(BATCH_MAX_REQUESTS).enumerate() {
+        // パートの区切りは本文に現れない固定文字列にする。
+        let boundary = format!("receipt_tui_batch_{n}");
+        let body = move_batch_body(&boundary, chunk, from_folder_id, to_folder_id);
+        let response = async {
+            http.post(BATCH_URL)
+                .bearer_auth(token)
+                .header(
+                    reqwest::header::CONTENT_TYPE,
+                    format!("multipart/mixed; boundary={boundary}"),
+                )
+                .body(body)
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await
+        }
+        .await;
+        match response {
+            // バッチ全体が成功しても個別のパートが失敗している場合がある。
+            Ok(response) => failed.extend(
+                batch_failures(&response)
+                    .into_iter()
+                    .filter_map(|(i, status)| chunk.get(i).map(|id| (id.clone(), status))),
+            ),
+            // リクエスト自体が失敗したら、このまとまりは1件も移動していない。
+            Err(e) => failed.extend(chunk.iter().map(|id| (id.clone(), e.to_string()))),
+        }
+    }
+    failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pdf_export_url_appends_page_options() {
+        // ページ設定がエクスポートURLのクエリとして付くことを検証する。
+        let params = [
+            ("size", "A4".to_string()),
+            ("portrait", "false".to_string()),
+        ];
+        assert_eq!(
+            pdf_export_url("abc", &params),
+            "https://docs.google.com/spreadsheets/d/abc/export?format=pdf&size=A4&portrait=false"
+        );
+    }
+
+    #[test]
+    fn test_folder_query_ors_mime_types() {
+        // 設定したMIMEタイプのいずれかに一致する検索式になることを検証する。
+        assert_eq!(
+            folder_query("abc", &["image/", "application/pdf"]),
+            "'abc' in parents and trashed=false and (mimeType contains 'image/' or mimeType contains 'application/pdf')"
+        );
+    }
+
+    #[test]
+    fn test_file_list_resp_reads_next_page_token() {
+        // 続きがあるページではトークンが読め、最終ページでは None になることを検証する。
+        let json = r#"{"nextPageToken": "p2", "files": [{"id": "a", "name": "a.jpg"}]}"#;
+        let resp: FileListResp = serde_json::from_str(json).unwrap();
+        assert_eq!(resp.next_page_token.as_deref(), Some("p2"));
+        assert_eq!(resp.files.len(), 1);
+
+        let json = r#"{"files": []}"#;
+        let resp: FileListResp = serde_json::from_str(json).unwrap();
+        assert_eq!(resp.next_page_token, None);
+    }
+
+    #[test]
+    fn test_ensure_pdf_accepts_pdf_magic() {
+        // PDFのマジックで始まるバイト列は通ることを検証する。
+        assert!(ensure_pdf(b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n").is_ok());
+    }
+
+    #[test]
+    fn test_ensure_pdf_rejects_html() {
+        // HTMLのエラーページは拒否され、本文の先頭がメッセージに含まれることを検証する。
+        let html = b"<!DOCTYPE html>\n<html>\n  <title>Sign in - Google Accounts</title>";
+        let err = ensure_pdf(html).unwrap_err().to_string();
+        assert!(err.starts_with("export did not return a PDF"));
+        assert!(err.contains("<!DOCTYPE html> <html> <title>Sign in"));
+        assert!(ensure_pdf(b"").is_err());
+    }
+
+    #[test]
+    fn test_permission_body_grants_user_role() {
+        // 指定したユーザーへ指定の権限を与える本文になることを検証する。
+        assert_eq!(
+            permission_body("boss@example.com", "writer"),
+            serde_json::json!({
+                "type": "user",
+                "role": "writer",
+                "emailAddress": "boss@example.com",
+            })
+        );
+    }
+
+    #[test]
+    fn test_permissions_url_disables_notification() {
+        // 共有時に通知メールを送らないURLになることを検証する。
+        assert_eq!(
+            permissions_url("sheet 1"),
+            "https://www.googleapis.com/drive/v3/files/sheet%201/permissions?fields=id&sendNotificationEmail=false"
+        );
+    }
+
+    #[test]
+    fn test_move_file_url_swaps_parents() {
+        // 移動先を追加し、移動元を外すクエリが付くことを検証する。
+        assert_eq!(
+            move_file_url("img1", "inbox", "done folder"),
+            "https://www.googleapis.com/drive/v3/files/img1?addParents=done%20folder&removeParents=inbox&fields=id"
+        );
+    }
+
+    #[test]
+    fn test_move_batch_body_has_one_part_per_file() {
+        // ファイルごとに親フォルダを付け替えるPATCHが並び、終端の区切りで閉じることを検証する。
+        let ids = vec!["img1".to_string(), "img2".to_string()];
+        let body = move_batch_body("b0", &ids, "inbox", "archive");
+        assert_eq!(
+            body,
+            "--b0\r\n\
+             Content-Type: application/http\r\n\
+             Content-ID: <move-0>\r\n\r\n\
+             PATCH /drive/v3/files/img1?addParents=archive&removeParents=inbox&fields=id HTTP/1.1\r\n\
+             Content-Type: application/json; charset=UTF-8\r\n\r\n\
+             {}\r\n\
+             --b0\r\n\
+             Content-Type: application/http\r\n\
+             Content-ID: <move-1>\r\n\r\n\
+             PATCH /drive/v3/files/img2?addParents=archive&removeParents=inbox&fields=id HTTP/1.1\r\n\
+             Content-Type: application/json; charset=UTF-8\r\n\r\n\
+             {}\r\n\
+             --b0--\r\n"
+        );
+    }
+
+    #[test]
+    fn test_batch_failures_picks_non_2xx_parts() {
+        // バッチレスポンス内の2xx以外のパートだけが失敗として返ることを検証する。
+        let response = "--batch_x\r\nContent-Type: application/http\r\n\r\n\
+                        HTTP/1.1 200 OK\r\n\r\n{\"id\": \"img1\"}\r\n\
+                        --batch_x\r\nContent-Type: application/http\r\n\r\n\
+                        HTTP/1.1 404 Not Found\r\n\r\n{}\r\n--batch_x--";
+        assert_eq!(
+            batch_failures(response),
+            vec![(1, "HTTP/1.1 404 Not Found".to_string())]
+        );
+
+        // 順不同で返っても、Content-IDから元のパートを特定する。
+        let response = "--batch_x\r\nContent-Type: application/http\r\n\
+                        Content-ID: <response-move-2>\r\n\r\n\
+                        HTTP/1.1 403 Forbidden\r\n\r\n{}\r\n\
+                        --batch_x\r\nContent-Type: application/http\r\n\
+                        Content-ID: <response-move-0>\r\n\r\n\
+                        HTTP/1.1 200 OK\r\n\r\n{}\r\n--batch_x--";
+        assert_eq!(
+            batch_failures(response),
+            vec![(2, "HTTP/1.1 403 Forbidden".to_string())]
+        );
+    }
+}
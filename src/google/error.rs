@@ -0,0 +1,674 @@
+//! Google APIのエラー型と、エラー種別に応じた再試行ヘルパー。
+//!
+//! Workerからは`WorkerError`に分類してUIへ渡し、UIが再試行/再認証/編集を選べるようにする。
+
+use std::{fmt, future::Future, time::Duration};
+
+/// 成功以外のHTTPステータスが返ったことを表すエラー。
+#[derive(Debug)]
+pub struct ApiError {
+    /// HTTPステータスコード。
+    pub status: u16,
+    /// レスポンスボディ（診断用）。
+    pub body: String,
+    /// `Retry-After`ヘッダーで指定された待ち時間。
+    pub retry_after: Option<Duration>,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Googleの構造化エラーならメッセージと詳細を、それ以外はボディをそのまま示す。
+        let Some(message) = error_message(&self.body) else {
+            return write!(f, "HTTP status {} error: {}", self.status, self.body);
+        };
+        write!(f, "HTTP status {} error: {message}", self.status)?;
+        let details = error_details(&self.body);
+        if !details.is_empty() {
+            write!(f, " ({})", details.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Googleのエラーボディ（`{"error": {"message": ...}}`）からメッセージを取り出す。
+fn error_message(body: &str) -> Option<String> {
+    let v: serde_json::Value = serde_json::from_str(body).ok()?;
+    v["error"]["message"]
+        .as_str()
+        .filter(|m| !m.is_empty())
+        .map(str::to_string)
+}
+
+/// Googleのエラーボディの`details`を、利用者が対処を判断できる1行ずつの説明にする。
+///
+/// どのクォータを超えたか、どの項目が不正かなどを示す種類だけを読み、それ以外は無視する。
+pub fn error_details(body: &str) -> Vec<String> {
+    let Ok(v) = serde_json::from_str::<serde_json::Value>(body) else {
+        return vec![];
+    };
+    let mut lines: Vec<String> = vec![];
+    for detail in v["error"]["details"].as_array().into_iter().flatten() {
+        // `@type`は`type.googleapis.com/google.rpc.ErrorInfo`の形なので末尾で見分ける。
+        let kind = detail["@type"].as_str().unwrap_or_default();
+        let kind = kind.rsplit('.').next().unwrap_or_default();
+        let str_of =
+            |v: &serde_json::Value, key: &str| v[key].as_str().unwrap_or_default().to_string();
+        match kind {
+            "ErrorInfo" => {
+                let meta = &detail["metadata"];
+                let metric = str_of(meta, "quota_metric");
+                if metric.is_empty() {
+                    lines.push(format!("reason {}", str_of(detail, "reason")));
+                    continue;
+                }
+                // クォータ超過は、超えた指標と上限を示す。
+                let mut line = format!("quota {metric} exceeded");
+                let limit = str_of(meta, "quota_limit");
+                let value = str_of(meta, "quota_limit_value");
+                match (limit.is_empty(), value.is_empty()) {
+                    (false, false) => line.push_str(&format!(" (limit {limit} = {value})")),
+                    (false, true) => line.push_str(&format!(" (limit {limit})")),
+                    _ => {}
+                }
+                lines.push(line);
+            }
+            "QuotaFailure" => {
+                for violation in detail["violations"].as_array().into_iter().flatten() {
+                    let description = str_of(violation, "description");
+                    let subject = str_of(violation, "subject");
+                    lines.push(format!(
+                        "quota: {}",
+                        if description.is_empty() {
+                            subject
+                        } else {
+                            description
+                        }
+                    ));
+                }
+            }
+            "BadRequest" => {
+                for violation in detail["fieldViolations"].as_array().into_iter().flatten() {
+                    lines.push(format!(
+                        "field {}: {}",
+                        str_of(violation, "field"),
+                        str_of(violation, "description")
+                    ));
+                }
+            }
+            "PreconditionFailure" => {
+                for violation in detail["violations"].as_array().into_iter().flatten() {
+                    lines.push(format!(
+                        "precondition {}: {}",
+                        str_of(violation, "subject"),
+                        str_of(violation, "description")
+                    ));
+                }
+            }
+            "RetryInfo" => {
+                let delay = str_of(detail, "retryDelay");
+                if !delay.is_empty() {
+                    lines.push(format!("retry after {delay}"));
+                }
+            }
+            "Help" => {
+                for link in detail["links"].as_array().into_iter().flatten() {
+                    lines.push(format!(
+                        "help: {} {}",
+                        str_of(link, "description"),
+                        str_of(link, "url")
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+    // 同じ内容が別の種類で重ねて返ることがあるため、重複を除く。
+    let mut seen = std::collections::HashSet::new();
+    lines.retain(|l| seen.insert(l.clone()));
+    lines
+}
+
+impl std::error::Error for ApiError {}
+
+/// UIが対応を選べるよう種別ごとに分類したWorkerのエラー。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WorkerError {
+    /// 認証の失敗（サインインし直す必要がある）。
+    Auth(String),
+    /// レート制限（時間をおけば再試行できる）。
+    RateLimited(String),
+    /// 対象のファイルやシートが見つからない。
+    NotFound(String),
+    /// 権限不足（共有設定の見直しが必要）。
+    PermissionDenied(String),
+    /// 通信エラーやサーバー側の一時的な失敗（再試行できる）。
+    Network(String),
+    /// 上記以外（設定不足や想定外のレスポンスなど）。
+    Other(String),
+}
+
+impl WorkerError {
+    /// HTTPステータスとボディからエラーを分類する。
+    pub fn from_status(status: u16, body: &str, message: String) -> Self {
+        match status {
+            401 => Self::Auth(message),
+            // 403はレート制限と権限不足の両方で返るため、ボディの理由で見分ける。
+            403 if is_rate_limit_body(body) => Self::RateLimited(message),
+            403 => Self::PermissionDenied(message),
+            404 => Self::NotFound(message),
+            429 => Self::RateLimited(message),
+            500..=599 => Self::Network(message),
+            _ => Self::Other(message),
+        }
+    }
+
+    /// エラーの説明文。
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Auth(m)
+            | Self::RateLimited(m)
+            | Self::NotFound(m)
+            | Self::PermissionDenied(m)
+            | Self::Network(m)
+            | Self::Other(m) => m,
+        }
+    }
+
+    /// ユーザーに示す対処の案内（無ければNone）。
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::Auth(_) => Some("delete token.json and restart to sign in again"),
+            Self::RateLimited(_) => Some("rate limited; wait a moment and retry"),
+            Self::NotFound(_) => Some("check the folder/sheet IDs in settings"),
+            Self::PermissionDenied(_) => Some("check that the file is shared with your account"),
+            Self::Network(_) => Some("temporary failure; retry"),
+            Self::Other(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for WorkerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 案内があれば説明文の後ろに添える。
+        match self.hint() {
+            Some(hint) => write!(f, "{} ({hint})", self.message()),
+            None => f.write_str(self.message()),
+        }
+    }
+}
+
+impl std::error::Error for WorkerError {}
+
+impl From<&ApiError> for WorkerError {
+    fn from(e: &ApiError) -> Self {
+        Self::from_status(e.status, &e.body, e.to_string())
+    }
+}
+
+impl From<&reqwest::Error> for WorkerError {
+    fn from(e: &reqwest::Error) -> Self {
+        if let Some(status) = e.status() {
+            return Self::from_status(status.as_u16(), "", e.to_string());
+        }
+        // 接続できない・時間切れは通信エラー、それ以外（デコード失敗等）はその他とする。
+        if e.is_connect() || e.is_timeout() || e.is_request() {
+            Self::Network(e.to_string())
+        } else {
+            Self::Other(e.to_string())
+        }
+    }
+}
+
+impl From<&serde_json::Error> for WorkerError {
+    fn from(e: &serde_json::Error) -> Self {
+        Self::Other(format!("unexpected response: {e}"))
+    }
+}
+
+impl From<anyhow::Error> for WorkerError {
+    fn from(e: anyhow::Error) -> Self {
+        // 原因の連鎖をたどり、分類できる最初のエラーで判定する。
+        let message = format!("{e:#}");
+        for cause in e.chain() {
+            if let Some(w) = cause.downcast_ref::<WorkerError>() {
+                return w.clone();
+            }
+            let classified = if let Some(api) = cause.downcast_ref::<ApiError>() {
+                WorkerError::from(api)
+            } else if let Some(http) = cause.downcast_ref::<reqwest::Error>() {
+                WorkerError::from(http)
+            } else if let Some(json) = cause.downcast_ref::<serde_json::Error>() {
+                WorkerError::from(json)
+            } else {
+                continue;
+            };
+            // 説明文は文脈を含む全体のものにそろえる。
+            return classified.with_message(message);
+        }
+        Self::Other(message)
+    }
+}
+
+impl WorkerError {
+    /// 種別を保ったまま説明文を差し替える。
+    fn with_message(self, message: String) -> Self {
+        match self {
+            Self::Auth(_) => Self::Auth(message),
+            Self::RateLimited(_) => Self::RateLimited(message),
+            Self::NotFound(_) => Self::NotFound(message),
+            Self::PermissionDenied(_) => Self::PermissionDenied(message),
+            Self::Network(_) => Self::Network(message),
+            Self::Other(_) => Self::Other(message),
+        }
+    }
+}
+
+/// エラーボディがレート制限を示しているか判定する。
+fn is_rate_limit_body(body: &str) -> bool {
+    [
+        "rateLimitExceeded",
+        "userRateLimitExceeded",
+        "RATE_LIMIT_EXCEEDED",
+    ]
+    .iter()
+    .any(|reason| body.contains(reason))
+}
+
+/// エラーが404（対象が見つからない）によるものか判定する。
+pub fn is_not_found(err: &anyhow::Error) -> bool {
+    // 自前のApiErrorとreqwestのステータスエラーの両方を見る。
+    if let Some(api) = err.downcast_ref::<ApiError>() {
+        return api.status == 404;
+    }
+    err.downcast_ref::<reqwest::Error>()
+        .and_then(|e| e.status())
+        .is_some_and(|s| s.as_u16() == 404)
+}
+
+/// 非2xxレスポンスを、`Retry-After`も含めた構造化エラーに変換する。
+pub async fn ensure_success(resp: reqwest::Response) -> anyhow::Result<reqwest::Response> {
+    // ステータスコードを取得する。
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    // 待ち時間の指定があれば控えておく。
+    let retry_after = resp
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_retry_after);
+    // ボディ内容を文字列化してエラーメッセージへ含める。
+    let body = resp.text().await.unwrap_or_else(|_| "".into());
+    Err(ApiError {
+        status: status.as_u16(),
+        body,
+        retry_after,
+    }
+    .into())
+}
+
+/// `Retry-After`の秒数指定を解釈する（日時指定は扱わない）。
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+/// 一時的な失敗を再試行する既定の最大試行回数。
+pub const TRANSIENT_RETRY_ATTEMPTS: u32 = 4;
+
+/// 一時的な失敗の再試行で最初に待つ時間（以降は倍々に延ばす）。
+const TRANSIENT_RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+
+/// 再試行すべき一時的な失敗なら、そのステータスと`Retry-After`を返す。
+fn transient_status(err: &anyhow::Error) -> Option<(u16, Option<Duration>)> {
+    let (status, retry_after) = if let Some(api) = err.downcast_ref::<ApiError>() {
+        (api.status, api.retry_after)
+    } else {
+        let status = err.downcast_ref::<reqwest::Error>()?.status()?;
+        (status.as_u16(), None)
+    };
+    matches!(status, 429 | 500 | 502 | 503).then_some((status, retry_after))
+}
+
+/// 再試行前に待つ時間（`Retry-After`があればそれを優先する）。
+fn retry_delay(base: Duration, retry: u32, retry_after: Option<Duration>) -> Duration {
+    retry_after.unwrap_or_else(|| base * 2u32.saturating_pow(retry.saturating_sub(1)))
+}
+
+/// 429や5xxの間だけ、待ち時間を倍々に延ばしながら最大`max_attempts`回まで処理を試みる。
+///
+/// 400/403/404などはそのまま返す。作成系の処理は5xxでも反映済みのことがあるため包まない。
+pub async fn retry_request<T, F, Fut>(max_attempts: u32, op: F) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    retry_request_with(max_attempts, TRANSIENT_RETRY_BASE_DELAY, op).await
+}
+
+/// `retry_request`の本体（待ち時間の基準を指定できる）。
+async fn retry_request_with<T, F, Fut>(
+    max_attempts: u32,
+    base: Duration,
+    mut op: F,
+) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let mut retries = 0;
+    loop {
+        let e = match op().await {
+            Ok(v) => return Ok(v),
+            Err(e) => e,
+        };
+        // 一時的な失敗かつ試行回数が残っていれば待って再試行する。
+        match transient_status(&e) {
+            Some((status, retry_after)) if retries + 1 < max_attempts => {
+                retries += 1;
+                let delay = retry_delay(base, retries, retry_after);
+                tracing::warn!(
+                    "HTTP {status}; retry {retries}/{} in {}ms",
+                    max_attempts - 1,
+                    delay.as_millis()
+                );
+                tokio::time::sleep(delay).await;
+            }
+            _ => return Err(e),
+        }
+    }
+}
+
+/// 404の間だけ、一定間隔で最大`attempts`回まで処理を試みる。
+///
+/// コピー直後のファイルがまだ見えない、といった反映遅延の吸収用。
+/// 戻り値の2つ目は再試行した回数。
+pub async fn retry_on_not_found<T, F, Fut>(
+    attempts: u32,
+    delay: Duration,
+    mut op: F,
+) -> anyhow::Result<(T, u32)>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    let mut retries = 0;
+    loop {
+        match op().await {
+            Ok(v) => return Ok((v, retries)),
+            // 404かつ試行回数が残っていれば待って再試行する。
+            Err(e) if is_not_found(&e) && retries + 1 < attempts => {
+                retries += 1;
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// テスト用のステータスエラーを作る。
+    fn api_error(status: u16) -> anyhow::Error {
+        ApiError {
+            status,
+            body: String::new(),
+            retry_after: None,
+        }
+        .into()
+    }
+
+    /// ボディ付きのステータスエラーを作る。
+    fn api_error_with_body(status: u16, body: &str) -> anyhow::Error {
+        ApiError {
+            status,
+            body: body.into(),
+            retry_after: None,
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_classify_representative_errors() {
+        // 代表的なエラーボディが正しい種別に分類されることを検証する。
+        let rate_limited =
+            r#"{"error": {"code": 403, "errors": [{"reason": "userRateLimitExceeded"}]}}"#;
+        let forbidden = r#"{"error": {"code": 403, "status": "PERMISSION_DENIED", "errors": [{"reason": "insufficientFilePermissions"}]}}"#;
+        let cases = [
+            (
+                api_error_with_body(401, r#"{"error": {"status": "UNAUTHENTICATED"}}"#),
+                "Auth",
+            ),
+            (api_error_with_body(403, rate_limited), "RateLimited"),
+            (
+                api_error_with_body(429, r#"{"error": {"status": "RESOURCE_EXHAUSTED"}}"#),
+                "RateLimited",
+            ),
+            (api_error_with_body(403, forbidden), "PermissionDenied"),
+            (
+                api_error_with_body(404, r#"{"error": {"message": "File not found"}}"#),
+                "NotFound",
+            ),
+            (api_error_with_body(503, "Service Unavailable"), "Network"),
+            (
+                api_error_with_body(400, r#"{"error": {"status": "INVALID_ARGUMENT"}}"#),
+                "Other",
+            ),
+            (anyhow::anyhow!("template_sheet_id is not set"), "Other"),
+        ];
+        for (err, expected) in cases {
+            let classified = WorkerError::from(err);
+            let name = format!("{classified:?}");
+            assert!(name.starts_with(expected), "{name} should be {expected}");
+        }
+    }
+
+    #[test]
+    fn test_classify_keeps_context_and_explicit_kind() {
+        // 文脈付きのエラーも原因で分類され、明示した種別はそのまま保たれることを検証する。
+        let err = api_error_with_body(404, "missing").context("list failed");
+        let classified = WorkerError::from(err);
+        assert_eq!(
+            classified,
+            WorkerError::NotFound("list failed: HTTP status 404 error: missing".into())
+        );
+        assert!(
+            classified
+                .to_string()
+                .ends_with("(check the folder/sheet IDs in settings)")
+        );
+
+        let json_err = serde_json::from_str::<serde_json::Value>("<html>").unwrap_err();
+        assert!(matches!(
+            WorkerError::from(anyhow::Error::from(json_err)),
+            WorkerError::Other(_)
+        ));
+
+        let auth: anyhow::Error = WorkerError::Auth("token failed".into()).into();
+        assert_eq!(
+            WorkerError::from(auth.context("refresh")),
+            WorkerError::Auth("token failed".into())
+        );
+    }
+
+    #[test]
+    fn test_error_details_quota_exceeded() {
+        // クォータ超過の詳細から、超えた指標・上限・待ち時間が読めることを検証する。
+        let body = r#"{"error": {
+            "code": 429,
+            "message": "Quota exceeded for quota metric 'Read requests'.",
+            "status": "RESOURCE_EXHAUSTED",
+            "details": [
+                {
+                    "@type": "type.googleapis.com/google.rpc.ErrorInfo",
+                    "reason": "RATE_LIMIT_EXCEEDED",
+                    "domain": "googleapis.com",
+                    "metadata": {
+                        "quota_metric": "sheets.googleapis.com/read_requests",
+                        "quota_limit": "ReadRequestsPerMinutePerUser",
+                        "quota_limit_value": "60",
+                        "service": "sheets.googleapis.com"
+                    }
+                },
+                {
+                    "@type": "type.googleapis.com/google.rpc.Help",
+                    "links": [{"description": "Request a higher quota limit.", "url": "https://cloud.google.com/docs/quota"}]
+                },
+                {"@type": "type.googleapis.com/google.rpc.RetryInfo", "retryDelay": "30s"}
+            ]
+        }}"#;
+        assert_eq!(
+            error_details(body),
+            vec![
+                "quota sheets.googleapis.com/read_requests exceeded (limit ReadRequestsPerMinutePerUser = 60)",
+                "help: Request a higher quota limit. https://cloud.google.com/docs/quota",
+                "retry after 30s",
+            ]
+        );
+        let err = ApiError {
+            status: 429,
+            body: body.into(),
+            retry_after: None,
+        };
+        assert!(err.to_string().starts_with(
+            "HTTP status 429 error: Quota exceeded for quota metric 'Read requests'. (quota sheets"
+        ));
+    }
+
+    #[test]
+    fn test_error_details_field_violations() {
+        // 不正な項目の詳細が項目ごとの行になり、詳細が無ければメッセージだけになることを検証する。
+        let body = r#"{"error": {
+            "code": 400,
+            "message": "Invalid requests[0].updateCells: Invalid range.",
+            "status": "INVALID_ARGUMENT",
+            "details": [
+                {
+                    "@type": "type.googleapis.com/google.rpc.BadRequest",
+                    "fieldViolations": [
+                        {"field": "data[0].range", "description": "Unable to parse range: Sheet1!B"},
+                        {"field": "valueInputOption", "description": "Must be RAW or USER_ENTERED"}
+                    ]
+                },
+                {"@type": "type.googleapis.com/google.rpc.DebugInfo", "detail": "internal"}
+            ]
+        }}"#;
+        assert_eq!(
+            error_details(body),
+            vec![
+                "field data[0].range: Unable to parse range: Sheet1!B",
+                "field valueInputOption: Must be RAW or USER_ENTERED",
+            ]
+        );
+
+        let plain = ApiError {
+            status: 404,
+            body: r#"{"error": {"code": 404, "message": "File not found: abc."}}"#.into(),
+            retry_after: None,
+        };
+        assert_eq!(
+            plain.to_string(),
+            "HTTP status 404 error: File not found: abc."
+        );
+        assert!(error_details("Service Unavailable").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_retry_resolves_after_first_not_found() {
+        // 初回404の後に成功すれば、その結果が返ることを検証する。
+        let mut calls = 0;
+        let (value, retries) = retry_on_not_found(3, Duration::ZERO, || {
+            calls += 1;
+            let result = if calls == 1 {
+                Err(api_error(404))
+            } else {
+                Ok("Sheet1")
+            };
+            async move { result }
+        })
+        .await
+        .unwrap();
+        assert_eq!(value, "Sheet1");
+        assert_eq!(retries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_and_skips_other_errors() {
+        // 404が続けば上限で諦め、404以外は即座に返すことを検証する。
+        let mut calls = 0;
+        let err = retry_on_not_found(3, Duration::ZERO, || {
+            calls += 1;
+            async { Err::<(), _>(api_error(404)) }
+        })
+        .await
+        .unwrap_err();
+        assert!(is_not_found(&err));
+        assert_eq!(calls, 3);
+
+        let mut calls = 0;
+        let err = retry_on_not_found(3, Duration::ZERO, || {
+            calls += 1;
+            async { Err::<(), _>(api_error(403)) }
+        })
+        .await
+        .unwrap_err();
+        assert!(!is_not_found(&err));
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_request_retries_transient_statuses_only() {
+        // 429/5xxは成功するまで再試行し、400/403/404は1回で返すことを検証する。
+        let mut calls = 0;
+        let value = retry_request_with(4, Duration::ZERO, || {
+            calls += 1;
+            let result = match calls {
+                1 => Err(api_error(429)),
+                2 => Err(api_error(503)),
+                _ => Ok("done"),
+            };
+            async move { result }
+        })
+        .await
+        .unwrap();
+        assert_eq!((value, calls), ("done", 3));
+
+        for status in [400, 403, 404] {
+            let mut calls = 0;
+            let err = retry_request_with(4, Duration::ZERO, || {
+                calls += 1;
+                async move { Err::<(), _>(api_error(status)) }
+            })
+            .await
+            .unwrap_err();
+            assert_eq!(err.downcast_ref::<ApiError>().unwrap().status, status);
+            assert_eq!(calls, 1);
+        }
+
+        let mut calls = 0;
+        retry_request_with(3, Duration::ZERO, || {
+            calls += 1;
+            async { Err::<(), _>(api_error(500)) }
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_and_honors_retry_after() {
+        // 待ち時間が倍々に延び、Retry-Afterがあればそれを使うことを検証する。
+        let base = Duration::from_millis(250);
+        let delays: Vec<_> = (1..=3)
+            .map(|n| retry_delay(base, n, None).as_millis())
+            .collect();
+        assert_eq!(delays, vec![250, 500, 1000]);
+        assert_eq!(
+            retry_delay(base, 1, parse_retry_after(" 7 ")),
+            Duration::from_secs(7)
+        );
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+    }
+}
@@ -4,7 +4,11 @@
 pub mod auth;
 /// Drive APIのラッパー。
 pub mod drive;
+/// APIエラー型と再試行ヘルパー。
+pub mod error;
 /// Sheets APIのラッパー。
 pub mod sheets;
 /// OAuthトークンの保存処理。
 pub mod token_store;
+/// Cloud Vision APIのラッパー（レシートの文字認識）。
+pub mod vision;
@@ -4,9 +4,13 @@ use anyhow::{Result, anyhow};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
+use super::error::{WorkerError, ensure_success};
+
 /// スプレッドシートレスポンスの最小ラッパー。
 #[derive(Debug, Deserialize)]
 pub struct Spreadsheet {
+    /// シートが1枚も無い場合、APIはこのフィールドごと省く。
+    #[serde(default)]
     pub sheets: Vec<Sheet>,
 }
 /// スプレッドシート内のシート情報。
@@ -16,6 +20,7 @@ pub struct Sheet {
 }
 /// アプリが利用するシートのプロパティ。
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SheetProps {
     pub title: String,
     #[serde(default)]
@@ -23,16 +28,51 @@ pub struct SheetProps {
 }
 /// グリッド情報（行数など）。
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GridProps {
     pub row_count: Option<u32>,
 }
 
-/// 最初のシート名と行数を取得する（行数は推定含む）。
-pub async fn get_first_sheet_title_and_rows(
+/// シートが1枚も無いスプレッドシートを指定された場合の案内。
+const NO_SHEETS_MESSAGE: &str =
+    "Template has no sheets — is the template ID pointing at the right file?";
+
+/// 行数がどうしても分からない場合に使う既定の行数。
+const FALLBACK_ROW_COUNT: u32 = 1000;
+
+/// 使用済みの最終行を調べる範囲の列（A列からこの列まで）。
+const USED_RANGE_LAST_COL: &str = "ZZ";
+
+/// シートの行数と、その求め方。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SheetRows {
+    /// グリッド情報にある行数（これより下の行へは書き込めない）。
+    Grid(u32),
+    /// グリッド情報が無いため、使用済みの最終行か既定の行数で代用した値。
+    Estimated(u32),
+}
+
+impl SheetRows {
+    /// 書き込める最終行を返す（グリッド情報から分かる場合のみ）。
+    pub fn limit(self) -> Option<u32> {
+        match self {
+            Self::Grid(rows) => Some(rows),
+            Self::Estimated(_) => None,
+        }
+    }
+}
+
+/// 対象シートの名前と行数を取得する。
+///
+/// `sheet_name`が指定されていればその名前のシートを、無ければ最初のシートを対象にする。
+/// グリッド情報が無い場合は値を読み出して使用済みの最終行を求め、
+/// それも分からなければ既定の行数で代用する。
+pub async fn get_sheet_title_and_rows(
     http: &Client,
     token: &str,
     spreadsheet_id: &str,
-) -> Result<(String, u32)> {
+    sheet_name: Option<&str>,
+) -> Result<(String, SheetRows)> {
     // シート情報だけを取得するURLを組み立てる。
     let url = format!(
         "https://sheets.googleapis.com/v4/spreadsheets/{}?fields=sheets(properties(title,gridProperties(rowCount)))",
@@ -44,17 +84,91 @@ pub async fn get_first_sheet_title_and_rows(
     // JSONを構造体へデコードする。
     let ss = resp.json::<Spreadsheet>().await?;
 
-    // 最初のシートを取り出す。
-    let s0 = ss.sheets.first().ok_or_else(|| anyhow!("no sheets"))?;
+    // 対象のシートを取り出す。
+    let s0 = select_sheet(&ss, sheet_name)?;
     let title = s0.properties.title.clone();
-    let rows = s0
-        .properties
-        .grid_properties
-        .as_ref()
-        .and_then(|g| g.row_count)
-        // グリッド情報が無い場合は妥当なサイズで代用する。
-        .unwrap_or(1000);
-    Ok((title, rows))
+    // グリッド情報があればその行数を使う。
+    if let Some(rows) = grid_row_count(&s0.properties) {
+        return Ok((title, SheetRows::Grid(rows)));
+    }
+    // 無ければ値を読み出して使用済みの最終行を求める。
+    let rows = match get_last_used_row(http, token, spreadsheet_id, &title).await {
+        Ok(Some(rows)) => rows,
+        Ok(None) => FALLBACK_ROW_COUNT,
+        Err(e) => {
+            tracing::warn!("failed to read used range of {title}: {e:#}");
+            FALLBACK_ROW_COUNT
+        }
+    };
+    Ok((title, SheetRows::Estimated(rows)))
+}
+
+/// 最初のシートを返す（1枚も無ければ、テンプレートIDの見直しを促すエラーにする）。
+fn first_sheet(ss: &Spreadsheet) -> Result<&Sheet, WorkerError> {
+    ss.sheets
+        .first()
+        .ok_or_else(|| WorkerError::Other(NO_SHEETS_MESSAGE.into()))
+}
+
+/// 名前の一致するシートを返す（未指定なら最初のシート、見つからなければ既存のシート名を添えたエラー）。
+fn select_sheet<'a>(
+    ss: &'a Spreadsheet,
+    sheet_name: Option<&str>,
+) -> Result<&'a Sheet, WorkerError> {
+    let Some(name) = sheet_name else {
+        return first_sheet(ss);
+    };
+    ss.sheets
+        .iter()
+        .find(|s| s.properties.title == name)
+        .ok_or_else(|| {
+            let titles = ss
+                .sheets
+                .iter()
+                .map(|s| s.properties.title.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            WorkerError::Other(format!(
+                "Sheet {name:?} not found in the template (sheets: {titles})"
+            ))
+        })
+}
+
+/// グリッド情報から行数を取り出す。
+fn grid_row_count(props: &SheetProps) -> Option<u32> {
+    props.grid_properties.as_ref().and_then(|g| g.row_count)
+}
+
+/// 1行目から読んだ値の行数を使用済みの最終行とみなす（空なら None）。
+fn last_used_row(values: &[Vec<String>]) -> Option<u32> {
+    // Values APIは末尾の空行を省くので、件数がそのまま最終行になる。
+    (!values.is_empty()).then_some(values.len() as u32)
+}
+
+/// シートを広い範囲で読み出し、使用済みの最終行を返す。
+async fn get_last_used_row(
+    http: &Client,
+    token: &str,
+    spreadsheet_id: &str,
+    sheet_title: &str,
+) -> Result<Option<u32>> {
+    // A1から広い列範囲を指定する。
+    let range = format!(
+        "{}!A1:{}",
+        quote_sheet_title(sheet_title),
+        USED_RANGE_LAST_COL
+    );
+    // Values取得用URLを構築する。
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}",
+        spreadsheet_id,
+        urlencoding::encode(&range)
+    );
+    // HTTPリクエストを実行し、成功レスポンスへ正規化する。
+    let resp = http.get(url).bearer_auth(token).send().await?;
+    let resp = ensure_success(resp).await?;
+    let resp = resp.json::<ValuesGetResp>().await?;
+    Ok(last_used_row(&resp.values))
 }
 
 /// 既存行数カウントに使うValuesレスポンス。
@@ -74,7 +188,13 @@ pub async fn count_existing_rows_in_col(
     start_row: u32,
 ) -> Result<u32> {
     // 読み取り範囲をA1形式で組み立てる。
-    let range = format!("{}!{}{}:{}", sheet_title, col, start_row, col);
+    let range = format!(
+        "{}!{}{}:{}",
+        quote_sheet_title(sheet_title),
+        col,
+        start_row,
+        col
+    );
     // Values取得用URLを構築する。
     let url = format!(
         "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}",
@@ -100,6 +220,477 @@ pub async fn count_existing_rows_in_col(
     Ok(n)
 }
 
+/// 経費列のどれかが埋まっている行を使用済みとみなし、先頭から連続する行数を数える。
+///
+/// 使い分けは`GeneralExpenseCfg::count_any_column`を参照。
+pub async fn count_existing_rows_in_cols(
+    http: &Client,
+    token: &str,
+    spreadsheet_id: &str,
+    sheet_title: &str,
+    cols: &[&str],
+    start_row: u32,
+) -> Result<u32> {
+    // 列名を列番号へ変換し、すべてを含むブロックを一度に読む。
+    let indices = cols
+        .iter()
+        .map(|c| column_index(c).ok_or_else(|| anyhow!("invalid column: {c:?}")))
+        .collect::<Result<Vec<u32>>>()?;
+    let (Some(&first), Some(&last)) = (indices.iter().min(), indices.iter().max()) else {
+        return Ok(0);
+    };
+    let range = format!(
+        "{}!{}{}:{}",
+        quote_sheet_title(sheet_title),
+        column_letter(first),
+        start_row,
+        column_letter(last)
+    );
+    // Values取得用URLを構築する。
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}",
+        spreadsheet_id,
+        urlencoding::encode(&range)
+    );
+    // HTTPリクエストを実行し、成功レスポンスへ正規化する。
+    let resp = http.get(url).bearer_auth(token).send().await?;
+    let resp = ensure_success(resp).await?;
+    let resp = resp.json::<ValuesGetResp>().await?;
+
+    let offsets: Vec<usize> = indices.iter().map(|i| (i - first) as usize).collect();
+    Ok(count_used_rows(&resp.values, &offsets))
+}
+
+/// ブロックの先頭から、指定位置の列のどれかが埋まっている行が続く数を返す。
+///
+/// `offsets`はブロックの先頭列からの位置。末尾の空セルは省かれて返るため、足りない列は空とみなす。
+pub fn count_used_rows(values: &[Vec<String>], offsets: &[usize]) -> u32 {
+    values
+        .iter()
+        .take_while(|row| {
+            offsets
+                .iter()
+                .any(|&i| row.get(i).is_some_and(|v| !v.trim().is_empty()))
+        })
+        .count() as u32
+}
+
+/// 見出しラベルに一致する行番号（1始まり）を探す。
+///
+/// `values` は `first_row` 行目から始まる1列分の値とみなす。
+pub fn locate_header_row(values: &[Vec<String>], label: &str, first_row: u32) -> Option<u32> {
+    // 前後の空白を無視してラベルと比較する。
+    let label = label.trim();
+    values
+        .iter()
+        .position(|row| row.first().map(|s| s.trim()) == Some(label))
+        .map(|i| first_row + i as u32)
+}
+
+/// 指定列を上から走査し、見出しラベルのある行番号を返す。
+pub async fn find_header_row(
+    http: &Client,
+    token: &str,
+    spreadsheet_id: &str,
+    sheet_title: &str,
+    col: &str,
+    label: &str,
+) -> Result<Option<u32>> {
+    // 列全体をA1形式で指定する。
+    let range = format!("{}!{}1:{}", quote_sheet_title(sheet_title), col, col);
+    // Values取得用URLを構築する。
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}",
+        spreadsheet_id,
+        urlencoding::encode(&range)
+    );
+    // HTTPリクエストを実行し、成功レスポンスへ正規化する。
+    let resp = http.get(url).bearer_auth(token).send().await?;
+    let resp = ensure_success(resp).await?;
+    let resp = resp.json::<ValuesGetResp>().await?;
+
+    // 1行目から始まる値として見出しを探す。
+    Ok(locate_header_row(&resp.values, label, 1))
+}
+
+/// 名前付き範囲の一覧レスポンス。
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NamedRangesResp {
+    #[serde(default)]
+    named_ranges: Vec<NamedRange>,
+}
+
+/// 名前付き範囲1件。
+#[derive(Debug, Deserialize)]
+struct NamedRange {
+    name: String,
+    range: GridRange,
+}
+
+/// 0始まりのグリッド範囲（0の項目はAPIが省略する）。
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct GridRange {
+    start_row_index: u32,
+    end_row_index: Option<u32>,
+    start_column_index: u32,
+    end_column_index: Option<u32>,
+}
+
+/// 名前付き範囲が指す領域（行は1始まり、列はA1形式）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedRangeArea {
+    /// 領域の先頭行。
+    pub start_row: u32,
+    /// 領域の先頭列。
+    pub first_col: String,
+    /// 領域の末尾列（列全体が無指定なら None）。
+    pub last_col: Option<String>,
+}
+
+/// A1形式の列名を0始まりの列番号へ変換する。
+pub fn column_index(col: &str) -> Option<u32> {
+    let col = col.trim();
+    if col.is_empty() || !col.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let n = col
+        .to_ascii_uppercase()
+        .bytes()
+        .fold(0u32, |n, b| n * 26 + u32::from(b - b'A' + 1));
+    Some(n - 1)
+}
+
+/// 0始まりの列番号をA1形式の列名へ変換する。
+pub fn column_letter(mut idx: u32) -> String {
+    let mut letters = vec![];
+    loop {
+        letters.push(char::from(b'A' + (idx % 26) as u8));
+        if idx < 26 {
+            break;
+        }
+        idx = idx / 26 - 1;
+    }
+    letters.iter().rev().collect()
+}
+
+/// 名前付き範囲の一覧から指定名の領域を取り出す。
+fn locate_named_range(resp: &NamedRangesResp, name: &str) -> Option<NamedRangeArea> {
+    resp.named_ranges
+        .iter()
+        .find(|r| r.name == name)
+        .map(|r| NamedRangeArea {
+            start_row: r.range.start_row_index + 1,
+            first_col: column_letter(r.range.start_column_index),
+            // 終端は排他的なので1つ手前の列が末尾になる。
+            last_col: r
+                .range
+                .end_column_index
+                .filter(|&end| end > 0)
+                .map(|end| column_letter(end - 1)),
+        })
+}
+
+/// スプレッドシートの名前付き範囲を取得し、指定名の領域を返す。
+pub async fn resolve_named_range(
+    http: &Client,
+    token: &str,
+    spreadsheet_id: &str,
+    name: &str,
+) -> Result<Option<NamedRangeArea>> {
+    // 名前付き範囲だけを取得するURLを組み立てる。
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}?fields=namedRanges(name,range)",
+        spreadsheet_id
+    );
+    // HTTPリクエストを実行し、成功レスポンスへ正規化する。
+    let resp = http.get(url).bearer_auth(token).send().await?;
+    let resp = ensure_success(resp).await?;
+    let resp = resp.json::<NamedRangesResp>().await?;
+    Ok(locate_named_range(&resp, name))
+}
+
+/// 結合セル取得用のレスポンス。
+#[derive(Debug, Deserialize)]
+struct MergesResp {
+    #[serde(default)]
+    sheets: Vec<SheetMergesEntry>,
+}
+
+/// シート1枚分の結合セル。
+#[derive(Debug, Deserialize)]
+struct SheetMergesEntry {
+    #[serde(default)]
+    properties: Option<SheetProps>,
+    #[serde(default)]
+    merges: Vec<GridRange>,
+}
+
+/// シート上の結合セルの一覧。
+#[derive(Clone, Debug, Default)]
+pub struct SheetMerges {
+    ranges: Vec<GridRange>,
+}
+
+impl SheetMerges {
+    /// 指定した名前のシートの結合セルをレスポンスから取り出す。
+    fn from_response(resp: MergesResp, sheet_title: &str) -> Self {
+        Self {
+            ranges: resp
+                .sheets
+                .into_iter()
+                .find(|s| s.properties.as_ref().is_none_or(|p| p.title == sheet_title))
+                .map(|s| s.merges)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// 指定した列にかかる結合セルがあるか。
+    pub fn merges_column(&self, col: &str) -> bool {
+        let Some(col) = column_index(col) else {
+            return false;
+        };
+        self.ranges
+            .iter()
+            .any(|m| col >= m.start_column_index && m.end_column_index.is_none_or(|end| col < end))
+    }
+
+    /// 指定セル（行は1始まり）が結合セルの左上以外にあれば、書き込むべき左上のセル番地を返す。
+    pub fn anchor_for(&self, row: u32, col: &str) -> Option<String> {
+        let (row, col) = (row.checked_sub(1)?, column_index(col)?);
+        self.ranges
+            .iter()
+            .find(|m| {
+                // 終端は排他的で、省略は端までを表す。
+                row >= m.start_row_index
+                    && m.end_row_index.is_none_or(|end| row < end)
+                    && col >= m.start_column_index
+                    && m.end_column_index.is_none_or(|end| col < end)
+            })
+            .filter(|m| (m.start_row_index, m.start_column_index) != (row, col))
+            .map(|m| {
+                format!(
+                    "{}{}",
+                    column_letter(m.start_column_index),
+                    m.start_row_index + 1
+                )
+            })
+    }
+}
+
+/// 指定した名前のシートの結合セルを取得する。
+pub async fn get_merges(
+    http: &Client,
+    token: &str,
+    spreadsheet_id: &str,
+    sheet_title: &str,
+) -> Result<SheetMerges> {
+    // シート名と結合セルだけを取得するURLを組み立てる。
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}?fields=sheets(properties(title),merges)",
+        spreadsheet_id
+    );
+    let resp = http.get(url).bearer_auth(token).send().await?;
+    let resp = ensure_success(resp).await?;
+    let resp = resp.json::<MergesResp>().await?;
+    Ok(SheetMerges::from_response(resp, sheet_title))
+}
+
+/// 見出し行を起点としたappend用のレンジを組み立てる。
+pub fn anchored_append_range(
+    sheet_title: &str,
+    first_col: &str,
+    last_col: &str,
+    header_row: u32,
+) -> String {
+    format!(
+        "{}!{}{}:{}{}",
+        quote_sheet_title(sheet_title),
+        first_col,
+        header_row,
+        last_col,
+        header_row
+    )
+}
+
+/// 確定時に書き込まれる経費行の見積もり。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WritePlan {
+    /// 書き込み先のシート名。
+    pub sheet_title: String,
+    /// 最初に書き込む行番号。
+    pub first_row: u32,
+    /// 書き込む行数。
+    pub rows: u32,
+    /// 経費行の先頭列。
+    pub first_col: String,
+    /// 経費行の末尾列。
+    pub last_col: String,
+}
+
+impl WritePlan {
+    /// 書き込まれるA1レンジを返す。
+    pub fn range(&self) -> String {
+        let last_row = self.first_row + self.rows.saturating_sub(1);
+        format!(
+            "{}!{}{}:{}{}",
+            quote_sheet_title(&self.sheet_title),
+            self.first_col,
+            self.first_row,
+            self.last_col,
+            last_row
+        )
+    }
+
+    /// ステータス表示用の説明文を返す。
+    pub fn describe(&self) -> String {
+        let noun = if self.rows == 1 { "row" } else { "rows" };
+        format!(
+            "Will write {} {noun} starting at row {} into {} ({})",
+            self.rows,
+            self.first_row,
+            self.sheet_title,
+            self.range()
+        )
+    }
+}
+
+/// appendAPIのレスポンス（書き込まれたレンジだけを使う）。
+#[derive(Debug, Default, Deserialize)]
+struct AppendResp {
+    #[serde(default)]
+    updates: Option<AppendUpdates>,
+}
+
+/// appendAPIで更新されたレンジの情報。
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppendUpdates {
+    updated_range: Option<String>,
+}
+
+/// appendAPIで表の最終行の後ろへ行を追加し、書き込まれたレンジを返す。
+pub async fn values_append(
+    http: &Client,
+    token: &str,
+    spreadsheet_id: &str,
+    range: &str,
+    values: Vec<Vec<serde_json::Value>>,
+) -> Result<Option<String>> {
+    // appendAPIのURLを作成する（テンプレートの体裁を保つため行は挿入しない）。
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}:append?valueInputOption=USER_ENTERED&insertDataOption=OVERWRITE",
+        spreadsheet_id,
+        urlencoding::encode(range)
+    );
+    // 追加する値をリクエストボディにする。
+    let body = serde_json::json!({ "values": values });
+
+    // HTTPリクエストを実行して成功を確認する。
+    let resp = http.post(url).bearer_auth(token).json(&body).send().await?;
+    let resp = ensure_success(resp).await?;
+    // 書き込まれたレンジが分かれば返す（読めなくても書き込み自体は成功している）。
+    let resp = resp.json::<AppendResp>().await.unwrap_or_default();
+    Ok(resp.updates.and_then(|u| u.updated_range))
+}
+
+/// 指定レンジの先頭行を表示形式の文字列で取得する（空なら空のVec）。
+pub async fn get_row_values(
+    http: &Client,
+    token: &str,
+    spreadsheet_id: &str,
+    range: &str,
+) -> Result<Vec<String>> {
+    // Values取得用URLを構築する。
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}",
+        spreadsheet_id,
+        urlencoding::encode(range)
+    );
+    let resp = http.get(url).bearer_auth(token).send().await?;
+    let resp = ensure_success(resp).await?;
+    let resp = resp.json::<ValuesGetResp>().await?;
+    Ok(resp.values.into_iter().next().unwrap_or_default())
+}
+
+/// Values APIのレスポンス（書式を外した値）。
+#[derive(Debug, Default, Deserialize)]
+struct RawValuesGetResp {
+    #[serde(default)]
+    values: Vec<Vec<serde_json::Value>>,
+}
+
+/// 指定レンジの先頭行を書式なしの値で取得する（日付だけは表示形式の文字列、空なら空のVec）。
+pub async fn get_row_unformatted(
+    http: &Client,
+    token: &str,
+    spreadsheet_id: &str,
+    range: &str,
+) -> Result<Vec<serde_json::Value>> {
+    // 金額の通貨記号や桁区切りが付かないよう、書式を外した値を求める。
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}?valueRenderOption=UNFORMATTED_VALUE&dateTimeRenderOption=FORMATTED_STRING",
+        spreadsheet_id,
+        urlencoding::encode(range)
+    );
+    let resp = http.get(url).bearer_auth(token).send().await?;
+    let resp = ensure_success(resp).await?;
+    let resp = resp.json::<RawValuesGetResp>().await?;
+    Ok(resp.values.into_iter().next().unwrap_or_default())
+}
+
+/// A1形式のレンジから、シート名の部分（引用符付きのまま）と先頭セルの行番号を取り出す。
+pub fn range_sheet_and_row(range: &str) -> Option<(&str, u32)> {
+    let (sheet, cells) = range.rsplit_once('!')?;
+    let first = cells.split(':').next()?;
+    let row = first.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+    Some((sheet, row.parse().ok()?))
+}
+
+/// 見出し行の値を、列名と見出しの組へ変換する（見出しが空の列は除く）。
+pub fn header_columns(row: &[String]) -> Vec<(String, String)> {
+    row.iter()
+        .enumerate()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(i, text)| (column_letter(i as u32), text.trim().to_string()))
+        .collect()
+}
+
+/// 画像URLを `=IMAGE()` 数式として起点セルへ書き込む更新を作る。
+pub fn image_formula_update(
+    sheet_title: &str,
+    anchor_cell: &str,
+    image_url: &str,
+) -> (String, Vec<Vec<serde_json::Value>>) {
+    (
+        format!("{}!{}", quote_sheet_title(sheet_title), anchor_cell),
+        vec![vec![serde_json::Value::String(format!(
+            "=IMAGE(\"{}\")",
+            image_url
+        ))]],
+    )
+}
+
+/// 既存行数から次の行の通し番号（1始まり）を書き込む更新を作る。
+pub fn sequence_number_update(
+    sheet_title: &str,
+    seq_col: &str,
+    first_data_row: u32,
+    existing: u32,
+) -> (String, Vec<Vec<serde_json::Value>>) {
+    // 書き込み先の行は既存行の直後で、番号は既存行数+1になる。
+    (
+        format!(
+            "{}!{}{}",
+            quote_sheet_title(sheet_title),
+            seq_col,
+            first_data_row + existing
+        ),
+        vec![vec![serde_json::Value::Number((existing + 1).into())]],
+    )
+}
+
 /// バッチ更新APIのリクエストボディ。
 #[derive(Debug, Serialize)]
 struct BatchUpdateReq<'a> {
@@ -151,14 +742,499 @@ pub async fn values_batch_update(
     Ok(())
 }
 
-/// 非2xxレスポンスを構造化エラーに変換する。
-async fn ensure_success(resp: reqwest::Response) -> Result<reqwest::Response> {
-    // ステータスコードを取得する。
-    let status = resp.status();
-    if status.is_success() {
-        return Ok(resp);
+/// 複数レンジの一括取得レスポンス。
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchGetResp {
+    #[serde(default)]
+    value_ranges: Vec<BatchGetRange>,
+}
+
+/// 一括取得内の1レンジ分の値。
+#[derive(Debug, Deserialize)]
+struct BatchGetRange {
+    #[serde(default)]
+    values: Vec<Vec<serde_json::Value>>,
+}
+
+/// 書き込み確認のため、複数レンジを書き込んだ形（数式はそのまま）で取得する。
+///
+/// 戻り値はレンジと同じ順で、末尾の空セル・空行はAPIが省く。
+pub async fn values_batch_get(
+    http: &Client,
+    token: &str,
+    spreadsheet_id: &str,
+    ranges: &[String],
+) -> Result<Vec<Vec<Vec<serde_json::Value>>>> {
+    // 日付は表示形式の文字列で受け取り、数値化されたかを見分けられるようにする。
+    let query: String = ranges
+        .iter()
+        .map(|r| format!("&ranges={}", urlencoding::encode(r)))
+        .collect();
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}/values:batchGet?valueRenderOption=FORMULA&dateTimeRenderOption=FORMATTED_STRING{}",
+        spreadsheet_id, query
+    );
+    let resp = http.get(url).bearer_auth(token).send().await?;
+    let resp = ensure_success(resp).await?;
+    let resp = resp.json::<BatchGetResp>().await?;
+    Ok(resp.value_ranges.into_iter().map(|r| r.values).collect())
+}
+
+/// USER_ENTEREDで数式として解釈される先頭の文字。
+const FORMULA_PREFIXES: [char; 4] = ['=', '+', '-', '@'];
+
+/// A1形式のレンジに使えるよう、シート名を`'`で囲む（名前中の`'`は`''`にする）。
+pub fn quote_sheet_title(sheet_title: &str) -> String {
+    format!("'{}'", sheet_title.replace('\'', "''"))
+}
+
+/// 自由入力の文字列が数式として解釈されないよう、数式の先頭文字で始まれば`'`を付ける。
+///
+/// USER_ENTEREDで書き込む文字列に使う（先頭の`'`はシート上に表示されない）。
+pub fn escape_formula(text: &str) -> String {
+    if text.starts_with(FORMULA_PREFIXES) {
+        format!("'{text}")
+    } else {
+        text.to_string()
+    }
+}
+
+/// 送った更新と読み戻した値を比べ、食い違うセルを説明文で列挙する。
+pub fn write_mismatches(
+    sent: &[(String, Vec<Vec<serde_json::Value>>)],
+    read: &[Vec<Vec<serde_json::Value>>],
+) -> Vec<String> {
+    let mut mismatches = vec![];
+    for (i, (range, rows)) in sent.iter().enumerate() {
+        let got_rows = read.get(i).map(Vec::as_slice).unwrap_or(&[]);
+        for (r, row) in rows.iter().enumerate() {
+            for (c, want) in row.iter().enumerate() {
+                // 省かれた末尾のセルは空として扱う。
+                let got = got_rows
+                    .get(r)
+                    .and_then(|g| g.get(c))
+                    .unwrap_or(&serde_json::Value::Null);
+                if !cell_matches(want, got) {
+                    mismatches.push(format!("{range} [{r},{c}]: sent {want}, read back {got}"));
+                }
+            }
+        }
+    }
+    mismatches
+}
+
+/// 送った値と読み戻した値が同じ内容か判定する。
+///
+/// 数値の文字列表記の違いと、同じ日付の表示形式の違いは一致とみなす。
+fn cell_matches(want: &serde_json::Value, got: &serde_json::Value) -> bool {
+    let (want, got) = (cell_text(want), cell_text(got));
+    if want == got {
+        return true;
+    }
+    if let (Ok(a), Ok(b)) = (want.parse::<f64>(), got.parse::<f64>()) {
+        return a == b;
+    }
+    match (parse_cell_date(&want), parse_cell_date(&got)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// セルの値を比較や読み戻しに使う文字列にする。
+pub fn cell_text(v: &serde_json::Value) -> String {
+    match v {
+        serde_json::Value::Null => String::new(),
+        // 文字列扱いを示す先頭の`'`はシートに残らないので比べない。
+        serde_json::Value::String(s) => {
+            let s = s.trim();
+            s.strip_prefix('\'').unwrap_or(s).to_string()
+        }
+        other => other.to_string(),
+    }
+}
+
+/// シートで一般的な日付表記を解釈する。
+fn parse_cell_date(s: &str) -> Option<chrono::NaiveDate> {
+    ["%Y-%m-%d", "%Y/%m/%d", "%Y年%m月%d日"]
+        .iter()
+        .find_map(|fmt| chrono::NaiveDate::parse_from_str(s, fmt).ok())
+}
+
+/// 診断用にスプレッドシートのメタデータを生のJSONで取得する。
+pub async fn fetch_metadata_json(
+    http: &Client,
+    token: &str,
+    spreadsheet_id: &str,
+) -> Result<serde_json::Value> {
+    // 書式やデータを含めず、構造に関わる項目だけを取得する。
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}?fields=spreadsheetId,properties(title,locale,timeZone),sheets(properties(sheetId,title,gridProperties)),namedRanges",
+        spreadsheet_id
+    );
+    let resp = http.get(url).bearer_auth(token).send().await?;
+    let resp = ensure_success(resp).await?;
+    Ok(resp.json().await?)
+}
+
+/// 診断用に指定レンジの値を生のJSONで取得する。
+pub async fn fetch_values_json(
+    http: &Client,
+    token: &str,
+    spreadsheet_id: &str,
+    range: &str,
+) -> Result<serde_json::Value> {
+    // Values取得用URLを構築する。
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}",
+        spreadsheet_id,
+        urlencoding::encode(range)
+    );
+    let resp = http.get(url).bearer_auth(token).send().await?;
+    let resp = ensure_success(resp).await?;
+    Ok(resp.json().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// テスト用に1列分の値を組み立てる。
+    fn column(values: &[&str]) -> Vec<Vec<String>> {
+        values.iter().map(|v| vec![v.to_string()]).collect()
+    }
+
+    #[test]
+    fn test_locate_named_range_from_response() {
+        // 名前付き範囲のレスポンスから先頭行と列が求まることを検証する。
+        let json = r#"{
+            "namedRanges": [
+                {"namedRangeId": "a", "name": "Header", "range": {"sheetId": 0, "endRowIndex": 3}},
+                {"namedRangeId": "b", "name": "ExpenseTable",
+                 "range": {"sheetId": 0, "startRowIndex": 6, "endRowIndex": 40,
+                           "startColumnIndex": 1, "endColumnIndex": 6}}
+            ]
+        }"#;
+        let resp: NamedRangesResp = serde_json::from_str(json).unwrap();
+        let area = locate_named_range(&resp, "ExpenseTable").unwrap();
+        assert_eq!(
+            area,
+            NamedRangeArea {
+                start_row: 7,
+                first_col: "B".into(),
+                last_col: Some("F".into()),
+            }
+        );
+        // 省略された開始位置は0（A1）として扱う。
+        let header = locate_named_range(&resp, "Header").unwrap();
+        assert_eq!((header.start_row, header.first_col.as_str()), (1, "A"));
+        assert!(locate_named_range(&resp, "Missing").is_none());
+    }
+
+    #[test]
+    fn test_row_count_without_grid_properties() {
+        // グリッド情報が無い場合は値の件数から最終行を求めることを検証する。
+        let json = r#"{"sheets": [{"properties": {"title": "Sheet1"}}]}"#;
+        let ss: Spreadsheet = serde_json::from_str(json).unwrap();
+        assert_eq!(grid_row_count(&ss.sheets[0].properties), None);
+
+        let values = vec![
+            vec!["経費精算書".to_string()],
+            vec![],
+            vec!["2025-01-01".into()],
+        ];
+        assert_eq!(last_used_row(&values), Some(3));
+        assert_eq!(last_used_row(&[]), None);
+
+        // グリッド情報があればそちらを使う。
+        let json = r#"{"sheets": [{"properties": {"title": "Sheet1", "gridProperties": {"rowCount": 2500}}}]}"#;
+        let ss: Spreadsheet = serde_json::from_str(json).unwrap();
+        assert_eq!(grid_row_count(&ss.sheets[0].properties), Some(2500));
+    }
+
+    #[test]
+    fn test_count_used_rows_includes_continuation_lines() {
+        // 日付列が空でも他の経費列が埋まっている続き行は使用済みとして数えることを検証する。
+        let row = |cells: &[&str]| cells.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let values = vec![
+            row(&["2025-12-01", "タクシー", "2480"]),
+            row(&["", "（深夜料金）"]),
+            row(&["", "", "", "", "続き"]),
+            row(&["2025-12-03", "会議", "1200"]),
+            row(&[]),
+            row(&["2025-12-09", "後続"]),
+        ];
+        assert_eq!(count_used_rows(&values, &[0, 1, 2, 3, 4]), 4);
+        // 日付列だけで数えると続き行で止まる。
+        assert_eq!(count_used_rows(&values, &[0]), 1);
+        assert_eq!(count_used_rows(&[], &[0, 1]), 0);
+    }
+
+    #[test]
+    fn test_header_columns_maps_letters_to_text() {
+        // 見出し行の各セルが列名と組になり、空の見出しは飛ばされることを検証する。
+        let row: Vec<String> = ["No.", "日付", "", " 用途 ", "金額"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            header_columns(&row),
+            vec![
+                ("A".to_string(), "No.".to_string()),
+                ("B".to_string(), "日付".to_string()),
+                ("D".to_string(), "用途".to_string()),
+                ("E".to_string(), "金額".to_string()),
+            ]
+        );
+        let mut wide = vec![String::new(); 27];
+        wide[26] = "備考".into();
+        assert_eq!(
+            header_columns(&wide),
+            vec![("AA".to_string(), "備考".to_string())]
+        );
+        assert!(header_columns(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_column_letter() {
+        // 列番号がA1形式の列名へ変換されることを検証する。
+        assert_eq!(column_letter(0), "A");
+        assert_eq!(column_letter(25), "Z");
+        assert_eq!(column_letter(26), "AA");
+        assert_eq!(column_letter(701), "ZZ");
+        assert_eq!(column_letter(702), "AAA");
+    }
+
+    #[test]
+    fn test_escape_formula_quotes_formula_like_text() {
+        // 数式の先頭文字で始まる文字列だけに`'`が付き、読み戻した値とは一致とみなすことを検証する。
+        use serde_json::json;
+        assert_eq!(escape_formula("=HYPERLINK(\"x\")"), "'=HYPERLINK(\"x\")");
+        assert_eq!(escape_formula("+1+1"), "'+1+1");
+        assert_eq!(escape_formula("-500 返金"), "'-500 返金");
+        assert_eq!(escape_formula("@mention"), "'@mention");
+        assert_eq!(escape_formula("タクシー = 2台"), "タクシー = 2台");
+        assert_eq!(escape_formula(""), "");
+
+        let sent = vec![("Sheet1!C44".to_string(), vec![vec![json!("'+1+1")]])];
+        let read = vec![vec![vec![json!("+1+1")]]];
+        assert!(write_mismatches(&sent, &read).is_empty());
+    }
+
+    #[test]
+    fn test_write_mismatches_reports_reinterpreted_cells() {
+        // 表記の違いだけなら一致とみなし、日付が数値になったセルや欠けたセルを報告することを検証する。
+        use serde_json::json;
+        let sent = vec![
+            ("Sheet1!F3".to_string(), vec![vec![json!("山田 太郎")]]),
+            (
+                "Sheet1!B44:F44".to_string(),
+                vec![vec![
+                    json!("2025-12-19"),
+                    json!("タクシー"),
+                    json!(2400),
+                    json!("交通費"),
+                    json!(""),
+                ]],
+            ),
+        ];
+        let ok = vec![
+            vec![vec![json!("山田 太郎")]],
+            vec![vec![
+                json!("2025/12/19"),
+                json!("タクシー"),
+                json!("2400"),
+                json!("交通費"),
+            ]],
+        ];
+        assert!(write_mismatches(&sent, &ok).is_empty());
+
+        let bad = vec![
+            vec![vec![json!("山田 太郎")]],
+            vec![vec![json!(46010), json!("タクシー"), json!(2400)]],
+        ];
+        assert_eq!(
+            write_mismatches(&sent, &bad),
+            vec![
+                "Sheet1!B44:F44 [0,0]: sent \"2025-12-19\", read back 46010".to_string(),
+                "Sheet1!B44:F44 [0,3]: sent \"交通費\", read back null".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_anchor_resolution() {
+        // 結合セル内のセルが左上の起点へ解決され、起点や結合外はNoneになることを検証する。
+        let json = r#"{
+            "sheets": [{
+                "merges": [
+                    {"sheetId": 0, "startRowIndex": 6, "endRowIndex": 7,
+                     "startColumnIndex": 4, "endColumnIndex": 7},
+                    {"sheetId": 0, "startRowIndex": 9, "endRowIndex": 11,
+                     "startColumnIndex": 5, "endColumnIndex": 6}
+                ]
+            }]
+        }"#;
+        let merges = SheetMerges::from_response(serde_json::from_str(json).unwrap(), "Sheet1");
+        // E7:G7の結合。
+        assert_eq!(merges.anchor_for(7, "F"), Some("E7".into()));
+        assert_eq!(merges.anchor_for(7, "G"), Some("E7".into()));
+        assert_eq!(merges.anchor_for(7, "E"), None);
+        assert_eq!(merges.anchor_for(7, "H"), None);
+        // F10:F11の縦方向の結合。
+        assert_eq!(merges.anchor_for(11, "F"), Some("F10".into()));
+        assert_eq!(merges.anchor_for(12, "F"), None);
+        // 列にかかる結合の有無。
+        assert!(merges.merges_column("E"));
+        assert!(merges.merges_column("F"));
+        assert!(!merges.merges_column("H"));
+        // 結合が無いシート。
+        let empty = SheetMerges::from_response(
+            serde_json::from_str(r#"{"sheets": [{}]}"#).unwrap(),
+            "Sheet1",
+        );
+        assert_eq!(empty.anchor_for(7, "F"), None);
+        assert!(!empty.merges_column("F"));
+        // 複数のシートがあれば、名前の一致するシートの結合セルだけを使う。
+        let json = r#"{
+            "sheets": [
+                {"properties": {"title": "Cover"}, "merges": [
+                    {"startRowIndex": 6, "endRowIndex": 7, "startColumnIndex": 4, "endColumnIndex": 7}
+                ]},
+                {"properties": {"title": "Expenses"}}
+            ]
+        }"#;
+        let other = SheetMerges::from_response(serde_json::from_str(json).unwrap(), "Expenses");
+        assert_eq!(other.anchor_for(7, "F"), None);
+        assert_eq!(column_index("AA"), Some(26));
+    }
+
+    #[test]
+    fn test_range_sheet_and_row_and_cell_text() {
+        // 書き込んだレンジからシート名と行番号が取れ、書式なしの値が文字列になることを検証する。
+        assert_eq!(
+            range_sheet_and_row("'Tab Name'!B9:F9"),
+            Some(("'Tab Name'", 9))
+        );
+        assert_eq!(range_sheet_and_row("Sheet1!C44"), Some(("Sheet1", 44)));
+        assert_eq!(range_sheet_and_row("B9:F9"), None);
+        assert_eq!(cell_text(&serde_json::json!(2400)), "2400");
+        assert_eq!(cell_text(&serde_json::json!("タクシー")), "タクシー");
+        assert_eq!(cell_text(&serde_json::Value::Null), "");
+    }
+
+    #[test]
+    fn test_sequence_number_matches_insertion_row() {
+        // 通し番号が挿入位置（開始行からの順番）と一致することを検証する。
+        let (range, values) = sequence_number_update("Sheet1", "A", 7, 0);
+        assert_eq!(range, "'Sheet1'!A7");
+        assert_eq!(values, vec![vec![serde_json::json!(1)]]);
+
+        let (range, values) = sequence_number_update("Sheet1", "A", 7, 3);
+        assert_eq!(range, "'Sheet1'!A10");
+        assert_eq!(values, vec![vec![serde_json::json!(4)]]);
+    }
+
+    #[test]
+    fn test_quote_sheet_title() {
+        // 空白や`'`を含むシート名もA1形式で使えるよう囲み、`'`は重ねることを検証する。
+        assert_eq!(quote_sheet_title("Sheet1"), "'Sheet1'");
+        assert_eq!(quote_sheet_title("Tab Name"), "'Tab Name'");
+        assert_eq!(quote_sheet_title("Bob's"), "'Bob''s'");
+    }
+
+    #[test]
+    fn test_image_formula_update() {
+        // 起点セルへのIMAGE数式更新が組み立てられることを検証する。
+        let (range, values) =
+            image_formula_update("Sheet1", "H7", "https://drive.google.com/uc?id=abc");
+        assert_eq!(range, "'Sheet1'!H7");
+        assert_eq!(
+            values,
+            vec![vec![serde_json::Value::String(
+                "=IMAGE(\"https://drive.google.com/uc?id=abc\")".into()
+            )]]
+        );
+    }
+
+    #[test]
+    fn test_locate_header_row_found() {
+        // 見出しラベルの行番号が返ることを検証する。
+        let values = column(&["経費精算書", "", "日付", "2025-01-01"]);
+        assert_eq!(locate_header_row(&values, "日付", 1), Some(3));
+    }
+
+    #[test]
+    fn test_locate_header_row_missing() {
+        // 見出しが無い場合はNoneになることを検証する。
+        let values = column(&["経費精算書", "2025-01-01"]);
+        assert_eq!(locate_header_row(&values, "日付", 1), None);
+    }
+
+    #[test]
+    fn test_anchor_feeds_append_range() {
+        // 見出し行の検出結果がappendレンジへ反映されることを検証する。
+        let values = column(&["", "", "", "", "", " 日付 ", "2025-01-01", "例: 交通費"]);
+        let header_row = locate_header_row(&values, "日付", 1).unwrap();
+        assert_eq!(
+            anchored_append_range("Sheet1", "B", "F", header_row),
+            "'Sheet1'!B6:F6"
+        );
+    }
+
+    #[test]
+    fn test_write_plan_range_and_description() {
+        // 書き込み行数に応じてレンジと説明文が変わることを検証する。
+        let mut plan = WritePlan {
+            sheet_title: "Sheet1".into(),
+            first_row: 9,
+            rows: 3,
+            first_col: "B".into(),
+            last_col: "F".into(),
+        };
+        assert_eq!(plan.range(), "'Sheet1'!B9:F11");
+        assert_eq!(
+            plan.describe(),
+            "Will write 3 rows starting at row 9 into Sheet1 ('Sheet1'!B9:F11)"
+        );
+
+        plan.rows = 1;
+        assert_eq!(plan.range(), "'Sheet1'!B9:F9");
+        assert_eq!(
+            plan.describe(),
+            "Will write 1 row starting at row 9 into Sheet1 ('Sheet1'!B9:F9)"
+        );
+    }
+
+    #[test]
+    fn test_empty_spreadsheet_reports_template_hint() {
+        // シートが無いレスポンスが、テンプレートIDの見直しを促すエラーになることを検証する。
+        for body in [r#"{"sheets": []}"#, "{}"] {
+            let ss: Spreadsheet = serde_json::from_str(body).unwrap();
+            let err = anyhow::Error::from(first_sheet(&ss).unwrap_err()).context("commit failed");
+            let err = WorkerError::from(err);
+            assert_eq!(err.to_string(), NO_SHEETS_MESSAGE);
+        }
+    }
+
+    #[test]
+    fn test_select_sheet_by_name() {
+        // 名前指定ならそのシートを、未指定なら最初のシートを選び、無い名前はエラーになることを検証する。
+        let ss: Spreadsheet = serde_json::from_str(
+            r#"{"sheets": [{"properties": {"title": "Cover"}}, {"properties": {"title": "Expenses"}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(select_sheet(&ss, None).unwrap().properties.title, "Cover");
+        assert_eq!(
+            select_sheet(&ss, Some("Expenses"))
+                .unwrap()
+                .properties
+                .title,
+            "Expenses"
+        );
+        assert_eq!(
+            select_sheet(&ss, Some("Summary")).unwrap_err().to_string(),
+            "Sheet \"Summary\" not found in the template (sheets: Cover, Expenses)"
+        );
     }
-    // ボディ内容を文字列化してエラーメッセージへ含める。
-    let body = resp.text().await.unwrap_or_else(|_| "".into());
-    Err(anyhow!("HTTP status {status} error: {body}"))
 }
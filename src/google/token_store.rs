@@ -3,7 +3,7 @@
 use async_trait::async_trait;
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 use sha2::{Digest, Sha256};
-use std::{collections::HashMap, io::ErrorKind, path::PathBuf};
+use std::{collections::HashMap, io, io::ErrorKind, path::PathBuf};
 use tokio::{
     fs,
     io::{AsyncWriteExt, BufWriter},
@@ -87,6 +87,19 @@ impl FileTokenStorage {
             .map_err(|e| TokenStorageError::Other(e.to_string().into()))?;
         Ok(())
     }
+
+    /// 保存済みのトークンをすべて取り出し、ファイルを削除する。
+    ///
+    /// ファイルが無ければ空を返す。壊れていて読めない場合も、ファイルは削除する。
+    pub async fn take_all(&self) -> io::Result<Vec<TokenInfo>> {
+        let map = self.load_map().await.unwrap_or_default();
+        match fs::remove_file(&self.path).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(map.into_values().collect())
+    }
 }
 
 #[async_trait]
@@ -109,3 +122,29 @@ impl TokenStorage for FileTokenStorage {
         map.remove(&key)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_take_all_removes_file_and_tolerates_missing() {
+        // 保存済みのトークンを取り出してファイルを消し、無い場合は空を返すことを検証する。
+        let path =
+            std::env::temp_dir().join(format!("receipt_tui_token_{}.json", uuid::Uuid::new_v4()));
+        let storage = FileTokenStorage::new(&path);
+        let token = TokenInfo {
+            access_token: Some("access".into()),
+            refresh_token: Some("refresh".into()),
+            expires_at: None,
+            id_token: None,
+        };
+        storage.set(&["scope"], token).await.unwrap();
+
+        let tokens = storage.take_all().await.unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].refresh_token.as_deref(), Some("refresh"));
+        assert!(!path.exists());
+        assert!(storage.take_all().await.unwrap().is_empty());
+    }
+}
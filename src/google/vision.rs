@@ -0,0 +1,80 @@
+//! Cloud Vision APIのヘルパー。
+
+use anyhow::{Result, anyhow};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use reqwest::Client;
+use serde_json::{Value, json};
+
+use super::error::ensure_success;
+
+/// 文字認識に必要なOAuthスコープ（Drive/Sheetsとは別に同意を求める）。
+pub const SCOPE: &str = "https://www.googleapis.com/auth/cloud-vision";
+
+/// 画像注釈APIのエンドポイント。
+const ANNOTATE_URL: &str = "https://vision.googleapis.com/v1/images:annotate";
+
+/// 1枚の画像へ文字検出を依頼するリクエスト本文を組み立てる。
+pub fn annotate_body(image: &[u8]) -> Value {
+    json!({
+        "requests": [{
+            "image": { "content": STANDARD.encode(image) },
+            "features": [{ "type": "TEXT_DETECTION" }],
+        }]
+    })
+}
+
+/// 画像注釈APIのレスポンスから認識した全文を取り出す（文字が無ければ空文字）。
+pub fn full_text(resp: &Value) -> Result<String> {
+    let first = &resp["responses"][0];
+    // 画像単位のエラーはHTTP 200の中に入って返る。
+    if let Some(msg) = first["error"]["message"].as_str() {
+        return Err(anyhow!("vision: {msg}"));
+    }
+    Ok(first["fullTextAnnotation"]["text"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// 画像の文字を検出して全文を返す。
+///
+/// `quota_project`があれば`x-goog-user-project`として送る（ユーザー認証では課金先が必要なことがある）。
+pub async fn detect_text(
+    http: &Client,
+    token: &str,
+    quota_project: Option<&str>,
+    image: &[u8],
+) -> Result<String> {
+    let mut req = http
+        .post(ANNOTATE_URL)
+        .bearer_auth(token)
+        .json(&annotate_body(image));
+    if let Some(project) = quota_project {
+        req = req.header("x-goog-user-project", project);
+    }
+    let resp = ensure_success(req.send().await?).await?;
+    full_text(&resp.json::<Value>().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_text_reads_annotation_and_errors() {
+        // 認識した全文を取り出し、画像単位のエラーは失敗として返すことを検証する。
+        let ok = json!({"responses": [{"fullTextAnnotation": {"text": "合計 ¥1,200\n"}}]});
+        assert_eq!(full_text(&ok).unwrap(), "合計 ¥1,200\n");
+
+        let empty = json!({"responses": [{}]});
+        assert_eq!(full_text(&empty).unwrap(), "");
+
+        let err = json!({"responses": [{"error": {"code": 3, "message": "Bad image data."}}]});
+        assert!(
+            full_text(&err)
+                .unwrap_err()
+                .to_string()
+                .contains("Bad image data.")
+        );
+    }
+}
@@ -5,6 +5,9 @@ use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Clear, Paragraph},
 };
+use unicode_width::UnicodeWidthChar;
+
+use crate::theme::Theme;
 
 /// InputBox入力状態
 #[derive(Clone, Debug)]
@@ -17,6 +20,10 @@ pub struct InputBoxState {
     pub cursor: usize,
     /// 入力完了時のコールバック識別子
     pub callback_id: InputCallbackId,
+    /// 入力できる最大文字数（無制限ならNone）
+    pub max_chars: Option<usize>,
+    /// 補完候補（前方一致した最初の候補をゴーストテキストで表示する）
+    pub suggestions: Vec<String>,
 }
 
 /// 入力完了時のコールバック識別子
@@ -28,6 +35,12 @@ pub enum InputCallbackId {
     SettingsTemplateId,
     SettingsFullName,
 
+    // Main画面用
+    ExportXlsxPath,
+    ExportCsvPath,
+    RetryWithMonth,
+    JobSearch,
+
     // EditJob画面用
     EditTargetMonth,
     EditJobField(usize), // 0..4 の範囲
@@ -39,11 +52,40 @@ pub enum InputCallbackId {
     WizardFullName,
 }
 
+impl InputCallbackId {
+    /// 日付や月、通貨など、確定前に書式を検証する入力か
+    pub fn has_format(&self) -> bool {
+        matches!(
+            self,
+            Self::EditJobField(0)
+                | Self::EditJobField(2)
+                | Self::EditJobField(5)
+                | Self::EditTargetMonth
+                | Self::RetryWithMonth
+        )
+    }
+
+    /// ウィザード画面の入力か
+    pub fn is_wizard(&self) -> bool {
+        matches!(
+            self,
+            Self::WizardInputFolder
+                | Self::WizardOutputFolder
+                | Self::WizardTemplateId
+                | Self::WizardFullName
+        )
+    }
+}
+
 impl InputBoxState {
     /// 文字を挿入
     pub fn insert_char(&mut self, c: char) {
         // 文字列を一旦Vec<char>へ変換する。
         let chars: Vec<char> = self.value.chars().collect();
+        // 上限に達していれば入力を受け付けない。
+        if self.max_chars.is_some_and(|max| chars.len() >= max) {
+            return;
+        }
         // カーソル位置までの文字を取り出す。
         let mut new_chars = chars[..self.cursor].to_vec();
         // 新しい文字を挿入する。
@@ -120,16 +162,79 @@ impl InputBoxState {
         self.cursor = self.value.chars().count();
     }
 
+    /// カーソルが末尾にあるか
+    pub fn cursor_at_end(&self) -> bool {
+        self.cursor == self.value.chars().count()
+    }
+
+    /// 現在の入力に続く補完の残り部分を返す
+    pub fn completion(&self) -> Option<&str> {
+        // 未入力やカーソルが途中にある場合は補完しない。
+        if self.value.is_empty() || !self.cursor_at_end() {
+            return None;
+        }
+        // 前方一致し、入力より長い最初の候補を採用する。
+        self.suggestions
+            .iter()
+            .find(|s| s.len() > self.value.len() && s.starts_with(&self.value))
+            .map(|s| &s[self.value.len()..])
+    }
+
+    /// 補完を確定する（補完できた場合はtrue）
+    pub fn accept_suggestion(&mut self) -> bool {
+        let Some(rest) = self.completion().map(str::to_string) else {
+            return false;
+        };
+        // 上限を超える分は確定しない。
+        for c in rest.chars() {
+            if self.max_chars.is_some_and(|max| self.cursor >= max) {
+                break;
+            }
+            self.insert_char(c);
+        }
+        true
+    }
+
     /// 行全体をクリア
     pub fn clear_line(&mut self) {
         // 入力値を空にし、カーソルも先頭へ。
         self.value.clear();
         self.cursor = 0;
     }
+
+    /// 表示幅`width`の入力欄に収まる、カーソル前後の可視部分を返す
+    ///
+    /// 全角文字や絵文字は幅2として数え、カーソル記号（幅1）を含めて`width`を超えない。
+    pub fn visible_segments(&self, width: usize) -> (String, String) {
+        let chars: Vec<char> = self.value.chars().collect();
+        let cursor = self.cursor.min(chars.len());
+        let char_width = |c: &char| c.width().unwrap_or(0);
+        // カーソルの後ろに1文字分の余白を残せるよう、カーソル前の表示幅を抑える。
+        let before_budget = width.saturating_sub(2);
+        let mut start = cursor;
+        let mut used = 0;
+        while start > 0 && used + char_width(&chars[start - 1]) <= before_budget {
+            start -= 1;
+            used += char_width(&chars[start]);
+        }
+        let before: String = chars[start..cursor].iter().collect();
+        // カーソル記号の分を除いた残りの幅で、カーソル後ろの文字を詰める。
+        let mut remaining = width.saturating_sub(used + 1);
+        let mut after = String::new();
+        for c in &chars[cursor..] {
+            let w = char_width(c);
+            if w > remaining {
+                break;
+            }
+            remaining -= w;
+            after.push(*c);
+        }
+        (before, after)
+    }
 }
 
 /// InputBoxをポップアップとして描画
-pub fn render_input_box(f: &mut Frame, state: &InputBoxState) {
+pub fn render_input_box(f: &mut Frame, state: &InputBoxState, theme: &Theme) {
     // 中央に配置されたポップアップ領域を計算する。
     let popup_area = centered_popup(f.area(), 70, 7);
 
@@ -140,7 +245,7 @@ pub fn render_input_box(f: &mut Frame, state: &InputBoxState) {
     let block = Block::default()
         .borders(Borders::ALL)
         .title("Input")
-        .style(Style::default().bg(Color::DarkGray));
+        .style(Style::default().bg(theme.popup_bg));
     f.render_widget(block, popup_area);
 
     // 内部レイアウト（プロンプト + 入力フィールド + ヘルプ）を定義する。
@@ -155,59 +260,51 @@ pub fn render_input_box(f: &mut Frame, state: &InputBoxState) {
         ])
         .split(popup_area);
 
-    // プロンプトメッセージを描画する。
-    let prompt_widget = Paragraph::new(state.prompt.clone()).style(
+    // プロンプトメッセージを描画する（上限があれば文字数を併記する）。
+    let prompt = match state.max_chars {
+        Some(max) => format!("{} ({}/{})", state.prompt, state.value.chars().count(), max),
+        None => state.prompt.clone(),
+    };
+    let prompt_widget = Paragraph::new(prompt).style(
         Style::default()
-            .fg(Color::Cyan)
+            .fg(theme.prompt_fg)
             .add_modifier(Modifier::BOLD),
     );
     f.render_widget(prompt_widget, inner_layout[0]);
 
-    // 入力値の表示（横スクロール対応）に備える。
+    // 表示幅に収まる範囲を切り出し（横スクロール対応）、カーソル位置に|を挿入する。
     let display_width = inner_layout[1].width as usize;
-    // カーソル位置が表示幅を超えた場合のスクロール量を算出する。
-    let scroll_offset = if state.cursor > display_width.saturating_sub(2) {
-        state.cursor.saturating_sub(display_width - 2)
-    } else {
-        0
-    };
-
-    // 現在の入力値を可視範囲に切り出す。
-    let chars: Vec<char> = state.value.chars().collect();
-    let visible_text: String = chars
-        .iter()
-        .skip(scroll_offset)
-        .take(display_width)
-        .collect();
-
-    // カーソル位置を視覚的に表現（|を挿入）する。
-    let cursor_pos_in_visible = state.cursor.saturating_sub(scroll_offset);
-    let visible_with_cursor = if cursor_pos_in_visible <= visible_text.chars().count() {
-        let visible_chars: Vec<char> = visible_text.chars().collect();
-        let before: String = visible_chars[..cursor_pos_in_visible.min(visible_chars.len())]
-            .iter()
-            .collect();
-        let after: String = visible_chars[cursor_pos_in_visible.min(visible_chars.len())..]
-            .iter()
-            .collect();
-        format!("{}|{}", before, after)
-    } else {
-        format!("{}|", visible_text)
-    };
+    let (before, after) = state.visible_segments(display_width);
+    let visible_with_cursor = format!("{}|{}", before, after);
 
-    // 文字列とカーソルを含む入力欄を描画する。
-    let input_widget = Paragraph::new(visible_with_cursor).style(Style::default().fg(Color::Green));
+    // 文字列とカーソルを含む入力欄を描画する（補完候補は薄く続ける）。
+    let completion = state.completion();
+    let input_line = Line::from(vec![
+        Span::raw(visible_with_cursor),
+        Span::styled(
+            completion.unwrap_or_default().to_string(),
+            Style::default()
+                .fg(theme.hint_fg)
+                .add_modifier(Modifier::DIM),
+        ),
+    ]);
+    let input_widget = Paragraph::new(input_line).style(Style::default().fg(theme.input_fg));
     f.render_widget(input_widget, inner_layout[1]);
 
     // ヘルプテキストを描画する。
-    let help = Paragraph::new("Enter=確定 | ESC=キャンセル | Ctrl+U=クリア")
-        .style(Style::default().fg(Color::Gray))
+    let help_text = if completion.is_some() {
+        "Tab=補完 | Enter=確定 | ESC=キャンセル | Ctrl+U=クリア"
+    } else {
+        "Enter=確定 | ESC=キャンセル | Ctrl+U=クリア"
+    };
+    let help = Paragraph::new(help_text)
+        .style(Style::default().fg(theme.hint_fg))
         .alignment(Alignment::Center);
     f.render_widget(help, inner_layout[3]);
 }
 
 /// 中央配置のポップアップ領域を計算
-fn centered_popup(area: Rect, width_percent: u16, height: u16) -> Rect {
+pub fn centered_popup(area: Rect, width_percent: u16, height: u16) -> Rect {
     // 縦方向の余白を作り、中央行を取り出す。
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -228,3 +325,144 @@ fn centered_popup(area: Rect, width_percent: u16, height: u16) -> Rect {
         ])
         .split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// テスト用の入力ボックスを作る。
+    fn input_box(value: &str, max_chars: Option<usize>) -> InputBoxState {
+        InputBoxState {
+            prompt: "Reason:".into(),
+            value: value.into(),
+            cursor: value.chars().count(),
+            callback_id: InputCallbackId::EditJobField(1),
+            max_chars,
+            suggestions: vec![],
+        }
+    }
+
+    #[test]
+    fn test_insert_char_stops_at_max_chars() {
+        // 上限に達した後の入力が無視されることを検証する。
+        let mut state = input_box("交通", Some(3));
+        state.insert_char('費');
+        assert_eq!(state.value, "交通費");
+        state.insert_char('x');
+        assert_eq!(state.value, "交通費");
+        assert_eq!(state.cursor, 3);
+
+        // 削除すれば再び入力できる。
+        state.backspace();
+        state.insert_char('x');
+        assert_eq!(state.value, "交通x");
+    }
+
+    #[test]
+    fn test_insert_char_without_limit() {
+        // 上限が無ければ入力が制限されないことを検証する。
+        let mut state = input_box("abc", None);
+        state.insert_char('d');
+        assert_eq!(state.value, "abcd");
+    }
+
+    #[test]
+    fn test_completion_matches_prefix_at_end() {
+        // 前方一致した候補の残りが補完され、確定できることを検証する。
+        let mut state = InputBoxState {
+            suggestions: vec!["交通費".into(), "交際費".into()],
+            ..input_box("交際", None)
+        };
+        assert_eq!(state.completion(), Some("費"));
+        assert!(state.accept_suggestion());
+        assert_eq!(state.value, "交際費");
+        assert_eq!(state.cursor, 3);
+        // 候補と完全一致したら補完は出ない。
+        assert_eq!(state.completion(), None);
+        assert!(!state.accept_suggestion());
+    }
+
+    #[test]
+    fn test_completion_hidden_when_not_applicable() {
+        // 未入力、カーソルが途中、一致なしの場合は補完しないことを検証する。
+        let mut state = InputBoxState {
+            suggestions: vec!["Taxi".into()],
+            ..input_box("", None)
+        };
+        assert_eq!(state.completion(), None);
+        state.insert_char('T');
+        assert_eq!(state.completion(), Some("axi"));
+        state.move_home();
+        assert_eq!(state.completion(), None);
+        state.move_end();
+        state.insert_char('r');
+        assert_eq!(state.completion(), None);
+    }
+
+    #[test]
+    fn test_accept_suggestion_respects_max_chars() {
+        // 補完でも最大文字数を超えないことを検証する。
+        let mut state = InputBoxState {
+            suggestions: vec!["Taxi fare".into()],
+            ..input_box("Ta", Some(4))
+        };
+        assert!(state.accept_suggestion());
+        assert_eq!(state.value, "Taxi");
+    }
+
+    #[test]
+    fn test_editing_with_emoji() {
+        // 絵文字も1文字として挿入・削除・カーソル移動できることを検証する。
+        let mut state = input_box("🍣", None);
+        state.insert_char('👍');
+        assert_eq!(state.value, "🍣👍");
+        assert_eq!(state.cursor, 2);
+        state.move_left();
+        state.insert_char('x');
+        assert_eq!(state.value, "🍣x👍");
+        state.backspace();
+        state.delete();
+        assert_eq!(state.value, "🍣");
+        assert_eq!(state.cursor, 1);
+        state.move_right();
+        assert_eq!(state.cursor, 1);
+        state.move_home();
+        state.delete();
+        assert_eq!(state.value, "");
+    }
+
+    #[test]
+    fn test_visible_segments_account_for_display_width() {
+        // 幅2の文字を含んでも、カーソル記号込みで表示幅に収まることを検証する。
+        use unicode_width::UnicodeWidthStr;
+        let state = input_box("🍣🍣🍣🍣🍣🍣", None);
+        let (before, after) = state.visible_segments(10);
+        assert_eq!(before, "🍣🍣🍣🍣");
+        assert_eq!(after, "");
+        assert!(before.width() < 10);
+
+        // カーソルが先頭なら後ろの文字を幅の許す限り表示する。
+        let mut state = input_box("🍣a🍣b🍣", None);
+        state.move_home();
+        let (before, after) = state.visible_segments(6);
+        assert_eq!(before, "");
+        assert_eq!(after, "🍣a🍣");
+        assert!(after.width() < 6);
+
+        // 途中にカーソルがある場合も合計が幅を超えない。
+        state.cursor = 3;
+        let (before, after) = state.visible_segments(6);
+        assert_eq!(before, "a🍣");
+        assert_eq!(after, "b");
+        assert!(before.width() + 1 + after.width() <= 6);
+    }
+
+    #[test]
+    fn test_visible_segments_ascii_fits_width() {
+        // 半角のみでもカーソル記号の分だけはみ出さないことを検証する。
+        let state = input_box("abcdefghij", None);
+        let (before, after) = state.visible_segments(8);
+        assert_eq!(before, "efghij");
+        assert_eq!(before.chars().count() + 1 + after.chars().count(), 7);
+    }
+}
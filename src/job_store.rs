@@ -0,0 +1,178 @@
+//! 読み込んだジョブと編集内容を保存し、再起動後に復元する。
+//!
+//! 設定ファイルと同じディレクトリの `jobs.json` に、ジョブ一覧をそのまま書き出す。
+//! プロファイルを使う場合は、入力フォルダが違うので `jobs_<プロファイル名>.json` に分けて保存する。
+
+use anyhow::Result;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::jobs::{Job, JobStatus};
+
+/// 保存ファイルの名前。
+pub const JOBS_FILE_NAME: &str = "jobs.json";
+
+/// 設定ファイルと同じディレクトリにある保存ファイルのパスを返す（プロファイルごとに別のファイル）。
+pub fn path_for(cfg_path: &Path, profile: Option<&str>) -> PathBuf {
+    match profile.filter(|name| !name.is_empty()) {
+        Some(name) => {
+            // ファイル名に使えない文字は`_`へ置き換える。
+            let name: String = name
+                .chars()
+                .map(|c| {
+                    if c.is_alphanumeric() || c == '-' || c == '_' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            cfg_path.with_file_name(format!("jobs_{name}.json"))
+        }
+        None => cfg_path.with_file_name(JOBS_FILE_NAME),
+    }
+}
+
+/// 保存済みのジョブを読み込む（無い・壊れている場合は空）。
+pub fn load(path: &Path) -> Vec<Job> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return vec![],
+        Err(e) => {
+            tracing::warn!("cannot read {}: {e}", path.display());
+            return vec![];
+        }
+    };
+    match serde_json::from_str::<Vec<Job>>(&content) {
+        Ok(jobs) => jobs.into_iter().map(restore).collect(),
+        Err(e) => {
+            // 壊れたファイルで起動を妨げず、次の保存で上書きする。
+            tracing::warn!("ignoring unreadable {}: {e}", path.display());
+            vec![]
+        }
+    }
+}
+
+/// ジョブ一覧を保存する。
+pub fn save(path: &Path, jobs: &[Job]) -> Result<()> {
+    let s = serde_json::to_string_pretty(jobs)?;
+    fs::write(path, s)?;
+    Ok(())
+}
+
+/// 読み込んだジョブを再開できる状態に戻す。
+fn restore(mut job: Job) -> Job {
+    // IDは保存せず、DriveファイルIDから導出し直す。
+    job.id = Job::id_for(&job.drive_file_id);
+    // 処理途中だったジョブは、Workerが引き継がないため編集待ちに戻す。
+    if matches!(
+        job.status,
+        JobStatus::Queued
+            | JobStatus::WritingSheet
+            | JobStatus::ExportingPdf
+            | JobStatus::UploadingPdf
+    ) {
+        job.status = JobStatus::WaitingUserFix;
+    }
+    job
+}
+
+/// Driveから取得したジョブへ保存済みの内容を引き継ぐ。
+///
+/// 戻り値は引き継いだ一覧と、今回の一覧に無かった保存済みジョブ。
+pub fn reconcile(saved: Vec<Job>, fresh: Vec<Job>) -> (Vec<Job>, Vec<Job>) {
+    let mut saved = saved;
+    let jobs = fresh
+        .into_iter()
+        .map(|f| {
+            match saved
+                .iter()
+                .position(|s| s.drive_file_id == f.drive_file_id)
+            {
+                // ファイル名は変わっている可能性があるのでDrive側に合わせる。
+                Some(i) => Job {
+                    filename: f.filename,
+                    mime_type: f.mime_type,
+                    ..saved.swap_remove(i)
+                },
+                None => f,
+            }
+        })
+        .collect();
+    (jobs, saved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 入力済みのジョブを作る。
+    fn edited(drive_file_id: &str, reason: &str) -> Job {
+        let mut job = Job::new(drive_file_id.into(), format!("{drive_file_id}.jpg"));
+        job.status = JobStatus::WaitingUserFix;
+        job.fields.reason = reason.into();
+        job
+    }
+
+    #[test]
+    fn test_path_for_separates_profiles() {
+        // プロファイルごとに別の保存ファイルになり、使えない文字は置き換わることを検証する。
+        let cfg = Path::new("work/config.toml");
+        assert_eq!(path_for(cfg, None), Path::new("work/jobs.json"));
+        assert_eq!(path_for(cfg, Some("")), Path::new("work/jobs.json"));
+        assert_eq!(path_for(cfg, Some("me")), Path::new("work/jobs_me.json"));
+        assert_eq!(
+            path_for(cfg, Some("a/b c")),
+            Path::new("work/jobs_a_b_c.json")
+        );
+    }
+
+    #[test]
+    fn test_reconcile_keeps_edits_drops_missing_adds_new() {
+        // Driveに残るジョブは編集内容を引き継ぎ、消えたものは残りとして返し、新規はそのまま入ることを検証する。
+        let saved = vec![edited("a", "タクシー"), edited("gone", "会議費")];
+        let fresh = vec![
+            Job::new("new".into(), "new.jpg".into()),
+            Job::new("a".into(), "renamed.jpg".into()),
+        ];
+
+        let (jobs, leftover) = reconcile(saved, fresh);
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].drive_file_id, "new");
+        assert_eq!(jobs[0].fields.reason, "");
+        assert_eq!(jobs[1].fields.reason, "タクシー");
+        assert_eq!(jobs[1].filename, "renamed.jpg");
+        assert_eq!(leftover.len(), 1);
+        assert_eq!(leftover[0].drive_file_id, "gone");
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        // 保存した内容が読み戻せ、処理途中の状態は編集待ちに戻ることを検証する。
+        let path =
+            std::env::temp_dir().join(format!("receipt_tui_jobs_{}.json", uuid::Uuid::new_v4()));
+        let mut writing = edited("b", "備品");
+        writing.status = JobStatus::WritingSheet;
+        let mut failed = edited("c", "交通費");
+        failed.status = JobStatus::Error("quota".into());
+        failed.failed_month = Some("2025-13".into());
+        save(&path, &[edited("a", "タクシー"), writing, failed]).unwrap();
+
+        let jobs = load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(jobs.len(), 3);
+        assert_eq!(jobs[0].id, Job::id_for("a"));
+        assert_eq!(jobs[0].fields.reason, "タクシー");
+        assert!(matches!(jobs[1].status, JobStatus::WaitingUserFix));
+        assert!(matches!(&jobs[2].status, JobStatus::Error(r) if r == "quota"));
+        assert_eq!(jobs[2].failed_month.as_deref(), Some("2025-13"));
+
+        // 無いファイルや壊れたファイルは空として扱う。
+        assert!(load(&path).is_empty());
+        fs::write(&path, "{not json").unwrap();
+        assert!(load(&path).is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,24 +1,201 @@
 //! ジョブと領収書入力項目のモデル。
 
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
+use crate::config::{CategoryRule, RequiredField};
+
 /// 1行分の領収書入力項目。
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ReceiptFields {
     /// 支払日（ISO形式: YYYY-MM-DD）。
     pub date_ymd: String, // "2025-12-19"
     /// 用途/摘要。
     pub reason: String,
-    /// 金額（円）。
+    /// 金額（`currency`の単位、既定は円）。
     pub amount_yen: i64,
+    /// 通貨コード（空なら設定の既定通貨）。
+    #[serde(default)]
+    pub currency: String,
     /// テンプレートが期待する勘定科目。
     pub category: String,
     /// 備考（任意）。
     pub note: String,
 }
 
+impl ReceiptFields {
+    /// 編集画面で扱う入力項目の数。
+    pub const FIELD_COUNT: usize = 6;
+
+    /// 有効な通貨コードを返す（未設定なら`default`）。
+    pub fn currency_or<'a>(&'a self, default: &'a str) -> &'a str {
+        if self.currency.trim().is_empty() {
+            default
+        } else {
+            self.currency.trim()
+        }
+    }
+
+    /// 編集画面のフィールド番号に対応する値を文字列で返す。
+    pub fn field_value(&self, idx: usize) -> String {
+        match idx {
+            0 => self.date_ymd.clone(),
+            1 => self.reason.clone(),
+            2 => self.amount_yen.to_string(),
+            3 => self.category.clone(),
+            4 => self.note.clone(),
+            5 => self.currency.clone(),
+            _ => String::new(),
+        }
+    }
+
+    /// 編集画面のフィールド番号に対応する値を更新する。
+    pub fn set_field_value(&mut self, idx: usize, value: String) {
+        match idx {
+            0 => self.date_ymd = value,
+            1 => self.reason = value,
+            // 金額は整数として解釈できなければ0にする（入力欄では事前に弾く）。
+            2 => self.amount_yen = value.trim().parse().unwrap_or(0),
+            3 => self.category = value,
+            4 => self.note = value,
+            // 通貨コードは大文字に揃える（空なら設定の既定通貨）。
+            5 => self.currency = value.trim().to_ascii_uppercase(),
+            _ => {}
+        }
+    }
+
+    /// 編集画面のフィールド番号に対応する表示名を返す。
+    pub fn field_name(idx: usize) -> &'static str {
+        match idx {
+            0 => "Date",
+            1 => "Reason",
+            2 => "Amount",
+            3 => "Category",
+            4 => "Note",
+            5 => "Currency",
+            _ => "",
+        }
+    }
+
+    /// シートから読み戻した1行（日付・用途・金額・勘定科目・備考の順）を入力項目に変換する。
+    ///
+    /// 日付は`/`区切りも受け付け、金額は通貨記号と桁区切りを除いた数値として解釈する（整数以外はエラー）。
+    pub fn from_sheet_row(row: &[String]) -> anyhow::Result<Self> {
+        let cell = |idx: usize| row.get(idx).map(|s| s.trim()).unwrap_or("").to_string();
+        // 日付は解釈できればYYYY-MM-DDへ揃え、できなければそのまま残す。
+        let date_raw = cell(0);
+        let date_ymd = ["%Y-%m-%d", "%Y/%m/%d"]
+            .iter()
+            .find_map(|fmt| chrono::NaiveDate::parse_from_str(&date_raw, fmt).ok())
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or(date_raw);
+        // 金額は桁区切りと前後の通貨記号だけを除き、残りを数値として解釈する。
+        let amount_raw = cell(2);
+        let cleaned: String = amount_raw
+            .chars()
+            .filter(|c| *c != ',' && !c.is_whitespace())
+            .collect();
+        let number = cleaned
+            .trim_start_matches(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.')))
+            .trim_end_matches('円');
+        let amount_yen = if number.is_empty() {
+            0
+        } else {
+            let invalid = || anyhow::anyhow!("invalid amount {amount_raw:?} in sheet row");
+            let value: f64 = number.parse().map_err(|_| invalid())?;
+            // 端数のある金額を丸めたり桁を詰めたりすると値が変わるため、取り込まない。
+            if !value.is_finite() || value.fract() != 0.0 {
+                return Err(invalid());
+            }
+            value as i64
+        };
+        Ok(Self {
+            date_ymd,
+            reason: cell(1),
+            amount_yen,
+            category: cell(3),
+            note: cell(4),
+            currency: String::new(),
+        })
+    }
+
+    /// 値が異なる入力項目のフィールド番号を返す。
+    pub fn differing_fields(&self, other: &ReceiptFields) -> Vec<usize> {
+        (0..Self::FIELD_COUNT)
+            .filter(|&idx| self.field_value(idx) != other.field_value(idx))
+            .collect()
+    }
+
+    /// 直前に確定した内容から、空欄の勘定科目と備考を引き継ぐ。
+    pub fn inherit_from(&mut self, last: &ReceiptFields) {
+        if self.category.is_empty() {
+            self.category = last.category.clone();
+        }
+        if self.note.is_empty() {
+            self.note = last.note.clone();
+        }
+    }
+
+    /// 選択中の勘定科目のルールで備考が必須か判定する。
+    pub fn note_required(&self, rules: &[CategoryRule]) -> bool {
+        let category = self.category.trim();
+        rules
+            .iter()
+            .any(|r| r.require_note && r.category.trim() == category)
+    }
+
+    /// 勘定科目のルールに反して備考が空か判定する。
+    pub fn missing_required_note(&self, rules: &[CategoryRule]) -> bool {
+        self.note_required(rules) && self.note.trim().is_empty()
+    }
+
+    /// 提出前に入力項目の妥当性を検証し、問題点を列挙する。
+    ///
+    /// `required`に含まれない項目は空欄（金額は0）でもよい。
+    pub fn validate(
+        &self,
+        required: &[RequiredField],
+        rules: &[CategoryRule],
+    ) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        // 日付は入力されていれば（必須なら常に）YYYY-MM-DD形式で実在する日であること。
+        let date_given = !self.date_ymd.trim().is_empty();
+        if (date_given || required.contains(&RequiredField::Date))
+            && chrono::NaiveDate::parse_from_str(&self.date_ymd, "%Y-%m-%d").is_err()
+        {
+            problems.push(format!("invalid date {:?}", self.date_ymd));
+        }
+        // 必須の文字列項目は空欄不可。
+        for (field, name, value) in [
+            (RequiredField::Reason, "reason", &self.reason),
+            (RequiredField::Category, "category", &self.category),
+            (RequiredField::Note, "note", &self.note),
+        ] {
+            if required.contains(&field) && value.trim().is_empty() {
+                problems.push(format!("{name} is empty"));
+            }
+        }
+        // 金額は負にできず、必須なら正の値であること。
+        if self.amount_yen < 0
+            || (self.amount_yen == 0 && required.contains(&RequiredField::Amount))
+        {
+            problems.push(format!("amount must be positive (got {})", self.amount_yen));
+        }
+        // 勘定科目によっては備考が必須。
+        if self.missing_required_note(rules) {
+            problems.push(format!("note is required for category {:?}", self.category));
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+}
+
 /// Worker内の処理進行に応じたジョブ状態。
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum JobStatus {
     /// 処理待ち。
     Queued,
@@ -37,33 +214,426 @@ pub enum JobStatus {
 }
 
 /// Drive上の画像1件とその処理状態。
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Job {
-    /// 状態更新に使う安定ID。
+    /// 状態更新に使う安定ID（DriveファイルIDから導出できるため保存しない）。
+    #[serde(skip)]
     pub id: Uuid,
     /// 画像ファイルのDrive ID。
     pub drive_file_id: String,
     /// 表示用のファイル名。
     pub filename: String,
+    /// DriveのMIMEタイプ（古い保存データでは空）。
+    #[serde(default)]
+    pub mime_type: String,
     /// 現在の処理状態。
     pub status: JobStatus,
     /// ユーザー入力の編集項目。
     pub fields: ReceiptFields,
+    /// 対象月に同じ画像を提出済みか（二重提出の検出が有効な場合のみ）。
+    pub already_submitted: bool,
+    /// ジョブ固有の対象月（Noneなら全体の対象月を使う）。
+    pub target_month: Option<String>,
+    /// 確定時にテンプレートから作成したスプレッドシートのID（診断用）。
+    pub copied_sheet_id: Option<String>,
+    /// 確定時に経費行を書き込んだレンジ（シートからの読み戻しに使う）。
+    pub committed_range: Option<String>,
+    /// 確定に失敗したときの対象月（月を直して再送する際の初期値）。
+    pub failed_month: Option<String>,
 }
 
+/// ジョブIDを導出するUUIDv5の名前空間（変えると過去の記録と対応が取れなくなる）。
+const JOB_ID_NAMESPACE: Uuid = Uuid::from_u128(0x6b1f_3c2e_9a4d_4e57_b0c8_27d5_e1f4_a903);
+
 impl Job {
     /// デフォルト入力値と待機状態でジョブを作成する。
     pub fn new(drive_file_id: String, filename: String) -> Self {
-        // 新しいUUIDを発行して安定IDとする。
         Self {
-            id: Uuid::new_v4(),
+            // 再起動しても同じ画像が同じIDになるよう、DriveファイルIDから導出する。
+            id: Self::id_for(&drive_file_id),
             // 受け取ったDrive情報をセットする。
             drive_file_id,
             filename,
+            mime_type: String::new(),
             // 初期状態は待機。
             status: JobStatus::Queued,
             // 入力項目はデフォルトで初期化する。
             fields: ReceiptFields::default(),
+            already_submitted: false,
+            target_month: None,
+            copied_sheet_id: None,
+            committed_range: None,
+            failed_month: None,
+        }
+    }
+
+    /// DriveファイルIDに対応する安定したジョブIDを返す。
+    pub fn id_for(drive_file_id: &str) -> Uuid {
+        Uuid::new_v5(&JOB_ID_NAMESPACE, drive_file_id.as_bytes())
+    }
+
+    /// 実際に使う対象月を返す（ジョブ固有の指定が優先、無効ならNone）。
+    pub fn effective_target_month(&self, global_ym: &str) -> Option<String> {
+        let ym = self.target_month.as_deref().unwrap_or(global_ym).trim();
+        // 不正な対象月で`{month}-01`を組み立てないよう検証する。
+        crate::dates::is_valid_ym(ym).then(|| ym.to_string())
+    }
+}
+
+/// 画像として扱えるMIMEタイプか（取得前の保存データなど空なら画像とみなす）。
+pub fn is_image_mime(mime_type: &str) -> bool {
+    mime_type.is_empty() || mime_type.starts_with("image/")
+}
+
+/// ジョブ一覧の状態別件数。
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JobStats {
+    /// 全件数。
+    pub total: usize,
+    /// 処理待ち。
+    pub queued: usize,
+    /// ユーザー編集待ち。
+    pub waiting_user_fix: usize,
+    /// スプレッドシートへの書き込み中。
+    pub writing_sheet: usize,
+    /// PDFエクスポート中。
+    pub exporting_pdf: usize,
+    /// PDFアップロード中。
+    pub uploading_pdf: usize,
+    /// 正常完了。
+    pub done: usize,
+    /// 失敗。
+    pub error: usize,
+    /// 完了したジョブの通貨別の金額合計（キーは大文字の通貨コード、空は既定の通貨）。
+    pub done_amounts: BTreeMap<String, i64>,
+}
+
+impl JobStats {
+    /// ジョブ一覧から状態別件数を集計する。
+    pub fn from_jobs(jobs: &[Job]) -> Self {
+        let mut stats = Self {
+            total: jobs.len(),
+            ..Self::default()
+        };
+        for job in jobs {
+            // 状態ごとに件数を加算する。
+            let slot = match job.status {
+                JobStatus::Queued => &mut stats.queued,
+                JobStatus::WaitingUserFix => &mut stats.waiting_user_fix,
+                JobStatus::WritingSheet => &mut stats.writing_sheet,
+                JobStatus::ExportingPdf => &mut stats.exporting_pdf,
+                JobStatus::UploadingPdf => &mut stats.uploading_pdf,
+                JobStatus::Done => {
+                    let currency = job.fields.currency_or("").to_ascii_uppercase();
+                    *stats.done_amounts.entry(currency).or_default() += job.fields.amount_yen;
+                    &mut stats.done
+                }
+                JobStatus::Error(_) => &mut stats.error,
+            };
+            *slot += 1;
         }
+        stats
+    }
+
+    /// 完了したジョブの金額合計を`JPY:3000,USD:12`の形で返す（通貨が空の分は`default_currency`に数える）。
+    ///
+    /// 通貨の違う金額は足し合わせない。完了したジョブが無ければ`none`を返す。
+    pub fn done_amounts_label(&self, default_currency: &str) -> String {
+        let mut totals: BTreeMap<String, i64> = BTreeMap::new();
+        for (currency, amount) in &self.done_amounts {
+            let code = if currency.is_empty() {
+                default_currency.to_ascii_uppercase()
+            } else {
+                currency.clone()
+            };
+            *totals.entry(code).or_default() += amount;
+        }
+        if totals.is_empty() {
+            return "none".into();
+        }
+        totals
+            .iter()
+            .map(|(code, amount)| format!("{code}:{amount}"))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Workerが処理中（書き込み・エクスポート・アップロード）の件数。
+    pub fn active(&self) -> usize {
+        self.writing_sheet + self.exporting_pdf + self.uploading_pdf
+    }
+
+    /// 完了件数の割合（0.0〜1.0、ジョブが無ければ0.0）。
+    pub fn done_ratio(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.done as f64 / self.total as f64
+        }
+    }
+}
+
+impl JobStatus {
+    /// Workerが処理中の状態か判定する。
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::WritingSheet | JobStatus::ExportingPdf | JobStatus::UploadingPdf
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ValidationCfg;
+
+    #[test]
+    fn test_job_id_is_stable_per_drive_file() {
+        // 同じDriveファイルIDからは常に同じIDが、異なるIDからは別のIDが作られることを検証する。
+        let a = Job::new("file-a".into(), "a.jpg".into());
+        let again = Job::new("file-a".into(), "renamed.jpg".into());
+        let b = Job::new("file-b".into(), "b.jpg".into());
+        assert_eq!(a.id, again.id);
+        assert_eq!(a.id, Job::id_for("file-a"));
+        assert_ne!(a.id, b.id);
+        assert_eq!(a.id.get_version_num(), 5);
+    }
+
+    #[test]
+    fn test_is_image_mime() {
+        // 画像と不明なMIMEタイプは画像扱い、PDFは画像扱いしないことを検証する。
+        assert!(is_image_mime("image/jpeg"));
+        assert!(is_image_mime(""));
+        assert!(!is_image_mime("application/pdf"));
+    }
+
+    #[test]
+    fn test_effective_target_month() {
+        // ジョブ固有の対象月が優先され、空や不正な値はNoneになることを検証する。
+        let job = Job::new("f".into(), "a.jpg".into());
+        assert_eq!(
+            job.effective_target_month("2025-12"),
+            Some("2025-12".into())
+        );
+        assert_eq!(job.effective_target_month(""), None);
+
+        let job = Job {
+            target_month: Some("2026-01".into()),
+            ..job
+        };
+        assert_eq!(
+            job.effective_target_month("2025-12"),
+            Some("2026-01".into())
+        );
+        let job = Job {
+            target_month: Some("  ".into()),
+            ..job
+        };
+        assert_eq!(job.effective_target_month("2025-12"), None);
+    }
+
+    #[test]
+    fn test_job_stats_counts_each_status() {
+        // 状態別の件数と完了割合が集計されることを検証する。
+        let statuses = [
+            JobStatus::Queued,
+            JobStatus::WritingSheet,
+            JobStatus::UploadingPdf,
+            JobStatus::Done,
+            JobStatus::Error("boom".into()),
+        ];
+        let jobs: Vec<Job> = statuses
+            .into_iter()
+            .map(|status| Job {
+                status,
+                ..Job::new("f".into(), "a.jpg".into())
+            })
+            .collect();
+        let stats = JobStats::from_jobs(&jobs);
+        assert_eq!(stats.total, 5);
+        assert_eq!(stats.queued, 1);
+        assert_eq!(stats.active(), 2);
+        assert_eq!(stats.done, 1);
+        assert_eq!(stats.error, 1);
+        assert!((stats.done_ratio() - 0.2).abs() < f64::EPSILON);
+        assert_eq!(JobStats::from_jobs(&[]).done_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_done_amounts_split_by_currency() {
+        // 完了した金額が通貨ごとに集計され、空の通貨は既定の通貨に数えられることを検証する。
+        let jobs: Vec<Job> = [("", 1200), ("jpy", 800), ("USD", 12), ("", 500)]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (currency, amount))| {
+                let mut job = Job::new("f".into(), "a.jpg".into());
+                job.status = if i == 3 {
+                    JobStatus::Queued
+                } else {
+                    JobStatus::Done
+                };
+                job.fields.currency = currency.into();
+                job.fields.amount_yen = amount;
+                job
+            })
+            .collect();
+        let stats = JobStats::from_jobs(&jobs);
+        assert_eq!(stats.done_amounts_label("JPY"), "JPY:2000,USD:12");
+        assert_eq!(stats.done_amounts_label("EUR"), "EUR:1200,JPY:800,USD:12");
+        assert_eq!(JobStats::from_jobs(&[]).done_amounts_label("JPY"), "none");
+    }
+
+    #[test]
+    fn test_validate_reports_problems() {
+        // 不正な入力項目が列挙され、正しい入力は通ることを検証する。
+        let mut f = ReceiptFields::default();
+        let problems = f
+            .validate(&ValidationCfg::default().required_fields, &[])
+            .unwrap_err();
+        assert_eq!(problems.len(), 3);
+
+        f.date_ymd = "2025-12-19".into();
+        f.reason = "会議費".into();
+        f.amount_yen = 1200;
+        assert!(
+            f.validate(&ValidationCfg::default().required_fields, &[])
+                .is_ok()
+        );
+
+        f.date_ymd = "2025-02-30".into();
+        assert_eq!(
+            f.validate(&ValidationCfg::default().required_fields, &[])
+                .unwrap_err()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_validate_follows_required_fields() {
+        // 同じ入力でも、必須項目の設定によって結果が変わることを検証する。
+        let f = ReceiptFields {
+            date_ymd: "2025-12-19".into(),
+            reason: "タクシー".into(),
+            amount_yen: 2400,
+            category: String::new(),
+            note: String::new(),
+            currency: String::new(),
+        };
+        // 勘定科目が必須のテンプレートでは失敗する。
+        let strict = [
+            RequiredField::Date,
+            RequiredField::Reason,
+            RequiredField::Amount,
+            RequiredField::Category,
+        ];
+        assert_eq!(
+            f.validate(&strict, &[]).unwrap_err(),
+            vec!["category is empty".to_string()]
+        );
+        // 勘定科目が任意のテンプレートでは通る。
+        let relaxed = [RequiredField::Date, RequiredField::Amount];
+        assert!(f.validate(&relaxed, &[]).is_ok());
+
+        // 必須でない項目も、入力されていれば形式は検証する。
+        let g = ReceiptFields {
+            date_ymd: "2025-13-01".into(),
+            amount_yen: -1,
+            ..ReceiptFields::default()
+        };
+        assert_eq!(g.validate(&[], &[]).unwrap_err().len(), 2);
+        assert!(ReceiptFields::default().validate(&[], &[]).is_ok());
+    }
+
+    #[test]
+    fn test_from_sheet_row_parses_formatted_values() {
+        // 表示形式の日付や金額が入力項目の形式へ戻ることを検証する。
+        let row: Vec<String> = ["2025/12/19", "タクシー", "¥2,400", "旅費交通費"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let f = ReceiptFields::from_sheet_row(&row).unwrap();
+        assert_eq!(f.date_ymd, "2025-12-19");
+        assert_eq!(f.reason, "タクシー");
+        assert_eq!(f.amount_yen, 2400);
+        assert_eq!(f.category, "旅費交通費");
+        // 末尾の空セルは省かれても空欄として扱う。
+        assert_eq!(f.note, "");
+
+        let local = ReceiptFields {
+            amount_yen: 2000,
+            note: "深夜".into(),
+            ..f.clone()
+        };
+        assert_eq!(local.differing_fields(&f), vec![2, 4]);
+
+        // 数値にならない金額はエラーにする。
+        for amount in ["1-2-3", "1-2", "1200.5"] {
+            let bad = vec!["2025-12-19".to_string(), "x".into(), amount.into()];
+            assert!(ReceiptFields::from_sheet_row(&bad).is_err(), "{amount}");
+        }
+        // 書式なしの整数や負の金額はそのまま読む。
+        for (amount, want) in [("1200", 1200), ("-300", -300), ("2400円", 2400)] {
+            let row = vec!["2025-12-19".to_string(), "x".into(), amount.into()];
+            assert_eq!(
+                ReceiptFields::from_sheet_row(&row).unwrap().amount_yen,
+                want
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_category_requires_note() {
+        // 備考必須の科目では備考が空だと失敗し、他の科目では問われないことを検証する。
+        let rules = vec![CategoryRule {
+            category: "その他".into(),
+            require_note: true,
+        }];
+        let mut f = ReceiptFields {
+            date_ymd: "2025-12-19".into(),
+            reason: "備品".into(),
+            amount_yen: 800,
+            category: "その他".into(),
+            note: String::new(),
+            currency: String::new(),
+        };
+        assert_eq!(
+            f.validate(&[], &rules).unwrap_err(),
+            vec!["note is required for category \"その他\"".to_string()]
+        );
+        assert!(f.missing_required_note(&rules));
+
+        f.note = "USBケーブル".into();
+        assert!(f.validate(&[], &rules).is_ok());
+
+        f.category = "交通費".into();
+        f.note = String::new();
+        assert!(!f.note_required(&rules));
+        assert!(f.validate(&[], &rules).is_ok());
+    }
+
+    #[test]
+    fn test_field_value_roundtrip() {
+        // フィールド番号での読み書きが往復で一致することを検証する。
+        let mut f = ReceiptFields::default();
+        for (idx, value) in ["2025-01-02", "打合せ", "1200", "交通費", "メモ", "USD"]
+            .iter()
+            .enumerate()
+        {
+            f.set_field_value(idx, value.to_string());
+            assert_eq!(f.field_value(idx), *value);
+        }
+        assert_eq!(f.amount_yen, 1200);
+        f.set_field_value(5, " eur ".into());
+        assert_eq!(f.currency, "EUR");
+    }
+
+    #[test]
+    fn test_set_amount_invalid_becomes_zero() {
+        // 数値でない金額は0になることを検証する。
+        let mut f = ReceiptFields::default();
+        f.set_field_value(2, "abc".into());
+        assert_eq!(f.amount_yen, 0);
     }
 }
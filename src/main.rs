@@ -1,20 +1,79 @@
 //! アプリケーションのエントリポイントとランタイム初期化。
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use std::path::PathBuf;
 use tracing_appender::non_blocking::WorkerGuard;
 
 mod app;
+mod bell;
+mod breaker;
+mod check;
+mod clipboard;
+mod column_map;
 mod config;
+mod config_watch;
+mod dates;
+mod diagnostics;
 mod events;
+mod export;
+mod format;
 mod google;
 mod input;
+mod job_store;
 mod jobs;
 mod layout;
+mod ocr;
 mod shortcuts;
+mod submissions;
+mod theme;
 mod ui;
+mod validation;
 mod wizard;
 mod worker;
 
+/// コマンドライン引数。
+#[derive(Debug, PartialEq, Eq)]
+struct CliArgs {
+    /// `check` サブコマンドが指定されたか。
+    check: bool,
+    /// 設定ファイルのパス（`--config`）。
+    config_path: PathBuf,
+    /// ショートカット設定ファイルのパス（`--shortcuts`）。
+    shortcuts_path: PathBuf,
+    /// Drive/Sheetsへ書き込まずに確定の流れを試すか（`--dry-run`）。
+    dry_run: bool,
+}
+
+/// コマンドライン引数を解釈する（プログラム名は含めない）。
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<CliArgs> {
+    let mut cli = CliArgs {
+        check: false,
+        config_path: PathBuf::from(config::DEFAULT_CONFIG_PATH),
+        shortcuts_path: PathBuf::from(shortcuts::DEFAULT_SHORTCUTS_PATH),
+        dry_run: false,
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "check" => cli.check = true,
+            "--dry-run" => cli.dry_run = true,
+            "--config" | "--shortcuts" => {
+                // フラグの直後の値をパスとして受け取る。
+                let value = args
+                    .next()
+                    .ok_or_else(|| anyhow!("{arg} requires a path"))?;
+                if arg == "--config" {
+                    cli.config_path = PathBuf::from(value);
+                } else {
+                    cli.shortcuts_path = PathBuf::from(value);
+                }
+            }
+            _ => return Err(anyhow!("unknown argument: {arg}")),
+        }
+    }
+    Ok(cli)
+}
+
 /// ファイルロギングを初期化し、非同期ガードを生存させる。
 fn init_logging() -> Result<WorkerGuard> {
     // ログ出力先ファイル名を決める。
@@ -38,6 +97,16 @@ fn init_logging() -> Result<WorkerGuard> {
 #[tokio::main]
 /// エントリポイント：ログ初期化→UI開始→端末復元。
 async fn main() -> Result<()> {
+    // 引数を解釈し、使う設定ファイルを決める。
+    let cli = parse_args(std::env::args().skip(1))?;
+
+    // `check` サブコマンドならTUIを起動せずに自己診断だけ行う。
+    if cli.check {
+        let results = check::run_checks(&cli.config_path, &cli.shortcuts_path);
+        let ok = check::print_report(&results);
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
     // ロガーを初期化し、ガードを保持して書き込みを継続させる。
     let _log_guard = init_logging()?;
     // 起動ログを出力する。
@@ -45,7 +114,13 @@ async fn main() -> Result<()> {
     // TUI用の端末状態へ切り替える。
     let mut terminal = ui::init_terminal()?;
     // メインアプリを実行する。
-    let res = app::run_app(&mut terminal).await;
+    let res = app::run_app(
+        &mut terminal,
+        cli.config_path,
+        &cli.shortcuts_path,
+        cli.dry_run,
+    )
+    .await;
     // 端末の状態を必ず元に戻す。
     ui::restore_terminal()?;
     // エラーがあればログに残す。
@@ -56,3 +131,45 @@ async fn main() -> Result<()> {
     tracing::info!("app exiting");
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 文字列スライスを引数列に変換する。
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args_defaults_and_paths() {
+        // 引数が無ければ既定のパスを使い、フラグでパスを差し替えられることを検証する。
+        let cli = parse_args(args(&[])).unwrap();
+        assert!(!cli.check);
+        assert!(!cli.dry_run);
+        assert_eq!(cli.config_path, PathBuf::from("config.toml"));
+        assert_eq!(cli.shortcuts_path, PathBuf::from("shortcut.toml"));
+
+        let cli = parse_args(args(&[
+            "check",
+            "--config",
+            "work/config.toml",
+            "--shortcuts",
+            "work/keys.toml",
+        ]))
+        .unwrap();
+        assert!(cli.check);
+        assert_eq!(cli.config_path, PathBuf::from("work/config.toml"));
+        assert_eq!(cli.shortcuts_path, PathBuf::from("work/keys.toml"));
+
+        let cli = parse_args(args(&["--dry-run"])).unwrap();
+        assert!(cli.dry_run);
+    }
+
+    #[test]
+    fn test_parse_args_rejects_bad_input() {
+        // 値の無いフラグや未知の引数がエラーになることを検証する。
+        assert!(parse_args(args(&["--config"])).is_err());
+        assert!(parse_args(args(&["--verbose"])).is_err());
+    }
+}
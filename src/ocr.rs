@@ -0,0 +1,313 @@
+//! レシート画像の文字認識（OCR）結果から入力項目を推測する。
+//!
+//! 推測は控えめに行い、候補が食い違うなど自信の無い項目は`None`のまま返す（空欄のまま残す）。
+
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
+use reqwest::Client;
+
+/// 合計金額が書かれた行を示す語句（英字は小文字で比べる）。
+const TOTAL_KEYWORDS: &[&str] = &[
+    "合計",
+    "お会計",
+    "お買上計",
+    "ご請求額",
+    "お支払金額",
+    "total",
+];
+
+/// 合計の語句を含んでも合計金額ではない行の語句。
+///
+/// 小計・点数・税額・預り金・釣銭の行を除く。
+const EXCLUDED_KEYWORDS: &[&str] = &[
+    "小計",
+    "subtotal",
+    "sub total",
+    "点",
+    "対象",
+    "消費税",
+    "内税",
+    "外税",
+    "預",
+    "釣",
+    "change",
+];
+
+/// OCRの結果から推測した入力項目。
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OcrGuess {
+    /// 支払日（YYYY-MM-DD）。
+    pub date_ymd: Option<String>,
+    /// 合計金額。
+    pub amount_yen: Option<i64>,
+}
+
+impl OcrGuess {
+    /// 推測できた項目が1つも無いか。
+    pub fn is_empty(&self) -> bool {
+        self.date_ymd.is_none() && self.amount_yen.is_none()
+    }
+}
+
+/// 認識した全文から日付と合計金額を推測する。
+pub fn guess(text: &str) -> OcrGuess {
+    let text = normalize(text);
+    OcrGuess {
+        date_ymd: guess_date(&text).map(|d| d.format("%Y-%m-%d").to_string()),
+        amount_yen: guess_total(&text),
+    }
+}
+
+/// 全角の数字と記号を半角へそろえる。
+fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '０'..='９' => char::from(b'0' + (c as u32 - '０' as u32) as u8),
+            '／' => '/',
+            '－' => '-',
+            '．' => '.',
+            '，' => ',',
+            '￥' => '¥',
+            '：' => ':',
+            _ => c,
+        })
+        .collect()
+}
+
+/// 本文に現れる日付がただ1つに定まれば返す。
+///
+/// `2025/12/19`・`2025-12-19`・`2025.12.19`・`2025年12月19日`・`令和7年12月19日`を読む。
+/// 異なる日付が複数あれば、どれが支払日か分からないので`None`を返す。
+fn guess_date(text: &str) -> Option<NaiveDate> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut found: Vec<NaiveDate> = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        // 数字列の途中からは読み始めない。
+        let at_start = i == 0 || !chars[i - 1].is_ascii_digit();
+        let parsed = if chars[i..].starts_with(&['令', '和']) {
+            parse_reiwa(&chars, i + 2)
+        } else if at_start && chars[i].is_ascii_digit() {
+            parse_western(&chars, i)
+        } else {
+            None
+        };
+        match parsed {
+            Some((date, end)) => {
+                if !found.contains(&date) {
+                    found.push(date);
+                }
+                i = end;
+            }
+            None => i += 1,
+        }
+    }
+    match found.as_slice() {
+        [date] => Some(*date),
+        _ => None,
+    }
+}
+
+/// 西暦4桁で始まる日付を読み、日付と読み終えた位置を返す。
+fn parse_western(chars: &[char], start: usize) -> Option<(NaiveDate, usize)> {
+    let (year, i) = read_number(chars, start, 4)?;
+    if !(2000..=2099).contains(&year) || i - start != 4 {
+        return None;
+    }
+    let sep = *chars.get(i)?;
+    let month_sep = match sep {
+        '/' | '-' | '.' => sep,
+        '年' => '月',
+        _ => return None,
+    };
+    read_month_day(chars, skip_spaces(chars, i + 1), year, month_sep)
+}
+
+/// `令和`に続く和暦の日付を読み、日付と読み終えた位置を返す。
+fn parse_reiwa(chars: &[char], start: usize) -> Option<(NaiveDate, usize)> {
+    let i = skip_spaces(chars, start);
+    let (era_year, i) = if chars.get(i) == Some(&'元') {
+        (1, i + 1)
+    } else {
+        read_number(chars, i, 2)?
+    };
+    let i = skip_spaces(chars, i);
+    if chars.get(i) != Some(&'年') {
+        return None;
+    }
+    // 令和元年は2019年。
+    read_month_day(chars, skip_spaces(chars, i + 1), 2018 + era_year, '月')
+}
+
+/// 年に続く「月、区切り、日」を読む（`月`区切りなら末尾の`日`も読み飛ばす）。
+fn read_month_day(
+    chars: &[char],
+    start: usize,
+    year: u32,
+    month_sep: char,
+) -> Option<(NaiveDate, usize)> {
+    let (month, i) = read_number(chars, start, 2)?;
+    if chars.get(i) != Some(&month_sep) {
+        return None;
+    }
+    let (day, mut i) = read_number(chars, skip_spaces(chars, i + 1), 2)?;
+    // 日の後ろに数字が続くなら日付ではない。
+    if chars.get(i).is_some_and(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    if month_sep == '月' && chars.get(i) == Some(&'日') {
+        i += 1;
+    }
+    let date = NaiveDate::from_ymd_opt(year as i32, month, day)?;
+    Some((date, i))
+}
+
+/// 最大`max_digits`桁の数字を読み、値と読み終えた位置を返す。
+fn read_number(chars: &[char], start: usize, max_digits: usize) -> Option<(u32, usize)> {
+    let digits: String = chars[start.min(chars.len())..]
+        .iter()
+        .take_while(|c| c.is_ascii_digit())
+        .take(max_digits)
+        .collect();
+    if digits.is_empty() {
+        return None;
+    }
+    Some((digits.parse().ok()?, start + digits.len()))
+}
+
+/// 空白を読み飛ばした位置を返す。
+fn skip_spaces(chars: &[char], mut i: usize) -> usize {
+    while chars
+        .get(i)
+        .is_some_and(|c| c.is_whitespace() && *c != '\n')
+    {
+        i += 1;
+    }
+    i
+}
+
+/// 合計の行から金額を読み、候補が1つに定まれば返す。
+///
+/// 語句と金額が別の行に分かれることがあるため、語句の行に金額が無ければ次の行を見る。
+fn guess_total(text: &str) -> Option<i64> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut found: Vec<i64> = vec![];
+    for (idx, line) in lines.iter().enumerate() {
+        let lower = line.to_lowercase();
+        if !TOTAL_KEYWORDS.iter().any(|k| lower.contains(k))
+            || EXCLUDED_KEYWORDS.iter().any(|k| lower.contains(k))
+        {
+            continue;
+        }
+        let amount = last_amount(line).or_else(|| lines.get(idx + 1).and_then(|l| last_amount(l)));
+        if let Some(amount) = amount
+            && !found.contains(&amount)
+        {
+            found.push(amount);
+        }
+    }
+    match found.as_slice() {
+        [amount] => Some(*amount),
+        _ => None,
+    }
+}
+
+/// 行の最後にある金額を読む（桁区切りのカンマを許し、小数や割合は金額とみなさない）。
+fn last_amount(line: &str) -> Option<i64> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut last = None;
+    let mut i = 0;
+    while i < chars.len() {
+        if !chars[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let mut digits = String::new();
+        while i < chars.len() {
+            let c = chars[i];
+            if c.is_ascii_digit() {
+                digits.push(c);
+                i += 1;
+            } else if c == ',' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()) {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        let next = chars.get(i).copied();
+        // `1,200.50`や`10%`は円の合計金額ではない。
+        let is_fraction = next == Some('.') && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit());
+        if is_fraction {
+            // 小数部も読み飛ばし、別の数として読まないようにする。
+            i += 1;
+            while chars.get(i).is_some_and(|c| c.is_ascii_digit()) {
+                i += 1;
+            }
+        }
+        if is_fraction || next == Some('%') {
+            last = None;
+            continue;
+        }
+        last = digits.parse::<i64>().ok().filter(|v| *v > 0);
+    }
+    last
+}
+
+/// 設定されたOCRエンドポイントへ画像を送り、認識した全文を受け取る。
+///
+/// 画像のバイト列をそのままPOSTし、`{"text": "..."}`形式のJSONを期待する。
+pub async fn recognize(http: &Client, endpoint: &str, image: &[u8]) -> Result<String> {
+    let resp = http
+        .post(endpoint)
+        .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+        .body(image.to_vec())
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<serde_json::Value>()
+        .await?;
+    resp["text"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("OCR endpoint response has no \"text\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guess_reads_date_and_total_from_receipt() {
+        // 典型的なレシートから日付と合計金額（小計・預り・釣銭ではなく）を推測することを検証する。
+        let text = "コンビニ 新宿店\n２０２５年１２月１９日(金) 12:34\nお茶 ¥150\n弁当 ¥550\n小計 ¥700\n(10%対象 ¥700)\n合計\n¥770\nお預り ¥1,000\nお釣り ¥230\n";
+        assert_eq!(
+            guess(text),
+            OcrGuess {
+                date_ymd: Some("2025-12-19".into()),
+                amount_yen: Some(770),
+            }
+        );
+
+        let text = "令和7年1月5日\n合計点数 3点\nTOTAL ¥12,340-\nお買上計 12,340円\n";
+        assert_eq!(
+            guess(text),
+            OcrGuess {
+                date_ymd: Some("2025-01-05".into()),
+                amount_yen: Some(12340),
+            }
+        );
+    }
+
+    #[test]
+    fn test_guess_leaves_ambiguous_fields_blank() {
+        // 日付や合計の候補が食い違う、または見つからないときは推測しないことを検証する。
+        let text = "2025/12/19\n有効期限 2026/03/31\n合計 ¥1,000\n総合計 ¥1,100\n";
+        assert!(guess(text).is_empty());
+
+        let text = "Receipt No. 20251219001\n$12.50\nTotal $12.50\n";
+        assert!(guess(text).is_empty());
+
+        let text = "2025-13-40\n";
+        assert!(guess(text).is_empty());
+    }
+}
@@ -5,18 +5,41 @@ use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+use crate::config::{self, Loaded};
+
+/// 既定のショートカット設定ファイルのパス。
+pub const DEFAULT_SHORTCUTS_PATH: &str = "shortcut.toml";
+
 /// ショートカット設定の全体。
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// ファイルに無いグループやキーは既定値を使う（空の配列を書けばそのキーを無効にできる）。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Shortcuts {
+    pub global: GlobalShortcuts,
     pub main: MainShortcuts,
     pub settings: SettingsShortcuts,
     pub edit_job: EditJobShortcuts,
     pub wizard: WizardShortcuts,
+    pub monitor: MonitorShortcuts,
+    pub logs: LogsShortcuts,
+    pub diagnostics: DiagnosticsShortcuts,
+    pub retry_prompt: RetryPromptShortcuts,
+    pub column_picker: ColumnPickerShortcuts,
+    pub confirm: ConfirmShortcuts,
     pub input_box: InputBoxShortcuts,
 }
 
+/// どの画面からでも使えるショートカット。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GlobalShortcuts {
+    pub settings: Vec<String>,
+}
+
 /// メイン画面のショートカット。
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MainShortcuts {
     pub quit: Vec<String>,
     pub settings: Vec<String>,
@@ -24,38 +47,132 @@ pub struct MainShortcuts {
     pub enter: Vec<String>,
     pub down: Vec<String>,
     pub up: Vec<String>,
+    pub export_xlsx: Vec<String>,
+    pub export_csv: Vec<String>,
+    pub toggle_mask: Vec<String>,
+    pub toggle_done: Vec<String>,
+    pub monitor: Vec<String>,
+    pub logs: Vec<String>,
+    pub pause: Vec<String>,
+    pub diagnostics: Vec<String>,
+    pub commit_all: Vec<String>,
+    pub commit_by_month: Vec<String>,
+    pub pull_from_sheet: Vec<String>,
+    pub retry_with_month: Vec<String>,
+    pub reset_month: Vec<String>,
+    pub ocr: Vec<String>,
+    pub status_filter: Vec<String>,
+    pub search: Vec<String>,
+    pub search_next: Vec<String>,
+    pub search_prev: Vec<String>,
 }
 
 /// 設定画面のショートカット。
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SettingsShortcuts {
     pub cancel: Vec<String>,
     pub save: Vec<String>,
+    pub apply: Vec<String>,
     pub input_folder: Vec<String>,
     pub output_folder: Vec<String>,
     pub template: Vec<String>,
     pub name: Vec<String>,
+    pub logout: Vec<String>,
+    pub reset: Vec<String>,
+    pub map_columns: Vec<String>,
+    pub profile: Vec<String>,
 }
 
 /// 編集画面のショートカット。
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct EditJobShortcuts {
     pub cancel: Vec<String>,
     pub next_field: Vec<String>,
     pub commit: Vec<String>,
     pub target_month: Vec<String>,
     pub edit_field: Vec<String>,
+    pub copy_field: Vec<String>,
+    pub paste_field: Vec<String>,
+    pub set_today: Vec<String>,
+    pub preview_write: Vec<String>,
+    pub reset_month: Vec<String>,
+    pub ocr: Vec<String>,
+    pub undo: Vec<String>,
 }
 
 /// ウィザード画面のショートカット。
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct WizardShortcuts {
     pub proceed: Vec<String>,
     pub skip: Vec<String>,
+    pub back: Vec<String>,
+}
+
+/// モニター画面のショートカット。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorShortcuts {
+    pub back: Vec<String>,
+}
+
+/// ログ画面のショートカット。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogsShortcuts {
+    pub back: Vec<String>,
+    pub up: Vec<String>,
+    pub down: Vec<String>,
+    pub page_up: Vec<String>,
+    pub page_down: Vec<String>,
+    pub top: Vec<String>,
+    pub bottom: Vec<String>,
+}
+
+/// 診断ポップアップのショートカット。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiagnosticsShortcuts {
+    pub close: Vec<String>,
+    pub up: Vec<String>,
+    pub down: Vec<String>,
+}
+
+/// 確定失敗時の再試行プロンプトのショートカット。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPromptShortcuts {
+    pub retry: Vec<String>,
+    pub edit: Vec<String>,
+    pub give_up: Vec<String>,
+}
+
+/// 列の割り当てピッカーのショートカット。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColumnPickerShortcuts {
+    pub up: Vec<String>,
+    pub down: Vec<String>,
+    pub pick: Vec<String>,
+    pub keep: Vec<String>,
+    pub cancel: Vec<String>,
+}
+
+/// 確定前の確認ポップアップのショートカット。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfirmShortcuts {
+    pub yes: Vec<String>,
+    pub no: Vec<String>,
+    /// 既定値へのリセットで、フォルダ/テンプレートIDを残してリセットする。
+    pub keep_ids: Vec<String>,
 }
 
 /// InputBoxのショートカット。
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct InputBoxShortcuts {
     pub confirm: Vec<String>,
     pub cancel: Vec<String>,
@@ -66,25 +183,106 @@ pub struct InputBoxShortcuts {
     pub home: Vec<String>,
     pub end: Vec<String>,
     pub clear_line: Vec<String>,
+    pub accept_suggestion: Vec<String>,
 }
 
 impl Shortcuts {
     /// TOMLから読み込み、無ければデフォルトを返す。
-    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let path = path.as_ref();
-        if path.exists() {
-            // 既存ファイルを読み込んでパースする。
-            let content = std::fs::read_to_string(path)?;
-            let shortcuts: Shortcuts = toml::from_str(&content)?;
-            Ok(shortcuts)
-        } else {
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Result<Loaded<Self>> {
+        match config::read_optional(path.as_ref()) {
+            // 既存ファイルをパースする。
+            Ok(Some(content)) => Ok(Loaded {
+                value: toml::from_str(&content)?,
+                warning: None,
+            }),
             // 未作成の場合は既定値を利用する。
-            Ok(Self::default())
+            Ok(None) => Ok(Loaded {
+                value: Self::default(),
+                warning: None,
+            }),
+            // 読めない場合も既定値で起動し、警告を返す。
+            Err(warning) => {
+                tracing::warn!("{warning}");
+                Ok(Loaded {
+                    value: Self::default(),
+                    warning: Some(warning),
+                })
+            }
         }
     }
 
+    /// すべてのキー文字列が解釈でき、同じ画面で操作どうしが衝突していないか検証し、問題点を列挙する。
+    pub fn validate(&self) -> std::result::Result<(), Vec<String>> {
+        let mut problems = vec![];
+        // 画面グループごとのキー一覧を走査し、解釈できたキーを控える。
+        let mut parsed = vec![];
+        for (group, action, keys) in self.bindings() {
+            for key in keys {
+                if !is_valid_shortcut(&key) {
+                    problems.push(format!("{group}.{action}: invalid key {key:?}"));
+                } else if let Some(k) = parse_shortcut(&key) {
+                    parsed.push((group.clone(), action.clone(), key, normalize(k)));
+                }
+            }
+        }
+        // 同じキーが別の操作にも割り当てられていれば、先に判定される方しか効かない。
+        for (i, (group, action, key, k)) in parsed.iter().enumerate() {
+            for (other_group, other_action, _, other_k) in &parsed[i + 1..] {
+                if k != other_k || (group == other_group && action == other_action) {
+                    continue;
+                }
+                let problem = if group == other_group {
+                    format!("{group}: {key:?} is bound to both {action} and {other_action}")
+                } else if group == "global" && GLOBAL_SCOPED_GROUPS.contains(&other_group.as_str())
+                {
+                    format!("global.{action} hides {other_group}.{other_action} (both use {key:?})")
+                } else if other_group == "global" && GLOBAL_SCOPED_GROUPS.contains(&group.as_str())
+                {
+                    format!("global.{other_action} hides {group}.{action} (both use {key:?})")
+                } else {
+                    continue;
+                };
+                // 同じ操作に同じキーを別の書き方で並べた場合も、報告は1件にする。
+                if !problems.contains(&problem) {
+                    problems.push(problem);
+                }
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// (画面グループ, 操作名, キー一覧) の組を列挙する。
+    fn bindings(&self) -> Vec<(String, String, Vec<String>)> {
+        // 新しい操作を追加しても漏れないよう、TOML表現を経由して走査する。
+        let Ok(toml::Value::Table(groups)) = toml::Value::try_from(self) else {
+            return vec![];
+        };
+        let mut out = vec![];
+        for (group, actions) in groups {
+            let toml::Value::Table(actions) = actions else {
+                continue;
+            };
+            for (action, keys) in actions {
+                // 文字列配列のみを対象にする。
+                let keys = keys
+                    .as_array()
+                    .map(|a| {
+                        a.iter()
+                            .filter_map(|k| k.as_str().map(String::from))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                out.push((group.clone(), action, keys));
+            }
+        }
+        out
+    }
+
     /// TOMLとして保存する。
-    #[allow(dead_code)]
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         // 文字列にシリアライズする。
         let content = toml::to_string_pretty(self)?;
@@ -94,47 +292,170 @@ impl Shortcuts {
     }
 }
 
-impl Default for Shortcuts {
+impl Default for GlobalShortcuts {
+    fn default() -> Self {
+        Self {
+            settings: vec!["Ctrl+t".into()],
+        }
+    }
+}
+
+impl Default for MainShortcuts {
+    fn default() -> Self {
+        Self {
+            quit: vec!["q".into()],
+            settings: vec!["t".into()],
+            refresh: vec!["r".into()],
+            enter: vec!["Enter".into()],
+            down: vec!["Down".into(), "j".into()],
+            up: vec!["Up".into(), "k".into()],
+            export_xlsx: vec!["x".into()],
+            export_csv: vec!["X".into()],
+            toggle_mask: vec!["v".into()],
+            toggle_done: vec!["h".into()],
+            monitor: vec!["w".into()],
+            logs: vec!["l".into()],
+            pause: vec!["p".into()],
+            diagnostics: vec!["i".into()],
+            commit_all: vec!["c".into()],
+            commit_by_month: vec!["g".into()],
+            pull_from_sheet: vec!["s".into()],
+            retry_with_month: vec!["m".into()],
+            reset_month: vec!["0".into()],
+            ocr: vec!["o".into()],
+            status_filter: vec!["f".into()],
+            search: vec!["/".into()],
+            search_next: vec!["n".into()],
+            search_prev: vec!["N".into()],
+        }
+    }
+}
+
+impl Default for SettingsShortcuts {
+    fn default() -> Self {
+        Self {
+            cancel: vec!["Esc".into()],
+            save: vec!["Enter".into()],
+            apply: vec!["Ctrl+s".into()],
+            input_folder: vec!["i".into()],
+            output_folder: vec!["o".into()],
+            template: vec!["p".into()],
+            name: vec!["n".into()],
+            logout: vec!["l".into()],
+            reset: vec!["R".into()],
+            map_columns: vec!["c".into()],
+            profile: vec!["P".into()],
+        }
+    }
+}
+
+impl Default for EditJobShortcuts {
+    fn default() -> Self {
+        Self {
+            cancel: vec!["Esc".into()],
+            next_field: vec!["Tab".into()],
+            commit: vec!["Enter".into()],
+            target_month: vec!["m".into()],
+            edit_field: vec!["e".into()],
+            copy_field: vec!["y".into()],
+            paste_field: vec!["p".into()],
+            set_today: vec!["d".into()],
+            preview_write: vec!["w".into()],
+            reset_month: vec!["0".into()],
+            ocr: vec!["o".into()],
+            undo: vec!["u".into()],
+        }
+    }
+}
+
+impl Default for WizardShortcuts {
+    fn default() -> Self {
+        Self {
+            proceed: vec!["Enter".into()],
+            skip: vec!["Esc".into()],
+            back: vec!["Left".into(), "b".into()],
+        }
+    }
+}
+
+impl Default for MonitorShortcuts {
+    fn default() -> Self {
+        Self {
+            back: vec!["Esc".into()],
+        }
+    }
+}
+
+impl Default for LogsShortcuts {
+    fn default() -> Self {
+        Self {
+            back: vec!["Esc".into(), "q".into()],
+            up: vec!["Up".into(), "k".into()],
+            down: vec!["Down".into(), "j".into()],
+            page_up: vec!["PageUp".into()],
+            page_down: vec!["PageDown".into()],
+            top: vec!["Home".into()],
+            bottom: vec!["End".into()],
+        }
+    }
+}
+
+impl Default for DiagnosticsShortcuts {
+    fn default() -> Self {
+        Self {
+            close: vec!["Esc".into(), "q".into()],
+            up: vec!["Up".into(), "k".into()],
+            down: vec!["Down".into(), "j".into()],
+        }
+    }
+}
+
+impl Default for ColumnPickerShortcuts {
     fn default() -> Self {
         Self {
-            main: MainShortcuts {
-                quit: vec!["q".into()],
-                settings: vec!["t".into()],
-                refresh: vec!["r".into()],
-                enter: vec!["Enter".into()],
-                down: vec!["Down".into(), "j".into()],
-                up: vec!["Up".into(), "k".into()],
-            },
-            settings: SettingsShortcuts {
-                cancel: vec!["Esc".into()],
-                save: vec!["Enter".into()],
-                input_folder: vec!["i".into()],
-                output_folder: vec!["o".into()],
-                template: vec!["p".into()],
-                name: vec!["n".into()],
-            },
-            edit_job: EditJobShortcuts {
-                cancel: vec!["Esc".into()],
-                next_field: vec!["Tab".into()],
-                commit: vec!["Enter".into()],
-                target_month: vec!["m".into()],
-                edit_field: vec!["e".into()],
-            },
-            wizard: WizardShortcuts {
-                proceed: vec!["Enter".into()],
-                skip: vec!["Esc".into()],
-            },
-            input_box: InputBoxShortcuts {
-                confirm: vec!["Enter".into()],
-                cancel: vec!["Esc".into()],
-                backspace: vec!["Backspace".into()],
-                delete: vec!["Delete".into()],
-                left: vec!["Left".into(), "h".into()],
-                right: vec!["Right".into(), "l".into()],
-                home: vec!["Home".into()],
-                end: vec!["End".into()],
-                clear_line: vec!["Ctrl+u".into()],
-            },
+            up: vec!["Up".into(), "k".into()],
+            down: vec!["Down".into(), "j".into()],
+            pick: vec!["Enter".into()],
+            keep: vec!["Tab".into()],
+            cancel: vec!["Esc".into()],
+        }
+    }
+}
+
+impl Default for RetryPromptShortcuts {
+    fn default() -> Self {
+        Self {
+            retry: vec!["r".into()],
+            edit: vec!["e".into()],
+            give_up: vec!["g".into(), "Esc".into()],
+        }
+    }
+}
+
+impl Default for ConfirmShortcuts {
+    fn default() -> Self {
+        Self {
+            // Enterの押し間違いで確定しないよう、Enterは割り当てない。
+            yes: vec!["y".into()],
+            no: vec!["n".into(), "Esc".into()],
+            keep_ids: vec!["k".into()],
+        }
+    }
+}
+
+impl Default for InputBoxShortcuts {
+    fn default() -> Self {
+        Self {
+            confirm: vec!["Enter".into()],
+            cancel: vec!["Esc".into()],
+            backspace: vec!["Backspace".into()],
+            delete: vec!["Delete".into()],
+            left: vec!["Left".into(), "h".into()],
+            right: vec!["Right".into(), "l".into()],
+            home: vec!["Home".into()],
+            end: vec!["End".into()],
+            clear_line: vec!["Ctrl+u".into()],
+            accept_suggestion: vec!["Tab".into()],
         }
     }
 }
@@ -146,6 +467,39 @@ pub fn matches_shortcut(key: &KeyEvent, shortcuts: &[String]) -> bool {
 
 /// KeyEventが単一のショートカット文字列と一致するか判定する。
 fn matches_single_shortcut(key: &KeyEvent, shortcut: &str) -> bool {
+    // 解析できないショートカットはどのキーにも一致しない。
+    let Some((expected_modifiers, code)) = parse_shortcut(shortcut) else {
+        return false;
+    };
+    // 大文字はShift付きで届くため、"N"と"Shift+N"のどちらの書き方でも一致させる。
+    if matches!(code, KeyCode::Char(c) if c.is_uppercase()) {
+        let without_shift = |m: KeyModifiers| m.difference(KeyModifiers::SHIFT);
+        return without_shift(key.modifiers) == without_shift(expected_modifiers)
+            && key.code == code;
+    }
+    // 修飾キーとキーコードの両方が一致すること。
+    key.modifiers == expected_modifiers && key.code == code
+}
+
+/// ショートカット文字列として解釈できるか判定する。
+pub fn is_valid_shortcut(shortcut: &str) -> bool {
+    parse_shortcut(shortcut).is_some()
+}
+
+/// 全画面共通のキーが先に判定される画面グループ。
+const GLOBAL_SCOPED_GROUPS: &[&str] = &["main", "settings", "edit_job", "monitor", "logs"];
+
+/// 同じキー入力に一致する書き方をそろえる（大文字は"N"と"Shift+N"を同じとみなす）。
+fn normalize((modifiers, code): (KeyModifiers, KeyCode)) -> (KeyModifiers, KeyCode) {
+    if matches!(code, KeyCode::Char(c) if c.is_uppercase()) {
+        (modifiers.difference(KeyModifiers::SHIFT), code)
+    } else {
+        (modifiers, code)
+    }
+}
+
+/// ショートカット文字列を修飾キーとキーコードへ分解する。
+fn parse_shortcut(shortcut: &str) -> Option<(KeyModifiers, KeyCode)> {
     // ショートカット文字列を分解する（例: "Ctrl+u", "a", "Enter"）。
     let parts: Vec<&str> = shortcut.split('+').collect();
 
@@ -158,50 +512,102 @@ fn matches_single_shortcut(key: &KeyEvent, shortcut: &str) -> bool {
     };
 
     // 修飾キーを解析して期待値を作る。
-    let mut expected_modifiers = KeyModifiers::empty();
+    let mut modifiers = KeyModifiers::empty();
     for modifier in modifiers_str {
         match *modifier {
-            "Ctrl" | "ctrl" => expected_modifiers |= KeyModifiers::CONTROL,
-            "Alt" | "alt" => expected_modifiers |= KeyModifiers::ALT,
-            "Shift" | "shift" => expected_modifiers |= KeyModifiers::SHIFT,
-            _ => return false,
+            "Ctrl" | "ctrl" => modifiers |= KeyModifiers::CONTROL,
+            "Alt" | "alt" => modifiers |= KeyModifiers::ALT,
+            "Shift" | "shift" => modifiers |= KeyModifiers::SHIFT,
+            _ => return None,
         }
     }
 
-    // 修飾キーが一致しなければ即座に不一致とする。
-    if key.modifiers != expected_modifiers {
-        return false;
-    }
-
-    // キーコードの種別ごとに一致判定を行う。
-    match key_str {
-        "Enter" | "enter" => key.code == KeyCode::Enter,
-        "Esc" | "esc" => key.code == KeyCode::Esc,
-        "Tab" | "tab" => key.code == KeyCode::Tab,
-        "Backspace" | "backspace" => key.code == KeyCode::Backspace,
-        "Delete" | "delete" => key.code == KeyCode::Delete,
-        "Up" | "up" => key.code == KeyCode::Up,
-        "Down" | "down" => key.code == KeyCode::Down,
-        "Left" | "left" => key.code == KeyCode::Left,
-        "Right" | "right" => key.code == KeyCode::Right,
-        "Home" | "home" => key.code == KeyCode::Home,
-        "End" | "end" => key.code == KeyCode::End,
-        // 単一文字は Char として比較する。
-        s if s.len() == 1 => {
-            if let Some(c) = s.chars().next() {
-                key.code == KeyCode::Char(c)
-            } else {
-                false
-            }
+    // キー名をキーコードへ変換する。
+    let code = match key_str {
+        "Enter" | "enter" => KeyCode::Enter,
+        "Esc" | "esc" => KeyCode::Esc,
+        "Tab" | "tab" => KeyCode::Tab,
+        "Backspace" | "backspace" => KeyCode::Backspace,
+        "Delete" | "delete" => KeyCode::Delete,
+        "Up" | "up" => KeyCode::Up,
+        "Down" | "down" => KeyCode::Down,
+        "Left" | "left" => KeyCode::Left,
+        "Right" | "right" => KeyCode::Right,
+        "Home" | "home" => KeyCode::Home,
+        "End" | "end" => KeyCode::End,
+        "PageUp" | "pageup" => KeyCode::PageUp,
+        "PageDown" | "pagedown" => KeyCode::PageDown,
+        "Insert" | "insert" => KeyCode::Insert,
+        // ファンクションキーは"F1"〜"F12"として扱う。
+        s if s.len() > 1 && (s.starts_with('F') || s.starts_with('f')) => {
+            let n = s[1..].parse::<u8>().ok().filter(|n| (1..=12).contains(n))?;
+            KeyCode::F(n)
         }
-        _ => false,
-    }
+        // 単一文字は Char として扱う。
+        s if s.len() == 1 => KeyCode::Char(s.chars().next()?),
+        _ => return None,
+    };
+    Some((modifiers, code))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_load_or_default_missing_and_unreadable() {
+        // 無いファイルは警告なし、読めないパスは警告付きで既定値になることを検証する。
+        let base = std::env::temp_dir().join(format!("receipt_tui_{}", uuid::Uuid::new_v4()));
+        let missing = Shortcuts::load_or_default(base.join("shortcut.toml")).unwrap();
+        assert!(missing.warning.is_none());
+
+        // ディレクトリは読み込みに失敗する（NotFound以外のIOエラー）。
+        std::fs::create_dir(&base).unwrap();
+        let unreadable = Shortcuts::load_or_default(&base).unwrap();
+        assert!(unreadable.warning.unwrap().starts_with("cannot read"));
+        assert_eq!(unreadable.value.main.quit, Shortcuts::default().main.quit);
+        std::fs::remove_dir(&base).ok();
+    }
+
+    #[test]
+    fn test_uppercase_key_matches_with_shift() {
+        // 大文字のキーはShift付きで届いても一致し、小文字のキーとは区別されることを検証する。
+        let shifted = KeyEvent::new(KeyCode::Char('N'), KeyModifiers::SHIFT);
+        assert!(matches_shortcut(&shifted, &["N".into()]));
+        assert!(matches_shortcut(&shifted, &["Shift+N".into()]));
+        assert!(!matches_shortcut(&shifted, &["n".into()]));
+        let plain = KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE);
+        assert!(!matches_shortcut(&plain, &["N".into()]));
+        let ctrl = KeyEvent::new(
+            KeyCode::Char('N'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        );
+        assert!(!matches_shortcut(&ctrl, &["N".into()]));
+    }
+
+    #[test]
+    fn test_partial_file_merges_with_defaults() {
+        // 一部のキーだけを書いたファイルが既定値と合成され、空配列はキーを無効にすることを検証する。
+        let toml = r#"
+            [main]
+            quit = ["Ctrl+q"]
+            pause = []
+
+            [edit_job]
+            commit = ["Ctrl+Enter"]
+        "#;
+        let sc: Shortcuts = toml::from_str(toml).unwrap();
+        let defaults = Shortcuts::default();
+        assert_eq!(sc.main.quit, vec!["Ctrl+q".to_string()]);
+        assert!(sc.main.pause.is_empty());
+        assert_eq!(sc.main.refresh, defaults.main.refresh);
+        assert_eq!(sc.edit_job.commit, vec!["Ctrl+Enter".to_string()]);
+        assert_eq!(sc.edit_job.cancel, defaults.edit_job.cancel);
+        // 書かれていないグループは丸ごと既定値になる。
+        assert_eq!(sc.input_box.confirm, defaults.input_box.confirm);
+        assert_eq!(sc.global.settings, defaults.global.settings);
+    }
+
     #[test]
     fn test_matches_shortcut_simple_char() {
         // 単一文字の一致判定を検証する。
@@ -234,6 +640,75 @@ mod tests {
         assert!(!matches_shortcut(&key, &[String::from("Down")]));
     }
 
+    #[test]
+    fn test_matches_shortcut_function_and_paging_keys() {
+        // ファンクションキー・PageUp/PageDown・Insertの一致判定を検証する。
+        let f5 = KeyEvent::new(KeyCode::F(5), KeyModifiers::empty());
+        assert!(matches_shortcut(&f5, &[String::from("F5")]));
+        assert!(!matches_shortcut(&f5, &[String::from("F6")]));
+        let ctrl_f12 = KeyEvent::new(KeyCode::F(12), KeyModifiers::CONTROL);
+        assert!(matches_shortcut(&ctrl_f12, &[String::from("Ctrl+F12")]));
+        for (code, name) in [
+            (KeyCode::PageUp, "PageUp"),
+            (KeyCode::PageDown, "PageDown"),
+            (KeyCode::Insert, "Insert"),
+        ] {
+            let key = KeyEvent::new(code, KeyModifiers::empty());
+            assert!(matches_shortcut(&key, &[name.to_string()]));
+        }
+        // 単独の"F"は文字として扱い、範囲外の番号は解釈しない。
+        let f = KeyEvent::new(KeyCode::Char('F'), KeyModifiers::SHIFT);
+        assert!(matches_shortcut(&f, &[String::from("F")]));
+        assert!(!is_valid_shortcut("F0"));
+        assert!(!is_valid_shortcut("F13"));
+    }
+
+    #[test]
+    fn test_is_valid_shortcut() {
+        // ショートカット文字列の妥当性判定を検証する。
+        assert!(is_valid_shortcut("q"));
+        assert!(is_valid_shortcut("Ctrl+u"));
+        assert!(is_valid_shortcut("Enter"));
+        assert!(!is_valid_shortcut("Ctrl+"));
+        assert!(!is_valid_shortcut("Foo"));
+        assert!(!is_valid_shortcut("Hyper+a"));
+    }
+
+    #[test]
+    fn test_validate_reports_invalid_keys() {
+        // 不正なキー文字列が画面グループ付きで報告されることを検証する。
+        assert!(Shortcuts::default().validate().is_ok());
+        let mut sc = Shortcuts::default();
+        sc.main.refresh = vec!["Foo".into()];
+        let problems = sc.validate().unwrap_err();
+        assert_eq!(
+            problems,
+            vec!["main.refresh: invalid key \"Foo\"".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_conflicting_keys() {
+        // 同じ画面で複数の操作に割り当てたキーと、全画面共通のキーに隠れるキーが報告されることを検証する。
+        let mut sc = Shortcuts::default();
+        sc.main.refresh = vec!["q".into()];
+        sc.main.search_prev = vec!["Shift+N".into(), "N".into()];
+        sc.main.search_next = vec!["N".into()];
+        sc.logs.top = vec!["Ctrl+t".into()];
+        // 別の画面や全画面共通が効かない画面での重複は問題にしない。
+        sc.edit_job.commit = vec!["q".into()];
+        sc.wizard.skip = vec!["Ctrl+t".into()];
+        let problems = sc.validate().unwrap_err();
+        assert_eq!(
+            problems,
+            vec![
+                "global.settings hides logs.top (both use \"Ctrl+t\")".to_string(),
+                "main: \"q\" is bound to both quit and refresh".to_string(),
+                "main: \"N\" is bound to both search_next and search_prev".to_string(),
+            ]
+        );
+    }
+
     #[test]
     fn test_matches_shortcut_multiple_keys() {
         // 複数キーバインドの一致判定を検証する。
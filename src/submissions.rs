@@ -0,0 +1,127 @@
+//! 提出済みの領収書画像を記録するローカル台帳（二重提出の検出用）。
+//!
+//! 1行に「対象月（YYYY-MM）<TAB>画像のDrive ID」を追記していく。
+
+use anyhow::Result;
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::jobs::Job;
+
+/// 台帳ファイルのパス。
+pub const SUBMISSIONS_PATH: &str = "submissions.log";
+
+/// 提出記録1件。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Submission {
+    /// 提出先の対象月（YYYY-MM）。
+    pub month_ym: String,
+    /// 提出した画像のDrive ID。
+    pub drive_file_id: String,
+}
+
+/// 台帳の内容を解析する（形式が崩れた行は無視する）。
+pub fn parse(content: &str) -> Vec<Submission> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (month_ym, drive_file_id) = line.split_once('\t')?;
+            let (month_ym, drive_file_id) = (month_ym.trim(), drive_file_id.trim());
+            if month_ym.is_empty() || drive_file_id.is_empty() {
+                return None;
+            }
+            Some(Submission {
+                month_ym: month_ym.into(),
+                drive_file_id: drive_file_id.into(),
+            })
+        })
+        .collect()
+}
+
+/// 台帳ファイルを読み込む（無ければ空）。
+pub fn load(path: &Path) -> Vec<Submission> {
+    match fs::read_to_string(path) {
+        Ok(content) => parse(&content),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+        Err(e) => {
+            // 読めない場合は検出を諦め、提出自体は妨げない。
+            tracing::warn!("cannot read {}: {e}", path.display());
+            vec![]
+        }
+    }
+}
+
+/// 提出記録を台帳の末尾へ追記する。
+pub fn append(path: &Path, month_ym: &str, drive_file_id: &str) -> Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}\t{}", month_ym, drive_file_id)?;
+    Ok(())
+}
+
+/// 指定月にその画像が提出済みか判定する。
+pub fn is_submitted(records: &[Submission], month_ym: &str, drive_file_id: &str) -> bool {
+    records
+        .iter()
+        .any(|r| r.month_ym == month_ym && r.drive_file_id == drive_file_id)
+}
+
+/// 指定月に提出済みのジョブへ印を付ける。
+pub fn mark_already_submitted(jobs: &mut [Job], records: &[Submission], month_ym: &str) {
+    for job in jobs {
+        job.already_submitted = is_submitted(records, month_ym, &job.drive_file_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_skips_malformed_lines() {
+        // 正しい行だけが記録として読み込まれることを検証する。
+        let records = parse("2025-12\tfile-a\n\nbroken line\n2026-01\t file-b \n\tfile-c\n");
+        assert_eq!(
+            records,
+            vec![
+                Submission {
+                    month_ym: "2025-12".into(),
+                    drive_file_id: "file-a".into(),
+                },
+                Submission {
+                    month_ym: "2026-01".into(),
+                    drive_file_id: "file-b".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mark_already_submitted_matches_month_and_file() {
+        // 同じ月・同じ画像のジョブだけに印が付くことを検証する。
+        let records = parse("2025-12\tfile-a\n2025-11\tfile-b\n");
+        let mut jobs = vec![
+            Job::new("file-a".into(), "a.jpg".into()),
+            Job::new("file-b".into(), "b.jpg".into()),
+            Job::new("file-c".into(), "c.jpg".into()),
+        ];
+        mark_already_submitted(&mut jobs, &records, "2025-12");
+        let marks: Vec<bool> = jobs.iter().map(|j| j.already_submitted).collect();
+        assert_eq!(marks, vec![true, false, false]);
+    }
+
+    #[test]
+    fn test_append_then_load_roundtrip() {
+        // 追記した記録が読み込めることを検証する。
+        let path = std::env::temp_dir().join(format!("receipt_tui_{}.log", uuid::Uuid::new_v4()));
+        assert!(load(&path).is_empty());
+        append(&path, "2025-12", "file-a").unwrap();
+        append(&path, "2025-12", "file-b").unwrap();
+        let records = load(&path);
+        fs::remove_file(&path).ok();
+        assert!(is_submitted(&records, "2025-12", "file-b"));
+        assert!(!is_submitted(&records, "2026-01", "file-b"));
+    }
+}
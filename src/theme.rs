@@ -0,0 +1,145 @@
+//! 画面の配色設定（`theme.toml`）。
+
+use anyhow::Result;
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::config::{self, Loaded};
+
+/// 既定の配色設定ファイルのパス。
+pub const DEFAULT_THEME_PATH: &str = "theme.toml";
+
+/// 画面の配色。
+///
+/// 色は`"red"`・`"lightblue"`のような名前、`"#ff8c00"`のような16進、`"208"`のような256色の番号で書く。
+/// ファイルに無い項目は既定値を使う。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// ジョブ一覧の選択行の背景色。
+    #[serde(with = "color_str")]
+    pub selection_bg: Color,
+    /// ジョブ一覧の選択行の文字色。
+    #[serde(with = "color_str")]
+    pub selection_fg: Color,
+    /// エラー表示（ステータスバー・エラー一覧）の文字色。
+    #[serde(with = "color_str")]
+    pub error_fg: Color,
+    /// 入力ボックスのプロンプトの文字色。
+    #[serde(with = "color_str")]
+    pub prompt_fg: Color,
+    /// 入力中の文字列の文字色。
+    #[serde(with = "color_str")]
+    pub input_fg: Color,
+    /// 補完候補や操作説明など、控えめに表示する文字色。
+    #[serde(with = "color_str")]
+    pub hint_fg: Color,
+    /// ポップアップの背景色。
+    #[serde(with = "color_str")]
+    pub popup_bg: Color,
+    /// 進捗ゲージの色。
+    #[serde(with = "color_str")]
+    pub gauge_fg: Color,
+}
+
+impl Default for Theme {
+    /// これまでの固定の配色と同じ既定値。
+    fn default() -> Self {
+        Self {
+            selection_bg: Color::Rgb(255, 140, 0),
+            selection_fg: Color::Black,
+            error_fg: Color::Red,
+            prompt_fg: Color::Cyan,
+            input_fg: Color::Green,
+            hint_fg: Color::Gray,
+            popup_bg: Color::DarkGray,
+            gauge_fg: Color::Green,
+        }
+    }
+}
+
+impl Theme {
+    /// TOMLから読み込み、無ければデフォルトを返す。
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Result<Loaded<Self>> {
+        match config::read_optional(path.as_ref()) {
+            // 既存ファイルをパースする。
+            Ok(Some(content)) => Ok(Loaded {
+                value: toml::from_str(&content)?,
+                warning: None,
+            }),
+            // 未作成の場合は既定値を利用する。
+            Ok(None) => Ok(Loaded {
+                value: Self::default(),
+                warning: None,
+            }),
+            // 読めない場合も既定値で起動し、警告を返す。
+            Err(warning) => {
+                tracing::warn!("{warning}");
+                Ok(Loaded {
+                    value: Self::default(),
+                    warning: Some(warning),
+                })
+            }
+        }
+    }
+}
+
+/// `Color`を文字列として読み書きする（serdeの`with`用）。
+mod color_str {
+    use ratatui::style::Color;
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(color: &Color, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&color.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Color, D::Error> {
+        let s = String::deserialize(d)?;
+        s.parse()
+            .map_err(|_| D::Error::custom(format!("invalid color {s:?}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_theme_merges_with_defaults() {
+        // 書いた色だけが置き換わり、名前・16進・番号のいずれでも指定できることを検証する。
+        let theme: Theme = toml::from_str(
+            r##"
+            error_fg = "lightblue"
+            selection_bg = "#0000ff"
+            prompt_fg = "208"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(theme.error_fg, Color::LightBlue);
+        assert_eq!(theme.selection_bg, Color::Rgb(0, 0, 255));
+        assert_eq!(theme.prompt_fg, Color::Indexed(208));
+        assert_eq!(theme.selection_fg, Theme::default().selection_fg);
+
+        // 保存した内容を読み直しても同じ配色になる。
+        let saved = toml::to_string(&theme).unwrap();
+        assert_eq!(toml::from_str::<Theme>(&saved).unwrap(), theme);
+    }
+
+    #[test]
+    fn test_invalid_color_is_rejected() {
+        // 解釈できない色は理由付きの読み込みエラーになることを検証する。
+        let err = toml::from_str::<Theme>(r#"error_fg = "reddish""#).unwrap_err();
+        assert!(err.to_string().contains("invalid color \"reddish\""));
+    }
+
+    #[test]
+    fn test_load_or_default_missing_file() {
+        // ファイルが無ければ警告なしで既定の配色になることを検証する。
+        let path =
+            std::env::temp_dir().join(format!("receipt_tui_theme_{}.toml", uuid::Uuid::new_v4()));
+        let loaded = Theme::load_or_default(&path).unwrap();
+        assert!(loaded.warning.is_none());
+        assert_eq!(loaded.value, Theme::default());
+    }
+}
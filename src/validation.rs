@@ -0,0 +1,193 @@
+//! 入力値の検証・正規化ヘルパー。
+
+/// 貼り付けたIDに紛れ込みやすいゼロ幅文字。
+const ZERO_WIDTH_CHARS: [char; 5] = [
+    '\u{200B}', // ZERO WIDTH SPACE
+    '\u{200C}', // ZERO WIDTH NON-JOINER
+    '\u{200D}', // ZERO WIDTH JOINER
+    '\u{2060}', // WORD JOINER
+    '\u{FEFF}', // BOM / ZERO WIDTH NO-BREAK SPACE
+];
+
+/// Drive/SheetsのIDから前後の空白と不可視文字を取り除く。
+pub fn sanitize_id(raw: &str) -> String {
+    // ゼロ幅文字と制御文字（タブ・改行含む）を除去する。
+    let stripped: String = raw
+        .chars()
+        .filter(|c| !ZERO_WIDTH_CHARS.contains(c) && !c.is_control())
+        .collect();
+    // 前後の空白を取り除く。
+    stripped.trim().to_string()
+}
+
+/// 貼り付けた値からDrive/SheetsのIDを取り出す。
+///
+/// `https://drive.google.com/drive/folders/<ID>`や`https://docs.google.com/spreadsheets/d/<ID>/edit`の
+/// ようなURLならIDの部分を返し、それ以外は`sanitize_id`した値をそのまま返す。
+pub fn extract_drive_id(raw: &str) -> String {
+    let cleaned = sanitize_id(raw);
+    for marker in ["/folders/", "/d/"] {
+        if let Some((_, rest)) = cleaned.split_once(marker) {
+            // IDに使われる英数字・`-`・`_`が続く範囲を取り出す。
+            let id: String = rest
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+                .collect();
+            if !id.is_empty() {
+                return id;
+            }
+        }
+    }
+    cleaned
+}
+
+/// ファイル名に使えないOSがある文字。
+const FORBIDDEN_FILE_NAME_CHARS: [char; 9] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// ファイル名に使えない文字と制御文字を`_`に置き換える。
+///
+/// Driveでは使えても、同期先のWindows/macOSで扱えない名前にしないため。
+pub fn sanitize_file_name(raw: &str) -> String {
+    raw.chars()
+        .map(|c| {
+            if FORBIDDEN_FILE_NAME_CHARS.contains(&c) || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// 列指定（例: "B", "AA"）として妥当か判定する。
+pub fn is_valid_column(col: &str) -> bool {
+    // Sheetsの列は最大3文字の英大文字で表される。
+    !col.is_empty() && col.len() <= 3 && col.chars().all(|c| c.is_ascii_uppercase())
+}
+
+/// A1形式のセル番地（例: "F3"）として妥当か判定する。
+pub fn is_valid_cell(cell: &str) -> bool {
+    // 列部分と行部分に分ける。
+    let split = cell
+        .find(|c: char| c.is_ascii_digit())
+        .unwrap_or(cell.len());
+    let (col, row) = cell.split_at(split);
+    // 列が妥当で、行が1以上の数値であること。
+    is_valid_column(col) && row.parse::<u32>().is_ok_and(|r| r >= 1)
+}
+
+/// メールアドレス（例: "name@example.com"）の形か判定する。
+pub fn is_valid_email(email: &str) -> bool {
+    // `@`の前後が空でなく、ドメインに`.`を含み、空白が無いこと。
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !domain.contains('@')
+                && !email.chars().any(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_column() {
+        // 列指定の妥当性判定を検証する。
+        assert!(is_valid_column("B"));
+        assert!(is_valid_column("AA"));
+        assert!(!is_valid_column(""));
+        assert!(!is_valid_column("b"));
+        assert!(!is_valid_column("B1"));
+        assert!(!is_valid_column("ABCD"));
+    }
+
+    #[test]
+    fn test_is_valid_cell() {
+        // セル番地の妥当性判定を検証する。
+        assert!(is_valid_cell("F3"));
+        assert!(is_valid_cell("AB120"));
+        assert!(!is_valid_cell("F"));
+        assert!(!is_valid_cell("3"));
+        assert!(!is_valid_cell("F0"));
+        assert!(!is_valid_cell("F3X"));
+    }
+
+    #[test]
+    fn test_sanitize_file_name_replaces_separators() {
+        // パス区切りやドライブ区切りなど使えない文字が置き換わることを検証する。
+        assert_eq!(
+            sanitize_file_name("2025/12/19 10:30.pdf"),
+            "2025_12_19 10_30.pdf"
+        );
+        assert_eq!(sanitize_file_name(r#"a\b*c?"d<e>f|g"#), "a_b_c__d_e_f_g");
+        assert_eq!(sanitize_file_name(" 経費\t精算.pdf "), "経費_精算.pdf");
+    }
+
+    #[test]
+    fn test_extract_drive_id_from_urls() {
+        // フォルダやシートのURLからIDだけを取り出し、IDはそのまま残すことを検証する。
+        assert_eq!(
+            extract_drive_id("https://drive.google.com/drive/folders/1AbC-d_E?usp=sharing"),
+            "1AbC-d_E"
+        );
+        assert_eq!(
+            extract_drive_id(" https://docs.google.com/spreadsheets/d/1xYz_9-Q/edit#gid=0\n"),
+            "1xYz_9-Q"
+        );
+        assert_eq!(
+            extract_drive_id("https://drive.google.com/drive/u/0/folders/abc123"),
+            "abc123"
+        );
+        assert_eq!(extract_drive_id("\u{200B}1AbC-d_E "), "1AbC-d_E");
+        assert_eq!(
+            extract_drive_id("https://example.com/d/"),
+            "https://example.com/d/"
+        );
+    }
+
+    #[test]
+    fn test_is_valid_email() {
+        // メールアドレスの形かどうかの判定を検証する。
+        assert!(is_valid_email("boss@example.com"));
+        assert!(is_valid_email("a.b+c@mail.example.co.jp"));
+        assert!(!is_valid_email("accounting"));
+        assert!(!is_valid_email("@example.com"));
+        assert!(!is_valid_email("boss@localhost"));
+        assert!(!is_valid_email("boss@example.com."));
+        assert!(!is_valid_email("boss @example.com"));
+    }
+
+    #[test]
+    fn test_sanitize_id_trailing_spaces() {
+        // 前後の空白が除去されることを検証する。
+        assert_eq!(sanitize_id("  1AbC-xyz  "), "1AbC-xyz");
+    }
+
+    #[test]
+    fn test_sanitize_id_tabs_and_newlines() {
+        // タブや改行が除去されることを検証する。
+        assert_eq!(sanitize_id("\t1AbC-xyz\n"), "1AbC-xyz");
+        assert_eq!(sanitize_id("1AbC\t-xyz"), "1AbC-xyz");
+    }
+
+    #[test]
+    fn test_sanitize_id_zero_width_chars() {
+        // ゼロ幅文字（ZWJ/ZWSP/BOM）が除去されることを検証する。
+        assert_eq!(sanitize_id("1AbC\u{200D}-xyz"), "1AbC-xyz");
+        assert_eq!(sanitize_id("\u{FEFF}1AbC-xyz\u{200B}"), "1AbC-xyz");
+    }
+
+    #[test]
+    fn test_sanitize_id_clean_value_unchanged() {
+        // 正常なIDはそのまま返ることを検証する。
+        assert_eq!(sanitize_id("1AbC-xyz_09"), "1AbC-xyz_09");
+    }
+}
@@ -15,10 +15,61 @@ pub enum WizardStep {
     TemplateSheetId,
     /// ユーザー名
     UserName,
+    /// 接続テスト
+    Verify,
     /// 完了
     Complete,
 }
 
+/// 接続テストで確かめる項目
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SetupCheck {
+    /// アクセストークンを取得できる
+    Token,
+    /// 入力フォルダの一覧を取得できる
+    InputFolder,
+    /// テンプレートがスプレッドシートへ解決できる
+    Template,
+}
+
+impl SetupCheck {
+    /// 確認する順の全項目
+    pub const ALL: [SetupCheck; 3] = [
+        SetupCheck::Token,
+        SetupCheck::InputFolder,
+        SetupCheck::Template,
+    ];
+
+    /// 画面表示用の名前
+    pub fn label(self) -> &'static str {
+        match self {
+            SetupCheck::Token => "Access token",
+            SetupCheck::InputFolder => "Input folder",
+            SetupCheck::Template => "Template sheet",
+        }
+    }
+
+    /// 失敗したときに直しに戻るステップ
+    pub fn step(self) -> WizardStep {
+        match self {
+            SetupCheck::Token => WizardStep::CheckAuth,
+            SetupCheck::InputFolder => WizardStep::InputFolderId,
+            SetupCheck::Template => WizardStep::TemplateSheetId,
+        }
+    }
+}
+
+/// 接続テストの各項目の結果
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CheckState {
+    /// 確認中
+    Pending,
+    /// 成功
+    Passed,
+    /// 失敗（理由付き）
+    Failed(String),
+}
+
 /// ウィザードの状態管理
 #[derive(Clone, Debug)]
 pub struct WizardState {
@@ -26,6 +77,8 @@ pub struct WizardState {
     pub current_step: WizardStep,
     /// 全ステップ数
     pub total_steps: usize,
+    /// 接続テストの結果（未実行なら空）
+    pub checks: Vec<(SetupCheck, CheckState)>,
 }
 
 impl WizardState {
@@ -34,7 +87,8 @@ impl WizardState {
         // 最初はWelcomeステップから開始する。
         Self {
             current_step: WizardStep::Welcome,
-            total_steps: 7,
+            total_steps: 8,
+            checks: vec![],
         }
     }
 
@@ -47,9 +101,75 @@ impl WizardState {
             WizardStep::InputFolderId => WizardStep::OutputFolderId,
             WizardStep::OutputFolderId => WizardStep::TemplateSheetId,
             WizardStep::TemplateSheetId => WizardStep::UserName,
-            WizardStep::UserName => WizardStep::Complete,
+            WizardStep::UserName => WizardStep::Verify,
+            WizardStep::Verify => WizardStep::Complete,
             WizardStep::Complete => WizardStep::Complete,
         };
+        // 接続テストの結果は、そのステップを離れたら使わない。
+        self.checks.clear();
+    }
+
+    /// 前のステップへ戻る（Welcomeでは何もしない）
+    pub fn prev_step(&mut self) {
+        // `next_step`の逆向きに前のステップを決定する。
+        self.current_step = match self.current_step {
+            WizardStep::Welcome => WizardStep::Welcome,
+            WizardStep::CheckAuth => WizardStep::Welcome,
+            WizardStep::InputFolderId => WizardStep::CheckAuth,
+            WizardStep::OutputFolderId => WizardStep::InputFolderId,
+            WizardStep::TemplateSheetId => WizardStep::OutputFolderId,
+            WizardStep::UserName => WizardStep::TemplateSheetId,
+            WizardStep::Verify => WizardStep::UserName,
+            WizardStep::Complete => WizardStep::Verify,
+        };
+        // 接続テストの結果は、そのステップを離れたら使わない。
+        self.checks.clear();
+    }
+
+    /// 接続テストを始め、全項目を確認中にする
+    pub fn start_checks(&mut self) {
+        self.checks = SetupCheck::ALL
+            .iter()
+            .map(|c| (*c, CheckState::Pending))
+            .collect();
+    }
+
+    /// 確認中の項目に結果を記録する（実行中でなければ無視する）
+    pub fn record_check(&mut self, check: SetupCheck, error: Option<String>) {
+        if let Some((_, state)) = self
+            .checks
+            .iter_mut()
+            .find(|(c, s)| *c == check && *s == CheckState::Pending)
+        {
+            *state = match error {
+                None => CheckState::Passed,
+                Some(e) => CheckState::Failed(e),
+            };
+        }
+    }
+
+    /// 接続テストを実行中か
+    pub fn checks_running(&self) -> bool {
+        self.checks.iter().any(|(_, s)| *s == CheckState::Pending)
+    }
+
+    /// 接続テストが全項目成功したか
+    pub fn checks_passed(&self) -> bool {
+        !self.checks.is_empty() && self.checks.iter().all(|(_, s)| *s == CheckState::Passed)
+    }
+
+    /// 最初に失敗した項目
+    pub fn first_failed_check(&self) -> Option<SetupCheck> {
+        self.checks
+            .iter()
+            .find(|(_, s)| matches!(s, CheckState::Failed(_)))
+            .map(|(c, _)| *c)
+    }
+
+    /// 指定したステップへ戻る（接続テストの結果は捨てる）
+    pub fn go_to(&mut self, step: WizardStep) {
+        self.current_step = step;
+        self.checks.clear();
     }
 
     /// 現在のステップのプロンプトメッセージを取得
@@ -74,6 +194,9 @@ impl WizardState {
             WizardStep::UserName => {
                 "ユーザー名の設定\n\nあなたの氏名を入力してください。\nEnterキーで入力画面を開きます。".to_string()
             }
+            WizardStep::Verify => {
+                "接続テスト\n\n認証・入力フォルダ・テンプレートが使えるか確認します。\nEnterキーで実行し、失敗した項目があればEnterキーで該当のステップへ戻ります。".to_string()
+            }
             WizardStep::Complete => {
                 "設定完了！\n\nすべての設定が完了しました。\nEnterキーを押してメイン画面に移動します。".to_string()
             }
@@ -90,7 +213,8 @@ impl WizardState {
             WizardStep::OutputFolderId => 4,
             WizardStep::TemplateSheetId => 5,
             WizardStep::UserName => 6,
-            WizardStep::Complete => 7,
+            WizardStep::Verify => 7,
+            WizardStep::Complete => 8,
         }
     }
 }
@@ -100,3 +224,48 @@ impl Default for WizardState {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prev_step_inverts_next_step() {
+        // 進んだステップを戻ると元のステップと番号に戻り、Welcomeでは動かないことを検証する。
+        let mut state = WizardState::new();
+        state.prev_step();
+        assert_eq!(state.current_step, WizardStep::Welcome);
+        for number in 1..state.total_steps {
+            let before = state.current_step.clone();
+            state.next_step();
+            assert_eq!(state.get_step_number(), number + 1);
+            state.prev_step();
+            assert_eq!(state.current_step, before);
+            assert_eq!(state.get_step_number(), number);
+            state.next_step();
+        }
+        assert_eq!(state.current_step, WizardStep::Complete);
+    }
+
+    #[test]
+    fn test_checks_record_results_and_reset_on_leave() {
+        // 接続テストの結果を記録して失敗項目を返し、ステップを離れると捨てることを検証する。
+        let mut state = WizardState::new();
+        state.current_step = WizardStep::Verify;
+        state.start_checks();
+        state.record_check(SetupCheck::Token, None);
+        state.record_check(SetupCheck::Template, Some("not a sheet".into()));
+        assert!(state.checks_running());
+        state.record_check(SetupCheck::InputFolder, None);
+        assert!(!state.checks_running());
+        assert!(!state.checks_passed());
+        assert_eq!(state.first_failed_check(), Some(SetupCheck::Template));
+
+        state.go_to(SetupCheck::Template.step());
+        assert_eq!(state.current_step, WizardStep::TemplateSheetId);
+        assert!(state.checks.is_empty());
+        // 実行中でなければ遅れて届いた結果は無視する。
+        state.record_check(SetupCheck::Token, None);
+        assert!(state.checks.is_empty());
+    }
+}
@@ -1,43 +1,225 @@
 //! Google APIジョブを処理するバックグラウンドワーカー。
 
 use crate::{
-    config::Config,
-    google::{auth, drive, sheets},
-    jobs::{Job, JobStatus, ReceiptFields},
+    breaker::{self, BreakerStatus, CircuitBreaker},
+    config::{Config, GeneralExpenseCfg, TargetMode, VerifyWrite},
+    dates,
+    google::{
+        auth, drive,
+        error::{self, WorkerError},
+        sheets,
+        token_store::FileTokenStorage,
+        vision,
+    },
+    jobs::{self, Job, JobStatus, ReceiptFields},
+    ocr::{self, OcrGuess},
+    submissions, validation,
+    wizard::SetupCheck,
 };
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
 use reqwest::Client;
+use std::{
+    collections::HashMap,
+    path::Path,
+    time::{Duration, Instant},
+};
 use tokio::sync::mpsc;
+use yup_oauth2::storage::TokenStorage;
+
+/// アクセストークンの残り時間を確かめる間隔。
+const TOKEN_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// 残り時間がこれを下回ったら、次の操作を待たずにトークンを更新する。
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(120);
+
+/// コピー直後のシート情報取得を試みる最大回数。
+const COPY_PROPAGATION_ATTEMPTS: u32 = 4;
+/// コピー直後のシート情報取得の再試行間隔。
+const COPY_PROPAGATION_DELAY: Duration = Duration::from_millis(500);
+/// 診断時に読み出す経費行の行数。
+const DIAGNOSTICS_ROWS: u32 = 50;
 
 /// UIからWorkerへ送るコマンド。
 #[derive(Debug)]
 pub enum WorkerCmd {
     /// Driveを再スキャンして入力画像を取得する。
     RefreshJobs,
+    /// 続きのページを読み込んでジョブ一覧へ追加する。
+    LoadMoreJobs { page_token: String },
     /// 設定を保存し反映する。
-    SaveSettings(Config),
+    SaveSettings(Box<Config>),
     /// 編集内容を書き込み、PDFをエクスポート/アップロードする。
-    CommitJobEdits {
+    CommitJobEdits(CommitRequest),
+    /// 複数のジョブを順に確定する（入力が足りないジョブは飛ばす）。
+    ///
+    /// `one_sheet_per_month`なら、対象月ごとに1枚のシートへ行をまとめ、PDFも1つにする。
+    CommitAll {
+        requests: Vec<CommitRequest>,
+        one_sheet_per_month: bool,
+    },
+    /// 選択ジョブに関わるシートを読み取り専用で取得し、生のJSONを返す。
+    Diagnose {
         job_id: uuid::Uuid,
-        fields: ReceiptFields,
-        target_month_ym: String,
+        copied_sheet_id: Option<String>,
+    },
+    /// テンプレートを読み取り、確定時に書き込まれる行の位置を見積もる。
+    PreviewWrite { rows: u32 },
+    /// 確定済みの経費行をシートから読み戻す。
+    PullFromSheet {
+        job_id: uuid::Uuid,
+        sheet_id: String,
+        range: String,
+    },
+    /// 保存済みのトークンを失効させて削除する（次のAPI呼び出しで再認証になる）。
+    Logout,
+    /// 元画像を文字認識し、日付と合計金額を推測する。
+    OcrJob {
+        job_id: uuid::Uuid,
+        drive_file_id: String,
+    },
+    /// テンプレートの見出し行を読み、列の割り当てに使う。
+    FetchTemplateHeaders {
+        template_sheet_id: String,
+        header_row: u32,
     },
+    /// ウィザードで入力したIDが存在し、用途に合う種類か確かめる。
+    CheckId { kind: IdKind, id: String },
+    /// ウィザードの接続テスト（トークン取得・入力フォルダの一覧・テンプレートの解決）。
+    VerifySetup {
+        input_folder_id: String,
+        template_sheet_id: String,
+    },
+}
+
+/// ウィザードで確かめるIDの種類。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdKind {
+    /// 入力フォルダ（Driveフォルダであること）。
+    InputFolder,
+    /// 出力フォルダ（Driveフォルダであること）。
+    OutputFolder,
+    /// テンプレート（スプレッドシートへ解決できること）。
+    TemplateSheet,
+}
+
+impl IdKind {
+    /// 画面表示用の名前。
+    pub fn label(self) -> &'static str {
+        match self {
+            IdKind::InputFolder => "Input folder ID",
+            IdKind::OutputFolder => "Output folder ID",
+            IdKind::TemplateSheet => "Template sheet ID",
+        }
+    }
+}
+
+/// 1ジョブ分のコミットに必要な入力。
+#[derive(Clone, Debug)]
+pub struct CommitRequest {
+    /// 状態更新に使うジョブID。
+    pub job_id: uuid::Uuid,
+    /// 元画像のDriveファイルID。
+    pub drive_file_id: String,
+    /// ログ表示用の元画像のファイル名。
+    pub filename: String,
+    /// 元ファイルのMIMEタイプ（画像以外は埋め込まない）。
+    pub mime_type: String,
+    /// シートへ書き込む入力項目。
+    pub fields: ReceiptFields,
+    /// 対象月（YYYY-MM）。
+    pub target_month_ym: String,
+}
+
+impl CommitRequest {
+    /// ジョブの現在の入力項目から確定リクエストを作る。
+    pub fn for_job(job: &Job, target_month_ym: String) -> Self {
+        Self {
+            job_id: job.id,
+            drive_file_id: job.drive_file_id.clone(),
+            filename: job.filename.clone(),
+            mime_type: job.mime_type.clone(),
+            fields: job.fields.clone(),
+            target_month_ym,
+        }
+    }
 }
 
 /// UI更新用にWorkerから送るイベント。
 #[derive(Clone, Debug)]
 pub enum WorkerEvent {
-    /// Driveから取得したジョブ一覧。
-    JobsLoaded(Vec<Job>),
+    /// Driveから取得したジョブ一覧（続きがあれば次ページのトークン付き）。
+    JobsLoaded {
+        jobs: Vec<Job>,
+        next_page_token: Option<String>,
+    },
+    /// 続きのページとして追加するジョブ一覧。
+    JobsAppended {
+        jobs: Vec<Job>,
+        next_page_token: Option<String>,
+    },
     /// 単一ジョブのステータス更新。
     JobUpdated {
         job_id: uuid::Uuid,
         status: JobStatus,
     },
+    /// 確定に失敗した（`ui.interactive_retry`有効時はUIが再試行を確認する）。
+    CommitFailed {
+        job_id: uuid::Uuid,
+        target_month_ym: String,
+        error: WorkerError,
+    },
+    /// 確定中にテンプレートのコピーを作成した。
+    SheetCopied {
+        job_id: uuid::Uuid,
+        copied_sheet_id: String,
+    },
+    /// 診断で取得した生のレスポンス。
+    Diagnostics { job_id: uuid::Uuid, text: String },
+    /// 確定時に書き込まれる行の見積もり。
+    WritePlanned(sheets::WritePlan),
+    /// 一括確定が終わった（件数の内訳と、送られてきたジョブのID付き）。
+    BatchFinished {
+        job_ids: Vec<uuid::Uuid>,
+        committed: usize,
+        total: usize,
+        skipped: usize,
+    },
+    /// 確定中に経費行を書き込んだ。
+    RowWritten { job_id: uuid::Uuid, range: String },
+    /// シートから読み戻した経費行の内容。
+    SheetPulled {
+        job_id: uuid::Uuid,
+        fields: ReceiptFields,
+    },
+    /// 文字認識で推測した入力項目（自信の無い項目は`None`）。
+    OcrGuessed { job_id: uuid::Uuid, guess: OcrGuess },
+    /// テンプレートの見出し行（列名, 見出し）。
+    TemplateHeaders {
+        header_row: u32,
+        columns: Vec<(String, String)>,
+    },
+    /// IDの確認結果（問題が無ければ`error`は`None`）。
+    IdChecked {
+        kind: IdKind,
+        id: String,
+        error: Option<String>,
+    },
+    /// 接続テストの1項目の結果（成功なら`error`は`None`）。
+    SetupChecked {
+        check: SetupCheck,
+        error: Option<String>,
+    },
+    /// サーキットブレーカーの状態が変わった。
+    Breaker(BreakerStatus),
+    /// ログアウトした（保存済みのトークンを削除した）。
+    LoggedOut,
     /// 情報ログ。
     Log(String),
-    /// ユーザーに見せるエラーメッセージ。
-    Error(String),
+    /// ユーザーに見せるエラー（種別に応じてUIが対応を選ぶ）。
+    Error(WorkerError),
+    /// ジョブ一覧の読み込み（リフレッシュ/続きのページ）に失敗した。
+    ///
+    /// 他の操作のエラーと分け、一覧の読み込み待ちだけを解除できるようにする。
+    ListFailed(WorkerError),
 }
 
 /// ワーカーメインループ：認証後、コマンドを逐次処理する。
@@ -45,32 +227,55 @@ pub async fn run(
     mut rx: mpsc::Receiver<WorkerCmd>,
     tx: mpsc::Sender<WorkerEvent>,
     mut cfg: Config,
+    dry_run: bool,
 ) {
     // 全API呼び出しで共有するHTTPクライアント。
     let http = Client::new();
     tracing::info!("worker started");
 
     // OAuth初期化は一度だけ行い、失敗時は終了する。
-    let authn = match auth::authenticator().await {
+    let authn = match auth::authenticator(Path::new(cfg.google.credentials_path())).await {
         Ok(a) => a,
         Err(e) => {
             tracing::error!("OAuth init failed: {e}");
             // UIへエラーを通知して終了する。
             let _ = tx
-                .send(WorkerEvent::Error(format!("OAuth init failed: {e}")))
+                .send(WorkerEvent::Error(WorkerError::Auth(format!(
+                    "OAuth init failed: {e}"
+                ))))
                 .await;
             return;
         }
     };
     tracing::info!("OAuth authenticator ready");
 
+    // 確定をまたいで保持する状態。
+    let mut state = CommitState {
+        dry_run,
+        ..CommitState::default()
+    };
+
+    // 待機中にトークンの期限が近づいたら更新し、次の操作で更新待ちにならないようにする。
+    let mut token_check = tokio::time::interval(TOKEN_CHECK_INTERVAL);
+    token_check.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
     // 状態整合性のため、コマンドは逐次処理する。
-    while let Some(cmd) = rx.recv().await {
+    loop {
+        let cmd = tokio::select! {
+            cmd = rx.recv() => cmd,
+            _ = token_check.tick() => {
+                refresh_token_if_expiring(&authn, &tx).await;
+                continue;
+            }
+        };
+        let Some(cmd) = cmd else {
+            break;
+        };
         match cmd {
             WorkerCmd::SaveSettings(new_cfg) => {
                 tracing::info!("settings updated");
                 // 設定を更新してログ通知する。
-                cfg = new_cfg;
+                cfg = *new_cfg;
                 let _ = tx.send(WorkerEvent::Log("settings updated".into())).await;
             }
 
@@ -80,121 +285,767 @@ pub async fn run(
                 if cfg.google.input_folder_id.is_empty() {
                     tracing::warn!("refresh aborted: input_folder_id missing");
                     let _ = tx
-                        .send(WorkerEvent::Error("input_folder_id is not set".into()))
+                        .send(WorkerEvent::ListFailed(WorkerError::Other(
+                            "input_folder_id is not set".into(),
+                        )))
                         .await;
                     continue;
                 }
 
-                match access_token(&authn).await {
-                    Ok(token) => {
-                        tracing::info!("access token acquired");
-                        // 画像ファイル一覧を取得し、編集可能なジョブへ変換する。
-                        match drive::list_images_in_folder(
-                            &http,
-                            &token,
-                            &cfg.google.input_folder_id,
-                        )
-                        .await
-                        {
-                            Ok(files) => {
-                                tracing::info!("drive list success: {} files", files.len());
-                                // 各ファイルをジョブに変換し、初期状態をセットする。
-                                let jobs = files
-                                    .into_iter()
-                                    .map(|f| {
-                                        let mut j = Job::new(f.id, f.name);
-                                        // ユーザーが編集できるよう初期状態を設定する。
-                                        j.status = JobStatus::WaitingUserFix;
-                                        j
-                                    })
-                                    .collect::<Vec<_>>();
-                                // UIへ一覧更新イベントを送る。
-                                let _ = tx.send(WorkerEvent::JobsLoaded(jobs)).await;
-                            }
-                            Err(e) => {
-                                tracing::error!("drive list failed: {e}");
-                                // 取得失敗をUIへ通知する。
-                                let _ = tx
-                                    .send(WorkerEvent::Error(format!("list failed: {e}")))
-                                    .await;
-                            }
-                        }
+                // 先頭ページを読み込み、UIへ一覧更新イベントを送る。
+                match load_jobs_page(&http, &authn, &cfg, None).await {
+                    Ok((jobs, next_page_token)) => {
+                        let _ = tx
+                            .send(WorkerEvent::JobsLoaded {
+                                jobs,
+                                next_page_token,
+                            })
+                            .await;
                     }
                     Err(e) => {
-                        tracing::error!("token failed: {e}");
-                        // トークン取得失敗をUIへ通知する。
+                        tracing::error!("{e:#}");
+                        // 取得失敗を分類してUIへ通知する。
+                        let _ = tx.send(WorkerEvent::ListFailed(e.into())).await;
+                    }
+                }
+            }
+
+            WorkerCmd::LoadMoreJobs { page_token } => {
+                tracing::info!("load more jobs");
+                // 続きのページを読み込み、UIへ追加イベントを送る。
+                match load_jobs_page(&http, &authn, &cfg, Some(&page_token)).await {
+                    Ok((jobs, next_page_token)) => {
                         let _ = tx
-                            .send(WorkerEvent::Error(format!("token failed: {e}")))
+                            .send(WorkerEvent::JobsAppended {
+                                jobs,
+                                next_page_token,
+                            })
                             .await;
                     }
+                    Err(e) => {
+                        tracing::error!("{e:#}");
+                        // 取得失敗を分類してUIへ通知する。
+                        let _ = tx.send(WorkerEvent::ListFailed(e.into())).await;
+                    }
                 }
             }
 
-            WorkerCmd::CommitJobEdits {
-                job_id,
-                fields,
-                target_month_ym,
+            WorkerCmd::CommitJobEdits(req) => {
+                commit_and_report(
+                    &http,
+                    &authn,
+                    &cfg,
+                    std::slice::from_ref(&req),
+                    &mut state,
+                    &tx,
+                )
+                .await;
+            }
+
+            WorkerCmd::CommitAll {
+                requests,
+                one_sheet_per_month,
             } => {
-                tracing::info!("commit job start: {job_id}");
-                // UIに即時反映させるためステータスを先に更新する。
+                tracing::info!("commit all: {} jobs", requests.len());
+                let total = requests.len();
+                let job_ids = requests.iter().map(|r| r.job_id).collect();
+                let mut committed = 0;
+                let mut skipped = 0;
+                // 二重提出の検出が有効なら、提出済みの画像も飛ばす。
+                let submitted = if cfg.duplicates.detect {
+                    submissions::load(Path::new(submissions::SUBMISSIONS_PATH))
+                } else {
+                    vec![]
+                };
+                // 入力が足りないジョブを飛ばし、確定できるものだけを残す。
+                let mut ready = vec![];
+                for mut req in requests {
+                    let prepared = if submissions::is_submitted(
+                        &submitted,
+                        &req.target_month_ym,
+                        &req.drive_file_id,
+                    ) {
+                        Err(format!(
+                            "{}: already submitted for {}",
+                            req.filename, req.target_month_ym
+                        ))
+                    } else {
+                        prepare_batch_request(&cfg, &mut req)
+                    };
+                    match prepared {
+                        Ok(notes) => {
+                            for note in notes {
+                                let _ = tx.send(WorkerEvent::Log(note)).await;
+                            }
+                        }
+                        Err(reason) => {
+                            tracing::warn!("commit all: skipped {}: {reason}", req.job_id);
+                            skipped += 1;
+                            let _ = tx
+                                .send(WorkerEvent::Log(format!("skipped: {reason}")))
+                                .await;
+                            continue;
+                        }
+                    }
+                    ready.push(req);
+                }
+                // 1件ずつ、またはシート1枚分ずつ順に確定する。
+                let groups = if one_sheet_per_month {
+                    group_by_month(ready)
+                } else {
+                    ready.into_iter().map(|req| vec![req]).collect()
+                };
+                for group in groups {
+                    if commit_and_report(&http, &authn, &cfg, &group, &mut state, &tx).await {
+                        committed += group.len();
+                    }
+                }
                 let _ = tx
-                    .send(WorkerEvent::JobUpdated {
-                        job_id,
-                        status: JobStatus::WritingSheet,
+                    .send(WorkerEvent::BatchFinished {
+                        job_ids,
+                        committed,
+                        total,
+                        skipped,
                     })
                     .await;
+            }
 
-                // 実際の書き込み/エクスポート/アップロードを行う。
-                let r =
-                    commit_one(&http, &authn, &cfg, &fields, &target_month_ym, &tx, job_id).await;
-                match r {
-                    Ok(_) => {
-                        tracing::info!("commit job done: {job_id}");
-                        // 完了状態へ更新する。
-                        let _ = tx
-                            .send(WorkerEvent::JobUpdated {
-                                job_id,
-                                status: JobStatus::Done,
-                            })
-                            .await;
+            WorkerCmd::Logout => {
+                tracing::info!("logout");
+                logout(&http, &tx).await;
+            }
+
+            WorkerCmd::Diagnose {
+                job_id,
+                copied_sheet_id,
+            } => {
+                tracing::info!("diagnose job: {job_id}");
+                // 読み取り専用の取得だけを行い、結果をUIへ返す。
+                let ev = match diagnose(&http, &authn, &cfg, copied_sheet_id.as_deref()).await {
+                    Ok(text) => WorkerEvent::Diagnostics { job_id, text },
+                    Err(e) => {
+                        tracing::error!("diagnose failed: {job_id}: {e:#}");
+                        WorkerEvent::Error(e.context("diagnostics failed").into())
+                    }
+                };
+                let _ = tx.send(ev).await;
+            }
+
+            WorkerCmd::PullFromSheet {
+                job_id,
+                sheet_id,
+                range,
+            } => {
+                tracing::info!("pull from sheet: {job_id} {range}");
+                // 確定済みの行を読み戻し、入力項目として返す。
+                let ev = match pull_row(&http, &authn, &cfg, &sheet_id, &range).await {
+                    Ok(fields) => WorkerEvent::SheetPulled { job_id, fields },
+                    Err(e) => {
+                        tracing::error!("pull from sheet failed: {job_id}: {e:#}");
+                        WorkerEvent::Error(e.context("pull from sheet failed").into())
+                    }
+                };
+                let _ = tx.send(ev).await;
+            }
+
+            WorkerCmd::OcrJob {
+                job_id,
+                drive_file_id,
+            } => {
+                tracing::info!("ocr job: {job_id}");
+                // 元画像を読み取り、推測できた項目だけをUIへ返す。
+                let ev = match ocr_job(&http, &authn, &cfg, &drive_file_id).await {
+                    Ok(guess) => WorkerEvent::OcrGuessed { job_id, guess },
+                    Err(e) => {
+                        tracing::error!("ocr failed: {job_id}: {e:#}");
+                        WorkerEvent::Error(e.context("OCR failed").into())
                     }
+                };
+                let _ = tx.send(ev).await;
+            }
+
+            WorkerCmd::FetchTemplateHeaders {
+                template_sheet_id,
+                header_row,
+            } => {
+                tracing::info!("fetch template headers: row {header_row}");
+                let ev = match fetch_template_headers(
+                    &http,
+                    &authn,
+                    &template_sheet_id,
+                    cfg.template.sheet_name(),
+                    header_row,
+                )
+                .await
+                {
+                    Ok(columns) => WorkerEvent::TemplateHeaders {
+                        header_row,
+                        columns,
+                    },
                     Err(e) => {
-                        tracing::error!("commit job failed: {job_id}: {e}");
-                        // 失敗状態へ更新し、エラー内容を伝える。
-                        let _ = tx
-                            .send(WorkerEvent::JobUpdated {
-                                job_id,
-                                status: JobStatus::Error(e.to_string()),
-                            })
-                            .await;
+                        tracing::error!("fetch template headers failed: {e:#}");
+                        WorkerEvent::Error(e.context("reading template headers failed").into())
                     }
+                };
+                let _ = tx.send(ev).await;
+            }
+
+            WorkerCmd::CheckId { kind, id } => {
+                tracing::info!("check id: {}", kind.label());
+                // 失敗はウィザードに表示するため、エラーイベントではなく結果として返す。
+                let error = check_id(&http, &authn, kind, &id).await.err().map(|e| {
+                    tracing::warn!("check id failed: {}: {e:#}", kind.label());
+                    format!("{e:#}")
+                });
+                let _ = tx.send(WorkerEvent::IdChecked { kind, id, error }).await;
+            }
+
+            WorkerCmd::VerifySetup {
+                input_folder_id,
+                template_sheet_id,
+            } => {
+                tracing::info!("verify setup");
+                let mime_types = cfg.google.input_mime_types();
+                verify_setup(
+                    &http,
+                    &authn,
+                    &input_folder_id,
+                    &mime_types,
+                    &template_sheet_id,
+                    &tx,
+                )
+                .await;
+            }
+
+            WorkerCmd::PreviewWrite { rows } => {
+                tracing::info!("preview write: {rows} rows");
+                // 書き込みは行わず、見積もり結果だけをUIへ返す。
+                let ev = match preview_write(&http, &authn, &cfg, rows, &tx).await {
+                    Ok(plan) => WorkerEvent::WritePlanned(plan),
+                    Err(e) => {
+                        tracing::error!("preview write failed: {e:#}");
+                        WorkerEvent::Error(e.context("write preview failed").into())
+                    }
+                };
+                let _ = tx.send(ev).await;
+            }
+        }
+    }
+
+    // 終了時に残っているアーカイブ待ちも移動しておく。
+    if cfg.archive.enabled() && !state.pending_archive.is_empty() {
+        flush_archive(&http, &authn, &cfg, &mut state.pending_archive, &tx).await;
+    }
+}
+
+/// 確定をまたいで保持するWorkerの状態。
+#[derive(Default)]
+struct CommitState {
+    /// 確定済みで、まだアーカイブへ移動していない画像のDriveファイルID。
+    pending_archive: Vec<String>,
+    /// API障害中に確定を送り続けないためのブレーカー。
+    breaker: CircuitBreaker,
+    /// テンプレートとシート名ごとの結合セル（コピーしても変わらないため使い回す）。
+    merges: HashMap<(String, String), sheets::SheetMerges>,
+    /// 書き込みを行わず、行うはずの操作をログに出すだけにするか（`--dry-run`）。
+    dry_run: bool,
+}
+
+/// 対象月ごとにリクエストをまとめる（月もジョブも最初に現れた順を保つ）。
+fn group_by_month(requests: Vec<CommitRequest>) -> Vec<Vec<CommitRequest>> {
+    let mut groups: Vec<Vec<CommitRequest>> = vec![];
+    for req in requests {
+        match groups
+            .iter_mut()
+            .find(|g| g[0].target_month_ym == req.target_month_ym)
+        {
+            Some(group) => group.push(req),
+            None => groups.push(vec![req]),
+        }
+    }
+    groups
+}
+
+/// 1枚のシートにまとめて確定し、結果に応じた状態更新・提出記録・アーカイブを行う（成功ならtrue）。
+///
+/// シートとPDFは1つなので、成否はまとめたジョブすべてで共通になる。
+async fn commit_and_report(
+    http: &Client,
+    authn: &auth::InstalledAuth,
+    cfg: &Config,
+    reqs: &[CommitRequest],
+    state: &mut CommitState,
+    tx: &mpsc::Sender<WorkerEvent>,
+) -> bool {
+    let breaker = &mut state.breaker;
+    let before = breaker.status(&cfg.breaker);
+    // ブレーカーが開いている間は、APIを呼ばずに即座に失敗させる。
+    let r = if breaker.allow(Instant::now()) {
+        for req in reqs {
+            tracing::info!("commit job start: {}", req.job_id);
+            // UIに即時反映させるためステータスを先に更新する。
+            let _ = tx
+                .send(WorkerEvent::JobUpdated {
+                    job_id: req.job_id,
+                    status: JobStatus::WritingSheet,
+                })
+                .await;
+        }
+
+        // 実際の書き込み/エクスポート/アップロードを行う（dry-runなら読み取りと記録だけ）。
+        let r = if state.dry_run {
+            rehearse_commit(http, authn, cfg, reqs, tx).await
+        } else {
+            commit_sheet(http, authn, cfg, reqs, &mut state.merges, tx).await
+        }
+        .map_err(|e| {
+            tracing::error!("commit failed: {}: {e:#}", job_ids(reqs));
+            WorkerError::from(e)
+        });
+        breaker.record(&cfg.breaker, r.as_ref().err(), Instant::now());
+        r
+    } else {
+        tracing::warn!("commit skipped, breaker open: {}", job_ids(reqs));
+        Err(WorkerError::Network(breaker::OPEN_MESSAGE.into()))
+    };
+    // 状態が変わったらステータス表示を更新する。
+    let after = breaker.status(&cfg.breaker);
+    if after != before {
+        let _ = tx.send(WorkerEvent::Breaker(after)).await;
+    }
+
+    match r {
+        Ok(_) => {
+            for req in reqs {
+                tracing::info!("commit job done: {}", req.job_id);
+                // 二重提出の検出用に提出記録を残す（dry-runでは提出していないので残さない）。
+                if cfg.duplicates.detect
+                    && !state.dry_run
+                    && let Err(e) = submissions::append(
+                        Path::new(submissions::SUBMISSIONS_PATH),
+                        &req.target_month_ym,
+                        &req.drive_file_id,
+                    )
+                {
+                    tracing::warn!("failed to record submission: {e}");
+                }
+                // 完了状態へ更新する。
+                let _ = tx
+                    .send(WorkerEvent::JobUpdated {
+                        job_id: req.job_id,
+                        status: JobStatus::Done,
+                    })
+                    .await;
+                // 完了した画像をアーカイブ待ちにする（処理済みフォルダへ移動済みなら対象外）。
+                if cfg.archive.enabled()
+                    && cfg.google.processed_folder_id().is_none()
+                    && !state.dry_run
+                {
+                    state.pending_archive.push(req.drive_file_id.clone());
                 }
             }
+            // 件数が揃ったらまとめて移動する。
+            if cfg.archive.enabled() && cfg.archive.is_due(state.pending_archive.len()) {
+                flush_archive(http, authn, cfg, &mut state.pending_archive, tx).await;
+            }
+            true
+        }
+        Err(error) => {
+            // 失敗内容と対象月を伝える（再試行の確認はUIが設定に応じて行う）。
+            for req in reqs {
+                let _ = tx
+                    .send(WorkerEvent::CommitFailed {
+                        job_id: req.job_id,
+                        target_month_ym: req.target_month_ym.clone(),
+                        error: error.clone(),
+                    })
+                    .await;
+            }
+            false
+        }
+    }
+}
+
+/// ログ用にジョブIDを連結する。
+fn job_ids(reqs: &[CommitRequest]) -> String {
+    reqs.iter()
+        .map(|r| r.job_id.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 保存済みのトークンの期限が近ければ、リフレッシュトークンで更新する。
+///
+/// トークンがまだ無い（未認証・ログアウト後）ときは、ブラウザでの同意を始めないよう何もしない。
+async fn refresh_token_if_expiring(authn: &auth::InstalledAuth, tx: &mpsc::Sender<WorkerEvent>) {
+    let scopes = auth::scopes();
+    let Some(stored) = FileTokenStorage::new(auth::TOKEN_PATH).get(&scopes).await else {
+        return;
+    };
+    let expires_at = stored.expires_at.map(|t| t.unix_timestamp());
+    if stored.refresh_token.is_none() || !needs_refresh(expires_at, chrono::Utc::now().timestamp())
+    {
+        return;
+    }
+    match authn.force_refreshed_token(&scopes).await {
+        Ok(_) => {
+            tracing::info!("access token refreshed in background");
+            let _ = tx
+                .send(WorkerEvent::Log("access token refreshed".into()))
+                .await;
+        }
+        // 失敗しても次の操作で通常どおり取得し直すので、ログに残すだけにする。
+        Err(e) => tracing::warn!("background token refresh failed: {e}"),
+    }
+}
+
+/// 期限（UNIX秒）までの残りが`TOKEN_REFRESH_MARGIN`を下回るか判定する（期限不明なら更新しない）。
+fn needs_refresh(expires_at: Option<i64>, now: i64) -> bool {
+    expires_at.is_some_and(|exp| exp - now < TOKEN_REFRESH_MARGIN.as_secs() as i64)
+}
+
+/// 保存済みのトークンを削除して失効させ、結果をUIへ伝える。
+async fn logout(http: &Client, tx: &mpsc::Sender<WorkerEvent>) {
+    // 手元のトークンを先に消し、失効に失敗してもこの端末からはログアウトさせる。
+    let tokens = match FileTokenStorage::new(auth::TOKEN_PATH).take_all().await {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            tracing::error!("logout failed: {e}");
+            let _ = tx
+                .send(WorkerEvent::Error(WorkerError::Other(format!(
+                    "logout failed: cannot delete {}: {e}",
+                    auth::TOKEN_PATH
+                ))))
+                .await;
+            return;
+        }
+    };
+    if tokens.is_empty() {
+        let _ = tx
+            .send(WorkerEvent::Log(
+                "no stored token; nothing to revoke".into(),
+            ))
+            .await;
+    }
+    for token in &tokens {
+        if let Err(e) = auth::revoke(http, token).await {
+            tracing::warn!("token revoke failed: {e:#}");
+            let _ = tx
+                .send(WorkerEvent::Log(format!("token revoke failed: {e:#}")))
+                .await;
+        }
+    }
+    let _ = tx.send(WorkerEvent::LoggedOut).await;
+}
+
+/// 一括確定の前に1件分の入力を確認する。
+///
+/// 確定できなければ飛ばす理由を返し、文字数の上限で切り詰めた場合はその内容を返す。
+fn prepare_batch_request(
+    cfg: &Config,
+    req: &mut CommitRequest,
+) -> std::result::Result<Vec<String>, String> {
+    let name = &req.filename;
+    // 対象月が無効なら確定できない。
+    if !dates::is_valid_ym(&req.target_month_ym) {
+        return Err(format!(
+            "{name}: invalid target month {:?}",
+            req.target_month_ym
+        ));
+    }
+    // 必須項目や科目ごとのルールを満たしていなければ飛ばす。
+    req.fields
+        .validate(&cfg.validation.required_fields, &cfg.category_rules)
+        .map_err(|problems| format!("{name}: {}", problems.join("; ")))?;
+    // 文字数の上限を適用する。
+    cfg.field_limits
+        .enforce(&mut req.fields)
+        .map_err(|problems| format!("{name}: {}", problems.join("; ")))
+}
+
+/// アーカイブ待ちの画像をまとめてアーカイブフォルダへ移動する（失敗時は次回に持ち越す）。
+async fn flush_archive(
+    http: &Client,
+    authn: &auth::InstalledAuth,
+    cfg: &Config,
+    pending: &mut Vec<String>,
+    tx: &mpsc::Sender<WorkerEvent>,
+) {
+    let token = match access_token(authn).await {
+        Ok(token) => token,
+        Err(e) => {
+            tracing::warn!("archive failed: {e:#}");
+            let message = format!(
+                "warning: failed to archive {} files (will retry): {e}",
+                pending.len()
+            );
+            let _ = tx.send(WorkerEvent::Log(message)).await;
+            return;
+        }
+    };
+    let failed = drive::move_files_batch(
+        http,
+        &token,
+        pending,
+        &cfg.google.input_folder_id,
+        &cfg.archive.folder_id,
+    )
+    .await;
+    let total = pending.len();
+    // 移動できたものは持ち越さず、失敗したものだけを次回に回す。
+    *pending = failed.iter().map(|(id, _)| id.clone()).collect();
+    let message = match failed.first() {
+        None => {
+            tracing::info!("archived {total} files");
+            format!("archived {total} files")
+        }
+        Some((_, reason)) => {
+            tracing::warn!(
+                "archive failed for {} of {total} files: {failed:?}",
+                failed.len()
+            );
+            format!(
+                "warning: failed to archive {} of {total} files (will retry): {reason}",
+                failed.len()
+            )
+        }
+    };
+    let _ = tx.send(WorkerEvent::Log(message)).await;
+}
+
+/// テンプレートと（あれば）コピー済みシートの生レスポンスを集める。
+async fn diagnose(
+    http: &Client,
+    authn: &auth::InstalledAuth,
+    cfg: &Config,
+    copied_sheet_id: Option<&str>,
+) -> Result<String> {
+    if cfg.google.template_sheet_id.is_empty() {
+        return Err(anyhow!("template_sheet_id is not set"));
+    }
+    let token = access_token(authn).await?;
+    let template_sheet_id =
+        drive::resolve_sheet_id(http, &token, &cfg.google.template_sheet_id).await?;
+
+    // 見出しセルと経費行の先頭部分を含むレンジを対象にする。
+    let ge = &cfg.general_expense;
+    let last_row = ge.start_row + DIAGNOSTICS_ROWS - 1;
+    let mut sections = vec![];
+    let targets = std::iter::once(("template", template_sheet_id.as_str()))
+        .chain(copied_sheet_id.map(|id| ("copied sheet", id)));
+    for (label, id) in targets {
+        let (title, _rows) =
+            sheets::get_sheet_title_and_rows(http, &token, id, cfg.template.sheet_name()).await?;
+        let range = format!(
+            "{}!A1:{}{}",
+            sheets::quote_sheet_title(&title),
+            ge.note_col,
+            last_row
+        );
+        let metadata = sheets::fetch_metadata_json(http, &token, id).await?;
+        let values = sheets::fetch_values_json(http, &token, id, &range).await?;
+        sections.push(format!(
+            "== {label} {id} metadata ==\n{}\n\n== {label} {range} ==\n{}",
+            serde_json::to_string_pretty(&metadata)?,
+            serde_json::to_string_pretty(&values)?
+        ));
+    }
+    Ok(sections.join("\n\n"))
+}
+
+/// 確定済みの経費行を読み戻して入力項目へ変換する。
+///
+/// 結合セルで備考が別の列へ書かれていても拾えるよう、行をA列から読み、設定された列で値を取り出す。
+async fn pull_row(
+    http: &Client,
+    authn: &auth::InstalledAuth,
+    cfg: &Config,
+    sheet_id: &str,
+    range: &str,
+) -> Result<ReceiptFields> {
+    let (sheet, row) =
+        sheets::range_sheet_and_row(range).ok_or_else(|| anyhow!("invalid range: {range}"))?;
+    let last_col = expense_columns(&cfg.general_expense)
+        .iter()
+        .filter_map(|col| sheets::column_index(col))
+        .max()
+        .ok_or_else(|| anyhow!("no valid expense columns configured"))?;
+    let read_range = format!("{sheet}!A{row}:{}{row}", sheets::column_letter(last_col));
+    let token = access_token(authn).await?;
+    let values = sheets::get_row_unformatted(http, &token, sheet_id, &read_range).await?;
+    if values.is_empty() {
+        return Err(anyhow!("{read_range} is empty"));
+    }
+    ReceiptFields::from_sheet_row(&expense_cells(&cfg.general_expense, &values))
+}
+
+/// 経費行の列を日付・用途・金額・勘定科目・備考の順に返す。
+fn expense_columns(ge: &GeneralExpenseCfg) -> [&str; 5] {
+    [
+        &ge.date_col,
+        &ge.reason_col,
+        &ge.amount_col,
+        &ge.category_col,
+        &ge.note_col,
+    ]
+}
+
+/// A列から読んだ1行から、設定された列の値を日付・用途・金額・勘定科目・備考の順に取り出す。
+fn expense_cells(ge: &GeneralExpenseCfg, row: &[serde_json::Value]) -> Vec<String> {
+    expense_columns(ge)
+        .iter()
+        .map(|col| {
+            sheets::column_index(col)
+                .and_then(|idx| row.get(idx as usize))
+                .map(sheets::cell_text)
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// 用途/備考を`field_limits`のセル幅に収めたリクエストを返し、切り詰めた項目をログへ残す。
+async fn fit_requests_for_sheet(
+    cfg: &Config,
+    reqs: &[CommitRequest],
+    tx: &mpsc::Sender<WorkerEvent>,
+) -> Vec<CommitRequest> {
+    let mut fitted = Vec::with_capacity(reqs.len());
+    for req in reqs {
+        let (fields, truncated) = cfg.field_limits.fit_for_sheet(&req.fields);
+        for note in truncated {
+            tracing::info!("{}: {note}", req.filename);
+            let _ = tx
+                .send(WorkerEvent::Log(format!("{}: {note}", req.filename)))
+                .await;
+        }
+        fitted.push(CommitRequest {
+            fields,
+            ..req.clone()
+        });
+    }
+    fitted
+}
+
+/// 元画像をダウンロードして文字認識し、日付と合計金額を推測する。
+///
+/// `ocr.endpoint`が空ならVision APIを使う（初回はVisionのスコープへの同意を求める）。
+async fn ocr_job(
+    http: &Client,
+    authn: &auth::InstalledAuth,
+    cfg: &Config,
+    drive_file_id: &str,
+) -> Result<OcrGuess> {
+    let token = access_token(authn).await?;
+    let image = drive::download_file(http, &token, drive_file_id)
+        .await
+        .context("image download failed")?;
+    let text = match cfg.ocr.endpoint() {
+        Some(endpoint) => ocr::recognize(http, endpoint, &image).await?,
+        None => {
+            let token = access_token_for(authn, &[vision::SCOPE]).await?;
+            vision::detect_text(http, &token, cfg.ocr.quota_project(), &image).await?
         }
+    };
+    tracing::info!("ocr text: {} chars", text.chars().count());
+    Ok(ocr::guess(&text))
+}
+
+/// Driveから画像一覧を1ページ分取得し、編集可能なジョブへ変換する。
+async fn load_jobs_page(
+    http: &Client,
+    authn: &auth::InstalledAuth,
+    cfg: &Config,
+    page_token: Option<&str>,
+) -> Result<(Vec<Job>, Option<String>)> {
+    // 一覧取得に使うアクセストークンを取得する。
+    let token = access_token(authn).await?;
+    tracing::info!("access token acquired");
+
+    // 表示上限が0なら全件を、そうでなければ1ページ分を取得する。
+    let folder_id = &cfg.google.input_folder_id;
+    let mime_types = cfg.google.input_mime_types();
+    let (files, next_page_token) = if cfg.ui.max_displayed_jobs == 0 {
+        drive::list_images_in_folder(http, &token, folder_id, &mime_types)
+            .await
+            .map(|files| (files, None))
+    } else {
+        // 全件取得と同じく、一時的な失敗は待ち時間を延ばしながら再試行する。
+        error::retry_request(error::TRANSIENT_RETRY_ATTEMPTS, || {
+            drive::list_images_page(
+                http,
+                &token,
+                folder_id,
+                &mime_types,
+                cfg.ui.max_displayed_jobs,
+                page_token,
+            )
+        })
+        .await
     }
+    .context("list failed")?;
+    tracing::info!("drive list success: {} files", files.len());
+
+    // 各ファイルをジョブに変換し、初期状態をセットする。
+    let jobs = files
+        .into_iter()
+        .map(|f| {
+            let mut j = Job::new(f.id, f.name);
+            j.mime_type = f.mime_type;
+            // ユーザーが編集できるよう初期状態を設定する。
+            j.status = JobStatus::WaitingUserFix;
+            j
+        })
+        .collect();
+    Ok((jobs, next_page_token))
 }
 
 /// Authenticatorから新しいアクセストークンを取得する。
 async fn access_token(authn: &auth::InstalledAuth) -> Result<String> {
-    // スコープ付きでトークン取得を行う。
-    let token = authn.token(&auth::scopes()).await?;
+    access_token_for(authn, &auth::scopes()).await
+}
+
+/// 指定したスコープのアクセストークンを取得する（未同意のスコープなら同意を求める）。
+async fn access_token_for(authn: &auth::InstalledAuth, scopes: &[&str]) -> Result<String> {
+    // スコープ付きでトークン取得を行う（失敗は再認証が必要な認証エラーとする）。
+    let token = authn
+        .token(scopes)
+        .await
+        .map_err(|e| WorkerError::Auth(format!("token failed: {e}")))?;
     // アクセストークン文字列を取り出す。
-    let token = token.token().ok_or_else(|| anyhow!("no access token"))?;
+    let token = token
+        .token()
+        .ok_or_else(|| WorkerError::Auth("no access token".into()))?;
     Ok(token.to_string())
 }
 
-/// シートへ値を書き込み、PDFをエクスポートしてDriveへアップロードする。
-async fn commit_one(
+/// 1枚のシートへ経費行を書き込み、`pdf.enabled`ならPDFをエクスポートしてDriveへアップロードする。
+///
+/// 複数のリクエストは同じ対象月である必要があり、先頭から順に次の空行へ書き込む。
+async fn commit_sheet(
     http: &Client,
     authn: &auth::InstalledAuth,
     cfg: &Config,
-    fields: &ReceiptFields,
-    target_month_ym: &str,
+    reqs: &[CommitRequest],
+    merges: &mut HashMap<(String, String), sheets::SheetMerges>,
     tx: &mpsc::Sender<WorkerEvent>,
-    job_id: uuid::Uuid,
 ) -> Result<()> {
-    // 必須IDが揃っているかを事前確認する。
-    if cfg.google.template_sheet_id.is_empty() || cfg.google.output_folder_id.is_empty() {
+    // 長い用途/備考はセル幅に収めてから書き込む。
+    let reqs = &fit_requests_for_sheet(cfg, reqs, tx).await;
+    // ヘッダーやファイル名には先頭のリクエストを使う。
+    let Some(first) = reqs.first() else {
+        return Ok(());
+    };
+    let target_month_ym = first.target_month_ym.as_str();
+    if reqs.iter().any(|r| r.target_month_ym != target_month_ym) {
+        return Err(anyhow!(
+            "rows on one sheet must share the same target month"
+        ));
+    }
+
+    // 必須IDが揃っているかを事前確認する（出力フォルダはPDFを出す場合だけ使う）。
+    if cfg.google.template_sheet_id.is_empty()
+        || (cfg.pdf.enabled && cfg.google.output_folder_id.is_empty())
+    {
         return Err(anyhow!("template_sheet_id / output_folder_id is not set"));
     }
 
@@ -203,95 +1054,1340 @@ async fn commit_one(
 
     // シート名は空白を除去して安定した名前にする。
     let safe_name = cfg.user.full_name.replace(' ', "");
-    let new_sheet_name = format!(
-        "立替経費精算書_{}_{}",
-        target_month_ym.replace('-', ""),
-        safe_name
-    );
+    let new_sheet_name = copied_sheet_name(target_month_ym, &safe_name);
+    // 読み取りや上書きなど、繰り返しても結果が変わらない呼び出しは一時的な失敗を再試行する。
+    // コピー・追記・アップロードは5xxでも反映済みのことがあるため、再試行しない。
+    let retry = error::TRANSIENT_RETRY_ATTEMPTS;
     // テンプレートがショートカットなら実体IDへ解決する。
-    let template_sheet_id =
-        drive::resolve_sheet_id(http, &token, &cfg.google.template_sheet_id).await?;
+    let template_sheet_id = error::retry_request(retry, || {
+        drive::resolve_sheet_id(http, &token, &cfg.google.template_sheet_id)
+    })
+    .await?;
     // テンプレートをコピーして新しいシートファイルを作成する。
     let copied_sheet_id =
         drive::copy_file(http, &token, &template_sheet_id, &new_sheet_name, None).await?;
+    // 失敗時も中身を確認できるよう、作成したシートをUIへ伝える。
+    for req in reqs {
+        let _ = tx
+            .send(WorkerEvent::SheetCopied {
+                job_id: req.job_id,
+                copied_sheet_id: copied_sheet_id.clone(),
+            })
+            .await;
+    }
+    // A1レンジを作るために書き込み先のシート名を取得する。
+    // コピー直後は反映が遅れて404になることがあるため、短い間隔で数回だけ再試行する。
+    let ((sheet_title, sheet_rows), retries) =
+        error::retry_on_not_found(COPY_PROPAGATION_ATTEMPTS, COPY_PROPAGATION_DELAY, || {
+            error::retry_request(retry, || {
+                sheets::get_sheet_title_and_rows(
+                    http,
+                    &token,
+                    &copied_sheet_id,
+                    cfg.template.sheet_name(),
+                )
+            })
+        })
+        .await?;
+    if retries > 0 {
+        tracing::info!("copied sheet became visible after {retries} retries");
+        let _ = tx
+            .send(WorkerEvent::Log(format!(
+                "copied sheet was not visible yet; succeeded after {retries} retries"
+            )))
+            .await;
+    }
 
-    // A1レンジを作るために最初のシート名を取得する。
-    let (sheet_title, _rows) =
-        sheets::get_first_sheet_title_and_rows(http, &token, &copied_sheet_id).await?;
-
-    // ヘッダー（氏名・対象月）を埋める。
-    let month_date = format!("{}-01", target_month_ym);
-    let mut updates: Vec<(String, Vec<Vec<serde_json::Value>>)> = vec![];
-
-    // 氏名セルの更新。
-    updates.push((
-        format!("{}!{}", sheet_title, cfg.template.name_cell),
-        vec![vec![serde_json::Value::String(cfg.user.full_name.clone())]],
-    ));
-    // 対象月セルの更新。
-    updates.push((
-        format!("{}!{}", sheet_title, cfg.template.target_month_cell),
-        vec![vec![serde_json::Value::String(month_date)]],
-    ));
-
-    // 経費テーブル内の次の空行を探す。
-    let existing = sheets::count_existing_rows_in_col(
-        http,
-        &token,
-        &copied_sheet_id,
-        &sheet_title,
-        &cfg.general_expense.date_col,
-        cfg.general_expense.start_row,
-    )
+    // コピーが見えるようになったので、設定された相手へ共有する（失敗しても確定は続け、ログに残す）。
+    let role = cfg.sheets.share_role.as_str();
+    for email in &cfg.sheets.share_with {
+        if let Err(e) = drive::share_file(http, &token, &copied_sheet_id, email, role).await {
+            tracing::warn!("sharing sheet with {email} failed: {e:#}");
+            let _ = tx
+                .send(WorkerEvent::Log(format!(
+                    "sharing sheet with {email} failed: {e:#}"
+                )))
+                .await;
+        }
+    }
+
+    // ヘッダー（氏名・対象月・通貨）を埋める。
+    let mut updates = header_updates(cfg, &sheet_title, target_month_ym, &first.fields);
+
+    // 設定されていれば領収書画像を起点セルへ埋め込む（起点セルは1つなので先頭の画像だけ）。
+    if cfg.pdf.embed_image && !jobs::is_image_mime(&first.mime_type) {
+        // PDFなどはIMAGE()で表示できないため埋め込まない。
+        let _ = tx
+            .send(WorkerEvent::Log(format!(
+                "{} is not an image; skipped embedding",
+                first.filename
+            )))
+            .await;
+    } else if cfg.pdf.embed_image {
+        let drive_file_id = first.drive_file_id.as_str();
+        // 非公開画像はIMAGE()で表示できないため、事前に警告しておく。
+        match drive::is_shared_publicly(http, &token, drive_file_id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::warn!("receipt image {drive_file_id} is not shared publicly");
+                let _ = tx
+                    .send(WorkerEvent::Log(
+                        "warning: receipt image is not public; embedded image may be blank".into(),
+                    ))
+                    .await;
+            }
+            Err(e) => tracing::warn!("failed to check image sharing: {e}"),
+        }
+        if reqs.len() > 1 {
+            let _ = tx
+                .send(WorkerEvent::Log(format!(
+                    "{} rows on one sheet; only the image of {} is embedded",
+                    reqs.len(),
+                    first.filename
+                )))
+                .await;
+        }
+        updates.push(sheets::image_formula_update(
+            &sheet_title,
+            &cfg.pdf.image_anchor_cell,
+            &drive::image_view_url(drive_file_id),
+        ));
+    }
+
+    let ge = &cfg.general_expense;
+    let start_row = error::retry_request(retry, || {
+        resolve_start_row(http, &token, cfg, &copied_sheet_id, tx)
+    })
     .await?;
 
-    // 追加する行番号を算出する。
-    let row = cfg.general_expense.start_row + existing;
+    // 結合セルはテンプレートとシートごとに一度だけ取得する（取得できなければ結合なしとみなす）。
+    let merges_key = (template_sheet_id.clone(), sheet_title.clone());
+    if !merges.contains_key(&merges_key) {
+        match error::retry_request(retry, || {
+            sheets::get_merges(http, &token, &template_sheet_id, &sheet_title)
+        })
+        .await
+        {
+            Ok(m) => {
+                merges.insert(merges_key.clone(), m);
+            }
+            Err(e) => tracing::warn!("failed to read merged cells: {e:#}"),
+        }
+    }
+    let sheet_merges = merges.get(&merges_key).cloned().unwrap_or_default();
+
+    match ge.target_mode {
+        TargetMode::Count => {
+            // 経費テーブル内の次の空行を探す。
+            let existing = error::retry_request(retry, || {
+                count_existing_rows(http, &token, cfg, &copied_sheet_id, &sheet_title, start_row)
+            })
+            .await?;
+            // 値APIはグリッドの外へは書けないため、書き込む前に行数が足りるか確かめる。
+            if let Some(limit) = sheet_rows.limit() {
+                ensure_rows_fit(&sheet_title, limit, start_row + existing, reqs.len() as u32)?;
+            }
+
+            // 既存行の後ろへ1件ずつ行を進めながら、値と付帯列を更新リストへ追加する。
+            let (rows, written) = expense_rows_updates(
+                cfg,
+                &sheet_title,
+                start_row,
+                existing,
+                reqs,
+                &sheet_merges,
+                chrono::Local::now(),
+            );
+            updates.extend(rows);
+
+            // まとめてバッチ更新し、書き込んだ行をUIへ伝える。
+            error::retry_request(retry, || {
+                sheets::values_batch_update(http, &token, &copied_sheet_id, updates.clone())
+            })
+            .await?;
+            verify_written(http, &token, cfg, &copied_sheet_id, &updates, tx).await?;
+            for (job_id, range) in written {
+                let _ = tx.send(WorkerEvent::RowWritten { job_id, range }).await;
+            }
+        }
+        TargetMode::AppendAnchored => {
+            // ヘッダー（氏名・対象月）を先に書き込む。
+            error::retry_request(retry, || {
+                sheets::values_batch_update(http, &token, &copied_sheet_id, updates.clone())
+            })
+            .await?;
+            verify_written(http, &token, cfg, &copied_sheet_id, &updates, tx).await?;
+
+            let header_row = error::retry_request(retry, || {
+                resolve_header_row(http, &token, cfg, &copied_sheet_id, &sheet_title, start_row)
+            })
+            .await?;
+
+            // 付帯列は、Googleが実際に追加した行（見本行などで列の件数とずれることがある）へ書く。
+            let mut extra_updates = vec![];
+            let now = chrono::Local::now();
+
+            // 備考列に結合セルがあれば、備考を切り離して勘定科目の列までを追加し、
+            // 備考は追加された行から求めた結合の左上へ後で書く。
+            let split_note = sheet_merges.merges_column(&ge.note_col);
+            let last_col = if split_note {
+                &ge.category_col
+            } else {
+                &ge.note_col
+            };
+            // 見出し行を起点に最終行の後ろへ1行ずつ追加する。
+            let range =
+                sheets::anchored_append_range(&sheet_title, &ge.date_col, last_col, header_row);
+            for req in reqs {
+                let mut row_values = expense_row_values(&req.fields);
+                let note = split_note.then(|| row_values[0].pop().unwrap_or_default());
+                let written = sheets::values_append(
+                    http,
+                    &token,
+                    &copied_sheet_id,
+                    &range,
+                    row_values.clone(),
+                )
+                .await?;
+                // 書き込まれたレンジが分かれば確認し、UIへ伝える。
+                if let Some(range) = written {
+                    let appended = [(range.clone(), row_values)];
+                    verify_written(http, &token, cfg, &copied_sheet_id, &appended, tx).await?;
+                    if let Some(note) = note {
+                        match appended_note_update(cfg, &sheet_title, &range, note, &sheet_merges) {
+                            Some(update) => extra_updates.push(update),
+                            None => tracing::warn!("cannot place note for appended range {range}"),
+                        }
+                    }
+                    if ge.writes_row_extras() {
+                        extra_updates.extend(appended_row_extra_updates(
+                            cfg,
+                            &sheet_title,
+                            header_row,
+                            &range,
+                            now,
+                        ));
+                    }
+                    let _ = tx
+                        .send(WorkerEvent::RowWritten {
+                            job_id: req.job_id,
+                            range,
+                        })
+                        .await;
+                }
+            }
+            if !extra_updates.is_empty() {
+                error::retry_request(retry, || {
+                    sheets::values_batch_update(
+                        http,
+                        &token,
+                        &copied_sheet_id,
+                        extra_updates.clone(),
+                    )
+                })
+                .await?;
+                verify_written(http, &token, cfg, &copied_sheet_id, &extra_updates, tx).await?;
+            }
+        }
+    }
+
+    // シートの後の段階を順に行う（PDFが無効ならエクスポート/アップロードを通らずに完了する）。
+    let steps = pdf_steps(cfg);
+    if steps.is_empty() {
+        tracing::info!("pdf disabled; commit finished after writing the sheet");
+    }
+    let mut pdf = vec![];
+    for step in steps {
+        send_status(tx, reqs, step.status()).await;
+        match step {
+            PdfStep::Export => {
+                let export_params = cfg.pdf_export.query_params();
+                pdf = error::retry_request(retry, || {
+                    drive::export_pdf(http, &token, &copied_sheet_id, &export_params)
+                })
+                .await?;
+                // PDF以外（HTMLのエラーページ等）をアップロードしないよう確認する。
+                drive::ensure_pdf(&pdf)?;
+            }
+            PdfStep::Upload => {
+                // PDFのファイル名を設定の書式から組み立てる。
+                let pdf_name = pdf_file_name(
+                    cfg.pdf.name_format(),
+                    target_month_ym,
+                    &safe_name,
+                    &first.fields.date_ymd,
+                    &dates::today_ymd(),
+                );
+                // Driveへアップロードして完了させる。
+                let _pdf_file_id = drive::upload_pdf(
+                    http,
+                    &token,
+                    &cfg.google.output_folder_id,
+                    &pdf_name,
+                    std::mem::take(&mut pdf),
+                )
+                .await?;
+            }
+        }
+    }
+
+    move_processed_images(http, &token, cfg, reqs, tx).await;
+    Ok(())
+}
+
+/// シートへの書き込みの後に行うPDFの段階。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PdfStep {
+    /// シートをPDFとしてエクスポートする。
+    Export,
+    /// PDFを出力フォルダへアップロードする。
+    Upload,
+}
+
+impl PdfStep {
+    /// この段階の間、ジョブに表示する状態。
+    fn status(self) -> JobStatus {
+        match self {
+            Self::Export => JobStatus::ExportingPdf,
+            Self::Upload => JobStatus::UploadingPdf,
+        }
+    }
+}
+
+/// 設定に応じて、シートの後に行うPDFの段階を返す（`pdf.enabled = false`なら無し）。
+fn pdf_steps(cfg: &Config) -> Vec<PdfStep> {
+    if cfg.pdf.enabled {
+        vec![PdfStep::Export, PdfStep::Upload]
+    } else {
+        vec![]
+    }
+}
+
+/// コピーして作るシートファイルの名前を組み立てる。
+fn copied_sheet_name(target_month_ym: &str, safe_name: &str) -> String {
+    format!(
+        "立替経費精算書_{}_{}",
+        target_month_ym.replace('-', ""),
+        safe_name
+    )
+}
+
+/// dry-run用に、確定で行うはずの書き込みを読み取りだけで見積もり、1段階ずつログへ出す。
+///
+/// テンプレートの解決や既存行の読み取りは行うが、コピー・書き込み・アップロード・移動は行わない。
+async fn rehearse_commit(
+    http: &Client,
+    authn: &auth::InstalledAuth,
+    cfg: &Config,
+    reqs: &[CommitRequest],
+    tx: &mpsc::Sender<WorkerEvent>,
+) -> Result<()> {
+    let Some(first) = reqs.first() else {
+        return Ok(());
+    };
+    let target_month_ym = first.target_month_ym.as_str();
+    if reqs.iter().any(|r| r.target_month_ym != target_month_ym) {
+        return Err(anyhow!(
+            "rows on one sheet must share the same target month"
+        ));
+    }
+    if cfg.pdf.enabled && cfg.google.output_folder_id.is_empty() {
+        return Err(anyhow!("output_folder_id is not set"));
+    }
+
+    let safe_name = cfg.user.full_name.replace(' ', "");
+    let mut steps = vec![format!(
+        "would copy template {} as {}",
+        cfg.google.template_sheet_id,
+        copied_sheet_name(target_month_ym, &safe_name)
+    )];
+    // 書き込み位置はテンプレートを読んで見積もる（確定と同じ位置の決め方を使う）。
+    let plan = preview_write(http, authn, cfg, reqs.len() as u32, tx).await?;
+    steps.push(format!("would write range {}", plan.range()));
+    if pdf_steps(cfg).contains(&PdfStep::Upload) {
+        let pdf_name = pdf_file_name(
+            cfg.pdf.name_format(),
+            target_month_ym,
+            &safe_name,
+            &first.fields.date_ymd,
+            &dates::today_ymd(),
+        );
+        steps.push(format!("would upload pdf {pdf_name}"));
+    }
+    if cfg.google.processed_folder_id().is_some() {
+        steps.push(format!(
+            "would move {} image(s) to the processed folder",
+            reqs.len()
+        ));
+    }
+    for step in steps {
+        tracing::info!("dry run: {step}");
+        let _ = tx.send(WorkerEvent::Log(format!("dry run: {step}"))).await;
+    }
+    Ok(())
+}
+
+/// 処理済みフォルダが設定されていれば、確定した元画像を入力フォルダから移動する。
+///
+/// 確定自体は済んでいるため、移動の失敗はログに残すだけにする。
+async fn move_processed_images(
+    http: &Client,
+    token: &str,
+    cfg: &Config,
+    reqs: &[CommitRequest],
+    tx: &mpsc::Sender<WorkerEvent>,
+) {
+    let Some(folder_id) = cfg.google.processed_folder_id() else {
+        return;
+    };
+    for req in reqs {
+        let message = match drive::move_file(
+            http,
+            token,
+            &req.drive_file_id,
+            &cfg.google.input_folder_id,
+            folder_id,
+        )
+        .await
+        {
+            Ok(()) => {
+                tracing::info!("moved {} to processed folder", req.drive_file_id);
+                format!("moved {} to the processed folder", req.filename)
+            }
+            Err(e) => {
+                tracing::warn!("failed to move {}: {e:#}", req.drive_file_id);
+                format!(
+                    "warning: failed to move {} to the processed folder: {e}",
+                    req.filename
+                )
+            }
+        };
+        let _ = tx.send(WorkerEvent::Log(message)).await;
+    }
+}
+
+/// `first_row`から`count`行を書き込んでも、シートの最終行（`sheet_rows`）を超えないか確かめる。
+fn ensure_rows_fit(sheet_title: &str, sheet_rows: u32, first_row: u32, count: u32) -> Result<()> {
+    let last_row = first_row + count.saturating_sub(1);
+    if last_row > sheet_rows {
+        return Err(anyhow!(
+            "{sheet_title} has only {sheet_rows} rows; writing {count} rows from row {first_row} would need row {last_row}. Add rows to the template"
+        ));
+    }
+    Ok(())
+}
+
+/// シートへのセル更新（A1レンジと値）。
+type CellUpdate = (String, Vec<Vec<serde_json::Value>>);
+
+/// 既存の`existing`行の後ろから1件1行ずつ書き込む更新と、ジョブごとの書き込みレンジを作る。
+fn expense_rows_updates(
+    cfg: &Config,
+    sheet_title: &str,
+    start_row: u32,
+    existing: u32,
+    reqs: &[CommitRequest],
+    merges: &sheets::SheetMerges,
+    now: chrono::DateTime<chrono::Local>,
+) -> (Vec<CellUpdate>, Vec<(uuid::Uuid, String)>) {
+    let ge = &cfg.general_expense;
+    let mut updates = vec![];
+    let mut written = vec![];
+    for (i, req) in reqs.iter().enumerate() {
+        // 前の行を書いた分だけ既存行が増えたものとして次の行へ進める。
+        let existing = existing + i as u32;
+        let row = start_row + existing;
+        written.push((
+            req.job_id,
+            format!(
+                "{}!{}{}:{}{}",
+                sheets::quote_sheet_title(sheet_title),
+                ge.date_col,
+                row,
+                ge.note_col,
+                row
+            ),
+        ));
+        updates.extend(row_value_updates(
+            cfg,
+            sheet_title,
+            row,
+            expense_row_values(&req.fields),
+            merges,
+        ));
+        updates.extend(row_extra_updates(
+            cfg,
+            sheet_title,
+            start_row,
+            existing,
+            now,
+        ));
+    }
+    (updates, written)
+}
+
+/// まとめて確定しているジョブすべての状態を更新する。
+async fn send_status(tx: &mpsc::Sender<WorkerEvent>, reqs: &[CommitRequest], status: JobStatus) {
+    for req in reqs {
+        let _ = tx
+            .send(WorkerEvent::JobUpdated {
+                job_id: req.job_id,
+                status: status.clone(),
+            })
+            .await;
+    }
+}
+
+/// 領収書1行分の値を組み立てる。
+///
+/// 日付と金額はSheetsに解釈させ、自由入力の文字列は数式にならないようにする。
+fn expense_row_values(fields: &ReceiptFields) -> Vec<Vec<serde_json::Value>> {
+    vec![vec![
+        serde_json::Value::String(fields.date_ymd.clone()),
+        serde_json::Value::String(sheets::escape_formula(&fields.reason)),
+        serde_json::Value::Number(fields.amount_yen.into()),
+        serde_json::Value::String(sheets::escape_formula(&fields.category)),
+        serde_json::Value::String(sheets::escape_formula(&fields.note)),
+    ]]
+}
+
+/// 設定されていれば書き込んだレンジを読み戻し、送った値と食い違えば設定に従って警告か失敗にする。
+///
+/// `start_row`のずれなどをPDFの前に気付けるようにする。テンプレートの書式で表示が変わるだけの
+/// セルも食い違いに数えうるため、既定（`warn`）では確定自体は止めない。
+async fn verify_written(
+    http: &Client,
+    token: &str,
+    cfg: &Config,
+    spreadsheet_id: &str,
+    updates: &[(String, Vec<Vec<serde_json::Value>>)],
+    tx: &mpsc::Sender<WorkerEvent>,
+) -> Result<()> {
+    let mode = cfg.general_expense.verify_write;
+    if mode == VerifyWrite::Off || updates.is_empty() {
+        return Ok(());
+    }
+    let ranges: Vec<String> = updates.iter().map(|(range, _)| range.clone()).collect();
+    let problem = match error::retry_request(error::TRANSIENT_RETRY_ATTEMPTS, || {
+        sheets::values_batch_get(http, token, spreadsheet_id, &ranges)
+    })
+    .await
+    {
+        Ok(read) => {
+            let mismatches = sheets::write_mismatches(updates, &read);
+            if mismatches.is_empty() {
+                return Ok(());
+            }
+            format!("written cells differ: {}", mismatches.join("; "))
+        }
+        Err(e) => format!("could not read back written cells: {e:#}"),
+    };
+    let message = verify_outcome(mode, &problem)?;
+    tracing::warn!("{message}");
+    let _ = tx.send(WorkerEvent::Log(message)).await;
+    Ok(())
+}
+
+/// 読み戻しで見つかった問題を、`fail`ならエラーに、それ以外ならログ用の警告文にする。
+fn verify_outcome(mode: VerifyWrite, problem: &str) -> Result<String> {
+    if mode == VerifyWrite::Fail {
+        tracing::warn!("write verification failed: {problem}");
+        return Err(anyhow!("write verification failed: {problem}"));
+    }
+    Ok(format!("warning: {problem}"))
+}
+
+/// 書式の置き換え文字を展開し、使えない文字を除いたPDFのファイル名を返す。
+fn pdf_file_name(
+    format: &str,
+    target_month_ym: &str,
+    name: &str,
+    receipt_date: &str,
+    today: &str,
+) -> String {
+    let expanded = format
+        .replace("{month}", target_month_ym)
+        .replace("{name}", name)
+        .replace("{receipt_date}", receipt_date)
+        .replace("{today}", today);
+    let mut file_name = validation::sanitize_file_name(&expanded);
+    // 拡張子が無ければ付ける。
+    if !file_name.to_ascii_lowercase().ends_with(".pdf") {
+        file_name.push_str(".pdf");
+    }
+    file_name
+}
+
+/// テンプレートのヘッダー（氏名・対象月、設定されていれば通貨）の更新を作る。
+fn header_updates(
+    cfg: &Config,
+    sheet_title: &str,
+    target_month_ym: &str,
+    fields: &ReceiptFields,
+) -> Vec<CellUpdate> {
+    header_cells(cfg, target_month_ym, fields)
+        .into_iter()
+        .map(|(cell, value)| {
+            (
+                format!("{}!{}", sheets::quote_sheet_title(sheet_title), cell),
+                vec![vec![serde_json::Value::String(value)]],
+            )
+        })
+        .collect()
+}
+
+/// ヘッダーに書き込むセル番地と値を返す（確定前の確認表示と書き込みで共用する）。
+pub fn header_cells(
+    cfg: &Config,
+    target_month_ym: &str,
+    fields: &ReceiptFields,
+) -> Vec<(String, String)> {
+    let mut cells = vec![
+        // 氏名セル。
+        (cfg.template.name_cell.clone(), cfg.user.full_name.clone()),
+        // 対象月セル（月初の日付として書く）。
+        (
+            cfg.template.target_month_cell.clone(),
+            format!("{}-01", target_month_ym),
+        ),
+    ];
+    // 通貨セルが設定されていれば通貨コードを入れる。
+    if let Some(cell) = &cfg.template.currency_cell {
+        let currency = fields.currency_or(cfg.user.default_currency());
+        cells.push((cell.clone(), currency.to_string()));
+    }
+    cells
+}
+
+/// 経費行1行分の更新を作る。
+///
+/// 備考列が結合セルの左上以外に当たる場合は、備考だけを結合の左上へ書き込む
+/// （結合の途中のセルへ書くとSheets APIがエラーを返すため）。
+fn row_value_updates(
+    cfg: &Config,
+    sheet_title: &str,
+    row: u32,
+    mut row_values: Vec<Vec<serde_json::Value>>,
+    merges: &sheets::SheetMerges,
+) -> Vec<(String, Vec<Vec<serde_json::Value>>)> {
+    let ge = &cfg.general_expense;
+    let Some(anchor) = merges.anchor_for(row, &ge.note_col) else {
+        let range = format!(
+            "{}!{}{}:{}{}",
+            sheets::quote_sheet_title(sheet_title),
+            ge.date_col,
+            row,
+            ge.note_col,
+            row
+        );
+        return vec![(range, row_values)];
+    };
+    tracing::info!(
+        "note cell {}{row} is merged; writing to {anchor}",
+        ge.note_col
+    );
+    // 備考を切り離し、残りを勘定科目の列までに書き込む。
+    let note = row_values[0].pop().unwrap_or(serde_json::Value::Null);
+    vec![
+        (
+            format!(
+                "{}!{}{}:{}{}",
+                sheets::quote_sheet_title(sheet_title),
+                ge.date_col,
+                row,
+                ge.category_col,
+                row
+            ),
+            row_values,
+        ),
+        (
+            format!("{}!{}", sheets::quote_sheet_title(sheet_title), anchor),
+            vec![vec![note]],
+        ),
+    ]
+}
+
+/// 経費行の開始行を決める（名前付き範囲が設定されていれば、その先頭行を使う）。
+async fn resolve_start_row(
+    http: &Client,
+    token: &str,
+    cfg: &Config,
+    sheet_id: &str,
+    tx: &mpsc::Sender<WorkerEvent>,
+) -> Result<u32> {
+    let ge = &cfg.general_expense;
+    let Some(name) = &ge.named_range else {
+        return Ok(ge.start_row);
+    };
+    match sheets::resolve_named_range(http, token, sheet_id, name).await? {
+        Some(area) => {
+            tracing::info!(
+                "named range {name:?} starts at {}{} (columns {}:{})",
+                area.first_col,
+                area.start_row,
+                area.first_col,
+                area.last_col.as_deref().unwrap_or("")
+            );
+            Ok(area.start_row)
+        }
+        None => {
+            tracing::warn!("named range {name:?} not found; using start_row");
+            let _ = tx
+                .send(WorkerEvent::Log(format!(
+                    "warning: named range {name:?} not found; using start_row {}",
+                    ge.start_row
+                )))
+                .await;
+            Ok(ge.start_row)
+        }
+    }
+}
+
+/// 見出し行を探し、見つからなければ開始行の直前を見出しとみなす。
+async fn resolve_header_row(
+    http: &Client,
+    token: &str,
+    cfg: &Config,
+    sheet_id: &str,
+    sheet_title: &str,
+    start_row: u32,
+) -> Result<u32> {
+    let ge = &cfg.general_expense;
+    let fallback_row = start_row.saturating_sub(1).max(1);
+    let Some(label) = &ge.header_label else {
+        return Ok(fallback_row);
+    };
+    let found =
+        sheets::find_header_row(http, token, sheet_id, sheet_title, &ge.date_col, label).await?;
+    Ok(found.unwrap_or_else(|| {
+        tracing::warn!("header label {label:?} not found; using row {fallback_row}");
+        fallback_row
+    }))
+}
 
-    // 領収書1行分の書き込みレンジを作る。
+/// テンプレートの書き込み先シートから見出し行を読み、見出しのある列を返す。
+async fn fetch_template_headers(
+    http: &Client,
+    authn: &auth::InstalledAuth,
+    template_sheet_id: &str,
+    sheet_name: Option<&str>,
+    header_row: u32,
+) -> Result<Vec<(String, String)>> {
+    if template_sheet_id.is_empty() {
+        return Err(anyhow!("template_sheet_id is not set"));
+    }
+    let token = access_token(authn).await?;
+    let retry = error::TRANSIENT_RETRY_ATTEMPTS;
+    let template_sheet_id = error::retry_request(retry, || {
+        drive::resolve_sheet_id(http, &token, template_sheet_id)
+    })
+    .await?;
+    let (sheet_title, _rows) = error::retry_request(retry, || {
+        sheets::get_sheet_title_and_rows(http, &token, &template_sheet_id, sheet_name)
+    })
+    .await?;
+    // 見出し行を丸ごと読み、見出しのある列だけを返す。
     let range = format!(
-        "{}!{}{}:{}{}",
-        sheet_title, cfg.general_expense.date_col, row, cfg.general_expense.note_col, row
+        "{}!{header_row}:{header_row}",
+        sheets::quote_sheet_title(&sheet_title)
     );
+    let row = error::retry_request(retry, || {
+        sheets::get_row_values(http, &token, &template_sheet_id, &range)
+    })
+    .await?;
+    Ok(sheets::header_columns(&row))
+}
+
+/// IDが存在し、フォルダ/スプレッドシートとして使えるか確かめる。
+async fn check_id(
+    http: &Client,
+    authn: &auth::InstalledAuth,
+    kind: IdKind,
+    id: &str,
+) -> Result<()> {
+    let token = access_token(authn).await?;
+    let retry = error::TRANSIENT_RETRY_ATTEMPTS;
+    match kind {
+        IdKind::InputFolder | IdKind::OutputFolder => {
+            error::retry_request(retry, || drive::ensure_folder(http, &token, id)).await
+        }
+        // ショートカットも確定時と同じ規則で解決できればよい。
+        IdKind::TemplateSheet => {
+            error::retry_request(retry, || drive::resolve_sheet_id(http, &token, id))
+                .await
+                .map(|_| ())
+        }
+    }
+}
 
-    // 1行分の値を更新リストへ追加する。
-    updates.push((
-        range,
-        vec![vec![
-            serde_json::Value::String(fields.date_ymd.clone()),
-            serde_json::Value::String(fields.reason.clone()),
-            serde_json::Value::Number(fields.amount_yen.into()),
-            serde_json::Value::String(fields.category.clone()),
-            serde_json::Value::String(fields.note.clone()),
-        ]],
-    ));
-
-    // まとめてバッチ更新する。
-    sheets::values_batch_update(http, &token, &copied_sheet_id, updates).await?;
-
-    // PDFエクスポートとアップロードを実行する。
-    let _ = tx
-        .send(WorkerEvent::JobUpdated {
-            job_id,
-            status: JobStatus::ExportingPdf,
+/// 接続テストの各項目を順に確かめ、項目ごとに結果をUIへ送る。
+///
+/// トークンが取れなければ残りの項目は確かめようが無いので、理由を添えて失敗にする。
+async fn verify_setup(
+    http: &Client,
+    authn: &auth::InstalledAuth,
+    input_folder_id: &str,
+    mime_types: &[&str],
+    template_sheet_id: &str,
+    tx: &mpsc::Sender<WorkerEvent>,
+) {
+    let send = |check: SetupCheck, result: Result<()>| async move {
+        let error = result.err().map(|e| {
+            tracing::warn!("verify setup failed: {}: {e:#}", check.label());
+            format!("{e:#}")
+        });
+        let _ = tx.send(WorkerEvent::SetupChecked { check, error }).await;
+    };
+    let token = match access_token(authn).await {
+        Ok(token) => {
+            send(SetupCheck::Token, Ok(())).await;
+            token
+        }
+        Err(e) => {
+            send(SetupCheck::Token, Err(e)).await;
+            for check in [SetupCheck::InputFolder, SetupCheck::Template] {
+                send(check, Err(anyhow!("not checked (no access token)"))).await;
+            }
+            return;
+        }
+    };
+    let retry = error::TRANSIENT_RETRY_ATTEMPTS;
+    // 1件だけ取得し、一覧できることだけを確かめる。
+    let folder = if input_folder_id.is_empty() {
+        Err(anyhow!("input_folder_id is not set"))
+    } else {
+        error::retry_request(retry, || {
+            drive::list_images_page(http, &token, input_folder_id, mime_types, 1, None)
+        })
+        .await
+        .map(|_| ())
+    };
+    send(SetupCheck::InputFolder, folder).await;
+    let template = if template_sheet_id.is_empty() {
+        Err(anyhow!("template_sheet_id is not set"))
+    } else {
+        error::retry_request(retry, || {
+            drive::resolve_sheet_id(http, &token, template_sheet_id)
         })
-        .await;
+        .await
+        .map(|_| ())
+    };
+    send(SetupCheck::Template, template).await;
+}
 
-    let pdf = drive::export_pdf(http, &token, &copied_sheet_id).await?;
+/// テンプレートを読み取り、確定時に書き込まれる行の位置を見積もる（シートは変更しない）。
+async fn preview_write(
+    http: &Client,
+    authn: &auth::InstalledAuth,
+    cfg: &Config,
+    rows: u32,
+    tx: &mpsc::Sender<WorkerEvent>,
+) -> Result<sheets::WritePlan> {
+    if cfg.google.template_sheet_id.is_empty() {
+        return Err(anyhow!("template_sheet_id is not set"));
+    }
+    let token = access_token(authn).await?;
+    let template_sheet_id =
+        drive::resolve_sheet_id(http, &token, &cfg.google.template_sheet_id).await?;
+    let (sheet_title, _rows) = sheets::get_sheet_title_and_rows(
+        http,
+        &token,
+        &template_sheet_id,
+        cfg.template.sheet_name(),
+    )
+    .await?;
 
-    // PDFアップロード中にステータスを更新する。
-    let _ = tx
-        .send(WorkerEvent::JobUpdated {
-            job_id,
-            status: JobStatus::UploadingPdf,
-        })
-        .await;
+    // 書き込みモードに応じて、既存行の直後を先頭行とする。
+    let ge = &cfg.general_expense;
+    let start_row = resolve_start_row(http, &token, cfg, &template_sheet_id, tx).await?;
+    let first_data_row = match ge.target_mode {
+        TargetMode::Count => start_row,
+        TargetMode::AppendAnchored => {
+            resolve_header_row(
+                http,
+                &token,
+                cfg,
+                &template_sheet_id,
+                &sheet_title,
+                start_row,
+            )
+            .await?
+                + 1
+        }
+    };
+    let existing = count_existing_rows(
+        http,
+        &token,
+        cfg,
+        &template_sheet_id,
+        &sheet_title,
+        first_data_row,
+    )
+    .await?;
+    Ok(sheets::WritePlan {
+        sheet_title,
+        first_row: first_data_row + existing,
+        rows,
+        first_col: ge.date_col.clone(),
+        last_col: ge.note_col.clone(),
+    })
+}
 
-    // PDFのファイル名を組み立てる。
-    let pdf_name = format!("{}_立替経費精算書_{}.pdf", target_month_ym, safe_name);
-    // Driveへアップロードして完了させる。
-    let _pdf_file_id =
-        drive::upload_pdf(http, &token, &cfg.google.output_folder_id, &pdf_name, pdf).await?;
+/// 経費テーブルの既存行数を数える（設定に応じて日付列だけ、または経費列のどれかで判定する）。
+async fn count_existing_rows(
+    http: &Client,
+    token: &str,
+    cfg: &Config,
+    spreadsheet_id: &str,
+    sheet_title: &str,
+    first_row: u32,
+) -> Result<u32> {
+    let ge = &cfg.general_expense;
+    if ge.count_any_column {
+        // 経費列のどれかが埋まっていれば使用済みとみなす（理由は`count_any_column`を参照）。
+        let cols = [
+            ge.date_col.as_str(),
+            ge.reason_col.as_str(),
+            ge.amount_col.as_str(),
+            ge.category_col.as_str(),
+            ge.note_col.as_str(),
+        ];
+        sheets::count_existing_rows_in_cols(
+            http,
+            token,
+            spreadsheet_id,
+            sheet_title,
+            &cols,
+            first_row,
+        )
+        .await
+    } else {
+        sheets::count_existing_rows_in_col(
+            http,
+            token,
+            spreadsheet_id,
+            sheet_title,
+            &ge.date_col,
+            first_row,
+        )
+        .await
+    }
+}
 
-    Ok(())
+/// 追加APIが返したレンジの行へ、通し番号・確定日時・提出者の更新を組み立てる。
+///
+/// 通し番号は見出しの次の行を1とした位置にする（行が分からなければ何も書かない）。
+fn appended_row_extra_updates(
+    cfg: &Config,
+    sheet_title: &str,
+    header_row: u32,
+    written_range: &str,
+    now: chrono::DateTime<chrono::Local>,
+) -> Vec<(String, Vec<Vec<serde_json::Value>>)> {
+    let first_data_row = header_row + 1;
+    match sheets::range_sheet_and_row(written_range) {
+        Some((_, row)) if row >= first_data_row => {
+            row_extra_updates(cfg, sheet_title, first_data_row, row - first_data_row, now)
+        }
+        _ => {
+            tracing::warn!("cannot place row extras for appended range {written_range}");
+            vec![]
+        }
+    }
+}
+
+/// 追加APIが返したレンジの行の備考を、備考列が結合されていれば結合の左上へ書く更新を作る。
+///
+/// 行が読み取れなければ`None`を返す。
+fn appended_note_update(
+    cfg: &Config,
+    sheet_title: &str,
+    written_range: &str,
+    note: serde_json::Value,
+    merges: &sheets::SheetMerges,
+) -> Option<CellUpdate> {
+    let (_, row) = sheets::range_sheet_and_row(written_range)?;
+    let note_col = &cfg.general_expense.note_col;
+    let cell = merges
+        .anchor_for(row, note_col)
+        .unwrap_or_else(|| format!("{note_col}{row}"));
+    Some((
+        format!("{}!{}", sheets::quote_sheet_title(sheet_title), cell),
+        vec![vec![note]],
+    ))
+}
+
+/// 追加する経費行の通し番号・確定日時・提出者の更新を組み立てる（未設定の列は省く）。
+fn row_extra_updates(
+    cfg: &Config,
+    sheet_title: &str,
+    first_data_row: u32,
+    existing: u32,
+    now: chrono::DateTime<chrono::Local>,
+) -> Vec<(String, Vec<Vec<serde_json::Value>>)> {
+    let ge = &cfg.general_expense;
+    let row = first_data_row + existing;
+    let mut updates = vec![];
+    if let Some(seq_col) = &ge.seq_col {
+        updates.push(sheets::sequence_number_update(
+            sheet_title,
+            seq_col,
+            first_data_row,
+            existing,
+        ));
+    }
+    if let Some(col) = &ge.submitted_at_col {
+        let submitted_at = now.format(ge.submitted_at_format()).to_string();
+        updates.push((
+            format!("{}!{}{}", sheets::quote_sheet_title(sheet_title), col, row),
+            vec![vec![serde_json::Value::String(submitted_at)]],
+        ));
+    }
+    if let Some(col) = &ge.submitter_col {
+        updates.push((
+            format!("{}!{}{}", sheets::quote_sheet_title(sheet_title), col, row),
+            vec![vec![serde_json::Value::String(cfg.user.full_name.clone())]],
+        ));
+    }
+    updates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_pdf_steps_skipped_when_disabled() {
+        // PDFが無効なら、シートの後のエクスポートとアップロードを行わないことを検証する。
+        let mut cfg = Config::default();
+        assert_eq!(pdf_steps(&cfg), vec![PdfStep::Export, PdfStep::Upload]);
+        assert!(matches!(PdfStep::Export.status(), JobStatus::ExportingPdf));
+        assert!(matches!(PdfStep::Upload.status(), JobStatus::UploadingPdf));
+
+        cfg.pdf.enabled = false;
+        assert!(pdf_steps(&cfg).is_empty());
+    }
+
+    #[test]
+    fn test_needs_refresh_within_margin() {
+        // 期限が近い・切れたトークンだけを更新し、期限不明なら更新しないことを検証する。
+        let now = 1_700_000_000;
+        assert!(needs_refresh(Some(now + 60), now));
+        assert!(needs_refresh(Some(now - 10), now));
+        assert!(!needs_refresh(Some(now + 3600), now));
+        assert!(!needs_refresh(None, now));
+    }
+
+    #[test]
+    fn test_row_extra_updates_ranges_and_values() {
+        // 付帯列の更新が挿入行を指し、設定どおりの値になることを検証する。
+        let mut cfg = Config::default();
+        cfg.user.full_name = "山田 太郎".into();
+        cfg.general_expense.submitted_at_col = Some("G".into());
+        cfg.general_expense.submitted_at_format = Some("%Y/%m/%d %H:%M".into());
+        cfg.general_expense.submitter_col = Some("H".into());
+        let now = chrono::Local
+            .with_ymd_and_hms(2025, 12, 19, 9, 5, 0)
+            .unwrap();
+
+        let updates = row_extra_updates(&cfg, "Sheet1", 7, 2, now);
+        assert_eq!(
+            updates,
+            vec![
+                (
+                    "'Sheet1'!G9".to_string(),
+                    vec![vec![serde_json::json!("2025/12/19 09:05")]]
+                ),
+                (
+                    "'Sheet1'!H9".to_string(),
+                    vec![vec![serde_json::json!("山田 太郎")]]
+                ),
+            ]
+        );
+
+        // 列が未設定なら何も書き込まない。
+        assert!(row_extra_updates(&Config::default(), "Sheet1", 7, 2, now).is_empty());
+    }
+
+    #[test]
+    fn test_appended_row_extras_follow_written_range() {
+        // 追加モードの付帯列は、追加APIが返した行へ書かれ、番号も見出しからの位置になることを検証する。
+        let mut cfg = Config::default();
+        cfg.general_expense.seq_col = Some("A".into());
+        cfg.general_expense.submitter_col = Some("H".into());
+        cfg.user.full_name = "山田 太郎".into();
+        let now = chrono::Local::now();
+
+        // 見出しが6行目で、見本行の後ろの12行目へ追加された。
+        let updates = appended_row_extra_updates(&cfg, "Sheet1", 6, "'Sheet1'!B12:F12", now);
+        assert_eq!(
+            updates,
+            vec![
+                ("'Sheet1'!A12".to_string(), vec![vec![serde_json::json!(6)]]),
+                (
+                    "'Sheet1'!H12".to_string(),
+                    vec![vec![serde_json::json!("山田 太郎")]]
+                ),
+            ]
+        );
+        // 行が読み取れなければ書かない。
+        assert!(appended_row_extra_updates(&cfg, "Sheet1", 6, "B:F", now).is_empty());
+    }
+
+    #[test]
+    fn test_appended_note_follows_written_range() {
+        // 切り離した備考が、追加APIが返した行の備考列へ書かれることを検証する。
+        let cfg = Config::default();
+        let merges = sheets::SheetMerges::default();
+        let note = serde_json::json!("会議");
+        assert_eq!(
+            appended_note_update(&cfg, "Sheet1", "'Sheet1'!B12:E12", note.clone(), &merges),
+            Some(("'Sheet1'!F12".to_string(), vec![vec![note.clone()]]))
+        );
+        assert_eq!(
+            appended_note_update(&cfg, "Sheet1", "B:E", note, &merges),
+            None
+        );
+    }
+
+    #[test]
+    fn test_expense_rows_advance_for_each_request() {
+        // 1枚のシートへまとめる行が既存行の後ろから1行ずつ進むことを検証する。
+        let mut cfg = Config::default();
+        cfg.general_expense.submitter_col = Some("H".into());
+        let now = chrono::Local::now();
+        let jobs = [
+            Job::new("f1".into(), "a.jpg".into()),
+            Job::new("f2".into(), "b.jpg".into()),
+        ];
+        let reqs: Vec<CommitRequest> = jobs
+            .iter()
+            .map(|j| CommitRequest::for_job(j, "2025-12".into()))
+            .collect();
+
+        let (updates, written) = expense_rows_updates(
+            &cfg,
+            "Sheet1",
+            7,
+            2,
+            &reqs,
+            &sheets::SheetMerges::default(),
+            now,
+        );
+        assert_eq!(
+            written,
+            vec![
+                (jobs[0].id, "'Sheet1'!B9:F9".to_string()),
+                (jobs[1].id, "'Sheet1'!B10:F10".to_string()),
+            ]
+        );
+        let ranges: Vec<&str> = updates.iter().map(|(r, _)| r.as_str()).collect();
+        assert_eq!(
+            ranges,
+            vec![
+                "'Sheet1'!B9:F9",
+                "'Sheet1'!H9",
+                "'Sheet1'!B10:F10",
+                "'Sheet1'!H10"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expense_cells_follow_configured_columns() {
+        // 読み戻した行から、設定された列の値が入力項目の順に取り出されることを検証する。
+        let mut cfg = Config::default();
+        cfg.general_expense.date_col = "B".into();
+        cfg.general_expense.reason_col = "C".into();
+        cfg.general_expense.amount_col = "E".into();
+        cfg.general_expense.category_col = "D".into();
+        cfg.general_expense.note_col = "G".into();
+        let row = vec![
+            serde_json::json!(1),
+            serde_json::json!("2025/12/19"),
+            serde_json::json!("タクシー"),
+            serde_json::json!("旅費交通費"),
+            serde_json::json!(2400),
+        ];
+        let cells = expense_cells(&cfg.general_expense, &row);
+        assert_eq!(
+            cells,
+            vec!["2025/12/19", "タクシー", "2400", "旅費交通費", ""]
+        );
+        let fields = ReceiptFields::from_sheet_row(&cells).unwrap();
+        assert_eq!(fields.amount_yen, 2400);
+        assert_eq!(fields.date_ymd, "2025-12-19");
+    }
+
+    #[test]
+    fn test_ensure_rows_fit_refuses_past_last_row() {
+        // シートの最終行までは書き込め、それを超える書き込みは理由付きで断ることを検証する。
+        assert!(ensure_rows_fit("Sheet1", 50, 48, 3).is_ok());
+        let err = ensure_rows_fit("Sheet1", 50, 49, 3)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Sheet1 has only 50 rows"));
+        assert!(err.contains("would need row 51"));
+    }
+
+    #[test]
+    fn test_group_by_month_keeps_order() {
+        // 対象月ごとにまとめ、月もジョブも最初に現れた順を保つことを検証する。
+        let req = |file: &str, month: &str| {
+            CommitRequest::for_job(&Job::new(file.into(), file.into()), month.into())
+        };
+        let groups = group_by_month(vec![
+            req("a", "2025-12"),
+            req("b", "2025-11"),
+            req("c", "2025-12"),
+        ]);
+        let files: Vec<Vec<&str>> = groups
+            .iter()
+            .map(|g| g.iter().map(|r| r.drive_file_id.as_str()).collect())
+            .collect();
+        assert_eq!(files, vec![vec!["a", "c"], vec!["b"]]);
+    }
+
+    #[test]
+    fn test_verify_outcome_fails_commit_only_in_fail_mode() {
+        // failでは食い違いの詳細付きで確定を失敗させ、warnでは警告文を返すことを検証する。
+        let sent = vec![(
+            "'Sheet1'!B44:F44".to_string(),
+            vec![vec![
+                serde_json::Value::String("2025-12-19".into()),
+                serde_json::Value::String("taxi".into()),
+            ]],
+        )];
+        let read = vec![vec![vec![
+            serde_json::Value::String("2025-12-19".into()),
+            serde_json::Value::String("bus".into()),
+        ]]];
+        let problem = format!(
+            "written cells differ: {}",
+            sheets::write_mismatches(&sent, &read).join("; ")
+        );
+
+        let err = verify_outcome(VerifyWrite::Fail, &problem)
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("write verification failed: "));
+        assert!(err.contains("'Sheet1'!B44:F44 [0,1]"));
+        assert!(err.contains("\"taxi\""));
+
+        let warning = verify_outcome(VerifyWrite::Warn, &problem).unwrap();
+        assert!(warning.starts_with("warning: written cells differ"));
+    }
+
+    #[test]
+    fn test_pdf_file_name_placeholders() {
+        // 既定の書式は従来の名前になり、各置き換え文字が展開されることを検証する。
+        let name =
+            |format| pdf_file_name(format, "2025-12", "山田太郎", "2025-12-19", "2026-01-05");
+        assert_eq!(
+            name(crate::config::DEFAULT_PDF_NAME_FORMAT),
+            "2025-12_立替経費精算書_山田太郎.pdf"
+        );
+        assert_eq!(name("{receipt_date}_{name}.pdf"), "2025-12-19_山田太郎.pdf");
+        assert_eq!(name("経費_{today}"), "経費_2026-01-05.pdf");
+    }
+
+    #[test]
+    fn test_pdf_file_name_sanitizes_separators() {
+        // 展開後の`/`や`:`がファイル名に残らないことを検証する。
+        assert_eq!(
+            pdf_file_name(
+                "{month}/{name}: {receipt_date}.pdf",
+                "2025-12",
+                "A",
+                "2025/12/19",
+                "-"
+            ),
+            "2025-12_A_ 2025_12_19.pdf"
+        );
+    }
+
+    #[test]
+    fn test_header_updates_write_currency_only_when_configured() {
+        // 通貨セルが未設定なら従来どおり氏名と対象月だけを書き、設定時はジョブの通貨か既定通貨を書くことを検証する。
+        let mut cfg = Config::default();
+        let mut fields = ReceiptFields::default();
+        let updates = header_updates(&cfg, "Sheet1", "2025-12", &fields);
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[1].1, vec![vec![serde_json::json!("2025-12-01")]]);
+
+        cfg.template.currency_cell = Some("H3".into());
+        let updates = header_updates(&cfg, "Sheet1", "2025-12", &fields);
+        assert_eq!(
+            updates[2],
+            (
+                "'Sheet1'!H3".to_string(),
+                vec![vec![serde_json::json!("JPY")]]
+            )
+        );
+
+        cfg.user.default_currency = Some("EUR".into());
+        let updates = header_updates(&cfg, "Sheet1", "2025-12", &fields);
+        assert_eq!(updates[2].1, vec![vec![serde_json::json!("EUR")]]);
+        fields.currency = "USD".into();
+        let updates = header_updates(&cfg, "Sheet1", "2025-12", &fields);
+        assert_eq!(updates[2].1, vec![vec![serde_json::json!("USD")]]);
+    }
+
+    #[test]
+    fn test_header_cells_match_header_updates() {
+        // 確認表示用のセル番地と値が、書き込む更新リストと同じ順で一致することを検証する。
+        let mut cfg = Config::default();
+        cfg.user.full_name = "山田 太郎".into();
+        cfg.template.currency_cell = Some("H3".into());
+        let fields = ReceiptFields::default();
+        let cells = header_cells(&cfg, "2025-12", &fields);
+        assert_eq!(
+            cells,
+            vec![
+                ("F3".to_string(), "山田 太郎".to_string()),
+                ("B3".to_string(), "2025-12-01".to_string()),
+                ("H3".to_string(), "JPY".to_string()),
+            ]
+        );
+        let updates = header_updates(&cfg, "Sheet1", "2025-12", &fields);
+        let written: Vec<(String, String)> = updates
+            .into_iter()
+            .map(|(range, values)| (range, values[0][0].as_str().unwrap().to_string()))
+            .collect();
+        let expected: Vec<(String, String)> = cells
+            .into_iter()
+            .map(|(cell, value)| (format!("'Sheet1'!{cell}"), value))
+            .collect();
+        assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn test_prepare_batch_request_skips_incomplete_jobs() {
+        // 日付や金額が空のジョブ、無効な対象月のジョブが理由付きで飛ばされることを検証する。
+        let cfg = Config::default();
+        let mut job = Job::new("f1".into(), "a.jpg".into());
+        job.fields = ReceiptFields {
+            date_ymd: "2025-12-19".into(),
+            reason: "タクシー".into(),
+            amount_yen: 2400,
+            ..ReceiptFields::default()
+        };
+        let mut req = CommitRequest::for_job(&job, "2025-12".into());
+        assert_eq!(prepare_batch_request(&cfg, &mut req), Ok(vec![]));
+
+        let mut missing = CommitRequest::for_job(&job, "2025-12".into());
+        missing.fields.date_ymd.clear();
+        missing.fields.amount_yen = 0;
+        let reason = prepare_batch_request(&cfg, &mut missing).unwrap_err();
+        assert!(reason.starts_with("a.jpg: "));
+        assert!(reason.contains("invalid date"));
+        assert!(reason.contains("amount must be positive"));
+
+        let mut bad_month = CommitRequest::for_job(&job, "2025-13".into());
+        assert_eq!(
+            prepare_batch_request(&cfg, &mut bad_month),
+            Err("a.jpg: invalid target month \"2025-13\"".into())
+        );
+    }
 }